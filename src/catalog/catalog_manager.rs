@@ -1,7 +1,8 @@
 use crate::catalog::{Catalog, CatalogError, CatalogIter};
+use crate::datum::{DataType, Datum};
 use crate::index::BPTIndex;
 use crate::storage::{BufferPoolManagerRef, PageID};
-use crate::table::{SchemaRef, Table};
+use crate::table::{Schema, SchemaRef, Slice, Table};
 use itertools::Itertools;
 use log::info;
 use std::cell::RefCell;
@@ -16,6 +17,18 @@ pub struct CatalogManager {
 
 pub type CatalogManagerRef = Rc<RefCell<CatalogManager>>;
 
+/// views are kept in the table catalog under a name no identifier can take
+fn view_key(view_name: &str) -> String {
+    format!("@{}", view_name)
+}
+
+fn view_schema() -> SchemaRef {
+    Rc::new(Schema::from_type_and_names(&[(
+        DataType::new_as_varchar(false),
+        "definition".to_string(),
+    )]))
+}
+
 impl CatalogManager {
     pub fn current_database(&self) -> Option<String> {
         self.current_database.clone()
@@ -50,6 +63,10 @@ impl CatalogManager {
     }
     pub fn create_table(&mut self, table_name: &str, page_id: PageID) -> Result<(), CatalogError> {
         if let Some(table_catalog) = self.table_catalog.as_mut() {
+            // tables and views share the names
+            if table_catalog.page_id_of(&view_key(table_name)).is_some() {
+                return Err(CatalogError::Duplicated);
+            }
             info!("create table {}", table_name);
             table_catalog.insert(page_id, table_name)?;
             Ok(())
@@ -60,6 +77,9 @@ impl CatalogManager {
     pub fn use_database(&mut self, database_name: &str) -> Result<(), CatalogError> {
         if let Some(page_id) = self.database_catalog.page_id_of(database_name) {
             let table_catalog = Catalog::open(self.bpm.clone(), page_id)?;
+            if let Some(table_catalog) = self.table_catalog.take() {
+                table_catalog.close()?;
+            }
             self.table_catalog = Some(table_catalog);
             info!("checkout to database {}", database_name);
             self.current_database = Some(database_name.to_owned());
@@ -95,6 +115,52 @@ impl CatalogManager {
             Err(CatalogError::NotUsingDatabase)
         }
     }
+    /// store the definition of the view in a page of its own
+    pub fn create_view(&mut self, view_name: &str, definition: &str) -> Result<(), CatalogError> {
+        let bpm = self.bpm.clone();
+        if let Some(table_catalog) = self.table_catalog.as_mut() {
+            let key = view_key(view_name);
+            if table_catalog.page_id_of(view_name).is_some()
+                || table_catalog.page_id_of(&key).is_some()
+            {
+                return Err(CatalogError::Duplicated);
+            }
+            info!("create view {}", view_name);
+            let slice = Slice::new_as_message(bpm, "definition", definition)?;
+            table_catalog.insert(slice.page_id(), &key)?;
+            Ok(())
+        } else {
+            Err(CatalogError::NotUsingDatabase)
+        }
+    }
+    pub fn find_view(&self, view_name: &str) -> Result<String, CatalogError> {
+        if let Some(table_catalog) = &self.table_catalog {
+            let page_id = table_catalog
+                .page_id_of(&view_key(view_name))
+                .ok_or(CatalogError::EntryNotFound)?;
+            let slice = Slice::open(self.bpm.clone(), view_schema(), page_id)?;
+            match slice.tuple_at(0)?.remove(0) {
+                Datum::VarChar(Some(definition)) => Ok(definition),
+                _ => unreachable!(),
+            }
+        } else {
+            Err(CatalogError::NotUsingDatabase)
+        }
+    }
+    /// remove the view and free the page of its definition
+    pub fn remove_view(&mut self, view_name: &str) -> Result<(), CatalogError> {
+        if let Some(table_catalog) = &mut self.table_catalog {
+            let key = view_key(view_name);
+            let page_id = table_catalog
+                .page_id_of(&key)
+                .ok_or(CatalogError::EntryNotFound)?;
+            table_catalog.remove(&key)?;
+            self.bpm.borrow_mut().free(page_id)?;
+            Ok(())
+        } else {
+            Err(CatalogError::NotUsingDatabase)
+        }
+    }
     pub fn remove_indexes_by_table(&mut self, table_name: &str) -> Result<(), CatalogError> {
         if let Some(table_catalog) = &mut self.table_catalog {
             table_catalog
@@ -120,7 +186,38 @@ impl CatalogManager {
                     let page_id = table_catalog.page_id_of(name).unwrap();
                     BPTIndex::open(self.bpm.clone(), page_id, table.schema.as_ref())
                 })
-                .collect_vec())
+                .collect::<Result<Vec<_>, _>>()?)
+        } else {
+            Err(CatalogError::NotUsingDatabase)
+        }
+    }
+    /// index entries are keyed by the indexed columns, so the ones over the column are re-keyed
+    pub fn rename_index_column(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+        new_column_name: &str,
+    ) -> Result<(), CatalogError> {
+        if let Some(table_catalog) = self.table_catalog.as_mut() {
+            let renamed = table_catalog
+                .prefix_with(&format!("{}:", table_name))
+                .into_iter()
+                .filter(|key| key.split(':').skip(1).any(|c| c == column_name))
+                .map(|key| {
+                    let columns = key
+                        .split(':')
+                        .skip(1)
+                        .map(|c| if c == column_name { new_column_name } else { c })
+                        .join(":");
+                    (key.to_owned(), format!("{}:{}", table_name, columns))
+                })
+                .collect_vec();
+            for (key, new_key) in renamed {
+                let page_id = table_catalog.page_id_of(&key).unwrap();
+                table_catalog.remove(&key)?;
+                table_catalog.insert(page_id, &new_key)?;
+            }
+            Ok(())
         } else {
             Err(CatalogError::NotUsingDatabase)
         }
@@ -136,9 +233,27 @@ impl CatalogManager {
         let table_names = table_catalog
             .iter()
             .map(|(name, _)| name.to_string())
+            .filter(|name| !name.starts_with('@'))
             .collect_vec();
         Ok(table_names)
     }
+    /// tables having a foreign key referencing the table
+    pub fn tables_referencing(&self, table_name: &str) -> Result<Vec<String>, CatalogError> {
+        let page_id = self.find_table(table_name)?.page_id();
+        Ok(self
+            .table_names()?
+            .into_iter()
+            .filter(|name| !name.contains(':') && name != table_name)
+            .filter(|name| {
+                let table = self.find_table(name).unwrap();
+                table
+                    .schema
+                    .foreign
+                    .iter()
+                    .any(|(page_id_of_ref, _)| *page_id_of_ref == page_id)
+            })
+            .collect_vec())
+    }
     pub fn drop_index(&mut self, table_name: &str, schema: SchemaRef) -> Result<(), CatalogError> {
         if let Some(table_catalog) = self.table_catalog.as_mut() {
             let columns = schema.columns.iter().map(|c| c.desc.clone()).collect_vec();
@@ -177,7 +292,7 @@ impl CatalogManager {
 mod tests {
     use super::*;
     use crate::datum::DataType;
-    use crate::storage::BufferPoolManager;
+    use crate::storage::{BufferPoolManager, PAGE_ID_OF_ROOT_DATABASE_CATALOG};
     use crate::table::Schema;
     use std::fs::remove_file;
 
@@ -209,4 +324,51 @@ mod tests {
         };
         remove_file(filename).unwrap();
     }
+
+    #[test]
+    fn test_switch_database() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(10);
+            let filename = bpm.borrow().filename();
+            let mut catalog_manager = CatalogManager::new(bpm.clone());
+            let database_names = ["db0", "db1", "db2"];
+            for database_name in &database_names {
+                catalog_manager.create_database(database_name).unwrap();
+            }
+            catalog_manager.use_database("db0").unwrap();
+            let table = Table::new(
+                Rc::new(Schema::from_type_and_names(&[(
+                    DataType::new_as_int(false),
+                    "v1".to_string(),
+                )])),
+                bpm.clone(),
+            );
+            catalog_manager
+                .create_table("sample_table", table.page_id())
+                .unwrap();
+            let page_id_of_db0 = catalog_manager.table_catalog.as_ref().unwrap().page_id();
+            for idx in 0..100 {
+                catalog_manager
+                    .use_database(database_names[idx % database_names.len()])
+                    .unwrap();
+            }
+            // the catalog of db0 is flushed when switching away
+            let page = bpm.borrow_mut().fetch(page_id_of_db0).unwrap();
+            assert!(!page.borrow().is_dirty);
+            bpm.borrow_mut().unpin(page_id_of_db0).unwrap();
+            // only the database catalog, the current table catalog and the table are pinned
+            let page_id_of_current = catalog_manager.table_catalog.as_ref().unwrap().page_id();
+            let mut expected = vec![
+                (PAGE_ID_OF_ROOT_DATABASE_CATALOG, 1),
+                (page_id_of_current, 1),
+                (table.page_id(), 1),
+            ];
+            expected.sort_unstable();
+            assert_eq!(bpm.borrow().page_pin_summary(), expected);
+            catalog_manager.use_database("db0").unwrap();
+            assert!(catalog_manager.find_table("sample_table").is_ok());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
 }
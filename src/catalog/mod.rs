@@ -1,7 +1,10 @@
+use crate::error::ErrorCode;
+use crate::index::IndexError;
 use crate::storage::{
     BufferPoolManagerRef, KeyDataIter, PageID, PageRef, SlottedPage, SlottedPageError,
     StorageError, PAGE_ID_OF_ROOT_DATABASE_CATALOG,
 };
+use crate::table::TableError;
 use itertools::Itertools;
 use log::info;
 use thiserror::Error;
@@ -94,6 +97,11 @@ impl Catalog {
     pub fn page_id(&self) -> PageID {
         self.page.borrow().page_id.unwrap()
     }
+    /// flush the catalog page and release it
+    pub fn close(self) -> Result<(), CatalogError> {
+        self.bpm.borrow_mut().flush(self.page_id())?;
+        Ok(())
+    }
     pub fn remove(&mut self, name: &str) -> Result<(), CatalogError> {
         info!("catalog: remove {}", name);
         let catalog_page = self.catalog_page_mut();
@@ -144,6 +152,24 @@ pub enum CatalogError {
     EntryNotFound,
     #[error("Duplicated")]
     Duplicated,
+    #[error("Table: {0}")]
+    Table(#[from] TableError),
+    #[error("Index: {0}")]
+    Index(#[from] IndexError),
+}
+
+impl CatalogError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Storage(err) => err.code(),
+            Self::CatalogPage(err) => err.code(),
+            Self::NotUsingDatabase => ErrorCode::NotUsingDatabase,
+            Self::EntryNotFound => ErrorCode::NotFound,
+            Self::Duplicated => ErrorCode::AlreadyExists,
+            Self::Table(err) => err.code(),
+            Self::Index(err) => err.code(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,16 +1,21 @@
+use crate::error::ErrorCode;
 use crate::table::Schema;
 use chrono::{Datelike, NaiveDate};
 use itertools::Itertools;
 use ordered_float::NotNan;
+use std::cmp::Ordering;
 use std::convert::{From, TryInto};
 use std::fmt;
+use std::mem::discriminant;
 use std::ops::{Add, Div};
+use std::str::FromStr;
+use thiserror::Error;
 
 pub use types::DataType;
 
 mod types;
 
-#[derive(Debug, PartialEq, PartialOrd, Ord, Eq, Clone)]
+#[derive(Debug, PartialEq, PartialOrd, Ord, Eq, Hash, Clone)]
 pub enum Datum {
     Int(Option<i32>),
     VarChar(Option<String>),
@@ -89,6 +94,109 @@ impl From<NaiveDate> for Datum {
 }
 
 impl Datum {
+    pub fn null_of(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::Int(_) => Self::Int(None),
+            DataType::VarChar(_) => Self::VarChar(None),
+            DataType::Bool(_) => Self::Bool(None),
+            DataType::Float(_) => Self::Float(None),
+            DataType::Date(_) => Self::Date(None),
+        }
+    }
+    /// the type of the value, nullable as the value alone can not tell
+    pub fn data_type(&self) -> DataType {
+        match self {
+            Self::Int(_) => DataType::new_as_int(true),
+            Self::VarChar(_) => DataType::new_as_varchar(true),
+            Self::Bool(_) => DataType::new_as_bool(true),
+            Self::Float(_) => DataType::new_as_float(true),
+            Self::Date(_) => DataType::new_as_date(true),
+        }
+    }
+    pub fn is_null(&self) -> bool {
+        matches!(
+            self,
+            Self::Int(None)
+                | Self::VarChar(None)
+                | Self::Bool(None)
+                | Self::Float(None)
+                | Self::Date(None)
+        )
+    }
+    /// the datum as a value of the type, strings are parsed, none if it is not one
+    pub fn cast_to(&self, data_type: &DataType) -> Option<Datum> {
+        match (self, data_type) {
+            (datum, _) if datum.is_null() => Some(Self::null_of(data_type)),
+            (Self::VarChar(Some(v)), DataType::Int(_)) => {
+                v.trim().parse::<i32>().ok().map(Self::from)
+            }
+            (Self::VarChar(Some(v)), DataType::Float(_)) => v
+                .trim()
+                .parse::<f32>()
+                .ok()
+                .and_then(|v| NotNan::new(v).ok())
+                .map(|v| Self::Float(Some(v))),
+            (Self::VarChar(Some(v)), DataType::Date(_)) => {
+                NaiveDate::from_str(v.trim()).ok().map(Self::from)
+            }
+            (Self::VarChar(Some(v)), DataType::Bool(_)) => bool::from_str(&v.trim().to_lowercase())
+                .ok()
+                .map(Self::from),
+            (Self::Int(Some(v)), DataType::Float(_)) => Some(Self::from(*v as f32)),
+            (datum, data_type)
+                if discriminant(datum) == discriminant(&Self::null_of(data_type)) =>
+            {
+                Some(datum.clone())
+            }
+            _ => None,
+        }
+    }
+    /// a numeric zero, which can not be a divisor
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Self::Int(Some(v)) => *v == 0,
+            Self::Float(Some(v)) => v.into_inner() == 0.0,
+            _ => false,
+        }
+    }
+    /// the smallest datum of the same type greater than this one, null is the smallest,
+    /// none if this one is the greatest
+    pub fn successor(&self) -> Option<Datum> {
+        match self {
+            Self::Int(None) => Some(Self::Int(Some(i32::MIN))),
+            Self::Int(Some(v)) => v.checked_add(1).map(|v| Self::Int(Some(v))),
+            Self::VarChar(None) => Some(Self::VarChar(Some(String::new()))),
+            Self::VarChar(Some(v)) => Some(Self::VarChar(Some(format!("{}\0", v)))),
+            Self::Bool(None) => Some(Self::Bool(Some(false))),
+            Self::Bool(Some(false)) => Some(Self::Bool(Some(true))),
+            Self::Bool(Some(true)) => None,
+            Self::Float(None) => Some(Self::from(f32::NEG_INFINITY)),
+            // a step landing on nan has no successor, or the scan would start over from
+            // the smallest float
+            Self::Float(Some(v)) => {
+                let v = v.into_inner();
+                let bits = v.to_bits();
+                let next = match v {
+                    _ if v == f32::INFINITY => return None,
+                    _ if v == 0.0 => f32::from_bits(1),
+                    _ if v > 0.0 => f32::from_bits(bits + 1),
+                    _ => f32::from_bits(bits - 1),
+                };
+                NotNan::new(next).ok().map(|v| Self::Float(Some(v)))
+            }
+            Self::Date(None) => Some(Self::Date(Some(chrono::naive::MIN_DATE))),
+            Self::Date(Some(v)) => v.succ_opt().map(|v| Self::Date(Some(v))),
+        }
+    }
+    /// the text of the datum, null is shown as null_display and bool as 1/0 if
+    /// bool_as_int
+    pub fn render(&self, null_display: &str, bool_as_int: bool) -> String {
+        match self {
+            _ if self.is_null() => null_display.to_owned(),
+            Self::Bool(Some(v)) if bool_as_int => (*v as i32).to_string(),
+            _ => self.to_string(),
+        }
+    }
     pub fn byte_size_inlined(&self) -> usize {
         match self {
             Self::Int(_) => 5,
@@ -159,9 +267,35 @@ impl Datum {
                     vec![0u8; 7]
                 }
             }
-            _ => todo!(),
+            Self::Bool(v) => {
+                if let Some(v) = v {
+                    vec![1u8, *v as u8]
+                } else {
+                    vec![0u8; 2]
+                }
+            }
+        }
+    }
+    /// length of to_bytes without serializing
+    pub fn bytes_len(&self) -> usize {
+        match self {
+            Self::Int(_) | Self::Float(_) => 5,
+            Self::VarChar(Some(v)) => 5 + v.len(),
+            Self::VarChar(None) => 1,
+            Self::Date(_) => 7,
+            Self::Bool(_) => 2,
         }
     }
+    /// length of bytes_from_tuple, where each datum not inlined takes an extra offset pair
+    pub fn tuple_bytes_len(datums: &[Datum]) -> usize {
+        datums
+            .iter()
+            .map(|dat| match dat.is_inlined() {
+                true => dat.bytes_len(),
+                false => 8 + dat.bytes_len(),
+            })
+            .sum()
+    }
     pub fn bytes_from_tuple(datums: &[Datum]) -> Vec<u8> {
         let mut bytes_fragment = vec![];
         let mut not_inlined_data = Vec::<(usize, &Datum)>::new();
@@ -191,31 +325,93 @@ impl Datum {
         }
         bytes_fragment.iter().rev().flatten().cloned().collect_vec()
     }
-    pub fn tuple_from_bytes_with_schema(bytes: &[u8], schema: &Schema) -> Vec<Datum> {
+    /// decode the tuple, the bytes are checked against the layout of the schema so
+    /// a mismatched or corrupted buffer fails instead of reading out of bound
+    pub fn tuple_from_bytes_with_schema(
+        bytes: &[u8],
+        schema: &Schema,
+    ) -> Result<Vec<Datum>, DatumError> {
         let base_offset = bytes.len();
+        let fixed_len = schema
+            .columns
+            .iter()
+            .map(|col| col.offset)
+            .max()
+            .unwrap_or(0);
+        if base_offset < fixed_len {
+            return Err(DatumError::BytesTooShort(fixed_len, base_offset));
+        }
         let mut datums = vec![];
         for col in schema.columns.iter() {
             let offset = base_offset - col.offset;
             let datum = if col.data_type.is_inlined() {
                 let start = offset;
                 let end = start + col.data_type.width_of_value().unwrap();
-                let bytes = bytes[start..end].to_vec();
-                Datum::from_bytes_with_type(&bytes, &col.data_type)
+                Datum::from_bytes_with_type(&bytes[start..end], &col.data_type)?
             } else {
-                let start = base_offset
-                    - u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize;
-                let end = base_offset
-                    - u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().unwrap())
-                        as usize;
-                let bytes = bytes[start..end].to_vec();
-                Datum::from_bytes_with_type(&bytes, &col.data_type)
+                let start = u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+                let end = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().unwrap());
+                let (start, end) = (start as usize, end as usize);
+                if start > base_offset || end > start {
+                    return Err(DatumError::InvalidOffset(start, end));
+                }
+                Datum::from_bytes_with_type(
+                    &bytes[base_offset - start..base_offset - end],
+                    &col.data_type,
+                )?
             };
             datums.push(datum);
         }
-        datums
+        Ok(datums)
     }
-    pub fn from_bytes_with_type(bytes: &[u8], data_type: &DataType) -> Self {
-        match data_type {
+    /// order of the serialized tuple against the key, same as comparing the decoded tuple
+    /// with the key, but no vec is built and strings are compared in place
+    pub fn cmp_tuple_bytes_with_key(bytes: &[u8], schema: &Schema, key: &[Datum]) -> Ordering {
+        let base_offset = bytes.len();
+        for (col, datum) in schema.columns.iter().zip(key.iter()) {
+            let offset = base_offset - col.offset;
+            let ord = if col.data_type.is_inlined() {
+                let end = offset + col.data_type.width_of_value().unwrap();
+                Datum::from_bytes_with_type(&bytes[offset..end], &col.data_type)
+                    .unwrap()
+                    .cmp(datum)
+            } else {
+                let start = u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+                let end = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().unwrap());
+                let value = &bytes[base_offset - start as usize..base_offset - end as usize];
+                match (value[0], datum) {
+                    (0, Datum::VarChar(None)) => Ordering::Equal,
+                    (0, Datum::VarChar(Some(_))) => Ordering::Less,
+                    (_, Datum::VarChar(None)) => Ordering::Greater,
+                    (_, Datum::VarChar(Some(s))) => {
+                        let len = u32::from_le_bytes(value[1..5].try_into().unwrap()) as usize;
+                        value[5..5 + len].cmp(s.as_bytes())
+                    }
+                    _ => Datum::from_bytes_with_type(value, &col.data_type)
+                        .unwrap()
+                        .cmp(datum),
+                }
+            };
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+        schema.columns.len().cmp(&key.len())
+    }
+    pub fn from_bytes_with_type(bytes: &[u8], data_type: &DataType) -> Result<Self, DatumError> {
+        let expected = match (bytes.first(), data_type) {
+            (None, _) => 1,
+            (Some(0), _) => 1,
+            (Some(_), DataType::VarChar(_)) if bytes.len() < 5 => 5,
+            (Some(_), DataType::VarChar(_)) => {
+                5 + u32::from_le_bytes(bytes[1..5].try_into().unwrap()) as usize
+            }
+            (Some(_), data_type) => data_type.width_of_value().unwrap(),
+        };
+        if bytes.len() < expected {
+            return Err(DatumError::BytesTooShort(expected, bytes.len()));
+        }
+        let datum = match data_type {
             DataType::Int(_) => {
                 if bytes[0] == 0 {
                     Datum::Int(None)
@@ -227,9 +423,9 @@ impl Datum {
                 if bytes[0] == 0 {
                     Datum::VarChar(None)
                 } else {
-                    let len = u32::from_le_bytes(bytes[1..5].try_into().unwrap()) as usize;
+                    let content = bytes[5..expected].to_vec();
                     Datum::VarChar(Some(
-                        String::from_utf8(bytes[5..5 + len].try_into().unwrap()).unwrap(),
+                        String::from_utf8(content).map_err(|_| DatumError::InvalidUtf8)?,
                     ))
                 }
             }
@@ -244,25 +440,24 @@ impl Datum {
                 if bytes[0] == 0 {
                     Datum::Float(None)
                 } else {
-                    Datum::Float(Some(
-                        f32::from_le_bytes(bytes[1..5].try_into().unwrap())
-                            .try_into()
-                            .unwrap(),
-                    ))
+                    let value = f32::from_le_bytes(bytes[1..5].try_into().unwrap());
+                    Datum::Float(Some(value.try_into().map_err(|_| DatumError::NaN)?))
                 }
             }
             DataType::Date(_) => {
                 if bytes[0] == 0 {
                     Datum::Date(None)
                 } else {
-                    Datum::Date(Some(NaiveDate::from_ymd(
+                    let date = NaiveDate::from_ymd_opt(
                         u32::from_le_bytes(bytes[1..5].try_into().unwrap()) as i32,
-                        u8::from_le_bytes(bytes[5..6].try_into().unwrap()) as u32,
-                        u8::from_le_bytes(bytes[6..7].try_into().unwrap()) as u32,
-                    )))
+                        bytes[5] as u32,
+                        bytes[6] as u32,
+                    );
+                    Datum::Date(Some(date.ok_or(DatumError::InvalidDate)?))
                 }
             }
-        }
+        };
+        Ok(datum)
     }
 }
 
@@ -283,6 +478,25 @@ impl fmt::Display for Datum {
     }
 }
 
+#[derive(Error, Debug)]
+pub enum DatumError {
+    #[error("Bytes Too Short, Expected {0} But Got {1}")]
+    BytesTooShort(usize, usize),
+    #[error("Invalid Offset Of Value: {0}..{1}")]
+    InvalidOffset(usize, usize),
+    #[error("Invalid UTF-8 String")]
+    InvalidUtf8,
+    #[error("Invalid Date")]
+    InvalidDate,
+    #[error("Float Is NaN")]
+    NaN,
+}
+
+impl DatumError {
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::Internal
+    }
+}
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -299,7 +513,177 @@ mod tests {
         let schema = Rc::new(schema);
         let datums = vec![Datum::Int(Some(1)), Datum::VarChar(Some("foo".to_string()))];
         let bytes = Datum::bytes_from_tuple(&datums);
-        let datums_to_check = Datum::tuple_from_bytes_with_schema(bytes.as_slice(), &schema);
+        let datums_to_check =
+            Datum::tuple_from_bytes_with_schema(bytes.as_slice(), &schema).unwrap();
         assert_eq!(datums, datums_to_check);
     }
+
+    #[test]
+    fn test_tuple_bytes_len() {
+        let tuples = vec![
+            vec![Datum::Int(Some(1)), Datum::VarChar(Some("foo".to_string()))],
+            vec![Datum::Int(None), Datum::VarChar(None), Datum::Float(None)],
+            vec![
+                Datum::Bool(Some(true)),
+                Datum::Date(NaiveDate::from_ymd_opt(2021, 1, 1)),
+                Datum::VarChar(Some(String::new())),
+            ],
+        ];
+        for tuple in tuples {
+            assert_eq!(
+                Datum::tuple_bytes_len(&tuple),
+                Datum::bytes_from_tuple(&tuple).len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_bool_order() {
+        assert!(Datum::Bool(Some(false)) < Datum::Bool(Some(true)));
+        assert!(Datum::Bool(None) < Datum::Bool(Some(false)));
+        let mut datums = vec![
+            Datum::Bool(Some(true)),
+            Datum::Bool(None),
+            Datum::Bool(Some(false)),
+            Datum::Bool(Some(true)),
+        ];
+        datums.sort();
+        assert_eq!(
+            datums,
+            vec![
+                Datum::Bool(None),
+                Datum::Bool(Some(false)),
+                Datum::Bool(Some(true)),
+                Datum::Bool(Some(true)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bool_to_from_bytes() {
+        let schema = Schema::from_type_and_names(&[
+            (DataType::new_as_bool(true), "v1".to_string()),
+            (DataType::new_as_bool(false), "v2".to_string()),
+            (DataType::new_as_int(false), "v3".to_string()),
+        ]);
+        for datums in [
+            vec![Datum::Bool(None), Datum::Bool(Some(true)), 1.into()],
+            vec![Datum::Bool(Some(false)), Datum::Bool(Some(false)), 2.into()],
+        ] {
+            let bytes = Datum::bytes_from_tuple(&datums);
+            assert_eq!(
+                Datum::tuple_from_bytes_with_schema(&bytes, &schema).unwrap(),
+                datums
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_bytes_mismatched_schema() {
+        let schema = Schema::from_type_and_names(&[
+            (DataType::new_as_int(false), "v1".to_string()),
+            (DataType::new_as_varchar(false), "v2".to_string()),
+        ]);
+        let datums = vec![Datum::Int(Some(1)), Datum::VarChar(Some("foo".to_string()))];
+        let bytes = Datum::bytes_from_tuple(&datums);
+        // shorter than the fixed part
+        assert!(matches!(
+            Datum::tuple_from_bytes_with_schema(&bytes[bytes.len() - 4..], &schema),
+            Err(DatumError::BytesTooShort(13, 4))
+        ));
+        assert!(Datum::tuple_from_bytes_with_schema(&[], &schema).is_err());
+        // the offsets of the varchar point out of the buffer
+        let mut corrupted = bytes;
+        let len = corrupted.len();
+        corrupted[len - 13..len - 9].copy_from_slice(&1000u32.to_le_bytes());
+        assert!(matches!(
+            Datum::tuple_from_bytes_with_schema(&corrupted, &schema),
+            Err(DatumError::InvalidOffset(1000, _))
+        ));
+        // the content of the varchar is truncated
+        let bytes = Datum::VarChar(Some("foo".to_string())).to_bytes();
+        assert!(matches!(
+            Datum::from_bytes_with_type(&bytes[..6], &DataType::new_as_varchar(false)),
+            Err(DatumError::BytesTooShort(8, 6))
+        ));
+        let bytes = Datum::Int(Some(1)).to_bytes();
+        assert!(Datum::from_bytes_with_type(&bytes[..3], &DataType::new_as_int(false)).is_err());
+        assert_eq!(
+            Datum::from_bytes_with_type(&bytes, &DataType::new_as_int(false)).unwrap(),
+            Datum::Int(Some(1))
+        );
+    }
+
+    #[test]
+    fn test_cmp_tuple_bytes_with_key() {
+        let schema = Schema::from_type_and_names(&[
+            (DataType::new_as_int(true), "v1".to_string()),
+            (DataType::new_as_varchar(true), "v2".to_string()),
+        ]);
+        let ints = vec![Datum::Int(None), Datum::Int(Some(-1)), Datum::Int(Some(2))];
+        let strs = vec![
+            Datum::VarChar(None),
+            Datum::VarChar(Some("".to_string())),
+            Datum::VarChar(Some("ab".to_string())),
+            Datum::VarChar(Some("abc".to_string())),
+            Datum::VarChar(Some("b".to_string())),
+        ];
+        let tuples = ints
+            .iter()
+            .cartesian_product(strs.iter())
+            .map(|(v1, v2)| vec![v1.clone(), v2.clone()])
+            .collect_vec();
+        // keys of a prefix or another type are ordered as the decoded tuple does
+        let keys = tuples
+            .iter()
+            .cloned()
+            .chain(ints.iter().map(|v1| vec![v1.clone()]))
+            .chain(std::iter::once(vec![
+                Datum::Int(Some(2)),
+                Datum::Int(Some(0)),
+            ]))
+            .collect_vec();
+        for tuple in tuples.iter() {
+            let bytes = Datum::bytes_from_tuple(tuple);
+            for key in keys.iter() {
+                assert_eq!(
+                    Datum::cmp_tuple_bytes_with_key(&bytes, &schema, key),
+                    tuple.as_slice().cmp(key.as_slice()),
+                    "{:?} with {:?}",
+                    tuple,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_successor() {
+        let datums = vec![
+            Datum::Int(None),
+            Datum::Int(Some(-1)),
+            Datum::VarChar(None),
+            Datum::VarChar(Some("ab".to_string())),
+            Datum::Bool(None),
+            Datum::Bool(Some(false)),
+            Datum::Float(None),
+            Datum::from(-1.5f32),
+            Datum::from(0.0f32),
+            Datum::from(2.5f32),
+            Datum::Date(None),
+            Datum::Date(NaiveDate::from_ymd_opt(2021, 12, 31)),
+        ];
+        for datum in datums {
+            let successor = datum.successor().unwrap();
+            assert!(successor > datum, "{:?}", datum);
+        }
+        assert_eq!(Datum::Int(Some(3)).successor(), Some(4.into()));
+        assert_eq!(
+            Datum::VarChar(Some("ab".to_string())).successor(),
+            Some("ab\0".into())
+        );
+        assert_eq!(Datum::Int(Some(i32::MAX)).successor(), None);
+        assert_eq!(Datum::Bool(Some(true)).successor(), None);
+        assert_eq!(Datum::from(f32::INFINITY).successor(), None);
+    }
 }
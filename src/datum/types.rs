@@ -60,6 +60,29 @@ impl DataType {
             | Self::Float(nullable) => *nullable,
         }
     }
+    pub fn with_nullable(&self, nullable: bool) -> Self {
+        match self {
+            Self::Int(_) => Self::Int(nullable),
+            Self::VarChar(_) => Self::VarChar(nullable),
+            Self::Bool(_) => Self::Bool(nullable),
+            Self::Date(_) => Self::Date(nullable),
+            Self::Float(_) => Self::Float(nullable),
+        }
+    }
+    /// the type values of both fit in, an int is widened to a float, nullable if either
+    /// is, none if they are incompatible
+    pub fn unify(&self, other: &Self) -> Option<Self> {
+        let nullable = self.nullable() || other.nullable();
+        match (self, other) {
+            (Self::Int(_), Self::Float(_)) | (Self::Float(_), Self::Int(_)) => {
+                Some(Self::Float(nullable))
+            }
+            (lhs, rhs) if lhs.with_nullable(false) == rhs.with_nullable(false) => {
+                Some(lhs.with_nullable(nullable))
+            }
+            _ => None,
+        }
+    }
     pub fn is_inlined(&self) -> bool {
         match self {
             Self::Bool(_) | Self::Int(_) | Self::Float(_) | Self::Date(_) => true,
@@ -95,3 +118,24 @@ pub enum DataTypeError {
     #[error("undefine datatype")]
     UndefinedDataType,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unify() {
+        let int = DataType::new_as_int(false);
+        let float = DataType::new_as_float(true);
+        let varchar = DataType::new_as_varchar(false);
+        assert_eq!(int.unify(&float), Some(DataType::new_as_float(true)));
+        assert_eq!(float.unify(&int), Some(DataType::new_as_float(true)));
+        assert_eq!(
+            int.unify(&DataType::new_as_int(true)),
+            Some(DataType::new_as_int(true))
+        );
+        assert_eq!(varchar.unify(&varchar), Some(varchar));
+        assert_eq!(int.unify(&varchar), None);
+        assert_eq!(DataType::new_as_date(false).unify(&varchar), None);
+    }
+}
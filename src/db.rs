@@ -1,17 +1,105 @@
 use crate::catalog::CatalogManager;
+use crate::error::ErrorCode;
 use crate::execution::{Engine, ExecutionError};
+use crate::expr::{FractionalInt, NullAggregation};
+use crate::parser::ast::Statement;
 use crate::parser::parse;
-use crate::planner::{PlanError, Planner};
-use crate::storage::{BufferPoolManager, BufferPoolManagerRef};
+use crate::planner::{PlanError, Planner, StatementRecord};
+use crate::storage::{BufferPoolManager, BufferPoolManagerRef, Synchronous};
 use crate::table::Table;
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Instant;
 use thiserror::Error;
 
 pub struct NaiveDB {
     bpm: BufferPoolManagerRef,
     engine: Engine,
     planner: Planner,
+    settings: SettingsRef,
+}
+
+/// settings of the session changed by pragmas, shared by the planner and the engine
+#[derive(Debug)]
+pub struct Settings {
+    /// how nulls are shown in the rendered results
+    pub null_display: String,
+    /// bools are rendered as 1/0
+    pub bool_as_int: bool,
+    /// how greatest and least treat null arguments
+    pub null_aggregation: NullAggregation,
+    /// whether dividing by zero fails the statement, or yields null otherwise
+    pub strict_division: bool,
+    /// what storing a fractional real into an int column does
+    pub fractional_int: FractionalInt,
+    /// how eagerly the changes of a statement are written back
+    pub synchronous: Synchronous,
+}
+
+pub type SettingsRef = Rc<RefCell<Settings>>;
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            null_display: "NULL".to_string(),
+            bool_as_int: false,
+            null_aggregation: NullAggregation::Strict,
+            strict_division: true,
+            fractional_int: FractionalInt::Error,
+            synchronous: Synchronous::Off,
+        }
+    }
+}
+
+impl Settings {
+    pub fn set(&mut self, setting: Setting) {
+        match setting {
+            Setting::NullDisplay(value) => self.null_display = value,
+            Setting::BoolAsInt(value) => self.bool_as_int = value,
+            Setting::NullAggregation(value) => self.null_aggregation = value,
+            Setting::DivisionByZero(strict) => self.strict_division = strict,
+            Setting::FractionalInt(value) => self.fractional_int = value,
+            Setting::Synchronous(value) => self.synchronous = value,
+        }
+    }
+}
+
+/// a setting of the session with the value a pragma gives it
+#[derive(Debug, Clone)]
+pub enum Setting {
+    NullDisplay(String),
+    BoolAsInt(bool),
+    NullAggregation(NullAggregation),
+    /// whether dividing by zero is an error, or yields null otherwise
+    DivisionByZero(bool),
+    FractionalInt(FractionalInt),
+    Synchronous(Synchronous),
+}
+
+impl Setting {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NullDisplay(_) => "null_display",
+            Self::BoolAsInt(_) => "bool_as_int",
+            Self::NullAggregation(_) => "null_aggregation",
+            Self::DivisionByZero(_) => "division_by_zero",
+            Self::FractionalInt(_) => "fractional_int",
+            Self::Synchronous(_) => "synchronous",
+        }
+    }
+    /// the value as the pragma takes it
+    pub fn value(&self) -> String {
+        match self {
+            Self::NullDisplay(value) => value.clone(),
+            Self::BoolAsInt(true) => "on".to_string(),
+            Self::BoolAsInt(false) => "off".to_string(),
+            Self::NullAggregation(value) => value.name().to_string(),
+            Self::DivisionByZero(true) => "error".to_string(),
+            Self::DivisionByZero(false) => "null".to_string(),
+            Self::FractionalInt(value) => value.name().to_string(),
+            Self::Synchronous(value) => value.name().to_string(),
+        }
+    }
 }
 
 impl NaiveDB {
@@ -21,39 +109,75 @@ impl NaiveDB {
     }
     #[allow(dead_code)]
     pub fn new_random() -> Self {
-        let bpm = BufferPoolManager::new_random_shared(4096);
-        let catalog = CatalogManager::new_shared(bpm.clone());
-        Self {
-            bpm: bpm.clone(),
-            engine: Engine::new(catalog.clone(), bpm),
-            planner: Planner::new(catalog),
-        }
+        Self::new_with_bpm(BufferPoolManager::new_random_shared(4096))
     }
     #[allow(dead_code)]
     pub fn new_with_name(filename: String) -> Self {
-        let bpm = Rc::new(RefCell::new(BufferPoolManager::new_with_name(
+        Self::new_with_bpm(Rc::new(RefCell::new(BufferPoolManager::new_with_name(
             4096, filename,
-        )));
-        let catalog = CatalogManager::new_shared(bpm.clone());
-        Self {
-            bpm: bpm.clone(),
-            engine: Engine::new(catalog.clone(), bpm),
-            planner: Planner::new(catalog),
-        }
+        ))))
     }
     pub fn new() -> Self {
-        let bpm = BufferPoolManager::new_shared(64 * 1024);
+        Self::new_with_bpm(BufferPoolManager::new_shared(64 * 1024))
+    }
+    fn new_with_bpm(bpm: BufferPoolManagerRef) -> Self {
         let catalog = CatalogManager::new_shared(bpm.clone());
+        let settings = Rc::new(RefCell::new(Settings::default()));
         Self {
             bpm: bpm.clone(),
-            engine: Engine::new(catalog.clone(), bpm),
-            planner: Planner::new(catalog),
+            engine: Engine::new(catalog.clone(), bpm.clone(), settings.clone()),
+            planner: Planner::new(catalog, bpm, settings.clone()),
+            settings,
         }
     }
+    /// how nulls are shown when rendering the results
+    pub fn null_display(&self) -> String {
+        self.settings.borrow().null_display.clone()
+    }
+    /// whether bools are shown as 1/0 when rendering the results
+    pub fn bool_as_int(&self) -> bool {
+        self.settings.borrow().bool_as_int
+    }
+    /// warnings of the last statement other than show warnings
+    #[allow(dead_code)]
+    pub fn warnings(&self) -> Vec<String> {
+        self.planner.warnings()
+    }
+    /// the last statements run successfully, the oldest first
+    #[allow(dead_code)]
+    pub fn history(&self) -> Vec<StatementRecord> {
+        self.planner.history()
+    }
     pub fn run(&mut self, sql: &str) -> Result<Table, NaiveDBError> {
+        let start = Instant::now();
         let stmt = parse(sql)?;
+        let show_history = matches!(stmt, Statement::ShowHistory);
+        let changes_data = matches!(
+            stmt,
+            Statement::Insert(_)
+                | Statement::Update(_)
+                | Statement::Delete(_)
+                | Statement::LoadFromFile(_)
+        );
+        if !matches!(stmt, Statement::ShowWarnings) {
+            self.planner.clear_warnings();
+        }
         let plan = self.planner.plan(stmt)?;
         let table = self.engine.execute(plan)?;
+        if changes_data {
+            self.bpm
+                .borrow_mut()
+                .sync_changes(self.settings.borrow().synchronous)
+                .map_err(ExecutionError::from)?;
+        }
+        // show history does not show itself
+        if !show_history {
+            self.planner.record(StatementRecord {
+                sql: sql.trim().to_string(),
+                elapsed: start.elapsed(),
+                rows: table.iter().map(|slice| slice.count()).sum(),
+            });
+        }
         Ok(table)
     }
 }
@@ -68,10 +192,25 @@ pub enum NaiveDBError {
     Plan(#[from] PlanError),
 }
 
+impl NaiveDBError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Parse(_) => ErrorCode::Parse,
+            Self::Execution(err) => err.code(),
+            Self::Plan(err) => err.code(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::datum::Datum;
-    use crate::db::NaiveDB;
+    use crate::datum::{DataType, Datum};
+    use crate::db::{NaiveDB, NaiveDBError};
+    use crate::error::ErrorCode;
+    use crate::index::BPTIndex;
+    use crate::parser::parse;
+    use crate::planner::{Plan, PlanError, HISTORY_SIZE};
+    use crate::table::Table;
     use chrono::NaiveDate;
     use itertools::Itertools;
     use rand::Rng;
@@ -431,4 +570,3399 @@ mod tests {
         };
         remove_file(filename).unwrap();
     }
+
+    #[test]
+    fn test_bool_index() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 bool null);")
+                .unwrap();
+            db.run("alter table t add index (v2);").unwrap();
+            db.run("insert into t values (1, true), (2, null), (3, false);")
+                .unwrap();
+            let table = db.run("select * from t where v2 > false;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![Datum::Int(Some(1)), true.into()]]);
+            let table = db.run("select v1 from t where v2 >= false;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![vec![Datum::Int(Some(3))], vec![Datum::Int(Some(1))]]
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_exists() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table a (x int not null, y varchar not null);")
+                .unwrap();
+            db.run("create table b (x int not null, z int not null);")
+                .unwrap();
+            db.run("insert into a values (1, 'foo'), (2, 'bar'), (3, 'baz');")
+                .unwrap();
+            db.run("insert into b values (1, 10), (3, 30), (3, 31), (4, 40);")
+                .unwrap();
+            let table = db
+                .run("select * from a where exists (select 1 from b where b.x = a.x);")
+                .unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![vec![1.into(), "foo".into()], vec![3.into(), "baz".into()]]
+            );
+            // one scratch page is used for all the outer rows, and freed afterwards
+            assert_eq!(db.bpm.borrow_mut().free_page_ids().unwrap().len(), 1);
+            let table = db
+                .run("select y from a where exists (select 1 from b where b.x = a.x and z > 30);")
+                .unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec!["baz".into()]]);
+            assert_eq!(db.bpm.borrow_mut().free_page_ids().unwrap().len(), 1);
+            let table = db
+                .run("select * from a where exists (select 1 from b where z > 100);")
+                .unwrap();
+            assert_eq!(table.iter().map(|s| s.count()).sum::<usize>(), 0);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_scalar_subquery() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table a (v1 int not null);").unwrap();
+            db.run("create table b (v2 int not null);").unwrap();
+            db.run("insert into a values (1), (2);").unwrap();
+            db.run("insert into b values (10), (20), (30);").unwrap();
+            let table = db
+                .run("select v1, (select count(*) from b) from a;")
+                .unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![vec![1.into(), 3.into()], vec![2.into(), 3.into()]]
+            );
+            let table = db
+                .run("select v1 from a where v1 = (select count(*) from a);")
+                .unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![2.into()]]);
+            assert!(db.run("select v1, (select v2 from b) from a;").is_err());
+            assert!(db.run("select v1, (select * from a, b) from a;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_update_indexed_columns() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar not null, v3 int not null, primary key (v1));")
+                .unwrap();
+            db.run("alter table t add index (v3);").unwrap();
+            db.run("insert into t values (1, 'foo', 10), (2, 'bar', 20), (3, 'baz', 30);")
+                .unwrap();
+            // collides with another row, the whole update is aborted
+            assert!(db
+                .run("update t set v1 = 2, v2 = 'qux' where v1 = 1;")
+                .is_err());
+            assert!(db.run("update t set v3 = 30 where v1 < 3;").is_err());
+            let table = db.run("select * from t;").unwrap();
+            let mut tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            tuples.sort();
+            assert_eq!(
+                tuples,
+                vec![
+                    vec![1.into(), "foo".into(), 10.into()],
+                    vec![2.into(), "bar".into(), 20.into()],
+                    vec![3.into(), "baz".into(), 30.into()],
+                ]
+            );
+            db.run("update t set v1 = 4, v2 = 'qux' where v1 = 1;")
+                .unwrap();
+            let table = db.run("select v2, v3 from t where v1 = 4;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec!["qux".into(), 10.into()]]);
+            let table = db.run("select * from t where v1 = 1;").unwrap();
+            assert_eq!(table.iter().map(|s| s.count()).sum::<usize>(), 0);
+            let table = db.run("select v1 from t where v3 = 10;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![4.into()]]);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_date_add() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 date null);")
+                .unwrap();
+            db.run("insert into t values (1, 2021-01-28), (2, null);")
+                .unwrap();
+            let table = db.run("select v1, date_add(v2, 7) from t;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![
+                    vec![1.into(), NaiveDate::from_str("2021-02-04").unwrap().into()],
+                    vec![2.into(), Datum::Date(None)],
+                ]
+            );
+            let table = db
+                .run("select date_add(v2, -28) from t where v1 = 1;")
+                .unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![vec![NaiveDate::from_str("2020-12-31").unwrap().into()]]
+            );
+            let table = db
+                .run("select v1 from t where date_add(v2, 4) = 2021-02-01;")
+                .unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![1.into()]]);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_drop_truncate_referenced() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table p (v1 int not null, primary key (v1));")
+                .unwrap();
+            db.run("create table c (v1 int not null, foreign key (v1) references p (v1));")
+                .unwrap();
+            db.run("create table u (v1 int not null);").unwrap();
+            db.run("insert into p values (1), (2), (3);").unwrap();
+            db.run("insert into c values (1), (3);").unwrap();
+            db.run("insert into u values (1);").unwrap();
+            // referenced by c
+            assert!(db.run("drop table p;").is_err());
+            assert!(db.run("truncate table p;").is_err());
+            assert!(db.run("drop table u;").is_ok());
+            // truncating the child releases the references
+            db.run("truncate table c;").unwrap();
+            let table = db.run("select * from c;").unwrap();
+            assert_eq!(table.iter().map(|s| s.count()).sum::<usize>(), 0);
+            assert!(db.run("delete from p where v1 = 1;").is_ok());
+            db.run("insert into c values (3);").unwrap();
+            db.run("truncate table p cascade;").unwrap();
+            let table = db.run("select * from c;").unwrap();
+            assert_eq!(table.iter().map(|s| s.count()).sum::<usize>(), 0);
+            db.run("insert into p values (1);").unwrap();
+            assert!(db.run("insert into p values (1);").is_err());
+            db.run("drop table p cascade;").unwrap();
+            assert!(db.run("insert into c values (4);").is_ok());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_pragma_index_stats() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int not null);")
+                .unwrap();
+            db.run("alter table t add index (v1);").unwrap();
+            for idx in 0..100 {
+                db.run(&format!("insert into t values ({}, {});", idx, idx))
+                    .unwrap();
+            }
+            let table = db.run("pragma index_stats t (v1);").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples.len(), 1);
+            assert_eq!(tuples[0][3], 100.into());
+            assert!(db.run("pragma index_stats t (v2);").is_err());
+            assert!(db.run("pragma no_such_pragma;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_in_subquery() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table a (v1 int not null, v2 varchar not null);")
+                .unwrap();
+            db.run("create table b (x int not null, y int not null);")
+                .unwrap();
+            db.run("insert into a values (1, 'foo'), (2, 'bar'), (3, 'baz'), (4, 'qux');")
+                .unwrap();
+            db.run("insert into b values (1, 10), (3, 30), (3, 31), (5, 50);")
+                .unwrap();
+            let table = db
+                .run("select * from a where v1 in (select x from b);")
+                .unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![vec![1.into(), "foo".into()], vec![3.into(), "baz".into()]]
+            );
+            let table = db
+                .run("select v2 from a where v1 in (select x from b where y > 30);")
+                .unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec!["baz".into()]]);
+            let table = db
+                .run("select * from a where v1 in (select x from b where y > 100);")
+                .unwrap();
+            assert_eq!(table.iter().map(|s| s.count()).sum::<usize>(), 0);
+            assert!(db
+                .run("select * from a where v1 in (select x, y from b);")
+                .is_err());
+            // a null among the values leaves the missing ones unknown
+            db.run("create table c (x int null, f float null);")
+                .unwrap();
+            db.run("insert into c values (1, 1.0), (null, 3.0);")
+                .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select(&mut db, "select v1 from a where v1 in (select x from c);"),
+                vec![vec![1.into()]]
+            );
+            assert!(select(
+                &mut db,
+                "select v1 from a where not (v1 in (select x from c));"
+            )
+            .is_empty());
+            // ints match the equal floats as by =
+            assert_eq!(
+                select(&mut db, "select v1 from a where v1 in (select f from c);"),
+                vec![vec![1.into()], vec![3.into()]]
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_constant_folding() {
+        use crate::parser::parse;
+        use crate::planner::Plan;
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null);").unwrap();
+            db.run("insert into t values (1), (4), (6), (9);").unwrap();
+            // always-true predicate is dropped from the plan
+            let plan = db
+                .planner
+                .plan(parse("select * from t where 2 + 3 = 5;").unwrap())
+                .unwrap();
+            assert!(!matches!(plan, Plan::Filter(_)));
+            let table = db.run("select * from t where 2 + 3 = 5;").unwrap();
+            assert_eq!(table.iter().map(|s| s.count()).sum::<usize>(), 4);
+            // always-false predicate is folded into a single constant
+            let plan = db
+                .planner
+                .plan(parse("select * from t where 1 = 2;").unwrap())
+                .unwrap();
+            match plan {
+                Plan::Filter(plan) => assert_eq!(
+                    plan.exprs
+                        .iter()
+                        .map(|expr| expr.as_constant())
+                        .collect_vec(),
+                    vec![Some(Datum::Bool(Some(false)))]
+                ),
+                _ => panic!("not filter plan"),
+            }
+            let table = db.run("select * from t where 1 = 2;").unwrap();
+            assert_eq!(table.iter().map(|s| s.count()).sum::<usize>(), 0);
+            let table = db.run("select * from t where v1 > 2 + 3;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![6.into()], vec![9.into()]]);
+            let table = db.run("select v1 * 2 - 1 from t where v1 < 5;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![1.into()], vec![7.into()]]);
+            // arithmetic over no numbers is rejected before anything is evaluated
+            assert!(db.run("select v1 + 'a' from t;").is_err());
+            assert!(db.run("select * from t where 'a' + 1 = 2;").is_err());
+            assert!(db.run("select * from t where true * 2 = 2;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_analyze() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 date);")
+                .unwrap();
+            db.run("insert into t values (3, 2021-01-02), (-5, null), (8, 2020-12-31), (3, null);")
+                .unwrap();
+            let table = db.run("analyze t;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![
+                    vec!["v1".into(), "-5".into(), "8".into(), 0.into(), 3.into()],
+                    vec![
+                        "v2".into(),
+                        "2020-12-31".into(),
+                        "2021-01-02".into(),
+                        2.into(),
+                        2.into()
+                    ],
+                ]
+            );
+            assert!(db.run("analyze not_exist;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_show_warnings() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 float not null);")
+                .unwrap();
+            db.run("pragma fractional_int = truncate;").unwrap();
+            db.run("insert into t values (1.5, 1.5), (2, 2.5);")
+                .unwrap();
+            assert_eq!(db.warnings().len(), 1);
+            let table = db.run("show warnings;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![vec!["Value 1.5 Truncated To Fit Int Column v1".into()]]
+            );
+            // show warnings keeps the warnings of the last statement
+            let table = db.run("show warnings;").unwrap();
+            assert_eq!(table.iter().map(|s| s.count()).sum::<usize>(), 1);
+            db.run("select * from t;").unwrap();
+            let table = db.run("show warnings;").unwrap();
+            assert_eq!(table.iter().map(|s| s.count()).sum::<usize>(), 0);
+            // the warnings of a long insert span several slices
+            let values = (0..1000).map(|idx| format!("({}.5, 1.5)", idx)).join(", ");
+            db.run(&format!("insert into t values {};", values))
+                .unwrap();
+            let table = db.run("show warnings;").unwrap();
+            assert!(table.iter().count() > 1);
+            assert_eq!(table.iter().map(|s| s.count()).sum::<usize>(), 1000);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_create_view() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar not null);")
+                .unwrap();
+            db.run("insert into t values (1, 'a'), (2, 'b'), (3, 'c'), (4, 'd');")
+                .unwrap();
+            db.run("create view v as select v1, v2 from t where v1 > 1;")
+                .unwrap();
+            assert!(db.run("create view v as select * from t;").is_err());
+            // a table and a view can not take the same name
+            assert!(db.run("create table v (v1 int);").is_err());
+            assert!(db.run("create view t as select * from t;").is_err());
+            let table = db.run("select * from v;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![
+                    vec![2.into(), "b".into()],
+                    vec![3.into(), "c".into()],
+                    vec![4.into(), "d".into()],
+                ]
+            );
+            let table = db.run("select v.v2 from v where v1 < 4;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec!["b".into()], vec!["c".into()]]);
+            // the view follows the changes of its table
+            db.run("insert into t values (5, 'e');").unwrap();
+            let table = db.run("select v1 from v where v1 > 4;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![5.into()]]);
+            // views are read-only and not listed as tables
+            assert!(db.run("insert into v values (6, 'f');").is_err());
+            let table = db.run("show tables;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec!["t".into()]]);
+            db.run("drop view v;").unwrap();
+            assert!(db.run("select * from v;").is_err());
+            assert!(db.run("drop view v;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_trim() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar null);")
+                .unwrap();
+            db.run("insert into t values (1, '  a b  '), (2, null), (3, 'xxcxx');")
+                .unwrap();
+            let table = db
+                .run("select trim(v2), ltrim(v2), rtrim(v2) from t;")
+                .unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![
+                    vec!["a b".into(), "a b  ".into(), "  a b".into()],
+                    vec![
+                        Datum::VarChar(None),
+                        Datum::VarChar(None),
+                        Datum::VarChar(None)
+                    ],
+                    vec!["xxcxx".into(), "xxcxx".into(), "xxcxx".into()],
+                ]
+            );
+            let table = db
+                .run("select trim(v2, 'x'), ltrim(v2, 'x') from t where v1 = 3;")
+                .unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec!["c".into(), "cxx".into()]]);
+            let table = db.run("select v1 from t where trim(v2) = 'a b';").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![1.into()]]);
+            assert!(db.run("select trim() from t;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_replace() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar null);")
+                .unwrap();
+            db.run("insert into t values (1, 'abcab'), (2, 'xyz'), (3, null);")
+                .unwrap();
+            let table = db.run("select replace(v2, 'ab', 'd') from t;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![
+                    vec!["dcd".into()],
+                    vec!["xyz".into()],
+                    vec![Datum::VarChar(None)],
+                ]
+            );
+            assert!(db.run("select replace(v2, 'a') from t;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_instr() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar null);")
+                .unwrap();
+            db.run("insert into t values (1, 'abxcx'), (2, 'abc'), (3, null);")
+                .unwrap();
+            let table = db.run("select instr(v2, 'x') from t;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![vec![3.into()], vec![0.into()], vec![Datum::Int(None)]]
+            );
+            let table = db
+                .run("select v1 from t where instr(v2, 'bc') = 2;")
+                .unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![2.into()]]);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_repeat() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int null, v2 varchar null);")
+                .unwrap();
+            db.run("insert into t values (3, 'ab'), (0, 'x'), (null, 'y'), (2, null);")
+                .unwrap();
+            let table = db.run("select repeat(v2, v1) from t;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![
+                    vec!["ababab".into()],
+                    vec!["".into()],
+                    vec![Datum::VarChar(None)],
+                    vec![Datum::VarChar(None)],
+                ]
+            );
+            let tuples = db
+                .run("select repeat(v2, 1000) from t where v1 = 3;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec!["ab".repeat(1000).as_str().into()]]);
+            // a too long result is an error for a constant count and a column one alike
+            assert_eq!(
+                db.run("select repeat(v2, 20000) from t;")
+                    .unwrap_err()
+                    .code(),
+                ErrorCode::InvalidArgument
+            );
+            assert_eq!(
+                db.run("select repeat(v2, 100000000) from t;")
+                    .unwrap_err()
+                    .code(),
+                ErrorCode::InvalidArgument
+            );
+            db.run("insert into t values (100000, 'z');").unwrap();
+            assert_eq!(
+                db.run("select repeat(v2, v1) from t;").unwrap_err().code(),
+                ErrorCode::InvalidArgument
+            );
+            assert!(db
+                .run("select repeat(v2, v1) from t where v1 < 10;")
+                .is_ok());
+            assert!(db.run("select repeat(v1, 2) from t;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_sign_mod() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int null, v2 float null);")
+                .unwrap();
+            db.run("insert into t values (7, 2.5), (-7, -2.5), (0, 0), (null, null);")
+                .unwrap();
+            let mut select = |sql: &str| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select("select sign(v1), sign(v2) from t;"),
+                vec![
+                    vec![1.into(), 1.into()],
+                    vec![(-1).into(), (-1).into()],
+                    vec![0.into(), 0.into()],
+                    vec![Datum::Int(None), Datum::Int(None)],
+                ]
+            );
+            assert_eq!(
+                select("select mod(v1, 3) from t;"),
+                vec![
+                    vec![1.into()],
+                    vec![(-1).into()],
+                    vec![0.into()],
+                    vec![Datum::Int(None)],
+                ]
+            );
+            assert_eq!(
+                select("select mod(v1, v2) from t where v2 > 1;"),
+                vec![vec![2.0f32.into()]]
+            );
+            assert_eq!(
+                select("select mod(v1, v2) from t where v2 < 0;"),
+                vec![vec![(-2.0f32).into()]]
+            );
+            assert_eq!(
+                db.run("select mod(v1, v2) from t;").unwrap_err().code(),
+                ErrorCode::InvalidArgument
+            );
+            // the rows taking the other branch never report their zero divisors
+            let tuples = db
+                .run("select case when v2 = 0 then 0 else mod(v1, v2) end from t where v1 >= 0;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![2.0f32.into()], vec![0.0f32.into()]]);
+            db.run("pragma division_by_zero = null;").unwrap();
+            let tuples = db
+                .run("select mod(v1, 0), mod(v2, 0) from t;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert!(tuples
+                .iter()
+                .all(|t| t == &vec![Datum::Int(None), Datum::Float(None)]));
+            assert!(db.run("select mod(v1, 'a') from t;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_not() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 bool null);")
+                .unwrap();
+            db.run("insert into t values (1, true), (2, false), (3, null);")
+                .unwrap();
+            let mut select = |sql: &str| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select("select v1 from t where not (v1 = 1);"),
+                vec![vec![2.into()], vec![3.into()]]
+            );
+            assert_eq!(
+                select("select v1 from t where not (not (v1 = 1));"),
+                vec![vec![1.into()]]
+            );
+            // not null is null, which never passes the filter
+            assert_eq!(
+                select("select v1 from t where not (v2);"),
+                vec![vec![2.into()]]
+            );
+            assert_eq!(
+                select("select not (v2) from t;"),
+                vec![
+                    vec![false.into()],
+                    vec![true.into()],
+                    vec![Datum::Bool(None)],
+                ]
+            );
+            assert_eq!(
+                db.run("select v1 from t where not (v1);")
+                    .unwrap_err()
+                    .code(),
+                ErrorCode::TypeMismatch
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_compare_with_null() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int null, v2 varchar null);")
+                .unwrap();
+            db.run("insert into t values (1, 'a'), (null, 'b'), (3, null);")
+                .unwrap();
+            let mut select = |sql: &str| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert!(select("select * from t where v1 = null;").is_empty());
+            assert!(select("select * from t where null = null;").is_empty());
+            assert_eq!(
+                select("select v2 from t where v1 is null;"),
+                vec![vec!["b".into()]]
+            );
+            assert_eq!(
+                select("select v1 from t where v2 is not null;"),
+                vec![vec![1.into()], vec![Datum::Int(None)]]
+            );
+            assert_eq!(
+                select("select v1 = 1, v1 < 2, v2 >= 'a', v1 is null from t;"),
+                vec![
+                    vec![true.into(), true.into(), true.into(), false.into()],
+                    vec![
+                        Datum::Bool(None),
+                        Datum::Bool(None),
+                        true.into(),
+                        true.into()
+                    ],
+                    vec![false.into(), false.into(), Datum::Bool(None), false.into()],
+                ]
+            );
+            // the rows compared null are neither in the result nor in its negation
+            assert_eq!(
+                select("select v1 from t where v1 < 2;"),
+                vec![vec![1.into()]]
+            );
+            assert_eq!(
+                select("select v1 from t where not (v1 < 2);"),
+                vec![vec![3.into()]]
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_dump_load_binary() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 float null, v3 varchar null, v4 date null, v5 bool null);")
+                .unwrap();
+            db.run("create table t1 (v1 int not null, v2 float null, v3 varchar null, v4 date null, v5 bool null);")
+                .unwrap();
+            db.run("insert into t values (1, 0.1, 'a', 2021-01-01, true), (2, null, null, null, null), (3, 2.5, 'bc', 2020-12-31, false);")
+                .unwrap();
+            let collect = |db: &mut NaiveDB, sql: &str| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            db.run("select * from t;")
+                .unwrap()
+                .dump_binary("dump.bin")
+                .unwrap();
+            db.run("load binary infile dump.bin into table t1;")
+                .unwrap();
+            assert_eq!(
+                collect(&mut db, "select * from t1;"),
+                collect(&mut db, "select * from t;")
+            );
+            // the dump does not fit a table with other columns
+            db.run("create table t2 (v1 int not null);").unwrap();
+            assert!(db
+                .run("load binary infile dump.bin into table t2;")
+                .is_err());
+            remove_file("dump.bin").unwrap();
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_pragma_foreign_keys() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, primary key (v1));")
+                .unwrap();
+            db.run("create table t1 (v1 int not null, v2 int not null, primary key (v1), foreign key (v2) references t (v1));").unwrap();
+            assert!(db.run("insert into t1 values (1, 1);").is_err());
+            // the child is loaded before its parent
+            db.run("pragma foreign_keys = off;").unwrap();
+            db.run("insert into t1 values (1, 1), (2, 1), (3, 2);")
+                .unwrap();
+            // the parent of (3, 2) is still missing, so the checks stay off
+            assert!(db.run("pragma foreign_keys = on;").is_err());
+            db.run("insert into t values (1), (2);").unwrap();
+            db.run("pragma foreign_keys = on;").unwrap();
+            // references are counted again once the checks are back
+            assert!(db.run("delete from t where v1 = 1;").is_err());
+            db.run("delete from t1 where v2 = 1;").unwrap();
+            db.run("delete from t where v1 = 1;").unwrap();
+            assert!(db.run("delete from t where v1 = 2;").is_err());
+            assert!(db.run("pragma foreign_keys = maybe;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_radix_literals() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null);").unwrap();
+            db.run("insert into t values (0xFF), (0b10), (0x7fffffff);")
+                .unwrap();
+            let table = db.run("select * from t;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![vec![255.into()], vec![2.into()], vec![i32::MAX.into()]]
+            );
+            let table = db.run("select v1 from t where v1 = 0b10;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![2.into()]]);
+            assert!(db.run("insert into t values (0x100000000);").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_agg_over_empty_input() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int not null);")
+                .unwrap();
+            let table = db.run("select v1, sum(v2) from t group by v1;").unwrap();
+            assert_eq!(table.iter().map(|s| s.count()).sum::<usize>(), 0);
+            db.run("insert into t values (1, 2), (1, 3), (2, 4);")
+                .unwrap();
+            let table = db
+                .run("select v1, max(v2) from t where v2 > 10 group by v1;")
+                .unwrap();
+            assert_eq!(table.iter().map(|s| s.count()).sum::<usize>(), 0);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_global_agg_over_empty_input() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int not null);")
+                .unwrap();
+            db.run("insert into t values (1, 2), (1, 3), (2, 4);")
+                .unwrap();
+            let tuples = db
+                .run("select count(*), sum(v2), max(v2) from t where v2 > 10;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![vec![0.into(), Datum::Int(None), Datum::Int(None)]]
+            );
+            let tuples = db
+                .run("select count(*) from t where v1 = 3;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![0.into()]]);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_max_min_date_varchar() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 date null, v2 varchar null);")
+                .unwrap();
+            db.run("insert into t values (null, 'b'), (2021-12-01, null), (2022-01-01, 'ab'), (null, null), (1999-06-30, 'c');")
+                .unwrap();
+            let tuples = db
+                .run("select max(v1), min(v1), max(v2), min(v2) from t;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![vec![
+                    NaiveDate::from_ymd(2022, 1, 1).into(),
+                    NaiveDate::from_ymd(1999, 6, 30).into(),
+                    "c".into(),
+                    "ab".into(),
+                ]]
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_error_code() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            assert_eq!(
+                db.run("select * from t;").unwrap_err().code(),
+                ErrorCode::NotUsingDatabase
+            );
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, primary key (v1));")
+                .unwrap();
+            db.run("insert into t values (1);").unwrap();
+            let duplicated = db.run("insert into t values (1);").unwrap_err().code();
+            let missing = db.run("select * from not_exist;").unwrap_err().code();
+            assert_eq!(duplicated, ErrorCode::DuplicateKey);
+            assert_eq!(missing, ErrorCode::NotFound);
+            assert_ne!(duplicated as u32, missing as u32);
+            assert_eq!(
+                db.run("select from t;").unwrap_err().code(),
+                ErrorCode::Parse
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_where_bool_literal() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, primary key (v1));")
+                .unwrap();
+            db.run("insert into t values (1), (2), (3);").unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert!(select(&mut db, "select * from t where false;").is_empty());
+            assert!(select(&mut db, "select * from t where v1 > 1 and false;").is_empty());
+            assert_eq!(
+                select(&mut db, "select * from t where true;"),
+                vec![vec![1.into()], vec![2.into()], vec![3.into()]]
+            );
+            assert_eq!(
+                select(&mut db, "select * from t where true and v1 < 3;"),
+                vec![vec![1.into()], vec![2.into()]]
+            );
+            db.run("delete from t where false;").unwrap();
+            assert_eq!(select(&mut db, "select * from t;").len(), 3);
+            db.run("delete from t where true;").unwrap();
+            assert!(select(&mut db, "select * from t;").is_empty());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_untyped_null() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 date null, v3 varchar null);")
+                .unwrap();
+            db.run("insert into t values (1, null, null), (2, 2021-01-01, 'a');")
+                .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select(&mut db, "select v2, v3 from t where v1 = 1;"),
+                vec![vec![Datum::Date(None), Datum::VarChar(None)]]
+            );
+            assert_eq!(
+                select(&mut db, "select null from t;"),
+                vec![vec![Datum::Int(None)], vec![Datum::Int(None)]]
+            );
+            assert_eq!(
+                select(&mut db, "select v1, null + v1 from t;"),
+                vec![
+                    vec![1.into(), Datum::Int(None)],
+                    vec![2.into(), Datum::Int(None)]
+                ]
+            );
+            // a null predicate passes no tuple, a predicate must be a bool
+            assert!(select(&mut db, "select * from t where null;").is_empty());
+            let err = db.run("select * from t where v1;").unwrap_err();
+            assert_eq!(err.code(), ErrorCode::TypeMismatch);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_create_table_as_select() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t1 (v1 int not null, v2 varchar not null, primary key (v1));")
+                .unwrap();
+            db.run("insert into t1 values (3, 'a'), (6, 'b'), (9, 'c');")
+                .unwrap();
+            let inserted = db
+                .run("create table t2 as select v1 from t1 where v1 > 5;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(inserted, vec![vec![2.into()]]);
+            let columns = db
+                .run("desc t2;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .map(|tuple| tuple[0].clone())
+                .collect_vec();
+            assert_eq!(columns, vec!["v1".into()]);
+            let tuples = db
+                .run("select * from t2;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![6.into()], vec![9.into()]]);
+            assert!(db.run("create table t2 as select * from t1;").is_err());
+            // a failed query leaves no table behind
+            assert!(db.run("create table t3 as select v1 / 0 from t1;").is_err());
+            assert!(db.run("select * from t3;").is_err());
+            db.run("create table t3 as select v1 from t1;").unwrap();
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_rename_column() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int not null, primary key (v1));")
+                .unwrap();
+            db.run("alter table t add index (v2, v1);").unwrap();
+            db.run("insert into t values (1, 10), (2, 20), (3, 30);")
+                .unwrap();
+            assert!(db.run("alter table t rename column v1 to v2;").is_err());
+            assert!(db.run("alter table t rename column v3 to v4;").is_err());
+            db.run("alter table t rename column v1 to id;").unwrap();
+            let columns = db
+                .run("desc t;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .map(|tuple| tuple[0].clone())
+                .collect_vec();
+            assert_eq!(columns, vec!["id".into(), "v2".into()]);
+            assert!(db.run("select v1 from t;").is_err());
+            let tuples = db
+                .run("select v2 from t where id = 2;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![20.into()]]);
+            // the primary index still rejects duplicated keys
+            assert!(db.run("insert into t values (2, 40);").is_err());
+            db.run("alter table t drop index (v2, id);").unwrap();
+            // the columns a check refers to keep their names
+            db.run("create table c (v1 int not null, v2 int not null, check (v1 > 0));")
+                .unwrap();
+            assert!(matches!(
+                db.run("alter table c rename column v1 to w;"),
+                Err(NaiveDBError::Plan(PlanError::ColumnInCheck(_)))
+            ));
+            db.run("alter table c rename column v2 to w;").unwrap();
+            db.run("insert into c values (1, 2);").unwrap();
+            assert!(db.run("insert into c values (0, 2);").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_index_bound_coercion() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, primary key (v1));")
+                .unwrap();
+            db.run("insert into t values (1), (2), (3), (4), (5), (6);")
+                .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select(&mut db, "select * from t where v1 < 5.0;"),
+                vec![
+                    vec![1.into()],
+                    vec![2.into()],
+                    vec![3.into()],
+                    vec![4.into()]
+                ]
+            );
+            assert_eq!(
+                select(&mut db, "select * from t where v1 >= 2.0 and v1 <= 4.0;"),
+                vec![vec![2.into()], vec![3.into()], vec![4.into()]]
+            );
+            // a fractional bound is widened for the scan and applied exactly by the filter
+            assert_eq!(
+                select(&mut db, "select * from t where v1 < 4.5;"),
+                vec![
+                    vec![1.into()],
+                    vec![2.into()],
+                    vec![3.into()],
+                    vec![4.into()]
+                ]
+            );
+            assert_eq!(
+                select(&mut db, "select * from t where v1 >= 2.5;"),
+                vec![
+                    vec![3.into()],
+                    vec![4.into()],
+                    vec![5.into()],
+                    vec![6.into()]
+                ]
+            );
+            assert_eq!(
+                select(&mut db, "select * from t where v1 > 1.5 and v1 <= 3.5;"),
+                vec![vec![2.into()], vec![3.into()]]
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_join_predicates() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table a (v1 int);").unwrap();
+            db.run("create table b (v1 int, v2 int);").unwrap();
+            db.run("create table c (v2 int);").unwrap();
+            db.run("insert into a values (1), (2), (3), (null);")
+                .unwrap();
+            db.run("insert into b values (1, 10), (3, 30), (null, 40);")
+                .unwrap();
+            db.run("insert into c values (10), (20), (30);").unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            // null keys never match
+            assert_eq!(
+                select(&mut db, "select a.v1, b.v2 from a, b where a.v1 = b.v1;"),
+                vec![vec![1.into(), 10.into()], vec![3.into(), 30.into()]]
+            );
+            // the page the pairs are checked in is freed, and reused by the next join
+            assert_eq!(db.bpm.borrow_mut().free_page_ids().unwrap().len(), 1);
+            select(&mut db, "select a.v1, b.v2 from a, b where a.v1 = b.v1;");
+            assert_eq!(db.bpm.borrow_mut().free_page_ids().unwrap().len(), 1);
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select a.v1, c.v2 from a, b, c where a.v1 = b.v1 and b.v2 = c.v2 and c.v2 > 10;"
+                ),
+                vec![vec![3.into(), 30.into()]]
+            );
+            assert!(select(&mut db, "select * from a, b where false;").is_empty());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_pragma_wal() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("pragma wal = on;").unwrap();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, primary key (v1));")
+                .unwrap();
+            db.run("insert into t values (1), (2), (3);").unwrap();
+            // crash, nothing is written back on drop
+            std::mem::forget(db);
+            filename
+        };
+        {
+            let mut db = NaiveDB::new_with_name(filename.clone());
+            db.run("use d;").unwrap();
+            let tuples = db
+                .run("select * from t;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![1.into()], vec![2.into()], vec![3.into()]]);
+            assert!(db.run("pragma wal = maybe;").is_err());
+            db.run("pragma wal = off;").unwrap();
+        }
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("pragma wal = on;").unwrap();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int);").unwrap();
+            db.run("insert into t values (1), (2);").unwrap();
+            let table = db.run("checkpoint;").unwrap();
+            let truncated = table.into_slice()[0].tuple_iter().collect_vec();
+            assert_ne!(truncated, vec![vec![0.into()]]);
+            let wal_filename = format!("{}.wal", filename);
+            assert_eq!(std::fs::metadata(&wal_filename).unwrap().len(), 0);
+            // crash right after the checkpoint
+            std::mem::forget(db);
+            filename
+        };
+        {
+            let mut db = NaiveDB::new_with_name(filename.clone());
+            db.run("use d;").unwrap();
+            let tuples = db
+                .run("select * from t;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![1.into()], vec![2.into()]]);
+            db.run("pragma wal = off;").unwrap();
+        }
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_explain_join() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table a (v1 int not null, primary key (v1));")
+                .unwrap();
+            db.run("create table b (v1 int not null, v2 int not null, primary key (v1));")
+                .unwrap();
+            let explain = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .map(|tuple| tuple[0].to_string())
+                    .collect_vec()
+            };
+            let lines = explain(&mut db, "explain select * from a, b where a.v1 = b.v1;");
+            assert_eq!(
+                lines,
+                vec![
+                    "NestedLoopJoin on a.v1 = b.v1",
+                    "  SeqScan a",
+                    "  SeqScan b"
+                ]
+            );
+            let lines = explain(&mut db, "explain select a.v1 from a, b;");
+            assert_eq!(lines[0], "Project a.v1");
+            assert_eq!(lines[1], "  NestedLoopJoin, cross product");
+            let lines = explain(&mut db, "explain select * from a where v1 = 1;");
+            assert!(lines
+                .iter()
+                .any(|line| line.trim().starts_with("IndexScan")));
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_derived_table() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int, v2 int);").unwrap();
+            db.run("insert into t values (1, 1), (1, 2), (1, 3), (2, 1), (3, 1), (3, 2), (3, 3), (3, 4);")
+                .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select * from (select v1, count(*) c from t group by v1) where c > 2 order by v1;"
+                ),
+                vec![vec![1.into(), 3.into()], vec![3.into(), 4.into()]]
+            );
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select v1 from (select v1, max(v2) as m from t group by v1) as s where s.m = 1;"
+                ),
+                vec![vec![2.into()]]
+            );
+            assert!(db
+                .run("select * from (select v1 from t) as s where x.v1 = 1;")
+                .is_err());
+            // the subquery is only planned by explain, it runs with the query
+            let lines = select(
+                &mut db,
+                "explain select * from (select v1 / 0 as q from t) as s;",
+            )
+            .into_iter()
+            .map(|tuple| tuple[0].to_string())
+            .collect_vec();
+            assert!(lines[0].starts_with("Derived s"));
+            assert!(lines[1].trim().starts_with("Project"));
+            assert!(db
+                .run("select * from (select v1 / 0 as q from t) as s;")
+                .is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_regexp() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar);")
+                .unwrap();
+            db.run("insert into t values (1, 'abz'), (2, 'abza'), (3, 'xaz'), (4, null);")
+                .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select(&mut db, "select v1 from t where v2 regexp '^a.*z$';"),
+                vec![vec![1.into()]]
+            );
+            assert_eq!(
+                select(&mut db, "select v1 from t where v2 regexp 'az';"),
+                vec![vec![3.into()]]
+            );
+            assert_eq!(
+                select(&mut db, "select v1 from t where v2 regexp 'z';"),
+                vec![vec![1.into()], vec![2.into()], vec![3.into()]]
+            );
+            assert_eq!(
+                db.run("select * from t where v2 regexp '(a';")
+                    .unwrap_err()
+                    .code(),
+                ErrorCode::InvalidArgument
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_delete_update_limit() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int not null, primary key (v1));")
+                .unwrap();
+            db.run("insert into t values (1, 0), (2, 0), (3, 0), (4, 1), (5, 0);")
+                .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            db.run("delete from t where v2 = 0 limit 2;").unwrap();
+            assert_eq!(
+                select(&mut db, "select * from t;"),
+                vec![
+                    vec![3.into(), 0.into()],
+                    vec![4.into(), 1.into()],
+                    vec![5.into(), 0.into()],
+                ]
+            );
+            assert_eq!(
+                select(&mut db, "update t set v2 = 2 where v2 = 0 limit 1;"),
+                vec![vec![1.into()]]
+            );
+            assert_eq!(
+                select(&mut db, "select v1 from t where v2 = 0;"),
+                vec![vec![5.into()]]
+            );
+            db.run("delete from t where v2 = 7 limit 2;").unwrap();
+            db.run("delete from t limit 0;").unwrap();
+            assert_eq!(select(&mut db, "select * from t;").len(), 3);
+            assert!(db.run("delete from t limit 1.5;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_bool_and_or() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 bool);")
+                .unwrap();
+            db.run(
+                "insert into t values (1, true), (1, null), (1, true), (2, false), (2, true), (3, null);",
+            )
+            .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select(&mut db, "select bool_and(v2), bool_or(v2) from t;"),
+                vec![vec![false.into(), true.into()]]
+            );
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select bool_and(v2), bool_or(v2) from t group by v1;"
+                ),
+                vec![
+                    vec![true.into(), true.into()],
+                    vec![false.into(), true.into()],
+                    vec![Datum::Bool(None), Datum::Bool(None)],
+                ]
+            );
+            assert!(db.run("select bool_and(v1) from t;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_pragma_list_free_pages() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar not null);")
+                .unwrap();
+            let values = (0..2000)
+                .map(|idx| format!("({}, 'value {}')", idx, idx))
+                .join(", ");
+            db.run(&format!("insert into t values {};", values))
+                .unwrap();
+            // the pages of a result are freed once it is erased
+            let table = db.run("select * from t;").unwrap();
+            let page_ids = table
+                .iter()
+                .map(|s| s.page_id())
+                .chain(std::iter::once(table.page_id()))
+                .collect_vec();
+            assert!(page_ids.len() > 2);
+            table.erase();
+            let free_page_ids: HashSet<usize> = db
+                .run("pragma list_free_pages;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .map(|tuple| i32::from(tuple[0].clone()) as usize)
+                .collect();
+            assert!(page_ids
+                .iter()
+                .all(|page_id| free_page_ids.contains(page_id)));
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_union() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table a (v1 int not null, v2 varchar not null);")
+                .unwrap();
+            db.run("create table b (x int, y varchar not null);")
+                .unwrap();
+            db.run("insert into a values (1, 'foo'), (2, 'bar'), (2, 'bar');")
+                .unwrap();
+            db.run("insert into b values (2, 'bar'), (3, 'baz'), (null, 'qux');")
+                .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select(&mut db, "select * from a union select * from b;"),
+                vec![
+                    vec![1.into(), "foo".into()],
+                    vec![2.into(), "bar".into()],
+                    vec![3.into(), "baz".into()],
+                    vec![Datum::Int(None), "qux".into()],
+                ]
+            );
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select v1 from a union all select x from b where x > 2;"
+                ),
+                vec![
+                    vec![1.into()],
+                    vec![2.into()],
+                    vec![2.into()],
+                    vec![3.into()]
+                ]
+            );
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select v1 from a union all select x from b union select v1 from a;"
+                ),
+                vec![
+                    vec![1.into()],
+                    vec![2.into()],
+                    vec![3.into()],
+                    vec![Datum::Int(None)]
+                ]
+            );
+            let table = db.run("select v1 from a union select x from b;").unwrap();
+            assert!(table.schema.columns[0].data_type.nullable());
+            assert_eq!(
+                db.run("select v1 from a union select y from b;")
+                    .unwrap_err()
+                    .code(),
+                ErrorCode::TypeMismatch
+            );
+            assert!(db.run("select * from a union select x from b;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_intersect_except() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table a (v1 int not null, v2 varchar not null);")
+                .unwrap();
+            db.run("create table b (x int, y varchar not null);")
+                .unwrap();
+            db.run("insert into a values (1, 'foo'), (2, 'bar'), (2, 'bar'), (3, 'baz');")
+                .unwrap();
+            db.run("insert into b values (2, 'bar'), (3, 'qux'), (null, 'foo');")
+                .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select(&mut db, "select * from a intersect select * from b;"),
+                vec![vec![2.into(), "bar".into()]]
+            );
+            assert_eq!(
+                select(&mut db, "select * from a except select * from b;"),
+                vec![vec![1.into(), "foo".into()], vec![3.into(), "baz".into()]]
+            );
+            assert_eq!(
+                select(&mut db, "select v1 from a intersect select x from b;"),
+                vec![vec![2.into()], vec![3.into()]]
+            );
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select v1 from a except select x from b union select x from b;"
+                ),
+                vec![
+                    vec![1.into()],
+                    vec![2.into()],
+                    vec![3.into()],
+                    vec![Datum::Int(None)]
+                ]
+            );
+            // intersect binds tighter than union and except
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select v1 from a except select x from b intersect select v1 from a where v1 = 3;"
+                ),
+                vec![vec![1.into()], vec![2.into()]]
+            );
+            let mut tuples = select(
+                &mut db,
+                "select x from b union select v1 from a intersect select v1 from a where v1 = 1;",
+            );
+            tuples.sort();
+            assert_eq!(
+                tuples,
+                vec![
+                    vec![Datum::Int(None)],
+                    vec![1.into()],
+                    vec![2.into()],
+                    vec![3.into()]
+                ]
+            );
+            assert_eq!(
+                db.run("select v2 from a intersect select x from b;")
+                    .unwrap_err()
+                    .code(),
+                ErrorCode::TypeMismatch
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_order_by_position() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar not null);")
+                .unwrap();
+            db.run("insert into t values (2, 'a'), (3, 'c'), (1, 'b');")
+                .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select(&mut db, "select v2, v1 from t order by 2;"),
+                vec![
+                    vec!["b".into(), 1.into()],
+                    vec!["a".into(), 2.into()],
+                    vec!["c".into(), 3.into()],
+                ]
+            );
+            assert_eq!(
+                select(&mut db, "select * from t order by 2 desc;"),
+                vec![
+                    vec![3.into(), "c".into()],
+                    vec![1.into(), "b".into()],
+                    vec![2.into(), "a".into()],
+                ]
+            );
+            assert_eq!(
+                select(&mut db, "select v1 + 1 from t order by 1 desc;"),
+                vec![vec![4.into()], vec![3.into()], vec![2.into()]]
+            );
+            for sql in [
+                "select v2, v1 from t order by 3;",
+                "select v1 from t order by 0;",
+            ] {
+                assert_eq!(db.run(sql).unwrap_err().code(), ErrorCode::InvalidArgument);
+            }
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_top_n_by_agg() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int not null);")
+                .unwrap();
+            for chunk in &(0..1000).chunks(100) {
+                let values = chunk.map(|v| format!("({}, {})", v * v % 13, v)).join(", ");
+                db.run(&format!("insert into t values {};", values))
+                    .unwrap();
+            }
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            let sorted = select(
+                &mut db,
+                "select v1, count(*) c from t group by v1 order by c desc, v1;",
+            );
+            assert_eq!(sorted.len(), 7);
+            assert!(sorted.windows(2).all(|w| w[0][1] >= w[1][1]));
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select v1, count(*) c from t group by v1 order by c desc, v1 limit 3;"
+                ),
+                sorted[..3].to_vec()
+            );
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select v1, count(*) c from t group by v1 order by 2, 1 limit 1;"
+                ),
+                vec![sorted[6].clone()]
+            );
+            let explain = select(
+                &mut db,
+                "explain select v1, count(*) c from t group by v1 order by c desc limit 3;",
+            );
+            assert_eq!(explain[0], vec!["TopN 3 by c desc".into()]);
+            assert_eq!(
+                select(&mut db, "select v2 from t where v1 = 0 limit 2;"),
+                vec![vec![0.into()], vec![13.into()]]
+            );
+            assert!(select(&mut db, "select * from t limit 0;").is_empty());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_pragma_null_display() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar);")
+                .unwrap();
+            db.run("insert into t values (1, null), (2, 'null_free');")
+                .unwrap();
+            let render = |db: &mut NaiveDB| {
+                let table = db.run("select * from t;").unwrap();
+                table.render(&db.null_display(), db.bool_as_int())
+            };
+            assert!(render(&mut db).contains("NULL"));
+            db.run("pragma null_display = '\\N';").unwrap();
+            assert_eq!(db.null_display(), "\\N");
+            let rendered = render(&mut db);
+            assert!(rendered.contains("\\N") && !rendered.contains("NULL"));
+            db.run("pragma null_display = '';").unwrap();
+            let rendered = render(&mut db);
+            assert!(!rendered.contains("NULL") && !rendered.contains("\\N"));
+            assert!(rendered.contains("null_free"));
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_pragma_bool_as_int() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 bool);")
+                .unwrap();
+            db.run("insert into t values (1, true), (2, false), (3, null);")
+                .unwrap();
+            let render = |db: &mut NaiveDB| {
+                let table = db.run("select v2 from t;").unwrap();
+                table.render(&db.null_display(), db.bool_as_int())
+            };
+            let rendered = render(&mut db);
+            assert!(rendered.contains("true") && rendered.contains("false"));
+            db.run("pragma bool_as_int = on;").unwrap();
+            assert!(db.bool_as_int());
+            let rendered = render(&mut db);
+            assert!(!rendered.contains("true") && !rendered.contains("false"));
+            assert!(rendered.contains('1') && rendered.contains('0'));
+            assert!(rendered.contains("NULL"));
+            // only the presentation changes, the stored values are still bools
+            let table = db.run("select v2 from t where v2;").unwrap();
+            assert_eq!(
+                table
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec(),
+                vec![vec![true.into()]]
+            );
+            db.run("pragma bool_as_int = off;").unwrap();
+            let rendered = render(&mut db);
+            assert!(rendered.contains("true") && rendered.contains("false"));
+            assert!(db.run("pragma bool_as_int = yes;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_division() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int, v3 float);")
+                .unwrap();
+            db.run("insert into t values (7, 2, 1.0), (9, 3, 4.0), (1, null, 0.5);")
+                .unwrap();
+            let mut select = |sql: &str| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select("select v1 / v2, v1 / v3 from t;"),
+                vec![
+                    vec![3.into(), 7.0f32.into()],
+                    vec![3.into(), 2.25f32.into()],
+                    vec![Datum::Int(None), 2.0f32.into()],
+                ]
+            );
+            assert_eq!(
+                select("select v1 from t where v1 / v2 = 3;"),
+                vec![vec![7.into()], vec![9.into()]]
+            );
+            assert_eq!(
+                db.run("select v1 / 0 from t;").unwrap_err().code(),
+                ErrorCode::InvalidArgument
+            );
+            assert!(db.run("select v1 from t where v3 / (v2 - 2) > 0;").is_err());
+            // only the rows whose quotients are used report a zero divisor
+            let mut select = |sql: &str| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select("select case when v2 = 2 then 0 else v1 / (v2 - 2) end from t;"),
+                vec![vec![0.into()], vec![9.into()], vec![Datum::Int(None)]]
+            );
+            assert_eq!(
+                select("select v1 from t where v2 > 2 and v1 / (v2 - 2) = 9;"),
+                vec![vec![9.into()]]
+            );
+            db.run("pragma division_by_zero = null;").unwrap();
+            let tuples = db
+                .run("select v1 / 0, v3 / 0 from t;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert!(tuples
+                .iter()
+                .all(|t| t == &vec![Datum::Int(None), Datum::Float(None)]));
+            db.run("pragma division_by_zero = error;").unwrap();
+            assert!(db.run("select v1 / 0 from t;").is_err());
+            assert!(db.run("pragma division_by_zero = maybe;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_show_history() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null);").unwrap();
+            db.run("insert into t values (1), (2), (3);").unwrap();
+            db.run("select * from t where v1 > 1;").unwrap();
+            assert!(db.run("select * from not_exist;").is_err());
+            let history = db
+                .run("show history;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            let statements = history.iter().map(|t| t[0].to_string()).collect_vec();
+            assert_eq!(
+                statements,
+                vec![
+                    "create database d;",
+                    "use d;",
+                    "create table t (v1 int not null);",
+                    "insert into t values (1), (2), (3);",
+                    "select * from t where v1 > 1;",
+                ]
+            );
+            assert_eq!(history[4][2], 2.into());
+            assert!(history.iter().all(|t| match &t[1] {
+                Datum::Float(Some(elapsed)) => elapsed.into_inner() >= 0.0,
+                _ => false,
+            }));
+            assert_eq!(db.history().len(), 5);
+            for _ in 0..HISTORY_SIZE {
+                db.run("select * from t;").unwrap();
+            }
+            let history = db.history();
+            assert_eq!(history.len(), HISTORY_SIZE);
+            assert!(history
+                .iter()
+                .all(|r| r.sql == "select * from t;" && r.rows == 3));
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_agg_distinct() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int not null);")
+                .unwrap();
+            db.run("insert into t values (1, 1), (2, 1), (2, 1), (4, 2), (4, 2), (4, 2);")
+                .unwrap();
+            let mut select = |sql: &str| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select("select sum(distinct v1), avg(distinct v1), count(distinct v1) from t;"),
+                vec![vec![7.into(), Datum::from((7.0f64 / 3.0) as f32), 3.into()]]
+            );
+            assert_eq!(
+                select("select sum(v1), sum(distinct v1) from t;"),
+                vec![vec![17.into(), 7.into()]]
+            );
+            assert_eq!(
+                select("select v2, sum(distinct v1) from t group by v2;"),
+                vec![vec![1.into(), 3.into()], vec![2.into(), 4.into()]]
+            );
+            // nulls are not counted as a distinct value
+            db.run("create table n (v1 int, v2 int not null);").unwrap();
+            db.run("insert into n values (null, 1), (1, 1), (null, 1), (1, 1), (2, 1), (null, 2);")
+                .unwrap();
+            let mut select = |sql: &str| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select("select count(distinct v1) from n;"),
+                vec![vec![2.into()]]
+            );
+            assert_eq!(
+                select("select v2, count(distinct v1) from n group by v2;"),
+                vec![vec![1.into(), 2.into()], vec![2.into(), 0.into()]]
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_explain_index_choice() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int not null, v3 int not null);")
+                .unwrap();
+            db.run("alter table t add index (v1, v2);").unwrap();
+            let explain = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .map(|tuple| tuple[0].to_string())
+                    .collect_vec()
+            };
+            let lines = explain(&mut db, "explain select * from t where v2 > 1;");
+            assert_eq!(
+                lines,
+                vec![
+                    "Filter v2 > 1",
+                    "  SeqScan t",
+                    "    - index (v1, v2) not chosen, no lower bound on v1, no upper bound on v1, v2",
+                ]
+            );
+            let lines = explain(&mut db, "explain select * from t where v1 = 1 and v2 > 2;");
+            assert!(lines[1].trim().starts_with("IndexScan"));
+            assert_eq!(lines[2], "    - index (v1, v2) chosen");
+            let lines = explain(&mut db, "explain select * from t;");
+            assert_eq!(lines, vec!["SeqScan t"]);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_choose_tightest_index() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int not null);")
+                .unwrap();
+            db.run("alter table t add index (v1);").unwrap();
+            db.run("alter table t add index (v2);").unwrap();
+            db.run("insert into t values (1, 6), (50, 7), (99, 20);")
+                .unwrap();
+            let explain = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .map(|tuple| tuple[0].to_string())
+                    .collect_vec()
+            };
+            let sql = "explain select * from t where v1 > 0 and v1 < 100 and v2 > 5 and v2 < 8;";
+            let lines = explain(&mut db, sql);
+            assert!(lines[1].trim().starts_with("IndexScan"));
+            assert_eq!(
+                lines[2..],
+                [
+                    "    - index (v1) not chosen, another index is at least as tight",
+                    "    - index (v2) chosen",
+                ]
+            );
+            // a range bounded on both sides beats one bounded on a single side
+            let lines = explain(&mut db, "explain select * from t where v1 > 3 and v2 = 7;");
+            assert_eq!(lines[3], "    - index (v2) chosen");
+            let tuples = db
+                .run("select * from t where v1 > 0 and v1 < 100 and v2 > 5 and v2 < 8;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![vec![1.into(), 6.into()], vec![50.into(), 7.into()]]
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_vacuum_analyze() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar);")
+                .unwrap();
+            db.run("alter table t add index (v1);").unwrap();
+            for chunk in &(0..3000).chunks(500) {
+                let values = chunk.map(|v| format!("({}, 'foo{}')", v, v % 7)).join(", ");
+                db.run(&format!("insert into t values {};", values))
+                    .unwrap();
+            }
+            db.run("delete from t where v1 > 100 and v1 < 2900;")
+                .unwrap();
+            let mut select = |sql: &str| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            let stats = select("vacuum analyze t;");
+            assert_eq!(
+                stats,
+                vec![
+                    vec!["v1".into(), "0".into(), "2999".into(), 0.into(), 201.into()],
+                    vec![
+                        "v2".into(),
+                        "foo0".into(),
+                        "foo6".into(),
+                        0.into(),
+                        7.into()
+                    ],
+                ]
+            );
+            // the surviving tuples fit into a single slice now
+            assert_eq!(select("vacuum t;"), vec![vec![1.into(), 1.into()]]);
+            // the rebuilt index still finds the tuples
+            assert_eq!(
+                select("select v2 from t where v1 = 2950;"),
+                vec![vec!["foo3".into()]]
+            );
+            assert_eq!(
+                select("select v1 from t where v1 > 98 and v1 < 2902;").len(),
+                4
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_length_and_octet_length() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar);")
+                .unwrap();
+            db.run("insert into t values (1, 'abc'), (2, 'héllo'), (3, '数据库'), (4, null);")
+                .unwrap();
+            let tuples = db
+                .run("select v1, length(v2), octet_length(v2) from t;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![
+                    vec![1.into(), 3.into(), 3.into()],
+                    vec![2.into(), 5.into(), 6.into()],
+                    vec![3.into(), 3.into(), 9.into()],
+                    vec![4.into(), Datum::Int(None), Datum::Int(None)],
+                ]
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_always_false_skips_scan() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar(20) not null);")
+                .unwrap();
+            db.run("create table u (v3 int not null);").unwrap();
+            for idx in 0..500 {
+                db.run(&format!("insert into t values ({}, 'value{}');", idx, idx))
+                    .unwrap();
+            }
+            db.run("insert into u values (1);").unwrap();
+            let run = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .map(|tuple| tuple[0].to_string())
+                    .collect_vec()
+            };
+            let lines = run(&mut db, "explain select * from t where 1 = 0;");
+            assert_eq!(lines, vec!["Empty, the predicates are always false"]);
+            let lines = run(
+                &mut db,
+                "explain select * from t, u where v1 = v3 and 1 > 2;",
+            );
+            assert_eq!(lines, vec!["Empty, the predicates are always false"]);
+            let fetches_of = |db: &mut NaiveDB, sql, len| {
+                let num_fetches = db.bpm.borrow().num_fetches();
+                assert_eq!(run(db, sql).len(), len);
+                db.bpm.borrow().num_fetches() - num_fetches
+            };
+            let empty_fetches = fetches_of(&mut db, "select * from t where 1 = 0;", 0);
+            let full_fetches = fetches_of(&mut db, "select * from t where 1 = 1;", 500);
+            let join_fetches =
+                fetches_of(&mut db, "select v3 from t, u where v1 = v3 and 1 > 2;", 0);
+            for idx in 500..1000 {
+                db.run(&format!("insert into t values ({}, 'value{}');", idx, idx))
+                    .unwrap();
+            }
+            // no slice of t is fetched, so no row is scanned however many rows t has
+            assert!(fetches_of(&mut db, "select * from t where 1 = 1;", 1000) > full_fetches);
+            assert_eq!(
+                fetches_of(&mut db, "select * from t where 1 = 0;", 0),
+                empty_fetches
+            );
+            assert_eq!(
+                fetches_of(&mut db, "select v3 from t, u where v1 = v3 and 1 > 2;", 0),
+                join_fetches
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_insert_returning_record_id() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar(20) not null);")
+                .unwrap();
+            db.run("insert into t values (0, 'zero');").unwrap();
+            let record_ids = db
+                .run("insert into t values (1, 'one'), (2, 'two'), (3, 'three') returning record_id;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .map(|tuple| {
+                    (
+                        i32::from(tuple[0].clone()) as usize,
+                        i32::from(tuple[1].clone()) as usize,
+                    )
+                })
+                .collect_vec();
+            assert_eq!(record_ids.len(), 3);
+            assert_eq!(record_ids.iter().unique().count(), 3);
+            // a table of the same schema resolves the record ids
+            let table = db.run("select * from t;").unwrap();
+            let tuples = record_ids
+                .iter()
+                .map(|record_id| table.tuple_at(*record_id).unwrap())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![
+                    vec![Datum::from(1), Datum::from("one")],
+                    vec![Datum::from(2), Datum::from("two")],
+                    vec![Datum::from(3), Datum::from("three")],
+                ]
+            );
+            // without returning, the count is reported
+            let res = db.run("insert into t values (4, 'four');").unwrap();
+            let tuples = res
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![Datum::from(1)]]);
+            // the keywords are still names
+            db.run("create table r (record_id int not null, returning int not null);")
+                .unwrap();
+            db.run("insert into r values (1, 2) returning record_id;")
+                .unwrap();
+            let tuples = db
+                .run("select returning from r where record_id = 1;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![Datum::from(2)]]);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_date_components() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 date);")
+                .unwrap();
+            db.run(
+                "insert into t values (1, 2021-03-14), (2, 2021-12-01), (3, 2022-01-31), (4, null);",
+            )
+            .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select year(v2), month(v2), day(v2) from t where v1 = 1;"
+                ),
+                vec![vec![2021.into(), 3.into(), 14.into()]]
+            );
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select year(v2), month(v2), day(v2) from t where v1 = 4;"
+                ),
+                vec![vec![Datum::Int(None), Datum::Int(None), Datum::Int(None)]]
+            );
+            let tuples = select(
+                &mut db,
+                "select year(v2), count(*) from t where v1 < 4 group by year(v2);",
+            )
+            .into_iter()
+            .sorted()
+            .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![vec![2021.into(), 2.into()], vec![2022.into(), 1.into()]]
+            );
+            assert!(db.run("select year(v1) from t;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_date_trunc() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 date);")
+                .unwrap();
+            db.run(
+                "insert into t values (1, 2021-03-14), (2, 2021-03-01), (3, 2021-12-31), (4, 2022-03-02), (5, null);",
+            )
+            .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            let date = |s| Datum::from(NaiveDate::from_str(s).unwrap());
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select date_trunc('year', v2), date_trunc('month', v2), date_trunc('day', v2) from t where v1 = 1;"
+                ),
+                vec![vec![date("2021-01-01"), date("2021-03-01"), date("2021-03-14")]]
+            );
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select date_trunc('month', v2) from t where v1 = 5;"
+                ),
+                vec![vec![Datum::Date(None)]]
+            );
+            let tuples = select(
+                &mut db,
+                "select date_trunc('month', v2), count(*) from t where v1 < 5 group by date_trunc('month', v2);",
+            )
+            .into_iter()
+            .sorted()
+            .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![
+                    vec![date("2021-03-01"), 2.into()],
+                    vec![date("2021-12-01"), 1.into()],
+                    vec![date("2022-03-01"), 1.into()],
+                ]
+            );
+            assert!(db.run("select date_trunc('week', v2) from t;").is_err());
+            assert!(db.run("select date_trunc('month', v1) from t;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_to_char() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 date);")
+                .unwrap();
+            db.run("insert into t values (1, 2021-03-04), (2, null);")
+                .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select to_char(v2, 'YYYY-MM'), to_char(v2, 'dd/mm/yy') from t where v1 = 1;"
+                ),
+                vec![vec!["2021-03".into(), "04/03/21".into()]]
+            );
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select to_char(v2, 'YYYY-MM') from t where v1 = 2;"
+                ),
+                vec![vec![Datum::VarChar(None)]]
+            );
+            assert_eq!(
+                db.run("select to_char(v2, 'YYYY-Q') from t;")
+                    .unwrap_err()
+                    .code(),
+                ErrorCode::InvalidArgument
+            );
+            assert!(db.run("select to_char(v1, 'YYYY') from t;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_pragma_synchronous() {
+        let run_then_crash = |synchronous: &str| {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run(&format!("pragma synchronous = {};", synchronous))
+                .unwrap();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null);").unwrap();
+            db.run("insert into t values (1), (2);").unwrap();
+            // crash, nothing is written back on drop
+            std::mem::forget(db);
+            filename
+        };
+        let filename = run_then_crash("full");
+        {
+            let mut db = NaiveDB::new_with_name(filename.clone());
+            db.run("use d;").unwrap();
+            let tuples = db
+                .run("select * from t;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![1.into()], vec![2.into()]]);
+            assert!(db.run("pragma synchronous = sometimes;").is_err());
+        }
+        remove_file(filename).unwrap();
+        // the pages are still in the pool when it crashes
+        let filename = run_then_crash("off");
+        {
+            let mut db = NaiveDB::new_with_name(filename.clone());
+            assert!(db.run("use d;").is_err());
+        }
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_case_when() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 bool null);")
+                .unwrap();
+            db.run("insert into t values (-2, true), (0, null), (3, false);")
+                .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select v1, case when v1 > 0 then 'pos' when v1 < 0 then 'neg' else 'zero' end from t;"
+                ),
+                vec![
+                    vec![(-2).into(), "neg".into()],
+                    vec![0.into(), "zero".into()],
+                    vec![3.into(), "pos".into()],
+                ]
+            );
+            // a null condition is not true, without else the result is null
+            assert_eq!(
+                select(&mut db, "select case when v2 then v1 end from t;"),
+                vec![
+                    vec![(-2).into()],
+                    vec![Datum::Int(None)],
+                    vec![Datum::Int(None)]
+                ]
+            );
+            // the int result is widened to float to match the other branch
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select case when v1 > 0 then 1.5 else v1 end from t where v1 > -1;"
+                ),
+                vec![vec![Datum::from(0.0f32)], vec![Datum::from(1.5f32)]]
+            );
+            assert!(db
+                .run("select case when v1 > 0 then 'pos' else v1 end from t;")
+                .is_err());
+            assert!(db.run("select case when v1 then 1 end from t;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_case_unify_types() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 float not null, v3 varchar not null);")
+                .unwrap();
+            db.run("insert into t values (1, 0.5, 'a'), (2, 2.5, 'b');")
+                .unwrap();
+            let table = db
+                .run("select case when v1 > 1 then v1 else v2 end from t;")
+                .unwrap();
+            assert_eq!(
+                table.schema.columns[0].data_type,
+                DataType::new_as_float(false)
+            );
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                vec![vec![Datum::from(0.5f32)], vec![Datum::from(2.0f32)]]
+            );
+            // without else the result may be null
+            let table = db
+                .run("select case when v1 > 1 then v1 end from t;")
+                .unwrap();
+            assert_eq!(
+                table.schema.columns[0].data_type,
+                DataType::new_as_int(true)
+            );
+            let err = db
+                .run("select case when v1 > 1 then v1 else v3 end from t;")
+                .unwrap_err();
+            assert!(matches!(err, NaiveDBError::Plan(_)));
+            assert_eq!(err.code(), ErrorCode::TypeMismatch);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_where_on_select_alias() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int not null);")
+                .unwrap();
+            db.run("insert into t values (3, 1), (5, 2), (7, 3);")
+                .unwrap();
+            let tables = db.run("select v1 + 1 as n from t where n > 5;").unwrap();
+            assert_eq!(tables[0].schema.columns[0].desc, "n");
+            assert_eq!(
+                tables
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec(),
+                vec![vec![6.into()], vec![8.into()]]
+            );
+            // the alias is substituted inside larger predicates
+            let tables = db
+                .run("select v1 * v2 as p from t where p - 1 < 10;")
+                .unwrap();
+            assert_eq!(
+                tables
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec(),
+                vec![vec![3.into()]]
+            );
+            // a column with the same name wins over the alias
+            let tables = db.run("select v1 + 1 as v2 from t where v2 > 1;").unwrap();
+            assert_eq!(
+                tables
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec(),
+                vec![vec![6.into()], vec![8.into()]]
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_index_skip_scan() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int not null, v3 int not null);")
+                .unwrap();
+            db.run("alter table t add index (v1, v2);").unwrap();
+            let values = (0..600)
+                .map(|i| format!("({}, {}, {})", i % 3, i % 200, i))
+                .join(", ");
+            db.run(&format!("insert into t values {};", values))
+                .unwrap();
+            let explain = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .map(|tuple| tuple[0].to_string())
+                    .collect_vec()
+            };
+            // the distinct leading keys are unknown before analyze
+            let lines = explain(&mut db, "explain select * from t where v2 = 5;");
+            assert!(lines[1].trim().starts_with("SeqScan"));
+            db.run("analyze t;").unwrap();
+            let lines = explain(&mut db, "explain select * from t where v2 = 5;");
+            assert!(lines[1].trim().starts_with("IndexSkipScan"));
+            assert_eq!(lines[2], "    - index (v1, v2) chosen");
+            // too many distinct leading keys to seek each
+            db.run("alter table t add index (v3, v2);").unwrap();
+            db.run("alter table t drop index (v1, v2);").unwrap();
+            db.run("analyze t;").unwrap();
+            let lines = explain(&mut db, "explain select * from t where v2 = 5;");
+            assert!(lines[1].trim().starts_with("SeqScan"));
+            let mut tuples = db
+                .run("select v3 from t where v2 = 5;")
+                .unwrap()
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            tuples.sort();
+            let expected = (0..600)
+                .filter(|i| i % 200 == 5)
+                .map(|i| vec![i.into()])
+                .collect_vec();
+            assert_eq!(tuples, expected);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_insert_real_into_int() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null);").unwrap();
+            let code = |db: &mut NaiveDB, sql| db.run(sql).unwrap_err().code();
+            assert_eq!(
+                code(&mut db, "insert into t values (3000000000);"),
+                ErrorCode::InvalidArgument
+            );
+            // a fraction is an error unless truncating is asked for
+            assert_eq!(
+                code(&mut db, "insert into t values (1.5);"),
+                ErrorCode::InvalidArgument
+            );
+            assert_eq!(
+                code(&mut db, "update t set v1 = 2.5;"),
+                ErrorCode::InvalidArgument
+            );
+            // an integral real fits
+            db.run("insert into t values (2.0), (4), (-2147483648);")
+                .unwrap();
+            let mut select = |sql: &str| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select("select * from t;"),
+                vec![vec![2.into()], vec![4.into()], vec![(-2147483648).into()]]
+            );
+            // a fractional real in a predicate is compared as a float
+            assert_eq!(
+                select("select * from t where v1 > 3.5;"),
+                vec![vec![4.into()]]
+            );
+            assert_eq!(
+                select("select * from t where v1 = 4.0;"),
+                vec![vec![4.into()]]
+            );
+            assert!(select("select * from t where v1 = 3000000000;").is_empty());
+            db.run("pragma fractional_int = truncate;").unwrap();
+            db.run("update t set v1 = 2.5 where v1 = 4;").unwrap();
+            assert_eq!(
+                db.warnings(),
+                vec!["Value 2.5 Truncated To Fit Int Column v1".to_string()]
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_distinct_on() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int not null, v3 varchar not null);")
+                .unwrap();
+            db.run("insert into t values (2, 5, 'a'), (1, 3, 'b'), (2, 2, 'c'), (3, 7, 'd'), (1, 1, 'e');")
+                .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select distinct on (v1) v1, v2 from t order by v1, v2;"
+                ),
+                vec![
+                    vec![1.into(), 1.into()],
+                    vec![2.into(), 2.into()],
+                    vec![3.into(), 7.into()],
+                ]
+            );
+            // the order by after the distinct on exprs picks the row kept
+            assert_eq!(
+                select(
+                    &mut db,
+                    "select distinct on (v1) v3 from t order by v1, v2 desc;"
+                ),
+                vec![vec!["b".into()], vec!["a".into()], vec!["d".into()]]
+            );
+            // sorted by the distinct on exprs without order by
+            assert_eq!(
+                select(&mut db, "select distinct on (v1) v1 from t;"),
+                vec![vec![1.into()], vec![2.into()], vec![3.into()]]
+            );
+            assert_eq!(
+                db.run("select distinct on (v1) v1, v2 from t order by v2;")
+                    .unwrap_err()
+                    .code(),
+                ErrorCode::InvalidArgument
+            );
+            assert_eq!(
+                db.run("select distinct on (v1) sum(v2) from t;")
+                    .unwrap_err()
+                    .code(),
+                ErrorCode::Unsupported
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_pragma_table_size() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar not null);")
+                .unwrap();
+            let table_size = |db: &mut NaiveDB| {
+                let table = db.run("pragma table_size t;").unwrap();
+                let tuples = table
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec();
+                match tuples[0][0] {
+                    Datum::Int(Some(bytes)) => bytes,
+                    _ => unreachable!(),
+                }
+            };
+            let empty = table_size(&mut db);
+            db.run("insert into t values (1, 'a'), (2, 'b');").unwrap();
+            let inserted = table_size(&mut db);
+            assert!(inserted > empty);
+            // the pages of the index count as well
+            db.run("alter table t add index (v1);").unwrap();
+            assert!(table_size(&mut db) > inserted);
+            // the total is the sum over the pages of the table and of each index
+            let plan = match db.planner.plan(parse("pragma table_size t;").unwrap()) {
+                Ok(Plan::TableSize(plan)) => plan,
+                _ => unreachable!(),
+            };
+            let table = Table::open(plan.table_page_id, db.bpm.clone());
+            let index_bytes = plan
+                .index_page_ids
+                .iter()
+                .map(|page_id| {
+                    BPTIndex::open(db.bpm.clone(), *page_id, table.schema.as_ref())
+                        .unwrap()
+                        .disk_footprint()
+                })
+                .sum::<usize>();
+            assert_eq!(plan.index_page_ids.len(), 1);
+            assert_eq!(
+                table_size(&mut db) as usize,
+                table.disk_footprint().unwrap() + index_bytes
+            );
+            assert!(db.run("pragma table_size not_exist;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_pragma_null_aggregation() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int, v2 int, v3 float);")
+                .unwrap();
+            db.run("insert into t values (1, null, 2.5), (4, 3, 0.5);")
+                .unwrap();
+            let select = |db: &mut NaiveDB| {
+                let table = db
+                    .run("select greatest(v1, v2, v3), least(v1, v2, null) from t;")
+                    .unwrap();
+                table
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            // null if any argument is null by default
+            assert_eq!(
+                select(&mut db),
+                vec![
+                    vec![Datum::Float(None), Datum::Int(None)],
+                    vec![Datum::from(4.0f32), Datum::Int(None)],
+                ]
+            );
+            db.run("pragma null_aggregation = skip;").unwrap();
+            assert_eq!(
+                select(&mut db),
+                vec![
+                    vec![Datum::from(2.5f32), 1.into()],
+                    vec![Datum::from(4.0f32), 3.into()],
+                ]
+            );
+            db.run("pragma null_aggregation = strict;").unwrap();
+            assert_eq!(
+                select(&mut db)[0],
+                vec![Datum::Float(None), Datum::Int(None)]
+            );
+            assert!(db.run("pragma null_aggregation = ignore;").is_err());
+            assert!(db.run("select greatest(v1, 'a') from t;").is_err());
+            assert!(db.run("select least() from t;").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_select_by_rowid() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar not null);")
+                .unwrap();
+            for idx in 0..500 {
+                db.run(&format!("insert into t values ({}, 'v{}');", idx, idx))
+                    .unwrap();
+            }
+            let tuples = |table: Table| {
+                table
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            let plan = db.planner.plan_scan("t", &[], true).unwrap();
+            let with_record_ids = tuples(db.engine.execute(plan).unwrap());
+            for tuple in with_record_ids.iter().step_by(50) {
+                let (page_id, idx) = (i32::from(tuple[2].clone()), i32::from(tuple[3].clone()));
+                let table = db
+                    .run(&format!(
+                        "select * from t where rowid = ({}, {});",
+                        page_id, idx
+                    ))
+                    .unwrap();
+                assert_eq!(tuples(table), vec![tuple[..2].to_vec()]);
+                let table = db
+                    .run(&format!(
+                        "select v2 from t where ctid = ({}, {}) and v1 > 1000;",
+                        page_id, idx
+                    ))
+                    .unwrap();
+                assert!(tuples(table).is_empty());
+            }
+            // a slot never used, and a page not of the table
+            let page_id = i32::from(with_record_ids[0][2].clone());
+            let table = db
+                .run(&format!(
+                    "select * from t where rowid = ({}, 100000);",
+                    page_id
+                ))
+                .unwrap();
+            assert!(tuples(table).is_empty());
+            let table = db.run("select * from t where rowid = (0, 0);").unwrap();
+            assert!(tuples(table).is_empty());
+            assert!(db.run("select * from t where rowid = (1, 2, 3);").is_err());
+            assert!(db.run("select * from t where rowid = (v1, 0);").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_sum_avg_of_ints() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int);").unwrap();
+            // some partial sums are beyond int, while the total is not
+            for _ in 0..100 {
+                db.run("insert into t values (2000000000, 1), (-2000000000, 2);")
+                    .unwrap();
+            }
+            db.run("insert into t values (7, null);").unwrap();
+            let table = db.run("select sum(v1), avg(v2) from t;").unwrap();
+            assert_eq!(
+                table.schema.columns[1].data_type,
+                DataType::new_as_float(true)
+            );
+            assert_eq!(
+                table
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec(),
+                vec![vec![7.into(), Datum::from(1.5f32)]]
+            );
+            db.run("insert into t values (2000000000, 1);").unwrap();
+            let err = db.run("select sum(v1) from t;").unwrap_err();
+            assert_eq!(err.code(), ErrorCode::InvalidArgument);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_sum_avg_of_floats_with_nulls() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 float null);")
+                .unwrap();
+            db.run("insert into t values (1, null), (1, 1.5), (1, null), (1, 2.5), (2, null);")
+                .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(
+                select(&mut db, "select sum(v2), avg(v2) from t;"),
+                vec![vec![Datum::from(4.0f32), Datum::from(2.0f32)]]
+            );
+            // a group of nulls only sums to null
+            assert_eq!(
+                select(&mut db, "select v1, sum(v2) from t group by v1;"),
+                vec![
+                    vec![1.into(), Datum::from(4.0f32)],
+                    vec![2.into(), Datum::Float(None)],
+                ]
+            );
+            // the average of nulls only or of no values is null
+            assert_eq!(
+                select(&mut db, "select v1, avg(v2) from t group by v1;"),
+                vec![
+                    vec![1.into(), Datum::from(2.0f32)],
+                    vec![2.into(), Datum::Float(None)],
+                ]
+            );
+            assert_eq!(
+                select(&mut db, "select avg(v2) from t where v1 > 2;"),
+                vec![vec![Datum::Float(None)]]
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_load_csv_with_rejects() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar not null);")
+                .unwrap();
+            std::fs::write("loadmixed.csv", "1,a\nx,b\n3,c,extra\n4,d\n5\n6,f\n").unwrap();
+            let table = db
+                .run("load data infile loadmixed.csv into table t rejects loadrejects.csv;")
+                .unwrap();
+            let tuples = |table: Table| {
+                table
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            assert_eq!(tuples(table), vec![vec![3.into(), 3.into()]]);
+            let table = db.run("select v1 from t;").unwrap();
+            assert_eq!(
+                tuples(table),
+                vec![vec![1.into()], vec![4.into()], vec![6.into()]]
+            );
+            let rejects = std::fs::read_to_string("loadrejects.csv").unwrap();
+            let lines = rejects
+                .lines()
+                .map(|line| line.split(',').next().unwrap())
+                .collect_vec();
+            assert_eq!(lines, vec!["2", "3", "5"]);
+            remove_file("loadmixed.csv").unwrap();
+            remove_file("loadrejects.csv").unwrap();
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_check_constraint() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int, check (v1 > 0), check (v2 < v1));")
+                .unwrap();
+            db.run("insert into t values (2, 1), (3, null);").unwrap();
+            assert_eq!(
+                db.run("insert into t values (0, -1);").unwrap_err().code(),
+                ErrorCode::ConstraintViolation
+            );
+            assert_eq!(
+                db.run("insert into t values (4, 5);").unwrap_err().code(),
+                ErrorCode::ConstraintViolation
+            );
+            assert_eq!(
+                db.run("update t set v1 = 1 where v1 = 2;")
+                    .unwrap_err()
+                    .code(),
+                ErrorCode::ConstraintViolation
+            );
+            db.run("update t set v1 = 5 where v1 = 2;").unwrap();
+            let table = db.run("select v1 from t order by v1;").unwrap();
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![3.into()], vec![5.into()]]);
+            assert_eq!(
+                db.run("create table s (v1 int, check (v1 + 1));")
+                    .unwrap_err()
+                    .code(),
+                ErrorCode::InvalidArgument
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_index_probe_with_string_literal() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 varchar not null);")
+                .unwrap();
+            db.run("alter table t add index (v1);").unwrap();
+            db.run("insert into t values (3, 'c'), (5, 'e'), (7, 'g');")
+                .unwrap();
+            let select = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .collect_vec()
+            };
+            let lines = select(&mut db, "explain select v2 from t where v1 = '5';");
+            assert!(lines
+                .iter()
+                .any(|line| line[0].to_string().trim().starts_with("IndexScan")));
+            assert_eq!(
+                select(&mut db, "select v2 from t where v1 = '5';"),
+                vec![vec!["e".into()]]
+            );
+            assert_eq!(
+                select(&mut db, "select v2 from t where v1 > '3' and v1 <= ' 7';"),
+                vec![vec!["e".into()], vec!["g".into()]]
+            );
+            assert_eq!(
+                db.run("select v2 from t where v1 = 'five';")
+                    .unwrap_err()
+                    .code(),
+                ErrorCode::InvalidArgument
+            );
+            // only the bounds of an index probe are cast, a string is not stored as an int
+            assert!(db.run("insert into t values ('5', 'f');").is_err());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_explain_estimated_rows() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            db.run("create database d;").unwrap();
+            db.run("use d;").unwrap();
+            db.run("create table t (v1 int not null, v2 int not null);")
+                .unwrap();
+            for chunk in &(0..1000).chunks(100) {
+                let values = chunk.map(|v| format!("({}, {})", v, v % 10)).join(", ");
+                db.run(&format!("insert into t values {};", values))
+                    .unwrap();
+            }
+            let explain = |db: &mut NaiveDB, sql| {
+                db.run(sql)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|s| s.tuple_iter().collect_vec())
+                    .map(|tuple| tuple[0].to_string())
+                    .collect_vec()
+            };
+            // nothing is estimated before analyze
+            assert_eq!(
+                explain(&mut db, "explain select * from t where v1 < 250;"),
+                vec!["Filter v1 < 250", "  SeqScan t"]
+            );
+            db.run("analyze t;").unwrap();
+            let rows_of = |line: &str| -> f64 {
+                let rows = line.rsplit("(rows ~").next().unwrap();
+                rows.trim_end_matches(')').parse().unwrap()
+            };
+            let lines = explain(&mut db, "explain select * from t where v1 < 250;");
+            assert!(lines[0].starts_with("Filter v1 < 250 (rows ~"));
+            assert!((200.0..=300.0).contains(&rows_of(&lines[0])));
+            assert_eq!(lines[1], "  SeqScan t (rows ~1000)");
+            let lines = explain(&mut db, "explain select * from t where v2 = 3;");
+            assert!((80.0..=120.0).contains(&rows_of(&lines[0])));
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
 }
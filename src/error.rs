@@ -0,0 +1,15 @@
+/// stable codes of the errors, callers match on these instead of the messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    Parse = 1,
+    NotUsingDatabase = 2,
+    NotFound = 3,
+    ColumnNotFound = 4,
+    AlreadyExists = 5,
+    DuplicateKey = 6,
+    ConstraintViolation = 7,
+    TypeMismatch = 8,
+    InvalidArgument = 9,
+    Unsupported = 10,
+    Internal = 11,
+}
@@ -1,27 +1,35 @@
-use crate::datum::Datum;
+use crate::datum::{DataType, Datum};
 use crate::execution::{ExecutionError, Executor, ExecutorImpl};
 use crate::expr::ExprImpl;
 use crate::parser::ast::AggAction;
 use crate::storage::BufferPoolManagerRef;
 use crate::table::{Schema, SchemaRef, Slice};
 use itertools::Itertools;
+use std::collections::HashSet;
+use std::convert::TryFrom;
 use std::rc::Rc;
 
 #[derive(Clone)]
 enum Reducer {
     Count(CountReducer),
     Max(MaxReducer),
+    Min(MinReducer),
     Avg(AvgReducer),
     Sum(SumReducer),
+    Bool(BoolReducer),
+    Distinct(DistinctReducer),
 }
 
 impl From<(AggAction, Datum)> for Reducer {
     fn from(action_and_init: (AggAction, Datum)) -> Self {
         match action_and_init.0 {
             AggAction::No | AggAction::Max => Reducer::Max(MaxReducer::new(action_and_init.1)),
+            AggAction::Min => Reducer::Min(MinReducer::new(action_and_init.1)),
             AggAction::Sum => Reducer::Sum(SumReducer::new(action_and_init.1)),
             AggAction::Cnt => Reducer::Count(CountReducer::new(1)),
             AggAction::Avg => Reducer::Avg(AvgReducer::new(action_and_init.1)),
+            AggAction::BoolAnd => Reducer::Bool(BoolReducer::new(action_and_init.1, true)),
+            AggAction::BoolOr => Reducer::Bool(BoolReducer::new(action_and_init.1, false)),
         }
     }
 }
@@ -31,16 +39,22 @@ impl Reducer {
         match self {
             Self::Count(r) => r.reduce(datum),
             Self::Max(r) => r.reduce(datum),
+            Self::Min(r) => r.reduce(datum),
             Self::Avg(r) => r.reduce(datum),
             Self::Sum(r) => r.reduce(datum),
+            Self::Bool(r) => r.reduce(datum),
+            Self::Distinct(r) => r.reduce(datum),
         }
     }
-    pub fn get(&self) -> Datum {
+    pub fn get(&self) -> Result<Datum, ExecutionError> {
         match self {
-            Self::Count(r) => r.get(),
-            Self::Max(r) => r.get(),
-            Self::Avg(r) => r.get(),
+            Self::Count(r) => Ok(r.get()),
+            Self::Max(r) => Ok(r.get()),
+            Self::Min(r) => Ok(r.get()),
+            Self::Avg(r) => Ok(r.get()),
             Self::Sum(r) => r.get(),
+            Self::Bool(r) => Ok(r.get()),
+            Self::Distinct(r) => r.get(),
         }
     }
 }
@@ -55,15 +69,93 @@ struct MaxReducer {
     max: Datum,
 }
 
+#[derive(Clone)]
+struct MinReducer {
+    min: Datum,
+}
+
 #[derive(Clone)]
 struct AvgReducer {
     cnt: usize,
-    sum: Datum,
+    sum: RunningSum,
 }
 
 #[derive(Clone)]
 struct SumReducer {
+    sum: RunningSum,
+}
+
+/// bool_and if all is set, otherwise bool_or
+#[derive(Clone)]
+struct BoolReducer {
+    value: Option<bool>,
+    all: bool,
+}
+
+/// reduces a value by the inner reducer only at its first occurrence, nulls are never reduced
+#[derive(Clone)]
+struct DistinctReducer {
+    seen: HashSet<Datum>,
+    inner: Box<Reducer>,
+}
+
+/// ints are summed in i64 so that no partial sum overflows, floats are summed with the
+/// rounding error compensated
+#[derive(Clone)]
+enum RunningSum {
+    Int(Option<i64>),
+    Float(FloatSum),
+}
+
+/// running sum which compensates the rounding error of floats with kahan summation
+#[derive(Clone)]
+struct FloatSum {
     sum: Datum,
+    compensation: f32,
+}
+
+impl FloatSum {
+    fn new(datum: Datum) -> Self {
+        Self {
+            sum: datum,
+            compensation: 0.0,
+        }
+    }
+    /// nulls are skipped, the sum starts from the first value
+    fn add(&mut self, datum: Datum) {
+        match (&self.sum, datum) {
+            (Datum::Float(Some(sum)), Datum::Float(Some(value))) => {
+                let sum = sum.into_inner();
+                let value = value.into_inner() - self.compensation;
+                let next = sum + value;
+                self.compensation = (next - sum) - value;
+                self.sum = next.into();
+            }
+            (_, datum @ Datum::Float(Some(_))) => self.sum = datum,
+            _ => {}
+        }
+    }
+    fn get(&self) -> Datum {
+        self.sum.clone()
+    }
+}
+
+impl RunningSum {
+    fn new(datum: Datum) -> Self {
+        match datum {
+            Datum::Int(v) => Self::Int(v.map(i64::from)),
+            datum => Self::Float(FloatSum::new(datum)),
+        }
+    }
+    fn add(&mut self, datum: Datum) {
+        match (self, datum) {
+            (Self::Int(sum), Datum::Int(Some(value))) => {
+                *sum = Some(sum.unwrap_or(0) + value as i64)
+            }
+            (Self::Int(_), _) => {}
+            (Self::Float(sum), datum) => sum.add(datum),
+        }
+    }
 }
 
 impl CountReducer {
@@ -79,6 +171,7 @@ impl CountReducer {
 }
 
 impl MaxReducer {
+    /// null is ordered before any value of the same type, so it never wins
     pub fn reduce(&mut self, datum: Datum) {
         if datum > self.max {
             self.max = datum;
@@ -92,28 +185,112 @@ impl MaxReducer {
     }
 }
 
+impl MinReducer {
+    pub fn reduce(&mut self, datum: Datum) {
+        if !datum.is_null() && (self.min.is_null() || datum < self.min) {
+            self.min = datum;
+        }
+    }
+    pub fn get(&self) -> Datum {
+        self.min.clone()
+    }
+    pub fn new(datum: Datum) -> Self {
+        Self { min: datum }
+    }
+}
+
 impl AvgReducer {
+    /// nulls are neither counted nor summed
     pub fn reduce(&mut self, datum: Datum) {
-        self.cnt += 1;
-        self.sum = self.sum.clone() + datum;
+        if !datum.is_null() {
+            self.cnt += 1;
+            self.sum.add(datum);
+        }
     }
+    /// the average of ints is a float, the one of no values is null
     pub fn get(&self) -> Datum {
-        self.sum.clone() / self.cnt
+        match &self.sum {
+            RunningSum::Int(sum) => {
+                Datum::Float(sum.map(|sum| ((sum as f64 / self.cnt as f64) as f32).into()))
+            }
+            RunningSum::Float(_) if self.cnt == 0 => Datum::Float(None),
+            RunningSum::Float(sum) => sum.get() / self.cnt,
+        }
     }
     pub fn new(datum: Datum) -> Self {
-        Self { cnt: 0, sum: datum }
+        Self {
+            cnt: !datum.is_null() as usize,
+            sum: RunningSum::new(datum),
+        }
     }
 }
 
 impl SumReducer {
     pub fn reduce(&mut self, datum: Datum) {
-        self.sum = self.sum.clone() + datum;
+        self.sum.add(datum);
     }
-    pub fn get(&self) -> Datum {
-        self.sum.clone()
+    /// the sum of ints must fit in int, as there is no wider type yet
+    pub fn get(&self) -> Result<Datum, ExecutionError> {
+        match &self.sum {
+            RunningSum::Int(Some(sum)) => i32::try_from(*sum)
+                .map(|sum| sum.into())
+                .map_err(|_| ExecutionError::SumOverflow(*sum)),
+            RunningSum::Int(None) => Ok(Datum::Int(None)),
+            RunningSum::Float(sum) => Ok(sum.get()),
+        }
     }
     pub fn new(datum: Datum) -> Self {
-        Self { sum: datum }
+        Self {
+            sum: RunningSum::new(datum),
+        }
+    }
+}
+
+impl BoolReducer {
+    /// nulls are skipped, the result is null only if all are null
+    pub fn reduce(&mut self, datum: Datum) {
+        if let Datum::Bool(Some(b)) = datum {
+            self.value = Some(match (self.value, self.all) {
+                (None, _) => b,
+                (Some(value), true) => value && b,
+                (Some(value), false) => value || b,
+            });
+        }
+    }
+    pub fn get(&self) -> Datum {
+        Datum::Bool(self.value)
+    }
+    pub fn new(datum: Datum, all: bool) -> Self {
+        let mut reducer = Self { value: None, all };
+        reducer.reduce(datum);
+        reducer
+    }
+}
+
+impl DistinctReducer {
+    pub fn reduce(&mut self, datum: Datum) {
+        if !datum.is_null() && self.seen.insert(datum.clone()) {
+            self.inner.reduce(datum);
+        }
+    }
+    pub fn get(&self) -> Result<Datum, ExecutionError> {
+        self.inner.get()
+    }
+    pub fn new(action: AggAction, datum: Datum) -> Self {
+        match (action, datum.is_null()) {
+            (AggAction::Cnt, true) => Self {
+                seen: HashSet::new(),
+                inner: Box::new(Reducer::Count(CountReducer::new(0))),
+            },
+            (action, true) => Self {
+                seen: HashSet::new(),
+                inner: Box::new(Reducer::from((action, datum))),
+            },
+            (action, false) => Self {
+                seen: std::iter::once(datum.clone()).collect(),
+                inner: Box::new(Reducer::from((action, datum))),
+            },
+        }
     }
 }
 
@@ -121,15 +298,50 @@ pub struct AggExecutor {
     child: Box<ExecutorImpl>,
     reducers: Vec<Vec<(Datum, Reducer)>>,
     exprs_with_action: Vec<(ExprImpl, AggAction)>,
+    aliases: Vec<Option<String>>,
+    distincts: Vec<bool>,
     group_by_expr: Option<ExprImpl>,
     bpm: BufferPoolManagerRef,
     buffer: Vec<Vec<Datum>>,
     executed: bool,
 }
 
+/// the type of the aggregated values, the average of ints is a float
+fn result_type(expr: &ExprImpl, action: &AggAction) -> DataType {
+    match (action, expr.return_type()) {
+        (AggAction::Avg, DataType::Int(nullable)) => DataType::Float(nullable),
+        (_, data_type) => data_type,
+    }
+}
+
+/// the output columns, named by the aliases or else by the aggregates
+pub fn agg_schema(
+    exprs_with_action: &[(ExprImpl, AggAction)],
+    aliases: &[Option<String>],
+    distincts: &[bool],
+) -> SchemaRef {
+    let type_and_names = exprs_with_action
+        .iter()
+        .zip(aliases.iter())
+        .zip(distincts.iter())
+        .map(|(((e, a), alias), distinct)| match (a, alias, distinct) {
+            (_, Some(alias), _) => (result_type(e, a), alias.clone()),
+            (AggAction::No, None, _) => (e.return_type(), e.to_string()),
+            (a, None, true) => (
+                result_type(e, a),
+                format!("{}(distinct {})", a.to_string(), e),
+            ),
+            (a, None, false) => (result_type(e, a), format!("{}({})", a.to_string(), e)),
+        })
+        .collect_vec();
+    Rc::new(Schema::from_type_and_names(&type_and_names))
+}
+
 impl AggExecutor {
     pub fn new(
         exprs_with_action: Vec<(ExprImpl, AggAction)>,
+        aliases: Vec<Option<String>>,
+        distincts: Vec<bool>,
         group_by_expr: Option<ExprImpl>,
         child: ExecutorImpl,
         bpm: BufferPoolManagerRef,
@@ -138,32 +350,49 @@ impl AggExecutor {
             child: Box::new(child),
             reducers: vec![vec![]; exprs_with_action.len()],
             exprs_with_action,
+            aliases,
+            distincts,
             group_by_expr,
             buffer: vec![],
             bpm,
             executed: false,
         }
     }
+    /// result of global aggregates over empty input, count is zero while the others are null
+    fn empty_tuple(&self) -> Vec<Datum> {
+        self.exprs_with_action
+            .iter()
+            .map(|(e, a)| match a {
+                AggAction::Cnt => 0i32.into(),
+                _ => Datum::null_of(&result_type(e, a)),
+            })
+            .collect_vec()
+    }
 }
 
 impl Executor for AggExecutor {
     fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
         if !self.executed {
             while let Some(slice) = self.child.execute()? {
-                let group_by = self.group_by_expr.as_mut().map(|e| e.eval(Some(&slice)));
+                let group_by = self
+                    .group_by_expr
+                    .as_mut()
+                    .map(|e| e.eval(Some(&slice)))
+                    .transpose()?;
                 let datums_per_expr = self
                     .exprs_with_action
                     .iter()
                     .map(|(e, _)| e.eval(Some(&slice)))
-                    .collect_vec();
+                    .collect::<Result<Vec<_>, _>>()?;
                 let actions = self
                     .exprs_with_action
                     .iter()
                     .map(|(_, a)| a.clone())
                     .collect_vec();
-                for ((datums, action), reducers) in datums_per_expr
+                for (((datums, action), distinct), reducers) in datums_per_expr
                     .into_iter()
                     .zip(actions)
+                    .zip(self.distincts.iter())
                     .zip(self.reducers.iter_mut())
                 {
                     for (idx, datum) in datums.iter().enumerate() {
@@ -177,21 +406,31 @@ impl Executor for AggExecutor {
                         {
                             r.reduce(datum.clone());
                         } else {
-                            reducers.push((key, Reducer::from((action.clone(), datum.clone()))))
+                            let reducer = match distinct {
+                                true => Reducer::Distinct(DistinctReducer::new(
+                                    action.clone(),
+                                    datum.clone(),
+                                )),
+                                false => Reducer::from((action.clone(), datum.clone())),
+                            };
+                            reducers.push((key, reducer))
                         }
                     }
                 }
             }
             self.executed = true;
+            if self.group_by_expr.is_none() && self.reducers.iter().all(|r| r.is_empty()) {
+                self.buffer.push(self.empty_tuple());
+            }
             self.reducers
                 .iter_mut()
                 .for_each(|column| column.sort_by(|a, b| a.0.cmp(&b.0)));
-            let columns: Vec<Vec<Datum>> = self
+            let columns = self
                 .reducers
                 .iter()
-                .map(|column| column.iter().map(|(_, r)| r.get()).collect_vec())
-                .collect_vec();
-            let len = columns[0].len();
+                .map(|column| column.iter().map(|(_, r)| r.get()).collect())
+                .collect::<Result<Vec<Vec<_>>, _>>()?;
+            let len = columns.first().map_or(0, |column| column.len());
             for idx in 0..len {
                 let tuple: Vec<Datum> = columns.iter().map(|c| c[idx].clone()).collect_vec();
                 self.buffer.push(tuple)
@@ -213,14 +452,127 @@ impl Executor for AggExecutor {
         }
     }
     fn schema(&self) -> SchemaRef {
-        let type_and_names = self
-            .exprs_with_action
-            .iter()
-            .map(|(e, a)| match a {
-                AggAction::No => (e.return_type(), e.to_string()),
-                a => (e.return_type(), format!("{}({})", a.to_string(), e)),
-            })
-            .collect_vec();
-        Rc::new(Schema::from_type_and_names(&type_and_names))
+        agg_schema(&self.exprs_with_action, &self.aliases, &self.distincts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_compensated_float_sum() {
+        let value = Datum::from(0.1f32);
+        let mut sum = SumReducer::new(value.clone());
+        let mut avg = AvgReducer::new(value.clone());
+        let mut naive = value.clone();
+        for _ in 1..100000 {
+            sum.reduce(value.clone());
+            avg.reduce(value.clone());
+            naive = naive + value.clone();
+        }
+        // naive summation drifts away by more than one
+        assert!(naive < Datum::from(9999.0f32));
+        assert_eq!(sum.get().unwrap(), Datum::from(10000.0f32));
+        assert_eq!(avg.get(), Datum::from(0.1f32));
+        let mut sum = SumReducer::new(1.into());
+        sum.reduce(2.into());
+        assert_eq!(sum.get().unwrap(), 3.into());
+    }
+
+    #[test]
+    fn test_int_sum_widened() {
+        let mut sum = SumReducer::new(i32::MAX.into());
+        let mut avg = AvgReducer::new(i32::MAX.into());
+        sum.reduce(i32::MAX.into());
+        avg.reduce(i32::MAX.into());
+        // the partial sum is beyond int, the average is not
+        assert!(sum.get().is_err());
+        assert_eq!(avg.get(), Datum::from(i32::MAX as f32));
+        sum.reduce(Datum::Int(None));
+        sum.reduce((-i32::MAX).into());
+        assert_eq!(sum.get().unwrap(), i32::MAX.into());
+        let mut avg = AvgReducer::new(1.into());
+        avg.reduce(2.into());
+        assert_eq!(avg.get(), Datum::from(1.5f32));
+    }
+
+    #[test]
+    fn test_max_min_skip_nulls() {
+        let dates = vec![
+            Datum::Date(None),
+            Datum::from(NaiveDate::from_ymd(2021, 12, 1)),
+            Datum::Date(None),
+            Datum::from(NaiveDate::from_ymd(2022, 1, 1)),
+            Datum::from(NaiveDate::from_ymd(1999, 6, 30)),
+        ];
+        let mut max = MaxReducer::new(dates[0].clone());
+        let mut min = MinReducer::new(dates[0].clone());
+        for date in dates.into_iter().skip(1) {
+            max.reduce(date.clone());
+            min.reduce(date);
+        }
+        assert_eq!(max.get(), Datum::from(NaiveDate::from_ymd(2022, 1, 1)));
+        assert_eq!(min.get(), Datum::from(NaiveDate::from_ymd(1999, 6, 30)));
+        let mut max = MaxReducer::new(Datum::from("b"));
+        let mut min = MinReducer::new(Datum::from("b"));
+        for s in vec![Datum::VarChar(None), "ab".into(), "c".into()] {
+            max.reduce(s.clone());
+            min.reduce(s);
+        }
+        assert_eq!(max.get(), Datum::from("c"));
+        assert_eq!(min.get(), Datum::from("ab"));
+        let mut min = MinReducer::new(Datum::VarChar(None));
+        min.reduce(Datum::VarChar(None));
+        assert_eq!(min.get(), Datum::VarChar(None));
+    }
+
+    #[test]
+    fn test_bool_and_or() {
+        let flags = vec![
+            Datum::Bool(None),
+            true.into(),
+            Datum::Bool(None),
+            false.into(),
+            true.into(),
+        ];
+        let mut bool_and = BoolReducer::new(flags[0].clone(), true);
+        let mut bool_or = BoolReducer::new(flags[0].clone(), false);
+        for flag in flags.into_iter().skip(1) {
+            bool_and.reduce(flag.clone());
+            bool_or.reduce(flag);
+        }
+        assert_eq!(bool_and.get(), false.into());
+        assert_eq!(bool_or.get(), true.into());
+        let mut bool_and = BoolReducer::new(true.into(), true);
+        let mut bool_or = BoolReducer::new(Datum::Bool(None), false);
+        bool_and.reduce(Datum::Bool(None));
+        bool_or.reduce(Datum::Bool(None));
+        assert_eq!(bool_and.get(), true.into());
+        assert_eq!(bool_or.get(), Datum::Bool(None));
+    }
+
+    #[test]
+    fn test_distinct() {
+        let values: Vec<Datum> = vec![3.into(), 1.into(), 3.into(), 2.into(), 1.into()];
+        let mut sum = DistinctReducer::new(AggAction::Sum, values[0].clone());
+        let mut avg = DistinctReducer::new(AggAction::Avg, values[0].clone());
+        let mut cnt = DistinctReducer::new(AggAction::Cnt, values[0].clone());
+        for value in values.into_iter().skip(1) {
+            sum.reduce(value.clone());
+            avg.reduce(value.clone());
+            cnt.reduce(value);
+        }
+        assert_eq!(sum.get().unwrap(), 6.into());
+        assert_eq!(avg.get().unwrap(), Datum::from(2.0f32));
+        assert_eq!(cnt.get().unwrap(), 3.into());
+        let mut cnt = DistinctReducer::new(AggAction::Cnt, Datum::Int(None));
+        for value in [1.into(), Datum::Int(None), 1.into(), 2.into()] {
+            cnt.reduce(value);
+        }
+        assert_eq!(cnt.get().unwrap(), 2.into());
+        let cnt = DistinctReducer::new(AggAction::Cnt, Datum::Int(None));
+        assert_eq!(cnt.get().unwrap(), 0.into());
     }
 }
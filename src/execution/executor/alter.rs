@@ -97,6 +97,34 @@ impl AddForeignExecutor {
     }
 }
 
+pub struct RenameColumnExecutor {
+    bpm: BufferPoolManagerRef,
+    catalog: CatalogManagerRef,
+    table_name: String,
+    column_name: String,
+    new_column_name: String,
+    executed: bool,
+}
+
+impl RenameColumnExecutor {
+    pub fn new(
+        bpm: BufferPoolManagerRef,
+        catalog: CatalogManagerRef,
+        table_name: String,
+        column_name: String,
+        new_column_name: String,
+    ) -> Self {
+        Self {
+            bpm,
+            catalog,
+            table_name,
+            column_name,
+            new_column_name,
+            executed: false,
+        }
+    }
+}
+
 impl Executor for AddPrimaryExecutor {
     fn schema(&self) -> SchemaRef {
         Rc::new(Schema::from_type_and_names(&[(
@@ -127,7 +155,7 @@ impl Executor for AddPrimaryExecutor {
         let mut index = BPTIndex::new(self.bpm.clone(), exprs.iter().cloned().collect_vec());
         let slices = table.into_slice();
         for slice in slices {
-            let rows = ExprImpl::batch_eval(&exprs, Some(&slice));
+            let rows = ExprImpl::batch_eval(&exprs, Some(&slice))?;
             for (idx, row) in rows.iter().enumerate() {
                 let record_id = (slice.page_id(), idx);
                 index.insert(row, record_id)?;
@@ -135,7 +163,7 @@ impl Executor for AddPrimaryExecutor {
         }
         let mut table = self.catalog.borrow().find_table(&self.table_name)?;
         table.meta_mut().page_id_of_primary_index = Some(index.get_page_id());
-        table.set_schema(Rc::new(schema));
+        table.set_schema(Rc::new(schema))?;
         let page_id = index.get_page_id();
         self.catalog.borrow_mut().add_index(
             &self.table_name,
@@ -164,11 +192,11 @@ impl Executor for AddUniqueExecutor {
         let mut schema = (*table.schema).clone();
         schema.unique.push(self.unique_set.clone());
         let exprs = schema.project_by(&self.unique_set);
-        table.set_schema(Rc::new(schema));
+        table.set_schema(Rc::new(schema))?;
         let mut index = BPTIndex::new(self.bpm.clone(), exprs.iter().cloned().collect_vec());
         let slices = table.into_slice();
         for slice in slices {
-            let rows = ExprImpl::batch_eval(&exprs, Some(&slice));
+            let rows = ExprImpl::batch_eval(&exprs, Some(&slice))?;
             for (idx, row) in rows.iter().enumerate() {
                 let record_id = (slice.page_id(), idx);
                 index.insert(row, record_id).unwrap();
@@ -219,7 +247,7 @@ impl Executor for AddForeignExecutor {
             })
             .collect::<Result<Vec<(_, _)>, SchemaError>>()?;
         schema.foreign.push((ref_table.page_id(), src_and_dst));
-        table.set_schema(Rc::new(schema));
+        table.set_schema(Rc::new(schema))?;
         Ok(Some(
             Slice::new_as_message(self.bpm.clone(), "Add Foreign", "Ok").unwrap(),
         ))
@@ -260,7 +288,7 @@ impl Executor for AddIndexExecutor {
         let slices = table.into_slice();
         let mut indexed_cnt = 0;
         for slice in slices {
-            let rows = ExprImpl::batch_eval(&self.exprs, Some(&slice));
+            let rows = ExprImpl::batch_eval(&self.exprs, Some(&slice))?;
             for (idx, row) in rows.iter().enumerate() {
                 let record_id = (slice.page_id(), idx);
                 index.insert(row, record_id).unwrap();
@@ -278,3 +306,30 @@ impl Executor for AddIndexExecutor {
         Ok(Some(msg))
     }
 }
+
+impl Executor for RenameColumnExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[(
+            DataType::new_as_varchar(false),
+            "Rename Column".to_owned(),
+        )]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        let mut table = self.catalog.borrow().find_table(&self.table_name)?;
+        let mut schema = (*table.schema).clone();
+        schema.rename_column(&self.column_name, &self.new_column_name)?;
+        table.set_schema(Rc::new(schema))?;
+        self.catalog.borrow_mut().rename_index_column(
+            &self.table_name,
+            &self.column_name,
+            &self.new_column_name,
+        )?;
+        self.executed = true;
+        Ok(Some(
+            Slice::new_as_message(self.bpm.clone(), "Rename Column", "Ok").unwrap(),
+        ))
+    }
+}
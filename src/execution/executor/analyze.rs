@@ -0,0 +1,142 @@
+use crate::datum::{DataType, Datum};
+use crate::execution::{ExecutionError, Executor};
+use crate::storage::BufferPoolManagerRef;
+use crate::table::{ColumnStats, Schema, SchemaRef, Slice, Table};
+use itertools::Itertools;
+use std::rc::Rc;
+
+/// stats of all the columns over the tuples
+pub fn stats_of(schema: &Schema, tuples: &[Vec<Datum>]) -> Vec<ColumnStats> {
+    schema
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(idx, column)| {
+            ColumnStats::collect(column.data_type, tuples.iter().map(|t| t[idx].clone()))
+        })
+        .collect_vec()
+}
+
+/// the rows output by analyze, one for each column
+pub fn insert_stats(
+    slice: &mut Slice,
+    schema: &Schema,
+    stats: Vec<ColumnStats>,
+) -> Result<(), ExecutionError> {
+    for (column, stats) in schema.columns.iter().zip(stats) {
+        slice.insert(&[
+            column.desc.as_str().into(),
+            stats.min.to_string().as_str().into(),
+            stats.max.to_string().as_str().into(),
+            (stats.num_nulls as i32).into(),
+            (stats.num_distinct as i32).into(),
+        ])?;
+    }
+    Ok(())
+}
+
+/// the schema of the rows output by analyze
+pub fn stats_schema() -> SchemaRef {
+    Rc::new(Schema::from_type_and_names(&[
+        (DataType::new_as_varchar(false), "column".to_string()),
+        (DataType::new_as_varchar(false), "min".to_string()),
+        (DataType::new_as_varchar(false), "max".to_string()),
+        (DataType::new_as_int(false), "nulls".to_string()),
+        (DataType::new_as_int(false), "distinct".to_string()),
+    ]))
+}
+
+pub struct AnalyzeExecutor {
+    table: Table,
+    bpm: BufferPoolManagerRef,
+    executed: bool,
+}
+
+impl AnalyzeExecutor {
+    pub fn new(table: Table, bpm: BufferPoolManagerRef) -> Self {
+        Self {
+            table,
+            bpm,
+            executed: false,
+        }
+    }
+}
+
+impl Executor for AnalyzeExecutor {
+    fn schema(&self) -> SchemaRef {
+        stats_schema()
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        self.executed = true;
+        let schema = self.table.schema.clone();
+        let tuples = self
+            .table
+            .iter()
+            .flat_map(|s| s.tuple_iter().collect_vec())
+            .collect_vec();
+        let stats = stats_of(&schema, &tuples);
+        self.table.set_stats(&stats)?;
+        let mut slice = Slice::new(self.bpm.clone(), self.schema());
+        insert_stats(&mut slice, &schema, stats)?;
+        Ok(Some(slice))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::BufferPoolManager;
+    use std::fs::remove_file;
+
+    #[test]
+    fn test_analyze() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(10);
+            let filename = bpm.borrow().filename();
+            let schema = Rc::new(Schema::from_type_and_names(&[
+                (DataType::new_as_int(false), "v1".to_string()),
+                (DataType::new_as_varchar(true), "v2".to_string()),
+            ]));
+            let mut table = Table::new(schema, bpm.clone());
+            for num in 0..1000 {
+                let v2 = match num % 3 {
+                    0 => Datum::VarChar(None),
+                    _ => format!("foo{}", num % 10).as_str().into(),
+                };
+                table.insert(vec![(num * 7 % 1000).into(), v2]).unwrap();
+            }
+            assert_eq!(table.stats(), None);
+            let page_id = table.page_id();
+            let mut executor = AnalyzeExecutor::new(table, bpm.clone());
+            let slice = executor.execute().unwrap().unwrap();
+            assert_eq!(slice.count(), 2);
+            drop(slice);
+            drop(executor);
+            let table = Table::open(page_id, bpm);
+            assert_eq!(
+                table.stats().unwrap(),
+                vec![
+                    ColumnStats {
+                        min: 0.into(),
+                        max: 999.into(),
+                        num_nulls: 0,
+                        num_distinct: 1000,
+                        num_rows: 1000,
+                    },
+                    ColumnStats {
+                        min: "foo0".into(),
+                        max: "foo9".into(),
+                        num_nulls: 334,
+                        num_distinct: 10,
+                        num_rows: 1000,
+                    },
+                ]
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+}
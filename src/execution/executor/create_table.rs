@@ -1,6 +1,6 @@
 use crate::catalog::{CatalogError, CatalogManagerRef};
 use crate::datum::DataType;
-use crate::execution::{ExecutionError, Executor};
+use crate::execution::{ExecutionError, Executor, ExecutorImpl};
 use crate::index::BPTIndex;
 use crate::storage::BufferPoolManagerRef;
 use crate::table::{Schema, SchemaRef, Slice, Table};
@@ -32,6 +32,31 @@ impl CreateTableExecutor {
     }
 }
 
+pub struct CreateTableAsExecutor {
+    bpm: BufferPoolManagerRef,
+    catalog: CatalogManagerRef,
+    table_name: String,
+    child: Box<ExecutorImpl>,
+    executed: bool,
+}
+
+impl CreateTableAsExecutor {
+    pub fn new(
+        bpm: BufferPoolManagerRef,
+        catalog: CatalogManagerRef,
+        table_name: String,
+        child: ExecutorImpl,
+    ) -> Self {
+        Self {
+            bpm,
+            catalog,
+            table_name,
+            child: Box::new(child),
+            executed: false,
+        }
+    }
+}
+
 impl Executor for CreateTableExecutor {
     fn schema(&self) -> SchemaRef {
         Rc::new(Schema::from_type_and_names(&[(
@@ -85,3 +110,50 @@ impl Executor for CreateTableExecutor {
         }
     }
 }
+
+impl Executor for CreateTableAsExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[(
+            DataType::new_as_int(false),
+            "Inserted".to_string(),
+        )]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        // constraints of the source do not carry over to the new table
+        let schema = Schema::from_type_and_names(&self.child.schema().to_type_and_names());
+        info!("create table as select, schema = {:?}", schema);
+        if self.catalog.borrow().find_table(&self.table_name).is_ok() {
+            return Err(ExecutionError::Catalog(CatalogError::Duplicated));
+        }
+        let mut table = Table::new(Rc::new(schema), self.bpm.clone());
+        self.catalog
+            .borrow_mut()
+            .create_table(&self.table_name, table.page_id())?;
+        let mut insert_all = || -> Result<usize, ExecutionError> {
+            let mut cnt = 0;
+            while let Some(slice) = self.child.execute()? {
+                for tuple in slice.tuple_iter() {
+                    table.insert(tuple)?;
+                    cnt += 1;
+                }
+            }
+            Ok(cnt)
+        };
+        // the table is dropped again if the query fails
+        let cnt = match insert_all() {
+            Ok(cnt) => cnt,
+            Err(err) => {
+                self.catalog.borrow_mut().remove_table(&self.table_name)?;
+                table.erase();
+                return Err(err);
+            }
+        };
+        self.executed = true;
+        Ok(Some(
+            Slice::new_as_count(self.bpm.clone(), "Inserted", cnt).unwrap(),
+        ))
+    }
+}
@@ -12,6 +12,10 @@ pub struct DeleteExecutor {
     table: Table,
     bpm: BufferPoolManagerRef,
     buffer: Vec<Vec<Datum>>,
+    /// keep the references of foreign keys
+    check_foreign: bool,
+    /// rows left to delete, unlimited if none
+    remaining: Option<usize>,
 }
 
 impl DeleteExecutor {
@@ -19,7 +23,9 @@ impl DeleteExecutor {
         child: Box<ExecutorImpl>,
         indexes: Vec<BPTIndex>,
         table: Table,
+        check_foreign: bool,
         bpm: BufferPoolManagerRef,
+        limit: Option<usize>,
     ) -> Self {
         Self {
             child,
@@ -27,6 +33,8 @@ impl DeleteExecutor {
             table,
             bpm,
             buffer: vec![],
+            check_foreign,
+            remaining: limit,
         }
     }
 }
@@ -36,9 +44,23 @@ impl Executor for DeleteExecutor {
         self.table.schema.clone()
     }
     fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
-        while let Some(input) = self.child.execute()? {
+        while self.remaining != Some(0) {
+            let input = match self.child.execute()? {
+                Some(input) => input,
+                None => break,
+            };
+            // only the leading rows of the slice within the limit are deleted
+            let cnt = self
+                .remaining
+                .map_or(input.count(), |remaining| remaining.min(input.count()));
+            self.remaining = self.remaining.map(|remaining| remaining - cnt);
             // stage-1: validate
-            for (page_id, src_and_dst) in &self.table.schema.foreign {
+            let foreign = if self.check_foreign {
+                self.table.schema.foreign.as_slice()
+            } else {
+                &[]
+            };
+            for (page_id, src_and_dst) in foreign {
                 let mut foreign_table = Table::open(*page_id, self.bpm.clone());
                 let page_id_of_index = foreign_table
                     .meta()
@@ -48,13 +70,13 @@ impl Executor for DeleteExecutor {
                     self.bpm.clone(),
                     page_id_of_index,
                     foreign_table.schema.as_ref(),
-                );
+                )?;
                 let exprs = self
                     .table
                     .schema
                     .project_by(&src_and_dst.iter().map(|(src, _)| *src).collect_vec());
-                let datums_from_slice = ExprImpl::batch_eval(&exprs, Some(&input));
-                for datums in datums_from_slice {
+                let datums_from_slice = ExprImpl::batch_eval(&exprs, Some(&input))?;
+                for datums in datums_from_slice.into_iter().take(cnt) {
                     let record_id = foreign_index.find(&datums).ok_or(IndexError::KeyNotFound)?;
                     let ref_cnt = foreign_table.ref_cnt_of(record_id)?;
                     foreign_table.set_ref_cnt_of(record_id, ref_cnt - 1)?;
@@ -65,13 +87,17 @@ impl Executor for DeleteExecutor {
                 .indexes
                 .iter_mut()
                 .map(|index| ExprImpl::batch_eval(&index.exprs, Some(&input)))
-                .collect_vec();
-            for idx in input.slot_iter() {
+                .collect::<Result<Vec<_>, _>>()?;
+            for idx in input.slot_iter().take(cnt) {
                 let mut tuple = input.tuple_at(idx)?;
                 let idx: i32 = tuple.pop().unwrap().into();
                 let page_id: i32 = tuple.pop().unwrap().into();
                 let record_id = (page_id as usize, idx as usize);
-                self.table.remove(record_id)?;
+                if self.check_foreign {
+                    self.table.remove(record_id)?;
+                } else {
+                    self.table.remove_unchecked(record_id)?;
+                }
                 for (rows, index) in indexes_rows.iter_mut().zip(&mut self.indexes) {
                     index.remove(&rows.remove(0))?;
                 }
@@ -0,0 +1,69 @@
+use crate::datum::Datum;
+use crate::execution::{ExecutionError, Executor, ExecutorImpl};
+use crate::expr::ExprImpl;
+use crate::storage::BufferPoolManagerRef;
+use crate::table::{SchemaRef, Slice};
+use itertools::Itertools;
+use std::collections::VecDeque;
+
+/// the child is sorted by the exprs, a tuple is kept if its key differs from the last one
+pub struct DistinctOnExecutor {
+    child: Box<ExecutorImpl>,
+    exprs: Vec<ExprImpl>,
+    bpm: BufferPoolManagerRef,
+    buffer: VecDeque<Vec<Datum>>,
+    /// key of the last kept tuple
+    last_key: Option<Vec<Datum>>,
+}
+
+impl DistinctOnExecutor {
+    pub fn new(bpm: BufferPoolManagerRef, child: Box<ExecutorImpl>, exprs: Vec<ExprImpl>) -> Self {
+        Self {
+            child,
+            exprs,
+            bpm,
+            buffer: VecDeque::new(),
+            last_key: None,
+        }
+    }
+}
+
+impl Executor for DistinctOnExecutor {
+    fn schema(&self) -> SchemaRef {
+        self.child.schema()
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        let mut output = Slice::new(self.bpm.clone(), self.schema());
+        loop {
+            if self.buffer.is_empty() {
+                let input = self.child.execute()?;
+                if let Some(slice) = input {
+                    let mut columns = self
+                        .exprs
+                        .iter()
+                        .map(|e| Ok(e.eval(Some(&slice))?.into_iter()))
+                        .collect::<Result<Vec<_>, ExecutionError>>()?;
+                    for tuple in slice.tuple_iter() {
+                        let key = columns.iter_mut().map(|c| c.next().unwrap()).collect_vec();
+                        if self.last_key.as_ref() != Some(&key) {
+                            self.buffer.push_back(tuple);
+                            self.last_key = Some(key);
+                        }
+                    }
+                } else if output.count() > 0 {
+                    return Ok(Some(output));
+                } else {
+                    return Ok(None);
+                }
+            }
+            if !self.buffer.is_empty() {
+                if output.insert(self.buffer.front().unwrap()).is_ok() {
+                    self.buffer.pop_front();
+                } else {
+                    break;
+                }
+            }
+        }
+        Ok(Some(output))
+    }
+}
@@ -1,3 +1,4 @@
+use super::truncate::release_references;
 use crate::catalog::CatalogManagerRef;
 use crate::datum::DataType;
 use crate::execution::{ExecutionError, Executor};
@@ -9,6 +10,7 @@ use std::rc::Rc;
 
 pub struct DropTableExecutor {
     table_name: String,
+    cascade: bool,
     executed: bool,
     catalog: CatalogManagerRef,
     bpm: BufferPoolManagerRef,
@@ -45,9 +47,15 @@ pub struct DropForeignExecuor {
 }
 
 impl DropTableExecutor {
-    pub fn new(table_name: String, catalog: CatalogManagerRef, bpm: BufferPoolManagerRef) -> Self {
+    pub fn new(
+        table_name: String,
+        cascade: bool,
+        catalog: CatalogManagerRef,
+        bpm: BufferPoolManagerRef,
+    ) -> Self {
         Self {
             table_name,
+            cascade,
             executed: false,
             catalog,
             bpm,
@@ -136,7 +144,7 @@ impl Executor for DropForeignExecuor {
                 src == self.column_idxes
             })
             .collect_vec();
-        table.set_schema(Rc::new(schema));
+        table.set_schema(Rc::new(schema))?;
         let output = Slice::new_as_message(self.bpm.clone(), "Drop Foreign", "Ok")?;
         Ok(Some(output))
     }
@@ -183,7 +191,7 @@ impl Executor for DropPrimaryExecutor {
             .drop_index(&self.table_name, Rc::new(schema))?;
         let mut schema = (*table.schema).clone();
         schema.primary = vec![];
-        table.set_schema(Rc::new(schema));
+        table.set_schema(Rc::new(schema))?;
         let slice = Slice::new_as_message(self.bpm.clone(), "Drop Primary", "Ok")?;
         Ok(Some(slice))
     }
@@ -223,6 +231,25 @@ impl Executor for DropTableExecutor {
         }
         self.executed = true;
         let table = self.catalog.borrow().find_table(&self.table_name)?;
+        let referencing = self.catalog.borrow().tables_referencing(&self.table_name)?;
+        if !referencing.is_empty() && !self.cascade {
+            return Err(ExecutionError::TableReferenced(
+                self.table_name.clone(),
+                referencing.join(", "),
+            ));
+        }
+        // drop the foreign keys referencing the table
+        for table_name in referencing {
+            let mut referencing_table = self.catalog.borrow().find_table(&table_name)?;
+            let mut schema = (*referencing_table.schema).clone();
+            schema.foreign = schema
+                .foreign
+                .into_iter()
+                .filter(|(page_id, _)| *page_id != table.page_id())
+                .collect_vec();
+            referencing_table.set_schema(Rc::new(schema))?;
+        }
+        release_references(&table, self.bpm.clone())?;
         table.erase();
         self.catalog.borrow_mut().remove_table(&self.table_name)?;
         self.catalog
@@ -0,0 +1,115 @@
+use crate::datum::Datum;
+use crate::execution::{ExecutionError, Executor, ExecutorImpl};
+use crate::expr::ExprImpl;
+use crate::storage::BufferPoolManagerRef;
+use crate::table::{SchemaRef, Slice, TableError};
+use itertools::Itertools;
+use std::collections::VecDeque;
+
+pub struct ExistsExecutor {
+    child: Box<ExecutorImpl>,
+    subquery: Box<ExecutorImpl>,
+    exprs: Vec<ExprImpl>,
+    schema: SchemaRef,
+    bpm: BufferPoolManagerRef,
+    inner: Option<Vec<Vec<Datum>>>,
+    /// the outer tuple joined with the inner ones, reused for every outer tuple
+    scratch: Option<Slice>,
+    buffer: VecDeque<Vec<Datum>>,
+}
+
+impl ExistsExecutor {
+    pub fn new(
+        bpm: BufferPoolManagerRef,
+        child: Box<ExecutorImpl>,
+        subquery: Box<ExecutorImpl>,
+        exprs: Vec<ExprImpl>,
+        schema: SchemaRef,
+    ) -> Self {
+        Self {
+            child,
+            subquery,
+            exprs,
+            schema,
+            bpm,
+            inner: None,
+            scratch: None,
+            buffer: VecDeque::new(),
+        }
+    }
+    fn any_match(exprs: &[ExprImpl], slice: &Slice) -> Result<bool, ExecutionError> {
+        if slice.count() == 0 {
+            return Ok(false);
+        }
+        Ok(ExprImpl::batch_eval(exprs, Some(slice))?
+            .iter()
+            .any(|row| row.iter().all(|d| matches!(d, Datum::Bool(Some(true))))))
+    }
+    /// evaluate the correlated predicates of the subquery with the outer tuple bound
+    fn exists(&mut self, outer: &[Datum]) -> Result<bool, ExecutionError> {
+        let slice = self.scratch.as_mut().unwrap();
+        slice.clear();
+        for inner in self.inner.as_ref().unwrap() {
+            let tuple = [outer, inner.as_slice()].concat();
+            if slice.insert(&tuple).is_err() {
+                if Self::any_match(&self.exprs, slice)? {
+                    return Ok(true);
+                }
+                slice.clear();
+                slice.insert(&tuple)?;
+            }
+        }
+        Self::any_match(&self.exprs, slice)
+    }
+    /// the scratch slice is freed once the outer tuples are consumed
+    fn free_scratch(&mut self) -> Result<(), ExecutionError> {
+        if let Some(slice) = self.scratch.take() {
+            let page_id = slice.page_id();
+            drop(slice);
+            self.bpm
+                .borrow_mut()
+                .free(page_id)
+                .map_err(TableError::from)?;
+        }
+        Ok(())
+    }
+}
+
+impl Executor for ExistsExecutor {
+    fn schema(&self) -> SchemaRef {
+        self.child.schema()
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.inner.is_none() {
+            let mut inner = vec![];
+            while let Some(slice) = self.subquery.execute()? {
+                inner.extend(slice.tuple_iter());
+            }
+            self.inner = Some(inner);
+            self.scratch = Some(Slice::new(self.bpm.clone(), self.schema.clone()));
+        }
+        let mut output = Slice::new(self.bpm.clone(), self.schema());
+        loop {
+            if self.buffer.is_empty() {
+                if let Some(slice) = self.child.execute()? {
+                    for tuple in slice.tuple_iter().collect_vec() {
+                        if self.exists(&tuple)? {
+                            self.buffer.push_back(tuple);
+                        }
+                    }
+                } else {
+                    self.free_scratch()?;
+                    return Ok(Some(output).filter(|output| output.count() > 0));
+                }
+            }
+            if !self.buffer.is_empty() {
+                if output.insert(self.buffer.front().unwrap()).is_ok() {
+                    self.buffer.pop_front();
+                } else {
+                    break;
+                }
+            }
+        }
+        Ok(Some(output))
+    }
+}
@@ -0,0 +1,40 @@
+use crate::datum::DataType;
+use crate::execution::{ExecutionError, Executor};
+use crate::storage::BufferPoolManagerRef;
+use crate::table::{ResultBuilder, Schema, SchemaRef, Slice};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+pub struct ExplainExecutor {
+    lines: Vec<String>,
+    bpm: BufferPoolManagerRef,
+    /// slices not output yet, built at the first execution
+    output: Option<VecDeque<Slice>>,
+}
+
+impl ExplainExecutor {
+    pub fn new(lines: Vec<String>, bpm: BufferPoolManagerRef) -> Self {
+        Self {
+            lines,
+            bpm,
+            output: None,
+        }
+    }
+}
+
+impl Executor for ExplainExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[(
+            DataType::new_as_varchar(false),
+            "plan".to_string(),
+        )]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.output.is_none() {
+            let rows = self.lines.iter().map(|line| vec![line.as_str().into()]);
+            let slices = ResultBuilder::from_rows(self.bpm.clone(), self.schema(), rows)?;
+            self.output = Some(slices.into());
+        }
+        Ok(self.output.as_mut().unwrap().pop_front())
+    }
+}
@@ -1,11 +1,55 @@
 use crate::datum::Datum;
 use crate::execution::{ExecutionError, Executor, ExecutorImpl};
-use crate::expr::ExprImpl;
+use crate::expr::{ExprError, ExprImpl, RowErrors};
 use crate::storage::BufferPoolManagerRef;
 use crate::table::{SchemaRef, Slice};
 use itertools::Itertools;
 use std::collections::VecDeque;
 
+/// whether each tuple of the slice passes all the predicates, an error of a predicate is only
+/// reported for the tuples which pass the predicates before it
+pub fn filter_map(exprs: &[ExprImpl], slice: &Slice) -> Result<Vec<bool>, ExprError> {
+    let mut check = vec![true; slice.count()];
+    for expr in exprs {
+        let mut row_errors = RowErrors::new();
+        let res = expr.eval_rows(Some(slice), &mut row_errors);
+        if let Some((_, err)) = row_errors.into_iter().find(|(idx, _)| check[*idx]) {
+            return Err(err);
+        }
+        check = check
+            .iter()
+            .zip(res.iter())
+            .map(|(b, d)| match d {
+                Datum::Bool(Some(d)) => b & d,
+                // null never passes the filter
+                Datum::Bool(None) => false,
+                _ => unreachable!(),
+            })
+            .collect_vec();
+    }
+    Ok(check)
+}
+
+/// yields nothing, only carries the schema of the child it replaces
+pub struct EmptyExecutor {
+    schema: SchemaRef,
+}
+
+impl EmptyExecutor {
+    pub fn new(schema: SchemaRef) -> Self {
+        Self { schema }
+    }
+}
+
+impl Executor for EmptyExecutor {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        Ok(None)
+    }
+}
+
 pub struct FilterExecutor {
     child: Box<ExecutorImpl>,
     exprs: Vec<ExprImpl>,
@@ -14,7 +58,13 @@ pub struct FilterExecutor {
 }
 
 impl FilterExecutor {
-    pub fn new(bpm: BufferPoolManagerRef, child: Box<ExecutorImpl>, exprs: Vec<ExprImpl>) -> Self {
+    pub fn new(
+        bpm: BufferPoolManagerRef,
+        child: Box<ExecutorImpl>,
+        mut exprs: Vec<ExprImpl>,
+    ) -> Self {
+        // the constant subtrees are evaluated once here rather than for every slice
+        exprs.iter_mut().for_each(ExprImpl::fold);
         Self {
             child,
             exprs,
@@ -22,24 +72,6 @@ impl FilterExecutor {
             buffer: VecDeque::new(),
         }
     }
-    fn filter_map(&self, slice: &Slice) -> Vec<bool> {
-        let check_results = self.exprs.iter().map(|e| e.eval(Some(slice))).collect_vec();
-        let len = check_results[0].len();
-        let check_results = check_results.iter().fold(vec![true; len], |check, res| {
-            check
-                .iter()
-                .zip(res.iter())
-                .map(|(b, d)| {
-                    if let Datum::Bool(Some(d)) = d {
-                        b & d
-                    } else {
-                        unreachable!()
-                    }
-                })
-                .collect_vec()
-        });
-        check_results
-    }
 }
 
 impl Executor for FilterExecutor {
@@ -47,13 +79,21 @@ impl Executor for FilterExecutor {
         self.child.schema()
     }
     fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        // an always-false predicate never needs the input
+        if self
+            .exprs
+            .iter()
+            .any(|e| e.as_constant() == Some(Datum::Bool(Some(false))))
+        {
+            return Ok(None);
+        }
         let mut output = Slice::new(self.bpm.clone(), self.schema());
         loop {
             if self.buffer.is_empty() {
                 let input = self.child.execute()?;
                 if let Some(slice) = input {
                     let tuples = slice.tuple_iter().collect_vec();
-                    let filter_map = self.filter_map(&slice);
+                    let filter_map = filter_map(&self.exprs, &slice)?;
                     for (tuple, check) in tuples.into_iter().zip(filter_map) {
                         if !check {
                             continue;
@@ -77,3 +117,81 @@ impl Executor for FilterExecutor {
         Ok(Some(output))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datum::DataType;
+    use crate::execution::SeqScanExecutor;
+    use crate::expr::{BinaryExpr, BinaryOp, ColumnRefExpr, ConstantExpr, EVALS};
+    use crate::storage::BufferPoolManager;
+    use crate::table::{Schema, Table};
+    use std::fs::remove_file;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_filter_constant_evaluated_once() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(10);
+            let filename = bpm.borrow().filename();
+            let schema = Rc::new(Schema::from_type_and_names(&[(
+                DataType::new_as_int(false),
+                "v1".to_string(),
+            )]));
+            let mut table = Table::new(schema.clone(), bpm.clone());
+            for num in 0..2000 {
+                table.insert(vec![(num % 10).into()]).unwrap();
+            }
+            let constant = |value: i32| {
+                Box::new(ExprImpl::Constant(ConstantExpr::new(
+                    value.into(),
+                    DataType::new_as_int(false),
+                )))
+            };
+            // v1 = 1 + 2
+            let expr = ExprImpl::Binary(BinaryExpr::new(
+                Box::new(ExprImpl::ColumnRef(ColumnRefExpr::new(
+                    0,
+                    DataType::new_as_int(false),
+                    "v1".to_string(),
+                ))),
+                Box::new(ExprImpl::Binary(BinaryExpr::new(
+                    constant(1),
+                    constant(2),
+                    BinaryOp::Add,
+                    true,
+                ))),
+                BinaryOp::Equal,
+                true,
+            ));
+            let scan = || {
+                Box::new(ExecutorImpl::SeqScan(SeqScanExecutor::new(
+                    bpm.clone(),
+                    Some(table.meta().page_id_of_first_slice),
+                    schema.clone(),
+                    false,
+                )))
+            };
+            let mut num_slices = 0;
+            let mut input = scan();
+            while input.execute().unwrap().is_some() {
+                num_slices += 1;
+            }
+            EVALS.with(|evals| evals.set(0));
+            let mut filter = FilterExecutor::new(bpm.clone(), scan(), vec![expr]);
+            // the rhs is folded while building the executor
+            assert_eq!(EVALS.with(|evals| evals.get()), 1);
+            let mut tuples = vec![];
+            while let Some(slice) = filter.execute().unwrap() {
+                tuples.extend(slice.tuple_iter());
+            }
+            assert!(num_slices > 1);
+            // only the comparison is evaluated for each slice
+            assert_eq!(EVALS.with(|evals| evals.get()), 1 + num_slices);
+            assert_eq!(tuples.len(), 200);
+            assert!(tuples.iter().all(|tuple| tuple == &vec![3.into()]));
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+}
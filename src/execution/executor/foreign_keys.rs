@@ -0,0 +1,108 @@
+use crate::catalog::CatalogManagerRef;
+use crate::datum::DataType;
+use crate::execution::{ExecutionError, Executor};
+use crate::index::BPTIndex;
+use crate::storage::{BufferPoolManagerRef, PageID};
+use crate::table::{Schema, SchemaError, SchemaRef, Slice, Table};
+use itertools::Itertools;
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+pub struct ForeignKeysExecutor {
+    enabled: bool,
+    foreign_keys: Rc<Cell<bool>>,
+    catalog: CatalogManagerRef,
+    bpm: BufferPoolManagerRef,
+    executed: bool,
+}
+
+impl ForeignKeysExecutor {
+    pub fn new(
+        enabled: bool,
+        foreign_keys: Rc<Cell<bool>>,
+        catalog: CatalogManagerRef,
+        bpm: BufferPoolManagerRef,
+    ) -> Self {
+        Self {
+            enabled,
+            foreign_keys,
+            catalog,
+            bpm,
+            executed: false,
+        }
+    }
+    /// count the references to every tuple from scratch, as they are not maintained while
+    /// foreign keys are off, failing on a foreign key referring to a missing tuple
+    fn revalidate(&self) -> Result<(), ExecutionError> {
+        let table_names = self
+            .catalog
+            .borrow()
+            .table_names()?
+            .into_iter()
+            .filter(|name| !name.contains(':'))
+            .collect_vec();
+        let tables = table_names
+            .iter()
+            .map(|name| self.catalog.borrow().find_table(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let referenced: HashSet<PageID> = tables
+            .iter()
+            .flat_map(|table| table.schema.foreign.iter().map(|(page_id, _)| *page_id))
+            .collect();
+        for page_id in referenced {
+            let table = Table::open(page_id, self.bpm.clone());
+            for mut slice in table.iter() {
+                for idx in slice.slot_iter().collect_vec() {
+                    slice.set_ref_cnt_at(idx, 0)?;
+                }
+            }
+        }
+        for (table_name, table) in table_names.iter().zip(tables.iter()) {
+            for (page_id, src_and_dst) in &table.schema.foreign {
+                let mut foreign_table = Table::open(*page_id, self.bpm.clone());
+                let page_id_of_index = foreign_table
+                    .meta()
+                    .page_id_of_primary_index
+                    .ok_or(SchemaError::PrimaryNotFound)?;
+                let foreign_index = BPTIndex::open(
+                    self.bpm.clone(),
+                    page_id_of_index,
+                    foreign_table.schema.as_ref(),
+                )?;
+                let src = src_and_dst.iter().map(|(src, _)| *src).collect_vec();
+                for tuple in table.iter().flat_map(|s| s.tuple_iter().collect_vec()) {
+                    let key = src.iter().map(|idx| tuple[*idx].clone()).collect_vec();
+                    let record_id = foreign_index.find(&key).ok_or_else(|| {
+                        ExecutionError::ForeignKeyViolation(table_name.clone(), key.clone())
+                    })?;
+                    let ref_cnt = foreign_table.ref_cnt_of(record_id)?;
+                    foreign_table.set_ref_cnt_of(record_id, ref_cnt + 1)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Executor for ForeignKeysExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[(
+            DataType::new_as_varchar(false),
+            "foreign_keys".to_string(),
+        )]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        self.executed = true;
+        if self.enabled && !self.foreign_keys.get() {
+            self.revalidate()?;
+        }
+        self.foreign_keys.set(self.enabled);
+        let state = if self.enabled { "on" } else { "off" };
+        let slice = Slice::new_as_message(self.bpm.clone(), "foreign_keys", state)?;
+        Ok(Some(slice))
+    }
+}
@@ -1,15 +1,16 @@
-use crate::datum::{DataType, Datum};
-use crate::execution::{ExecutionError, Executor};
+use crate::datum::Datum;
+use crate::execution::{scan_schema, ExecutionError, Executor};
 use crate::index::BPTIndex;
 use crate::storage::BufferPoolManagerRef;
-use crate::table::{Schema, SchemaRef, Slice, Table};
-use std::rc::Rc;
+use crate::table::{SchemaRef, Slice, Table};
 
 pub struct IndexScanExecutor {
     table: Table,
     index: BPTIndex,
-    begin_datums: Vec<Datum>,
-    end_datums: Vec<Datum>,
+    /// None means scanning from the first leaf
+    begin_datums: Option<Vec<Datum>>,
+    /// None means scanning to the last leaf
+    end_datums: Option<Vec<Datum>>,
     bpm: BufferPoolManagerRef,
     done: bool,
     with_record_id: bool,
@@ -19,8 +20,8 @@ impl IndexScanExecutor {
     pub fn new(
         table: Table,
         index: BPTIndex,
-        begin_datums: Vec<Datum>,
-        end_datums: Vec<Datum>,
+        begin_datums: Option<Vec<Datum>>,
+        end_datums: Option<Vec<Datum>>,
         bpm: BufferPoolManagerRef,
         with_record_id: bool,
     ) -> Self {
@@ -38,32 +39,30 @@ impl IndexScanExecutor {
 
 impl Executor for IndexScanExecutor {
     fn schema(&self) -> SchemaRef {
-        if !self.with_record_id {
-            self.table.schema.clone()
-        } else {
-            let mut type_and_names = self.table.schema.to_type_and_names();
-            type_and_names.push((DataType::new_as_int(false), "_page_id".to_string()));
-            type_and_names.push((DataType::new_as_int(false), "_idx".to_string()));
-            Rc::new(Schema::from_type_and_names(&type_and_names))
-        }
+        scan_schema(&self.table.schema, self.with_record_id)
     }
     fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
         if self.done {
             return Ok(None);
         }
         let mut output = Slice::new(self.bpm.clone(), self.schema());
-        let iter = self.index.iter_start_from(&self.begin_datums).unwrap();
-        for (datums, record_id) in iter {
-            if datums > self.end_datums {
-                break;
-            }
+        let iter = match &self.begin_datums {
+            Some(begin_datums) => self.index.iter_start_from(begin_datums),
+            None => Some(self.index.iter()),
+        };
+        let iter = iter.map(|iter| match &self.end_datums {
+            Some(end_datums) => iter.with_upper_bound(end_datums.clone(), true),
+            None => iter,
+        });
+        for (key, record_id) in iter.into_iter().flatten() {
             let mut datums = self.table.tuple_at(record_id).unwrap();
             if self.with_record_id {
                 datums.push(Datum::Int(Some(record_id.0 as i32)));
                 datums.push(Datum::Int(Some(record_id.1 as i32)));
             }
             if output.insert(&datums).is_err() {
-                self.begin_datums = datums;
+                // resume from the key not yet emitted
+                self.begin_datums = Some(key);
                 return Ok(Some(output));
             }
         }
@@ -71,3 +70,82 @@ impl Executor for IndexScanExecutor {
         Ok(Some(output))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datum::DataType;
+    use crate::expr::{ColumnRefExpr, ExprImpl};
+    use crate::storage::BufferPoolManager;
+    use crate::table::Schema;
+    use itertools::Itertools;
+    use std::fs::remove_file;
+    use std::rc::Rc;
+
+    fn collect(mut executor: IndexScanExecutor) -> Vec<Vec<Datum>> {
+        let mut tuples = vec![];
+        while let Some(slice) = executor.execute().unwrap() {
+            tuples.extend(slice.tuple_iter());
+        }
+        tuples
+    }
+
+    #[test]
+    fn test_open_ended_scan() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(50);
+            let filename = bpm.borrow().filename();
+            let schema = Rc::new(Schema::from_type_and_names(&[(
+                DataType::new_as_int(false),
+                "v1".to_string(),
+            )]));
+            let exprs = vec![ExprImpl::ColumnRef(ColumnRefExpr::new(
+                0,
+                DataType::new_as_int(false),
+                "v1".to_string(),
+            ))];
+            let mut table = Table::new(schema, bpm.clone());
+            let mut index = BPTIndex::new(bpm.clone(), exprs);
+            // nothing to probe in an empty index
+            let executor = IndexScanExecutor::new(
+                Table::open(table.page_id(), bpm.clone()),
+                BPTIndex::open(bpm.clone(), index.get_page_id(), table.schema.as_ref()).unwrap(),
+                None,
+                None,
+                bpm.clone(),
+                false,
+            );
+            assert!(collect(executor).is_empty());
+            for num in (0..3000).rev() {
+                let record_id = table.insert(vec![Datum::Int(Some(num))]).unwrap();
+                index.insert(&[Datum::Int(Some(num))], record_id).unwrap();
+            }
+            let end_datums = Some(vec![Datum::Int(Some(2000))]);
+            let open = collect(IndexScanExecutor::new(
+                Table::open(table.page_id(), bpm.clone()),
+                BPTIndex::open(bpm.clone(), index.get_page_id(), table.schema.as_ref()).unwrap(),
+                None,
+                end_datums.clone(),
+                bpm.clone(),
+                false,
+            ));
+            let closed = collect(IndexScanExecutor::new(
+                Table::open(table.page_id(), bpm.clone()),
+                BPTIndex::open(bpm.clone(), index.get_page_id(), table.schema.as_ref()).unwrap(),
+                Some(index.first_key()),
+                end_datums,
+                bpm.clone(),
+                false,
+            ));
+            assert_eq!(open, closed);
+            assert_eq!(
+                open,
+                (0..=2000)
+                    .map(|num| vec![Datum::Int(Some(num))])
+                    .collect_vec()
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+}
@@ -0,0 +1,179 @@
+use crate::datum::Datum;
+use crate::execution::{scan_schema, ExecutionError, Executor};
+use crate::index::BPTIndex;
+use crate::storage::BufferPoolManagerRef;
+use crate::table::{SchemaRef, Slice, Table};
+use itertools::Itertools;
+
+/// for each distinct leading key of the index, seek the range bounded on the rest keys
+pub struct IndexSkipScanExecutor {
+    table: Table,
+    index: BPTIndex,
+    /// bounds of the exprs after the leading one, both inclusive
+    begin_datums: Vec<Datum>,
+    end_datums: Vec<Datum>,
+    /// the key to seek from next, none means from the first leaf
+    cursor: Option<Vec<Datum>>,
+    bpm: BufferPoolManagerRef,
+    done: bool,
+    with_record_id: bool,
+}
+
+impl IndexSkipScanExecutor {
+    pub fn new(
+        table: Table,
+        index: BPTIndex,
+        begin_datums: Vec<Datum>,
+        end_datums: Vec<Datum>,
+        bpm: BufferPoolManagerRef,
+        with_record_id: bool,
+    ) -> Self {
+        Self {
+            table,
+            index,
+            begin_datums,
+            end_datums,
+            cursor: None,
+            bpm,
+            done: false,
+            with_record_id,
+        }
+    }
+    /// the first key at or after the cursor
+    fn first_key_from_cursor(&self) -> Option<Vec<Datum>> {
+        let mut iter = match &self.cursor {
+            Some(cursor) => self.index.iter_start_from(cursor)?,
+            None => self.index.iter(),
+        };
+        iter.next().map(|(key, _)| key)
+    }
+}
+
+impl Executor for IndexSkipScanExecutor {
+    fn schema(&self) -> SchemaRef {
+        scan_schema(&self.table.schema, self.with_record_id)
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.done {
+            return Ok(None);
+        }
+        let mut output = Slice::new(self.bpm.clone(), self.schema());
+        while let Some(key) = self.first_key_from_cursor() {
+            let leading = key[0].clone();
+            let begin = std::iter::once(leading.clone())
+                .chain(self.begin_datums.iter().cloned())
+                .collect_vec();
+            let end = std::iter::once(leading.clone())
+                .chain(self.end_datums.iter().cloned())
+                .collect_vec();
+            // the cursor is past the begin if resuming within the range
+            let begin = match self.cursor.take() {
+                Some(cursor) if cursor > begin => cursor,
+                _ => begin,
+            };
+            let iter = self
+                .index
+                .iter_start_from(&begin)
+                .map(|iter| iter.with_upper_bound(end, true));
+            for (key, record_id) in iter.into_iter().flatten() {
+                let mut datums = self.table.tuple_at(record_id).unwrap();
+                if self.with_record_id {
+                    datums.push(Datum::Int(Some(record_id.0 as i32)));
+                    datums.push(Datum::Int(Some(record_id.1 as i32)));
+                }
+                if output.insert(&datums).is_err() {
+                    // resume from the key not yet emitted
+                    self.cursor = Some(key);
+                    return Ok(Some(output));
+                }
+            }
+            // skip the rest keys with the same leading one
+            match leading.successor() {
+                Some(successor) => self.cursor = Some(vec![successor]),
+                None => break,
+            }
+        }
+        self.done = true;
+        Ok(Some(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datum::DataType;
+    use crate::expr::{ColumnRefExpr, ExprImpl};
+    use crate::storage::BufferPoolManager;
+    use crate::table::Schema;
+    use std::fs::remove_file;
+    use std::rc::Rc;
+
+    fn collect(mut executor: IndexSkipScanExecutor) -> Vec<Vec<Datum>> {
+        let mut tuples = vec![];
+        while let Some(slice) = executor.execute().unwrap() {
+            tuples.extend(slice.tuple_iter());
+        }
+        tuples
+    }
+
+    #[test]
+    fn test_skip_scan() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(50);
+            let filename = bpm.borrow().filename();
+            let schema = Rc::new(Schema::from_type_and_names(&[
+                (DataType::new_as_int(false), "v1".to_string()),
+                (DataType::new_as_int(false), "v2".to_string()),
+            ]));
+            let exprs = vec![
+                ExprImpl::ColumnRef(ColumnRefExpr::new(
+                    0,
+                    DataType::new_as_int(false),
+                    "v1".to_string(),
+                )),
+                ExprImpl::ColumnRef(ColumnRefExpr::new(
+                    1,
+                    DataType::new_as_int(false),
+                    "v2".to_string(),
+                )),
+            ];
+            let mut table = Table::new(schema, bpm.clone());
+            let mut index = BPTIndex::new(bpm.clone(), exprs);
+            for v1 in 0..5 {
+                for v2 in (0..1000).rev() {
+                    let tuple = vec![Datum::Int(Some(v1)), Datum::Int(Some(v2))];
+                    let record_id = table.insert(tuple.clone()).unwrap();
+                    index.insert(&tuple, record_id).unwrap();
+                }
+            }
+            let scan = |begin: i32, end: i32| {
+                collect(IndexSkipScanExecutor::new(
+                    Table::open(table.page_id(), bpm.clone()),
+                    BPTIndex::open(bpm.clone(), index.get_page_id(), table.schema.as_ref())
+                        .unwrap(),
+                    vec![Datum::Int(Some(begin))],
+                    vec![Datum::Int(Some(end))],
+                    bpm.clone(),
+                    false,
+                ))
+            };
+            assert_eq!(
+                scan(5, 5),
+                (0..5)
+                    .map(|v1| vec![Datum::Int(Some(v1)), Datum::Int(Some(5))])
+                    .collect_vec()
+            );
+            // more tuples than a slice holds
+            assert_eq!(
+                scan(100, 899),
+                (0..5)
+                    .cartesian_product(100..900)
+                    .map(|(v1, v2)| vec![Datum::Int(Some(v1)), Datum::Int(Some(v2))])
+                    .collect_vec()
+            );
+            assert!(scan(1000, 2000).is_empty());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+}
@@ -1,11 +1,12 @@
-use crate::datum::DataType;
+use crate::datum::{DataType, Datum};
 use crate::execution::{ExecutionError, Executor, ExecutorImpl};
 use crate::expr::ExprImpl;
-use crate::index::{BPTIndex, IndexError};
+use crate::index::{BPTIndex, IndexError, RecordID};
 use crate::storage::BufferPoolManagerRef;
 use crate::table::{Schema, SchemaError, SchemaRef, Slice, Table};
 use itertools::Itertools;
 use log::info;
+use std::collections::VecDeque;
 use std::rc::Rc;
 
 pub struct InsertExecutor {
@@ -15,6 +16,28 @@ pub struct InsertExecutor {
     child: Box<ExecutorImpl>,
     cnt: usize,
     executed: bool,
+    /// keep the references of foreign keys
+    check_foreign: bool,
+    /// output the record ids of the inserted tuples rather than the count
+    returning: bool,
+    /// record ids not output yet
+    record_ids: VecDeque<RecordID>,
+    /// check constraints of the table
+    checks: Vec<ExprImpl>,
+}
+
+/// a tuple violates a check if it evaluates to false, null passes
+pub fn check_constraints(checks: &[ExprImpl], slice: &Slice) -> Result<(), ExecutionError> {
+    for check in checks {
+        if check
+            .eval(Some(slice))?
+            .iter()
+            .any(|datum| *datum == Datum::Bool(Some(false)))
+        {
+            return Err(ExecutionError::CheckViolation(check.to_string()));
+        }
+    }
+    Ok(())
 }
 
 impl InsertExecutor {
@@ -22,6 +45,9 @@ impl InsertExecutor {
         table: Table,
         indexes: Vec<BPTIndex>,
         child: Box<ExecutorImpl>,
+        check_foreign: bool,
+        returning: bool,
+        checks: Vec<ExprImpl>,
         bpm: BufferPoolManagerRef,
     ) -> Self {
         Self {
@@ -31,12 +57,40 @@ impl InsertExecutor {
             child,
             cnt: 0,
             executed: false,
+            check_foreign,
+            returning,
+            record_ids: VecDeque::new(),
+            checks,
+        }
+    }
+
+    pub fn child(&self) -> &ExecutorImpl {
+        self.child.as_ref()
+    }
+    fn output_record_ids(&mut self) -> Option<Slice> {
+        if self.record_ids.is_empty() {
+            return None;
+        }
+        let mut slice = Slice::new(self.bpm.clone(), self.schema());
+        while let Some((page_id, idx)) = self.record_ids.front() {
+            let tuple = [(*page_id as i32).into(), (*idx as i32).into()];
+            if slice.insert(&tuple).is_err() {
+                break;
+            }
+            self.record_ids.pop_front();
         }
+        Some(slice)
     }
 }
 
 impl Executor for InsertExecutor {
     fn schema(&self) -> SchemaRef {
+        if self.returning {
+            return Rc::new(Schema::from_type_and_names(&[
+                (DataType::new_as_int(false), "page_id".to_string()),
+                (DataType::new_as_int(false), "slot".to_string()),
+            ]));
+        }
         Rc::new(Schema::from_type_and_names(&[(
             DataType::new_as_int(false),
             "Inserted".to_string(),
@@ -44,12 +98,18 @@ impl Executor for InsertExecutor {
     }
     fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
         if self.executed {
-            return Ok(None);
+            return Ok(self.output_record_ids());
         }
         self.executed = true;
         while let Some(input) = self.child.execute()? {
             // stage-1: validate
-            for (page_id, src_and_dst) in &self.table.schema.foreign {
+            check_constraints(&self.checks, &input)?;
+            let foreign = if self.check_foreign {
+                self.table.schema.foreign.as_slice()
+            } else {
+                &[]
+            };
+            for (page_id, src_and_dst) in foreign {
                 let mut foreign_table = Table::open(*page_id, self.bpm.clone());
                 let page_id_of_index = foreign_table
                     .meta()
@@ -59,40 +119,171 @@ impl Executor for InsertExecutor {
                     self.bpm.clone(),
                     page_id_of_index,
                     foreign_table.schema.as_ref(),
-                );
+                )?;
                 let exprs = self
                     .table
                     .schema
                     .project_by(&src_and_dst.iter().map(|(src, _)| *src).collect_vec());
-                let datums_from_slice = ExprImpl::batch_eval(&exprs, Some(&input));
+                let datums_from_slice = ExprImpl::batch_eval(&exprs, Some(&input))?;
                 for datums in datums_from_slice {
                     let record_id = foreign_index.find(&datums).ok_or(IndexError::KeyNotFound)?;
                     let ref_cnt = foreign_table.ref_cnt_of(record_id)?;
                     foreign_table.set_ref_cnt_of(record_id, ref_cnt + 1)?;
                 }
             }
-            // stage-2: update
+            // stage-2: check the keys of the slice against the indexes and each other
             let mut indexes_rows = vec![];
-            for index in &mut self.indexes {
-                let rows = ExprImpl::batch_eval(&index.exprs, Some(&input));
-                indexes_rows.push(rows);
+            for index in &self.indexes {
+                let rows = ExprImpl::batch_eval(&index.exprs, Some(&input))?;
+                let order = (0..rows.len())
+                    .sorted_by(|lhs, rhs| rows[*lhs].cmp(&rows[*rhs]))
+                    .collect_vec();
+                // the first duplicated key in insertion order is reported
+                let duplicated = order
+                    .iter()
+                    .tuple_windows()
+                    .filter(|(lhs, rhs)| rows[**lhs] == rows[**rhs])
+                    .map(|(_, rhs)| *rhs)
+                    .chain((0..rows.len()).filter(|idx| index.find(&rows[*idx]).is_some()))
+                    .min();
+                if let Some(idx) = duplicated {
+                    return Err(ExecutionError::InsertDuplicatedKey(rows[idx].clone()));
+                }
+                indexes_rows.push((rows, order));
             }
+            // stage-3: update, the keys go into each index in key order
+            let mut record_ids = vec![];
             for tuple in input.tuple_iter() {
                 info!("insert tuple {:?}", tuple);
-                for (rows, index) in indexes_rows.iter_mut().zip(&mut self.indexes) {
-                    if index.find(&rows[0]).is_some() {
-                        return Err(ExecutionError::InsertDuplicatedKey(rows[0].clone()));
+                record_ids.push(self.table.insert(tuple)?);
+                self.cnt += 1;
+            }
+            let mut inserted = vec![];
+            for (nth, (rows, order)) in indexes_rows.iter().enumerate() {
+                for idx in order {
+                    if let Err(err) = self.indexes[nth].insert(&rows[*idx], record_ids[*idx]) {
+                        // take back the keys and the tuples of the slice, no tuple is left
+                        // in the table without its keys
+                        for (nth, idx) in inserted {
+                            self.indexes[nth].remove(&indexes_rows[nth].0[idx])?;
+                        }
+                        self.cnt -= record_ids.len();
+                        for record_id in record_ids {
+                            self.table.remove_unchecked(record_id)?;
+                        }
+                        return Err(err.into());
                     }
+                    inserted.push((nth, *idx));
                 }
-                let record_id = self.table.insert(tuple)?;
-                for (rows, index) in indexes_rows.iter_mut().zip(&mut self.indexes) {
-                    index.insert(&rows.remove(0), record_id)?;
-                }
-                self.cnt += 1;
+            }
+            if self.returning {
+                self.record_ids.extend(record_ids);
             }
         }
+        if self.returning {
+            return Ok(self.output_record_ids());
+        }
         Ok(Some(
             Slice::new_as_count(self.bpm.clone(), "Inserted", self.cnt).unwrap(),
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datum::Datum;
+    use crate::execution::ValuesExecutor;
+    use crate::expr::ConstantExpr;
+    use crate::storage::{BufferPoolManager, PageID};
+    use rand::seq::SliceRandom;
+    use std::fs::remove_file;
+
+    fn values_of(nums: &[i32]) -> Vec<Vec<ExprImpl>> {
+        nums.iter()
+            .map(|num| {
+                vec![
+                    ExprImpl::Constant(ConstantExpr::new(
+                        Datum::Int(Some(*num)),
+                        DataType::new_as_int(false),
+                    )),
+                    ExprImpl::Constant(ConstantExpr::new(
+                        Datum::VarChar(Some(format!("value {}", num))),
+                        DataType::new_as_varchar(false),
+                    )),
+                ]
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn test_batch_insert() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(4096);
+            let filename = bpm.borrow().filename();
+            let schema = Rc::new(Schema::from_type_and_names(&[
+                (DataType::new_as_int(false), "v1".to_string()),
+                (DataType::new_as_varchar(false), "v2".to_string()),
+            ]));
+            let mut nums = (0..5000).collect_vec();
+            nums.shuffle(&mut rand::thread_rng());
+            let insert = |nums: &[i32], table_page_id: PageID, index_page_id: PageID| {
+                let table = Table::open(table_page_id, bpm.clone());
+                let index = BPTIndex::open(bpm.clone(), index_page_id, schema.as_ref()).unwrap();
+                let values = ValuesExecutor::new(values_of(nums), schema.clone(), bpm.clone());
+                let mut executor = InsertExecutor::new(
+                    table,
+                    vec![index],
+                    Box::new(ExecutorImpl::Values(values)),
+                    false,
+                    false,
+                    vec![],
+                    bpm.clone(),
+                );
+                executor.execute()
+            };
+            let page_ids = (0..2)
+                .map(|_| {
+                    let table = Table::new(schema.clone(), bpm.clone());
+                    let index = BPTIndex::new(bpm.clone(), schema.project_by(&[0]));
+                    (table.page_id(), index.get_page_id())
+                })
+                .collect_vec();
+            // all at once, then one at a time
+            insert(&nums, page_ids[0].0, page_ids[0].1).unwrap();
+            for num in nums.iter() {
+                insert(&[*num], page_ids[1].0, page_ids[1].1).unwrap();
+            }
+            let states = page_ids
+                .iter()
+                .map(|(table_page_id, index_page_id)| {
+                    let table = Table::open(*table_page_id, bpm.clone());
+                    let index =
+                        BPTIndex::open(bpm.clone(), *index_page_id, schema.as_ref()).unwrap();
+                    index.sanity_check();
+                    let tuples = table
+                        .iter()
+                        .flat_map(|s| s.tuple_iter().collect_vec())
+                        .collect_vec();
+                    let keys = index.iter().map(|(key, _)| key).collect_vec();
+                    for tuple in tuples.iter() {
+                        let record_id = index.find(&tuple[0..1]).unwrap();
+                        assert_eq!(&table.tuple_at(record_id).unwrap(), tuple);
+                    }
+                    (tuples, keys)
+                })
+                .collect_vec();
+            assert_eq!(states[0], states[1]);
+            assert_eq!(states[0].0.len(), nums.len());
+            // duplicated in the batch, nothing of the batch is inserted
+            assert!(matches!(
+                insert(&[6000, 6001, 6000], page_ids[0].0, page_ids[0].1),
+                Err(ExecutionError::InsertDuplicatedKey(key)) if key == vec![Datum::Int(Some(6000))]
+            ));
+            let index = BPTIndex::open(bpm, page_ids[0].1, schema.as_ref()).unwrap();
+            assert!(index.find(&[6001.into()]).is_none());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+}
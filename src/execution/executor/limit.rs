@@ -0,0 +1,46 @@
+use crate::execution::{ExecutionError, Executor, ExecutorImpl};
+use crate::storage::BufferPoolManagerRef;
+use crate::table::{SchemaRef, Slice};
+
+/// the leading tuples of the child, the child is not executed any more once enough
+pub struct LimitExecutor {
+    child: Box<ExecutorImpl>,
+    bpm: BufferPoolManagerRef,
+    /// tuples left to output
+    remaining: usize,
+}
+
+impl LimitExecutor {
+    pub fn new(bpm: BufferPoolManagerRef, child: Box<ExecutorImpl>, limit: usize) -> Self {
+        Self {
+            child,
+            bpm,
+            remaining: limit,
+        }
+    }
+}
+
+impl Executor for LimitExecutor {
+    fn schema(&self) -> SchemaRef {
+        self.child.schema()
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        let input = match self.child.execute()? {
+            Some(input) => input,
+            None => return Ok(None),
+        };
+        if input.count() <= self.remaining {
+            self.remaining -= input.count();
+            return Ok(Some(input));
+        }
+        let mut output = Slice::new(self.bpm.clone(), self.schema());
+        for tuple in input.tuple_iter().take(self.remaining) {
+            output.insert(&tuple)?;
+        }
+        self.remaining = 0;
+        Ok(Some(output))
+    }
+}
@@ -1,31 +1,200 @@
 use crate::datum::{DataType, Datum};
-use crate::execution::{ExecutionError, Executor};
-use crate::storage::BufferPoolManagerRef;
-use crate::table::{SchemaRef, Slice};
+use crate::execution::{ExecutionError, Executor, ExecutorImpl, InsertExecutor};
+use crate::storage::{BufferPoolManagerRef, StorageError};
+use crate::table::{Schema, SchemaRef, Slice, Table, TableError};
 use chrono::NaiveDate;
-use csv::{Reader, ReaderBuilder};
-use itertools::Itertools;
+use csv::{Reader, ReaderBuilder, StringRecord, Writer};
+use log::info;
 use std::collections::VecDeque;
 use std::fs::File;
+use std::io;
+use std::rc::Rc;
 use std::str::FromStr;
 
+/// tuples parsed from the file at a time
+const LOAD_BATCH_SIZE: usize = 1000;
+
+/// rejected lines kept to be written out, the rest are only counted
+const MAX_REJECTED_LINES: usize = 100;
+
+/// a line of the file failed to load, numbered from 1
+#[derive(Debug, Clone)]
+struct RejectedLine {
+    line: u64,
+    reason: String,
+}
+
 pub struct LoadFromFileExecutor {
     schema: SchemaRef,
     reader: Reader<File>,
     bpm: BufferPoolManagerRef,
     buffer: VecDeque<Vec<Datum>>,
+    exhausted: bool,
+    num_rejected: usize,
+    /// the first rejected lines, up to MAX_REJECTED_LINES
+    rejected: Vec<RejectedLine>,
+    /// where the rejected lines are written once the file is read through
+    rejects_file_name: Option<String>,
+}
+
+/// loads the lines of a csv file by the insert, then outputs how many are loaded and
+/// rejected
+pub struct LoadSummaryExecutor {
+    insert: InsertExecutor,
+    bpm: BufferPoolManagerRef,
+    executed: bool,
+}
+
+/// the tuple of the record, or why it does not fit the schema
+fn parse_record(record: &StringRecord, schema: &Schema) -> Result<Vec<Datum>, String> {
+    if record.len() != schema.columns.len() {
+        return Err(format!(
+            "Expect {} Fields, Found {}",
+            schema.columns.len(),
+            record.len()
+        ));
+    }
+    record
+        .iter()
+        .zip(&schema.columns)
+        .map(|(data, col)| {
+            let datum = match col.data_type {
+                DataType::Int(_) => data
+                    .parse::<i32>()
+                    .map(Datum::from)
+                    .map_err(|e| e.to_string()),
+                DataType::Date(_) => NaiveDate::from_str(data)
+                    .map(Datum::from)
+                    .map_err(|e| e.to_string()),
+                DataType::Float(_) => f32::from_str(data)
+                    .map(Datum::from)
+                    .map_err(|e| e.to_string()),
+                DataType::VarChar(_) => Ok(data.into()),
+                DataType::Bool(_) => bool::from_str(data)
+                    .map(Datum::from)
+                    .map_err(|e| e.to_string()),
+            };
+            datum.map_err(|err| format!("Invalid Value {:?} Of {}: {}", data, col.desc, err))
+        })
+        .collect()
+}
+
+pub struct LoadBinaryExecutor {
+    schema: SchemaRef,
+    bpm: BufferPoolManagerRef,
+    buffer: VecDeque<Vec<Datum>>,
+}
+
+impl LoadBinaryExecutor {
+    pub fn new(
+        schema: SchemaRef,
+        file_name: String,
+        bpm: BufferPoolManagerRef,
+    ) -> Result<Self, ExecutionError> {
+        let (dumped, tuples) = Table::load_binary(&file_name)?;
+        if dumped.fingerprint() != schema.fingerprint() {
+            return Err(TableError::InvalidDump(file_name).into());
+        }
+        Ok(Self {
+            schema,
+            bpm,
+            buffer: tuples.into(),
+        })
+    }
+}
+
+impl Executor for LoadBinaryExecutor {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        let mut output = Slice::new(self.bpm.clone(), self.schema.clone());
+        while !self.buffer.is_empty() {
+            if output.insert(self.buffer.front().unwrap()).is_ok() {
+                self.buffer.pop_front().unwrap();
+            } else {
+                break;
+            }
+        }
+        if output.count() == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(output))
+        }
+    }
 }
 
 impl LoadFromFileExecutor {
-    pub fn new(schema: SchemaRef, file_name: String, bpm: BufferPoolManagerRef) -> Self {
+    pub fn new(
+        schema: SchemaRef,
+        file_name: String,
+        rejects_file_name: Option<String>,
+        bpm: BufferPoolManagerRef,
+    ) -> Self {
         Self {
             schema,
+            // the number of fields is checked line by line, so that a bad line is rejected
             reader: ReaderBuilder::new()
                 .has_headers(false)
+                .flexible(true)
                 .from_path(file_name)
                 .unwrap(),
             bpm,
             buffer: VecDeque::new(),
+            exhausted: false,
+            num_rejected: 0,
+            rejected: vec![],
+            rejects_file_name,
+        }
+    }
+    pub fn num_rejected(&self) -> usize {
+        self.num_rejected
+    }
+    fn reject(&mut self, line: u64, reason: String) {
+        info!("reject line {}: {}", line, reason);
+        self.num_rejected += 1;
+        if self.rejected.len() < MAX_REJECTED_LINES {
+            self.rejected.push(RejectedLine { line, reason });
+        }
+    }
+    /// read lines until a batch of tuples is parsed or the file is read through
+    fn fill_buffer(&mut self) {
+        let mut record = StringRecord::new();
+        while self.buffer.len() < LOAD_BATCH_SIZE && !self.exhausted {
+            let line = self.reader.position().line();
+            match self.reader.read_record(&mut record) {
+                Ok(true) => match parse_record(&record, &self.schema) {
+                    Ok(tuple) => self.buffer.push_back(tuple),
+                    Err(reason) => self.reject(line, reason),
+                },
+                Ok(false) => self.exhausted = true,
+                Err(err) => {
+                    // the reader can not go on after an io error
+                    self.exhausted = err.is_io_error();
+                    self.reject(line, err.to_string());
+                }
+            }
+        }
+    }
+    fn write_rejects(&self, file_name: &str) -> Result<(), ExecutionError> {
+        let to_storage_error = |err: csv::Error| StorageError::from(io::Error::from(err));
+        let mut writer = Writer::from_path(file_name).map_err(to_storage_error)?;
+        for rejected in &self.rejected {
+            writer
+                .write_record(&[rejected.line.to_string(), rejected.reason.clone()])
+                .map_err(to_storage_error)?;
+        }
+        writer.flush().map_err(StorageError::from)?;
+        Ok(())
+    }
+}
+
+impl LoadSummaryExecutor {
+    pub fn new(insert: InsertExecutor, bpm: BufferPoolManagerRef) -> Self {
+        Self {
+            insert,
+            bpm,
+            executed: false,
         }
     }
 }
@@ -37,20 +206,11 @@ impl Executor for LoadFromFileExecutor {
     fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
         let mut output = Slice::new(self.bpm.clone(), self.schema.clone());
         if self.buffer.is_empty() {
-            for record in self.reader.records().take(1000) {
-                let record = record.unwrap();
-                let tuple: Vec<Datum> = record
-                    .iter()
-                    .zip(&self.schema.columns)
-                    .map(|(data, col)| match col.data_type {
-                        DataType::Int(_) => data.parse::<i32>().unwrap().into(),
-                        DataType::Date(_) => NaiveDate::from_str(data).unwrap().into(),
-                        DataType::Float(_) => f32::from_str(data).unwrap().into(),
-                        DataType::VarChar(_) => data.into(),
-                        DataType::Bool(_) => bool::from_str(data).unwrap().into(),
-                    })
-                    .collect_vec();
-                self.buffer.push_back(tuple);
+            self.fill_buffer();
+        }
+        if self.exhausted {
+            if let Some(file_name) = self.rejects_file_name.take() {
+                self.write_rejects(&file_name)?;
             }
         }
         while !self.buffer.is_empty() {
@@ -67,3 +227,31 @@ impl Executor for LoadFromFileExecutor {
         }
     }
 }
+
+impl Executor for LoadSummaryExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[
+            (DataType::new_as_int(false), "Loaded".to_string()),
+            (DataType::new_as_int(false), "Rejected".to_string()),
+        ]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        self.executed = true;
+        let mut loaded = 0;
+        while let Some(slice) = self.insert.execute()? {
+            if let Some(tuple) = slice.tuple_iter().next() {
+                loaded = i32::from(tuple[0].clone());
+            }
+        }
+        let rejected = match self.insert.child() {
+            ExecutorImpl::LoadFromFile(load) => load.num_rejected(),
+            _ => 0,
+        };
+        let mut output = Slice::new(self.bpm.clone(), self.schema());
+        output.insert(&[loaded.into(), (rejected as i32).into()])?;
+        Ok(Some(output))
+    }
+}
@@ -1,46 +1,83 @@
 use crate::execution::ExecutionError;
 use crate::table::{SchemaRef, Slice};
 
-pub use agg::AggExecutor;
-pub use alter::{AddForeignExecutor, AddIndexExecutor, AddPrimaryExecutor, AddUniqueExecutor};
+pub use agg::{agg_schema, AggExecutor};
+pub use alter::{
+    AddForeignExecutor, AddIndexExecutor, AddPrimaryExecutor, AddUniqueExecutor,
+    RenameColumnExecutor,
+};
+pub use analyze::AnalyzeExecutor;
 pub use create_database::CreateDatabaseExecutor;
-pub use create_table::CreateTableExecutor;
+pub use create_table::{CreateTableAsExecutor, CreateTableExecutor};
 pub use delete::DeleteExecutor;
 pub use desc::{DescExecutor, ShowTablesExecutor};
+pub use distinct_on::DistinctOnExecutor;
 pub use drop::{
     DropDatabaseExecutor, DropForeignExecuor, DropIndexExecutor, DropPrimaryExecutor,
     DropTableExecutor,
 };
-pub use filter::FilterExecutor;
+pub use exists::ExistsExecutor;
+pub use explain::ExplainExecutor;
+pub use filter::{EmptyExecutor, FilterExecutor};
+pub use foreign_keys::ForeignKeysExecutor;
 pub use index_scan::IndexScanExecutor;
+pub use index_skip_scan::IndexSkipScanExecutor;
 pub use insert::InsertExecutor;
-pub use load_from_file::LoadFromFileExecutor;
+pub use limit::LimitExecutor;
+pub use load_from_file::{LoadBinaryExecutor, LoadFromFileExecutor, LoadSummaryExecutor};
 pub use nested_loop_join::NestedLoopJoinExecutor;
-pub use project::ProjectExecutor;
-pub use seq_scan::SeqScanExecutor;
+pub use pragma::{FreePagesExecutor, IndexStatsExecutor, SetExecutor, TableSizeExecutor};
+pub use project::{project_schema, ProjectExecutor};
+pub use rowid_fetch::RowIdFetchExecutor;
+pub use seq_scan::{scan_schema, SeqScanExecutor};
+pub use set_op::{set_op_schema, SetOpExecutor};
 pub use show_databases::ShowDatabasesExecutor;
+pub use show_history::ShowHistoryExecutor;
+pub use show_warnings::ShowWarningsExecutor;
+pub use sort::{SortExecutor, TopNExecutor, SORT_BUFFER_SIZE};
+pub use truncate::TruncateTableExecutor;
 pub use update::UpdateExecutor;
 pub use use_database::UseDatabaseExecutor;
+pub use vacuum::VacuumExecutor;
 pub use values::ValuesExecutor;
+pub use view::{CreateViewExecutor, DropViewExecutor};
+pub use wal::{CheckpointExecutor, WalExecutor};
 
 mod agg;
 mod alter;
+mod analyze;
 mod create_database;
 mod create_table;
 mod delete;
 mod desc;
+mod distinct_on;
 mod drop;
+mod exists;
+mod explain;
 mod filter;
+mod foreign_keys;
 mod index_scan;
+mod index_skip_scan;
 mod insert;
+mod limit;
 mod load_from_file;
 mod nested_loop_join;
+mod pragma;
 mod project;
+mod rowid_fetch;
 mod seq_scan;
+mod set_op;
 mod show_databases;
+mod show_history;
+mod show_warnings;
+mod sort;
+mod truncate;
 mod update;
 mod use_database;
+mod vacuum;
 mod values;
+mod view;
+mod wal;
 
 pub trait Executor {
     fn execute(&mut self) -> Result<Option<Slice>, ExecutionError>;
@@ -75,6 +112,34 @@ pub enum ExecutorImpl {
     LoadFromFile(LoadFromFileExecutor),
     Agg(AggExecutor),
     Update(UpdateExecutor),
+    Exists(ExistsExecutor),
+    Sort(SortExecutor),
+    TruncateTable(TruncateTableExecutor),
+    IndexStats(IndexStatsExecutor),
+    Analyze(AnalyzeExecutor),
+    ShowWarnings(ShowWarningsExecutor),
+    CreateView(CreateViewExecutor),
+    DropView(DropViewExecutor),
+    LoadBinary(LoadBinaryExecutor),
+    ForeignKeys(ForeignKeysExecutor),
+    CreateTableAs(CreateTableAsExecutor),
+    RenameColumn(RenameColumnExecutor),
+    Wal(WalExecutor),
+    Checkpoint(CheckpointExecutor),
+    Explain(ExplainExecutor),
+    FreePages(FreePagesExecutor),
+    SetOp(SetOpExecutor),
+    ShowHistory(ShowHistoryExecutor),
+    Vacuum(VacuumExecutor),
+    Empty(EmptyExecutor),
+    IndexSkipScan(IndexSkipScanExecutor),
+    DistinctOn(DistinctOnExecutor),
+    TableSize(TableSizeExecutor),
+    RowIdFetch(RowIdFetchExecutor),
+    LoadSummary(LoadSummaryExecutor),
+    Limit(LimitExecutor),
+    TopN(TopNExecutor),
+    Set(SetExecutor),
 }
 
 impl ExecutorImpl {
@@ -106,6 +171,34 @@ impl ExecutorImpl {
             Self::Agg(executor) => executor.execute(),
             Self::ShowTables(executor) => executor.execute(),
             Self::Update(executor) => executor.execute(),
+            Self::Exists(executor) => executor.execute(),
+            Self::Sort(executor) => executor.execute(),
+            Self::TruncateTable(executor) => executor.execute(),
+            Self::IndexStats(executor) => executor.execute(),
+            Self::Analyze(executor) => executor.execute(),
+            Self::ShowWarnings(executor) => executor.execute(),
+            Self::CreateView(executor) => executor.execute(),
+            Self::DropView(executor) => executor.execute(),
+            Self::LoadBinary(executor) => executor.execute(),
+            Self::ForeignKeys(executor) => executor.execute(),
+            Self::CreateTableAs(executor) => executor.execute(),
+            Self::RenameColumn(executor) => executor.execute(),
+            Self::Wal(executor) => executor.execute(),
+            Self::Checkpoint(executor) => executor.execute(),
+            Self::Explain(executor) => executor.execute(),
+            Self::FreePages(executor) => executor.execute(),
+            Self::SetOp(executor) => executor.execute(),
+            Self::ShowHistory(executor) => executor.execute(),
+            Self::Vacuum(executor) => executor.execute(),
+            Self::Empty(executor) => executor.execute(),
+            Self::IndexSkipScan(executor) => executor.execute(),
+            Self::DistinctOn(executor) => executor.execute(),
+            Self::TableSize(executor) => executor.execute(),
+            Self::RowIdFetch(executor) => executor.execute(),
+            Self::LoadSummary(executor) => executor.execute(),
+            Self::Limit(executor) => executor.execute(),
+            Self::TopN(executor) => executor.execute(),
+            Self::Set(executor) => executor.execute(),
         }
     }
     pub fn schema(&self) -> SchemaRef {
@@ -136,6 +229,34 @@ impl ExecutorImpl {
             Self::Agg(executor) => executor.schema(),
             Self::ShowTables(executor) => executor.schema(),
             Self::Update(executor) => executor.schema(),
+            Self::Exists(executor) => executor.schema(),
+            Self::Sort(executor) => executor.schema(),
+            Self::TruncateTable(executor) => executor.schema(),
+            Self::IndexStats(executor) => executor.schema(),
+            Self::Analyze(executor) => executor.schema(),
+            Self::ShowWarnings(executor) => executor.schema(),
+            Self::CreateView(executor) => executor.schema(),
+            Self::DropView(executor) => executor.schema(),
+            Self::LoadBinary(executor) => executor.schema(),
+            Self::ForeignKeys(executor) => executor.schema(),
+            Self::CreateTableAs(executor) => executor.schema(),
+            Self::RenameColumn(executor) => executor.schema(),
+            Self::Wal(executor) => executor.schema(),
+            Self::Checkpoint(executor) => executor.schema(),
+            Self::Explain(executor) => executor.schema(),
+            Self::FreePages(executor) => executor.schema(),
+            Self::SetOp(executor) => executor.schema(),
+            Self::ShowHistory(executor) => executor.schema(),
+            Self::Vacuum(executor) => executor.schema(),
+            Self::Empty(executor) => executor.schema(),
+            Self::IndexSkipScan(executor) => executor.schema(),
+            Self::DistinctOn(executor) => executor.schema(),
+            Self::TableSize(executor) => executor.schema(),
+            Self::RowIdFetch(executor) => executor.schema(),
+            Self::LoadSummary(executor) => executor.schema(),
+            Self::Limit(executor) => executor.schema(),
+            Self::TopN(executor) => executor.schema(),
+            Self::Set(executor) => executor.schema(),
         }
     }
 }
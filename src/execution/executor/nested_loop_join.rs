@@ -1,31 +1,59 @@
+use super::filter::filter_map;
 use super::{ExecutionError, Executor, ExecutorImpl};
 use crate::datum::Datum;
+use crate::expr::ExprImpl;
 use crate::storage::BufferPoolManagerRef;
-use crate::table::{SchemaRef, Slice};
+use crate::table::{SchemaRef, Slice, TableError};
 use itertools::Itertools;
 
 pub struct NestedLoopJoinExecutor {
     schema: SchemaRef,
     children: Vec<ExecutorImpl>,
+    exprs: Vec<ExprImpl>,
     buffer: Vec<Vec<Datum>>,
     bpm: BufferPoolManagerRef,
     initialized: bool,
 }
 
 impl NestedLoopJoinExecutor {
-    pub fn new(bpm: BufferPoolManagerRef, children: Vec<ExecutorImpl>, schema: SchemaRef) -> Self {
+    pub fn new(
+        bpm: BufferPoolManagerRef,
+        children: Vec<ExecutorImpl>,
+        schema: SchemaRef,
+        exprs: Vec<ExprImpl>,
+    ) -> Self {
         Self {
             schema,
             children,
+            exprs,
             bpm,
             buffer: vec![],
             initialized: false,
         }
     }
+    /// keep the joined tuples in the slice which pass the predicates
+    fn keep_matched(&mut self, joined: &Slice) -> Result<(), ExecutionError> {
+        let matched = filter_map(&self.exprs, joined)?;
+        self.buffer.extend(
+            joined
+                .tuple_iter()
+                .zip(matched)
+                .filter_map(|(tuple, matched)| matched.then(|| tuple)),
+        );
+        Ok(())
+    }
 }
 
 impl Executor for NestedLoopJoinExecutor {
     fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        // an always-false predicate never needs the product
+        if self
+            .exprs
+            .iter()
+            .any(|e| e.as_constant() == Some(Datum::Bool(Some(false))))
+        {
+            return Ok(None);
+        }
         if !self.initialized {
             let mut buffers = self
                 .children
@@ -38,17 +66,31 @@ impl Executor for NestedLoopJoinExecutor {
                     buffer.into_iter()
                 })
                 .collect_vec();
-            let join_iter = buffers.remove(0);
-            self.buffer = buffers
-                .into_iter()
-                .fold(join_iter, |iter, buffer| {
-                    iter.cartesian_product(buffer.into_iter())
-                        .map(|(t0, t1)| [t0, t1].concat())
-                        .collect_vec()
-                        .into_iter()
-                })
-                .rev()
-                .collect_vec();
+            let join_iter: Box<dyn Iterator<Item = Vec<Datum>>> = Box::new(buffers.remove(0));
+            let join_iter = buffers.into_iter().fold(join_iter, |iter, buffer| {
+                Box::new(
+                    iter.cartesian_product(buffer)
+                        .map(|(t0, t1)| [t0, t1].concat()),
+                )
+            });
+            // the pairs are checked a slice at a time, so only the matched ones are kept, the
+            // slice is reused for every batch and freed once the product is consumed
+            let mut joined = Slice::new(self.bpm.clone(), self.schema.clone());
+            for tuple in join_iter {
+                if joined.insert(&tuple).is_err() {
+                    self.keep_matched(&joined)?;
+                    joined.clear();
+                    joined.insert(&tuple)?;
+                }
+            }
+            self.keep_matched(&joined)?;
+            let page_id = joined.page_id();
+            drop(joined);
+            self.bpm
+                .borrow_mut()
+                .free(page_id)
+                .map_err(TableError::from)?;
+            self.buffer.reverse();
             self.initialized = true;
         }
         let mut slice = Slice::new(self.bpm.clone(), self.schema.clone());
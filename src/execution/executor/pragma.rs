@@ -0,0 +1,169 @@
+use crate::datum::DataType;
+use crate::db::{Setting, SettingsRef};
+use crate::execution::{ExecutionError, Executor};
+use crate::index::BPTIndex;
+use crate::storage::{BufferPoolManagerRef, PageID};
+use crate::table::{Schema, SchemaRef, Slice, Table};
+use std::rc::Rc;
+
+pub struct IndexStatsExecutor {
+    index: BPTIndex,
+    bpm: BufferPoolManagerRef,
+    executed: bool,
+}
+
+impl IndexStatsExecutor {
+    pub fn new(index: BPTIndex, bpm: BufferPoolManagerRef) -> Self {
+        Self {
+            index,
+            bpm,
+            executed: false,
+        }
+    }
+}
+
+pub struct TableSizeExecutor {
+    table: Table,
+    indexes: Vec<BPTIndex>,
+    bpm: BufferPoolManagerRef,
+    executed: bool,
+}
+
+impl TableSizeExecutor {
+    pub fn new(table: Table, indexes: Vec<BPTIndex>, bpm: BufferPoolManagerRef) -> Self {
+        Self {
+            table,
+            indexes,
+            bpm,
+            executed: false,
+        }
+    }
+}
+
+pub struct FreePagesExecutor {
+    bpm: BufferPoolManagerRef,
+    /// page ids not output yet, in reverse order
+    buffer: Option<Vec<PageID>>,
+}
+
+impl FreePagesExecutor {
+    pub fn new(bpm: BufferPoolManagerRef) -> Self {
+        Self { bpm, buffer: None }
+    }
+}
+
+/// changes a setting of the session, and outputs its new value
+pub struct SetExecutor {
+    setting: Setting,
+    settings: SettingsRef,
+    bpm: BufferPoolManagerRef,
+    executed: bool,
+}
+
+impl SetExecutor {
+    pub fn new(setting: Setting, settings: SettingsRef, bpm: BufferPoolManagerRef) -> Self {
+        Self {
+            setting,
+            settings,
+            bpm,
+            executed: false,
+        }
+    }
+}
+
+impl Executor for IndexStatsExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[
+            (DataType::new_as_int(false), "height".to_string()),
+            (DataType::new_as_int(false), "internal_nodes".to_string()),
+            (DataType::new_as_int(false), "leaf_nodes".to_string()),
+            (DataType::new_as_int(false), "keys".to_string()),
+        ]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        self.executed = true;
+        let stats = self.index.stats();
+        let mut slice = Slice::new(self.bpm.clone(), self.schema());
+        slice.insert(&[
+            (stats.height as i32).into(),
+            (stats.num_internal_nodes as i32).into(),
+            (stats.num_leaf_nodes as i32).into(),
+            (stats.num_keys as i32).into(),
+        ])?;
+        Ok(Some(slice))
+    }
+}
+
+impl Executor for FreePagesExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[(
+            DataType::new_as_int(false),
+            "page_id".to_string(),
+        )]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        // walk the chain before the output takes any free page
+        if self.buffer.is_none() {
+            let mut page_ids = self.bpm.borrow_mut().free_page_ids()?;
+            page_ids.reverse();
+            self.buffer = Some(page_ids);
+        }
+        if self.buffer.as_ref().unwrap().is_empty() {
+            return Ok(None);
+        }
+        let mut slice = Slice::new(self.bpm.clone(), self.schema());
+        let buffer = self.buffer.as_mut().unwrap();
+        while let Some(page_id) = buffer.last() {
+            if slice.insert(&[(*page_id as i32).into()]).is_err() {
+                break;
+            }
+            buffer.pop();
+        }
+        Ok(Some(slice))
+    }
+}
+
+impl Executor for TableSizeExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[(
+            DataType::new_as_int(false),
+            "table_size".to_string(),
+        )]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        self.executed = true;
+        let bytes = self.table.disk_footprint()?
+            + self
+                .indexes
+                .iter()
+                .map(|index| index.disk_footprint())
+                .sum::<usize>();
+        let slice = Slice::new_as_count(self.bpm.clone(), "table_size", bytes)?;
+        Ok(Some(slice))
+    }
+}
+
+impl Executor for SetExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[(
+            DataType::new_as_varchar(false),
+            self.setting.name().to_string(),
+        )]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        self.executed = true;
+        self.settings.borrow_mut().set(self.setting.clone());
+        let slice =
+            Slice::new_as_message(self.bpm.clone(), self.setting.name(), &self.setting.value())?;
+        Ok(Some(slice))
+    }
+}
@@ -6,17 +6,38 @@ use crate::table::{Schema, SchemaRef, Slice};
 use itertools::Itertools;
 use std::rc::Rc;
 
+/// a column for each expr, named by the alias or the expr itself
+pub fn project_schema(exprs: &[ExprImpl], aliases: &[Option<String>]) -> SchemaRef {
+    let type_and_names = exprs
+        .iter()
+        .zip(aliases.iter())
+        .map(|(expr, alias)| {
+            let name = alias.clone().unwrap_or_else(|| expr.to_string());
+            (expr.return_type(), name)
+        })
+        .collect_vec();
+    Rc::new(Schema::from_type_and_names(&type_and_names))
+}
+
 pub struct ProjectExecutor {
     exprs: Vec<ExprImpl>,
+    /// names of the output columns, the expr itself if none
+    aliases: Vec<Option<String>>,
     child: Box<ExecutorImpl>,
     buffer: Vec<Vec<Datum>>,
     bpm: BufferPoolManagerRef,
 }
 
 impl ProjectExecutor {
-    pub fn new(exprs: Vec<ExprImpl>, child: Box<ExecutorImpl>, bpm: BufferPoolManagerRef) -> Self {
+    pub fn new(
+        exprs: Vec<ExprImpl>,
+        aliases: Vec<Option<String>>,
+        child: Box<ExecutorImpl>,
+        bpm: BufferPoolManagerRef,
+    ) -> Self {
         ProjectExecutor {
             exprs,
+            aliases,
             child,
             buffer: vec![],
             bpm,
@@ -26,7 +47,7 @@ impl ProjectExecutor {
 
 impl Executor for ProjectExecutor {
     fn schema(&self) -> SchemaRef {
-        Rc::new(Schema::from_exprs(&self.exprs))
+        project_schema(&self.exprs, &self.aliases)
     }
     fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
         let schema = self.schema();
@@ -39,7 +60,7 @@ impl Executor for ProjectExecutor {
                         .exprs
                         .iter_mut()
                         .map(|e| e.eval(Some(&from_child)))
-                        .collect_vec();
+                        .collect::<Result<Vec<_>, _>>()?;
                     let len = columns[0].len();
                     for _ in 0..len {
                         let datums = columns.iter_mut().map(|v| v.remove(0)).collect_vec();
@@ -51,10 +72,13 @@ impl Executor for ProjectExecutor {
                     return Ok(Some(slice));
                 }
             }
-            if slice.insert(&self.buffer[0]).is_ok() {
-                self.buffer.remove(0);
-            } else {
-                break;
+            match slice.insert(&self.buffer[0]) {
+                Ok(_) => {
+                    self.buffer.remove(0);
+                }
+                // the rest goes to the next slice
+                Err(_) if slice.count() > 0 => break,
+                Err(err) => return Err(err.into()),
             }
         }
         Ok(Some(slice))
@@ -0,0 +1,42 @@
+use crate::execution::{ExecutionError, Executor};
+use crate::index::RecordID;
+use crate::storage::BufferPoolManagerRef;
+use crate::table::{SchemaRef, Slice, Table};
+
+pub struct RowIdFetchExecutor {
+    table: Table,
+    record_id: RecordID,
+    bpm: BufferPoolManagerRef,
+    executed: bool,
+}
+
+impl RowIdFetchExecutor {
+    pub fn new(table: Table, record_id: RecordID, bpm: BufferPoolManagerRef) -> Self {
+        Self {
+            table,
+            record_id,
+            bpm,
+            executed: false,
+        }
+    }
+}
+
+impl Executor for RowIdFetchExecutor {
+    fn schema(&self) -> SchemaRef {
+        self.table.schema.clone()
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        self.executed = true;
+        let mut slice = Slice::new(self.bpm.clone(), self.schema());
+        // the page must be a slice of the table, or it is read as a slice of another schema
+        if self.table.slice_page_ids()?.contains(&self.record_id.0) {
+            if let Some(tuple) = self.table.tuple_at(self.record_id) {
+                slice.insert(&tuple)?;
+            }
+        }
+        Ok(Some(slice))
+    }
+}
@@ -4,6 +4,18 @@ use crate::storage::{BufferPoolManagerRef, PageID};
 use crate::table::{Schema, SchemaRef, Slice};
 use std::rc::Rc;
 
+/// the columns of the table, followed by the record id if it is output
+pub fn scan_schema(schema: &SchemaRef, with_record_id: bool) -> SchemaRef {
+    if !with_record_id {
+        schema.clone()
+    } else {
+        let mut type_and_names = schema.to_type_and_names();
+        type_and_names.push((DataType::new_as_int(false), "_page_id".to_string()));
+        type_and_names.push((DataType::new_as_int(false), "_idx".to_string()));
+        Rc::new(Schema::from_type_and_names(&type_and_names))
+    }
+}
+
 pub struct SeqScanExecutor {
     bpm: BufferPoolManagerRef,
     page_id: Option<PageID>,
@@ -31,19 +43,12 @@ impl SeqScanExecutor {
 
 impl Executor for SeqScanExecutor {
     fn schema(&self) -> SchemaRef {
-        if !self.with_record_id {
-            self.schema.clone()
-        } else {
-            let mut type_and_names = self.schema.to_type_and_names();
-            type_and_names.push((DataType::new_as_int(false), "_page_id".to_string()));
-            type_and_names.push((DataType::new_as_int(false), "_idx".to_string()));
-            Rc::new(Schema::from_type_and_names(&type_and_names))
-        }
+        scan_schema(&self.schema, self.with_record_id)
     }
     fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
         if let Some(page_id) = self.page_id {
             if !self.with_record_id {
-                let slice = Slice::open(self.bpm.clone(), self.schema.clone(), page_id);
+                let slice = Slice::open(self.bpm.clone(), self.schema.clone(), page_id)?;
                 self.page_id = slice.meta()?.next_page_id;
                 Ok(Some(slice))
             } else {
@@ -53,10 +58,12 @@ impl Executor for SeqScanExecutor {
                         if self.page_id.is_none() {
                             break;
                         }
-                        let page_id = self.page_id.unwrap();
-                        let source = Slice::open(self.bpm.clone(), self.schema.clone(), page_id);
-                        for idx in source.slot_iter().collect::<Vec<_>>() {
-                            let mut tuple = source.tuple_at(idx)?;
+                        let source = Slice::open(
+                            self.bpm.clone(),
+                            self.schema.clone(),
+                            self.page_id.unwrap(),
+                        )?;
+                        for ((page_id, idx), mut tuple) in source.record_iter() {
                             tuple.push(Datum::Int(Some(page_id as i32)));
                             tuple.push(Datum::Int(Some(idx as i32)));
                             self.buffer.push(tuple);
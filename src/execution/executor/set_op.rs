@@ -0,0 +1,132 @@
+use crate::datum::{DataType, Datum};
+use crate::execution::{ExecutionError, Executor, ExecutorImpl};
+use crate::parser::ast::SetOp;
+use crate::storage::BufferPoolManagerRef;
+use crate::table::{Schema, SchemaError, SchemaRef, Slice};
+use itertools::Itertools;
+use std::collections::HashSet;
+use std::mem::discriminant;
+use std::rc::Rc;
+
+pub struct SetOpExecutor {
+    children: Vec<ExecutorImpl>,
+    /// the child being output
+    idx: usize,
+    op: SetOp,
+    /// tuples output so far, not kept by union all
+    seen: HashSet<Vec<Datum>>,
+    /// tuples of the rhs, materialized for intersect and except
+    rhs_tuples: Option<HashSet<Vec<Datum>>>,
+    schema: SchemaRef,
+    bpm: BufferPoolManagerRef,
+    buffer: Vec<Vec<Datum>>,
+}
+
+/// the columns are named after the lhs, a column is nullable if it is on either side
+pub fn set_op_schema(lhs_schema: &Schema, rhs_schema: &Schema) -> Result<SchemaRef, SchemaError> {
+    if lhs_schema.columns.len() != rhs_schema.columns.len() {
+        return Err(SchemaError::NotMatch);
+    }
+    let type_and_names = lhs_schema
+        .columns
+        .iter()
+        .zip(rhs_schema.columns.iter())
+        .map(|(l, r)| {
+            if discriminant(&l.data_type) != discriminant(&r.data_type) {
+                return Err(SchemaError::NotMatch);
+            }
+            let data_type = match r.data_type.nullable() {
+                true => r.data_type,
+                false => l.data_type,
+            };
+            Ok((data_type, l.desc.clone()))
+        })
+        .collect::<Result<Vec<(DataType, String)>, _>>()?;
+    Ok(Rc::new(Schema::from_type_and_names(&type_and_names)))
+}
+
+impl SetOpExecutor {
+    pub fn new(
+        lhs: ExecutorImpl,
+        rhs: ExecutorImpl,
+        op: SetOp,
+        bpm: BufferPoolManagerRef,
+    ) -> Result<Self, SchemaError> {
+        let schema = set_op_schema(&lhs.schema(), &rhs.schema())?;
+        Ok(Self {
+            children: vec![lhs, rhs],
+            idx: 0,
+            op,
+            seen: HashSet::new(),
+            rhs_tuples: None,
+            schema,
+            bpm,
+            buffer: vec![],
+        })
+    }
+    /// union streams both children, intersect and except only stream the lhs
+    fn num_streamed(&self) -> usize {
+        match self.op {
+            SetOp::Union | SetOp::UnionAll => 2,
+            SetOp::Intersect | SetOp::Except => 1,
+        }
+    }
+    fn materialize_rhs(&mut self) -> Result<(), ExecutionError> {
+        let mut tuples = HashSet::new();
+        while let Some(slice) = self.children[1].execute()? {
+            tuples.extend(slice.tuple_iter());
+        }
+        self.rhs_tuples = Some(tuples);
+        Ok(())
+    }
+}
+
+impl Executor for SetOpExecutor {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.num_streamed() == 1 && self.rhs_tuples.is_none() {
+            self.materialize_rhs()?;
+        }
+        let mut output = Slice::new(self.bpm.clone(), self.schema());
+        loop {
+            while let Some(tuple) = self.buffer.last() {
+                if output.insert(tuple).is_err() {
+                    return Ok(Some(output));
+                }
+                self.buffer.pop();
+            }
+            if self.idx == self.num_streamed() {
+                break;
+            }
+            let input = match self.children[self.idx].execute()? {
+                Some(input) => input,
+                None => {
+                    self.idx += 1;
+                    continue;
+                }
+            };
+            let (op, seen, rhs_tuples) = (self.op, &mut self.seen, &self.rhs_tuples);
+            self.buffer = input
+                .tuple_iter()
+                .filter(|tuple| match op {
+                    SetOp::UnionAll => true,
+                    SetOp::Union => seen.insert(tuple.clone()),
+                    SetOp::Intersect => {
+                        rhs_tuples.as_ref().unwrap().contains(tuple) && seen.insert(tuple.clone())
+                    }
+                    SetOp::Except => {
+                        !rhs_tuples.as_ref().unwrap().contains(tuple) && seen.insert(tuple.clone())
+                    }
+                })
+                .collect_vec();
+            self.buffer.reverse();
+        }
+        if output.count() > 0 {
+            Ok(Some(output))
+        } else {
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::datum::DataType;
+use crate::execution::{ExecutionError, Executor};
+use crate::planner::StatementRecord;
+use crate::storage::BufferPoolManagerRef;
+use crate::table::{Schema, SchemaRef, Slice};
+use std::rc::Rc;
+
+/// statements longer than this are cut, so that any record fits into a slice
+const STATEMENT_WIDTH: usize = 256;
+
+pub struct ShowHistoryExecutor {
+    records: Vec<StatementRecord>,
+    bpm: BufferPoolManagerRef,
+    /// records not output yet, in reverse order
+    buffer: Option<Vec<StatementRecord>>,
+}
+
+impl ShowHistoryExecutor {
+    pub fn new(records: Vec<StatementRecord>, bpm: BufferPoolManagerRef) -> Self {
+        Self {
+            records,
+            bpm,
+            buffer: None,
+        }
+    }
+}
+
+impl Executor for ShowHistoryExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[
+            (DataType::new_as_varchar(false), "statement".to_string()),
+            (DataType::new_as_float(false), "elapsed_ms".to_string()),
+            (DataType::new_as_int(false), "rows".to_string()),
+        ]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.buffer.is_none() {
+            let mut records = std::mem::take(&mut self.records);
+            records.reverse();
+            self.buffer = Some(records);
+        }
+        if self.buffer.as_ref().unwrap().is_empty() {
+            return Ok(None);
+        }
+        let mut slice = Slice::new(self.bpm.clone(), self.schema());
+        let buffer = self.buffer.as_mut().unwrap();
+        while let Some(record) = buffer.last() {
+            let tuple = [
+                record
+                    .sql
+                    .chars()
+                    .take(STATEMENT_WIDTH)
+                    .collect::<String>()
+                    .as_str()
+                    .into(),
+                (record.elapsed.as_secs_f32() * 1000.0).into(),
+                (record.rows as i32).into(),
+            ];
+            if slice.insert(&tuple).is_err() {
+                break;
+            }
+            buffer.pop();
+        }
+        Ok(Some(slice))
+    }
+}
@@ -0,0 +1,43 @@
+use crate::datum::DataType;
+use crate::execution::{ExecutionError, Executor};
+use crate::storage::BufferPoolManagerRef;
+use crate::table::{ResultBuilder, Schema, SchemaRef, Slice};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+pub struct ShowWarningsExecutor {
+    warnings: Vec<String>,
+    bpm: BufferPoolManagerRef,
+    /// slices not output yet, built at the first execution
+    output: Option<VecDeque<Slice>>,
+}
+
+impl ShowWarningsExecutor {
+    pub fn new(warnings: Vec<String>, bpm: BufferPoolManagerRef) -> Self {
+        Self {
+            warnings,
+            bpm,
+            output: None,
+        }
+    }
+}
+
+impl Executor for ShowWarningsExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[(
+            DataType::new_as_varchar(false),
+            "warning".to_string(),
+        )]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.output.is_none() {
+            let rows = self
+                .warnings
+                .iter()
+                .map(|warning| vec![warning.as_str().into()]);
+            let slices = ResultBuilder::from_rows(self.bpm.clone(), self.schema(), rows)?;
+            self.output = Some(slices.into());
+        }
+        Ok(self.output.as_mut().unwrap().pop_front())
+    }
+}
@@ -0,0 +1,399 @@
+use crate::datum::Datum;
+use crate::execution::{ExecutionError, Executor, ExecutorImpl};
+use crate::expr::ExprImpl;
+use crate::storage::{BufferPoolManagerRef, PageID};
+use crate::table::{SchemaRef, Slice, TableError};
+use itertools::Itertools;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use std::rc::Rc;
+
+/// max number of tuples buffered in memory before spilling a sorted run
+pub const SORT_BUFFER_SIZE: usize = 4096;
+
+/// cursor over a sorted run, holding at most one page of the run in memory
+struct Run {
+    page_id: Option<PageID>,
+    buffer: VecDeque<(Vec<Datum>, Vec<Datum>)>,
+}
+
+/// compare the sort keys, each key in the order of its desc flag
+fn compare_keys(lhs: &[Datum], rhs: &[Datum], descs: impl Iterator<Item = bool>) -> Ordering {
+    for ((l, r), desc) in lhs.iter().zip(rhs.iter()).zip(descs) {
+        let ord = if desc { r.cmp(l) } else { l.cmp(r) };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    Ordering::Equal
+}
+
+pub struct SortExecutor {
+    child: Box<ExecutorImpl>,
+    exprs_with_desc: Vec<(ExprImpl, bool)>,
+    bpm: BufferPoolManagerRef,
+    buffer_size: usize,
+    runs: Vec<Run>,
+    buffer: VecDeque<Vec<Datum>>,
+    executed: bool,
+}
+
+impl SortExecutor {
+    pub fn new(
+        child: Box<ExecutorImpl>,
+        exprs_with_desc: Vec<(ExprImpl, bool)>,
+        bpm: BufferPoolManagerRef,
+        buffer_size: usize,
+    ) -> Self {
+        Self {
+            child,
+            exprs_with_desc,
+            bpm,
+            buffer_size,
+            runs: vec![],
+            buffer: VecDeque::new(),
+            executed: false,
+        }
+    }
+    fn compare(&self, lhs: &[Datum], rhs: &[Datum]) -> Ordering {
+        compare_keys(lhs, rhs, self.exprs_with_desc.iter().map(|(_, desc)| *desc))
+    }
+    fn keys_of(&self, slice: &Slice) -> Result<Vec<Vec<Datum>>, ExecutionError> {
+        let exprs = self
+            .exprs_with_desc
+            .iter()
+            .map(|(e, _)| e.clone())
+            .collect_vec();
+        Ok(ExprImpl::batch_eval(&exprs, Some(slice))?)
+    }
+    /// write the sorted tuples into a chain of slices
+    fn spill(&mut self, tuples: Vec<(Vec<Datum>, Vec<Datum>)>) -> Result<(), ExecutionError> {
+        let mut slice = Slice::new(self.bpm.clone(), self.child.schema());
+        let page_id = slice.page_id();
+        for (_, tuple) in tuples {
+            if slice.insert(&tuple).is_err() {
+                let mut next = Slice::new(self.bpm.clone(), self.child.schema());
+                slice.meta_mut()?.next_page_id = Some(next.page_id());
+                next.insert(&tuple)?;
+                slice = next;
+            }
+        }
+        self.runs.push(Run {
+            page_id: Some(page_id),
+            buffer: VecDeque::new(),
+        });
+        Ok(())
+    }
+    /// load the next page of the run if its buffer is drained, the loaded page is freed
+    fn fill(&mut self, idx: usize) -> Result<(), ExecutionError> {
+        let page_id = match (self.runs[idx].buffer.is_empty(), self.runs[idx].page_id) {
+            (true, Some(page_id)) => page_id,
+            _ => return Ok(()),
+        };
+        let slice = Slice::open(self.bpm.clone(), self.child.schema(), page_id)?;
+        let keys = self.keys_of(&slice)?;
+        let run = &mut self.runs[idx];
+        run.buffer = keys.into_iter().zip(slice.tuple_iter()).collect();
+        run.page_id = slice.meta()?.next_page_id;
+        drop(slice);
+        self.bpm
+            .borrow_mut()
+            .free(page_id)
+            .map_err(TableError::from)?;
+        Ok(())
+    }
+    /// pop the smallest head among all runs
+    fn merge_next(&mut self) -> Result<Option<Vec<Datum>>, ExecutionError> {
+        for idx in 0..self.runs.len() {
+            self.fill(idx)?;
+        }
+        let min = self
+            .runs
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, run)| run.buffer.front().map(|(key, _)| (idx, key)))
+            .min_by(|(_, lhs), (_, rhs)| self.compare(lhs, rhs))
+            .map(|(idx, _)| idx);
+        Ok(min.map(|idx| self.runs[idx].buffer.pop_front().unwrap().1))
+    }
+    fn sort(&mut self) -> Result<(), ExecutionError> {
+        let mut tuples = vec![];
+        while let Some(slice) = self.child.execute()? {
+            tuples.extend(self.keys_of(&slice)?.into_iter().zip(slice.tuple_iter()));
+            if tuples.len() > self.buffer_size {
+                tuples.sort_by(|(lhs, _), (rhs, _)| self.compare(lhs, rhs));
+                self.spill(tuples)?;
+                tuples = vec![];
+            }
+        }
+        tuples.sort_by(|(lhs, _), (rhs, _)| self.compare(lhs, rhs));
+        if self.runs.is_empty() {
+            self.buffer = tuples.into_iter().map(|(_, tuple)| tuple).collect();
+        } else if !tuples.is_empty() {
+            self.spill(tuples)?;
+        }
+        Ok(())
+    }
+}
+
+impl Executor for SortExecutor {
+    fn schema(&self) -> SchemaRef {
+        self.child.schema()
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if !self.executed {
+            self.sort()?;
+            self.executed = true;
+        }
+        let mut output = Slice::new(self.bpm.clone(), self.schema());
+        loop {
+            if self.buffer.is_empty() {
+                match self.merge_next()? {
+                    Some(tuple) => self.buffer.push_back(tuple),
+                    None => break,
+                }
+            }
+            if output.insert(self.buffer.front().unwrap()).is_ok() {
+                self.buffer.pop_front();
+            } else {
+                break;
+            }
+        }
+        if output.count() > 0 {
+            Ok(Some(output))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// a tuple kept by top-n, ties are broken by the arrival so the order is the one of sort
+struct Ranked {
+    key: Vec<Datum>,
+    seq: usize,
+    tuple: Vec<Datum>,
+    descs: Rc<[bool]>,
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_keys(&self.key, &other.key, self.descs.iter().copied())
+            .then(self.seq.cmp(&other.seq))
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+/// the leading tuples of the sorted order, only the limit best tuples seen so far are kept in
+/// a max heap, rather than sorting all of the child
+pub struct TopNExecutor {
+    child: Box<ExecutorImpl>,
+    exprs_with_desc: Vec<(ExprImpl, bool)>,
+    bpm: BufferPoolManagerRef,
+    limit: usize,
+    heap: BinaryHeap<Ranked>,
+    buffer: VecDeque<Vec<Datum>>,
+    executed: bool,
+}
+
+impl TopNExecutor {
+    pub fn new(
+        child: Box<ExecutorImpl>,
+        exprs_with_desc: Vec<(ExprImpl, bool)>,
+        bpm: BufferPoolManagerRef,
+        limit: usize,
+    ) -> Self {
+        Self {
+            child,
+            exprs_with_desc,
+            bpm,
+            limit,
+            heap: BinaryHeap::new(),
+            buffer: VecDeque::new(),
+            executed: false,
+        }
+    }
+    fn select(&mut self) -> Result<(), ExecutionError> {
+        let (exprs, descs): (Vec<_>, Vec<_>) = self.exprs_with_desc.iter().cloned().unzip();
+        let descs: Rc<[bool]> = descs.into();
+        let mut seq = 0;
+        while let Some(slice) = self.child.execute()? {
+            let keys = ExprImpl::batch_eval(&exprs, Some(&slice))?;
+            for (key, tuple) in keys.into_iter().zip(slice.tuple_iter()) {
+                self.heap.push(Ranked {
+                    key,
+                    seq,
+                    tuple,
+                    descs: descs.clone(),
+                });
+                seq += 1;
+                // the greatest one is behind the others kept
+                if self.heap.len() > self.limit {
+                    self.heap.pop();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Executor for TopNExecutor {
+    fn schema(&self) -> SchemaRef {
+        self.child.schema()
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if !self.executed {
+            self.select()?;
+            self.executed = true;
+        }
+        if !self.heap.is_empty() {
+            let heap = std::mem::take(&mut self.heap);
+            self.buffer = heap
+                .into_sorted_vec()
+                .into_iter()
+                .map(|r| r.tuple)
+                .collect();
+        }
+        let mut output = Slice::new(self.bpm.clone(), self.schema());
+        while let Some(tuple) = self.buffer.front() {
+            if output.insert(tuple).is_err() {
+                break;
+            }
+            self.buffer.pop_front();
+        }
+        if output.count() > 0 {
+            Ok(Some(output))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datum::DataType;
+    use crate::execution::SeqScanExecutor;
+    use crate::expr::ColumnRefExpr;
+    use crate::storage::BufferPoolManager;
+    use crate::table::{Schema, Table};
+    use rand::seq::SliceRandom;
+    use std::fs::remove_file;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_external_sort() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(10);
+            let filename = bpm.borrow().filename();
+            let schema = Rc::new(Schema::from_type_and_names(&[
+                (DataType::new_as_int(false), "v1".to_string()),
+                (DataType::new_as_varchar(false), "v2".to_string()),
+            ]));
+            let mut table = Table::new(schema.clone(), bpm.clone());
+            let mut nums = (0..2000i32).collect_vec();
+            nums.shuffle(&mut rand::thread_rng());
+            for num in nums {
+                table
+                    .insert(vec![num.into(), format!("foo{}", num).as_str().into()])
+                    .unwrap();
+            }
+            let child = ExecutorImpl::SeqScan(SeqScanExecutor::new(
+                bpm.clone(),
+                Some(table.meta().page_id_of_first_slice),
+                schema,
+                false,
+            ));
+            let expr = ExprImpl::ColumnRef(ColumnRefExpr::new(
+                0,
+                DataType::new_as_int(false),
+                "v1".to_string(),
+            ));
+            // the budget is much smaller than the input, so there must be several runs
+            let mut sort = SortExecutor::new(Box::new(child), vec![(expr, true)], bpm.clone(), 100);
+            let mut tuples = vec![];
+            while let Some(slice) = sort.execute().unwrap() {
+                tuples.extend(slice.tuple_iter());
+            }
+            assert!(sort.runs.len() > 1);
+            assert_eq!(
+                tuples,
+                (0..2000i32)
+                    .rev()
+                    .map(|num| vec![num.into(), format!("foo{}", num).as_str().into()])
+                    .collect_vec()
+            );
+            drop(sort);
+            // pages of the runs are all released
+            assert_eq!(bpm.borrow().page_pin_summary(), vec![(table.page_id(), 1)]);
+            assert!(bpm.borrow_mut().get_page_id_of_first_free_page().is_some());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_top_n() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(10);
+            let filename = bpm.borrow().filename();
+            let schema = Rc::new(Schema::from_type_and_names(&[
+                (DataType::new_as_int(false), "v1".to_string()),
+                (DataType::new_as_int(false), "v2".to_string()),
+            ]));
+            let mut table = Table::new(schema.clone(), bpm.clone());
+            let mut nums = (0..2000i32).collect_vec();
+            nums.shuffle(&mut rand::thread_rng());
+            for num in nums {
+                table.insert(vec![(num % 100).into(), num.into()]).unwrap();
+            }
+            let scan = || {
+                Box::new(ExecutorImpl::SeqScan(SeqScanExecutor::new(
+                    bpm.clone(),
+                    Some(table.meta().page_id_of_first_slice),
+                    schema.clone(),
+                    false,
+                )))
+            };
+            let exprs_with_desc = vec![(
+                ExprImpl::ColumnRef(ColumnRefExpr::new(
+                    0,
+                    DataType::new_as_int(false),
+                    "v1".to_string(),
+                )),
+                true,
+            )];
+            let mut sort = SortExecutor::new(scan(), exprs_with_desc.clone(), bpm.clone(), 4096);
+            let mut sorted = vec![];
+            while let Some(slice) = sort.execute().unwrap() {
+                sorted.extend(slice.tuple_iter());
+            }
+            drop(sort);
+            let mut top_n = TopNExecutor::new(scan(), exprs_with_desc, bpm.clone(), 30);
+            top_n.select().unwrap();
+            top_n.executed = true;
+            // no more than the limit is kept while consuming the whole child
+            assert_eq!(top_n.heap.len(), 30);
+            let mut tuples = vec![];
+            while let Some(slice) = top_n.execute().unwrap() {
+                tuples.extend(slice.tuple_iter());
+            }
+            // the ties are in the order of the full sort
+            assert_eq!(tuples, sorted.into_iter().take(30).collect_vec());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+use crate::catalog::CatalogManagerRef;
+use crate::datum::DataType;
+use crate::execution::{ExecutionError, Executor};
+use crate::index::{BPTIndex, IndexError};
+use crate::storage::BufferPoolManagerRef;
+use crate::table::{Schema, SchemaError, SchemaRef, Slice, Table};
+use itertools::Itertools;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+pub struct TruncateTableExecutor {
+    table_name: String,
+    cascade: bool,
+    catalog: CatalogManagerRef,
+    bpm: BufferPoolManagerRef,
+    executed: bool,
+}
+
+/// decrease the ref_cnt of the tuples referred by the table, as all of its tuples are
+/// going to be removed
+pub fn release_references(table: &Table, bpm: BufferPoolManagerRef) -> Result<(), ExecutionError> {
+    for (page_id, src_and_dst) in &table.schema.foreign {
+        let mut foreign_table = Table::open(*page_id, bpm.clone());
+        let page_id_of_index = foreign_table
+            .meta()
+            .page_id_of_primary_index
+            .ok_or(SchemaError::PrimaryNotFound)?;
+        let foreign_index =
+            BPTIndex::open(bpm.clone(), page_id_of_index, foreign_table.schema.as_ref())?;
+        let src = src_and_dst.iter().map(|(src, _)| *src).collect_vec();
+        for tuple in table.iter().flat_map(|s| s.tuple_iter().collect_vec()) {
+            let key = src.iter().map(|idx| tuple[*idx].clone()).collect_vec();
+            let record_id = foreign_index.find(&key).ok_or(IndexError::KeyNotFound)?;
+            let ref_cnt = foreign_table.ref_cnt_of(record_id)?;
+            foreign_table.set_ref_cnt_of(record_id, ref_cnt - 1)?;
+        }
+    }
+    Ok(())
+}
+
+impl TruncateTableExecutor {
+    pub fn new(
+        table_name: String,
+        cascade: bool,
+        catalog: CatalogManagerRef,
+        bpm: BufferPoolManagerRef,
+    ) -> Self {
+        Self {
+            table_name,
+            cascade,
+            catalog,
+            bpm,
+            executed: false,
+        }
+    }
+    fn truncate(
+        &self,
+        table_name: &str,
+        truncated: &mut HashSet<String>,
+    ) -> Result<(), ExecutionError> {
+        if !truncated.insert(table_name.to_owned()) {
+            return Ok(());
+        }
+        let referencing = self.catalog.borrow().tables_referencing(table_name)?;
+        if !referencing.is_empty() && !self.cascade {
+            return Err(ExecutionError::TableReferenced(
+                table_name.to_owned(),
+                referencing.join(", "),
+            ));
+        }
+        for table_name in &referencing {
+            self.truncate(table_name, truncated)?;
+        }
+        let mut table = self.catalog.borrow().find_table(table_name)?;
+        release_references(&table, self.bpm.clone())?;
+        table.truncate()?;
+        for mut index in self.catalog.borrow().find_indexes_by_table(table_name)? {
+            index.clear()?;
+        }
+        Ok(())
+    }
+}
+
+impl Executor for TruncateTableExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[(
+            DataType::new_as_varchar(false),
+            "table".to_string(),
+        )]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        self.executed = true;
+        self.truncate(&self.table_name, &mut HashSet::new())?;
+        Ok(Some(Slice::new_as_message(
+            self.bpm.clone(),
+            "table",
+            &self.table_name,
+        )?))
+    }
+}
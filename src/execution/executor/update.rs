@@ -1,58 +1,230 @@
-use crate::datum::Datum;
+use super::insert::check_constraints;
+use crate::datum::{DataType, Datum};
 use crate::execution::{ExecutionError, Executor, ExecutorImpl};
+use crate::expr::ExprImpl;
+use crate::index::{BPTIndex, IndexError, RecordID};
 use crate::storage::BufferPoolManagerRef;
-use crate::table::{SchemaRef, Slice};
+use crate::table::{Schema, SchemaError, SchemaRef, Slice, Table, TableError};
+use itertools::Itertools;
+use std::collections::BTreeSet;
+use std::rc::Rc;
 
 pub struct UpdateExecutor {
     bpm: BufferPoolManagerRef,
     child: Box<ExecutorImpl>,
     column_idx_with_values: Vec<(usize, Datum)>,
-    schema: SchemaRef,
-    buffer: Vec<Vec<Datum>>,
+    table: Table,
+    indexes: Vec<BPTIndex>,
+    executed: bool,
+    /// at most this many rows are updated
+    limit: Option<usize>,
+    /// check constraints of the table
+    checks: Vec<ExprImpl>,
+}
+
+struct UpdatedRow {
+    record_id: RecordID,
+    old: Vec<Datum>,
+    new: Vec<Datum>,
+}
+
+fn project(exprs: &[ExprImpl], tuple: &[Datum]) -> Vec<Datum> {
+    exprs
+        .iter()
+        .map(|e| {
+            if let ExprImpl::ColumnRef(column_ref) = e {
+                tuple[column_ref.as_idx()].clone()
+            } else {
+                unreachable!()
+            }
+        })
+        .collect_vec()
+}
+
+fn project_by_idx(idxes: &[usize], tuple: &[Datum]) -> Vec<Datum> {
+    idxes.iter().map(|idx| tuple[*idx].clone()).collect_vec()
 }
 
 impl UpdateExecutor {
     pub fn new(
         column_idx_with_values: Vec<(usize, Datum)>,
-        schema: SchemaRef,
+        table: Table,
+        indexes: Vec<BPTIndex>,
         bpm: BufferPoolManagerRef,
         child: ExecutorImpl,
+        limit: Option<usize>,
+        checks: Vec<ExprImpl>,
     ) -> Self {
         Self {
             bpm,
             child: Box::new(child),
             column_idx_with_values,
-            schema,
-            buffer: vec![],
+            table,
+            indexes,
+            executed: false,
+            limit,
+            checks,
+        }
+    }
+    /// check every constraint against the new tuples before touching anything, so that a
+    /// violation aborts the whole update
+    fn validate(&self, rows: &[UpdatedRow]) -> Result<(), ExecutionError> {
+        if !self.checks.is_empty() {
+            let mut slice = Slice::new(self.bpm.clone(), self.table.schema.clone());
+            for row in rows {
+                if slice.insert(&row.new).is_err() {
+                    check_constraints(&self.checks, &slice)?;
+                    slice = Slice::new(self.bpm.clone(), self.table.schema.clone());
+                    slice.insert(&row.new)?;
+                }
+            }
+            check_constraints(&self.checks, &slice)?;
+        }
+        for index in &self.indexes {
+            let changed = rows
+                .iter()
+                .filter(|row| project(&index.exprs, &row.old) != project(&index.exprs, &row.new))
+                .collect_vec();
+            let removed: BTreeSet<RecordID> = changed.iter().map(|row| row.record_id).collect();
+            let mut inserted = BTreeSet::new();
+            for row in changed {
+                let key = project(&index.exprs, &row.new);
+                if let Some(record_id) = index.find(&key) {
+                    if !removed.contains(&record_id) {
+                        return Err(ExecutionError::UpdateDuplicatedKey(key));
+                    }
+                }
+                if !inserted.insert(key.clone()) {
+                    return Err(ExecutionError::UpdateDuplicatedKey(key));
+                }
+            }
+        }
+        for (page_id, src_and_dst) in &self.table.schema.foreign {
+            let foreign_table = Table::open(*page_id, self.bpm.clone());
+            let page_id_of_index = foreign_table
+                .meta()
+                .page_id_of_primary_index
+                .ok_or(SchemaError::PrimaryNotFound)?;
+            let foreign_index = BPTIndex::open(
+                self.bpm.clone(),
+                page_id_of_index,
+                foreign_table.schema.as_ref(),
+            )?;
+            let src = src_and_dst.iter().map(|(src, _)| *src).collect_vec();
+            for row in rows {
+                let key = project_by_idx(&src, &row.new);
+                if key != project_by_idx(&src, &row.old) {
+                    foreign_index.find(&key).ok_or(IndexError::KeyNotFound)?;
+                }
+            }
         }
+        let primary = &self.table.schema.primary;
+        for row in rows {
+            if project_by_idx(primary, &row.old) != project_by_idx(primary, &row.new)
+                && self.table.ref_cnt_of(row.record_id)? > 0
+            {
+                return Err(TableError::RemovingReferedTuple.into());
+            }
+        }
+        Ok(())
+    }
+    fn update_foreign_ref_cnt(&mut self, rows: &[UpdatedRow]) -> Result<(), ExecutionError> {
+        for (page_id, src_and_dst) in &self.table.schema.foreign {
+            let mut foreign_table = Table::open(*page_id, self.bpm.clone());
+            let page_id_of_index = foreign_table
+                .meta()
+                .page_id_of_primary_index
+                .ok_or(SchemaError::PrimaryNotFound)?;
+            let foreign_index = BPTIndex::open(
+                self.bpm.clone(),
+                page_id_of_index,
+                foreign_table.schema.as_ref(),
+            )?;
+            let src = src_and_dst.iter().map(|(src, _)| *src).collect_vec();
+            for row in rows {
+                let old_key = project_by_idx(&src, &row.old);
+                let new_key = project_by_idx(&src, &row.new);
+                if old_key == new_key {
+                    continue;
+                }
+                let record_id = foreign_index
+                    .find(&old_key)
+                    .ok_or(IndexError::KeyNotFound)?;
+                let ref_cnt = foreign_table.ref_cnt_of(record_id)?;
+                foreign_table.set_ref_cnt_of(record_id, ref_cnt - 1)?;
+                let record_id = foreign_index
+                    .find(&new_key)
+                    .ok_or(IndexError::KeyNotFound)?;
+                let ref_cnt = foreign_table.ref_cnt_of(record_id)?;
+                foreign_table.set_ref_cnt_of(record_id, ref_cnt + 1)?;
+            }
+        }
+        Ok(())
     }
 }
 
 impl Executor for UpdateExecutor {
     fn schema(&self) -> SchemaRef {
-        self.schema.clone()
+        Rc::new(Schema::from_type_and_names(&[(
+            DataType::new_as_int(false),
+            "Updated".to_string(),
+        )]))
     }
     fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
-        while let Some(slice) = self.child.execute()? {
-            for mut tuple in slice.tuple_iter() {
+        if self.executed {
+            return Ok(None);
+        }
+        self.executed = true;
+        let mut rows = vec![];
+        let limit = self.limit.unwrap_or(usize::MAX);
+        while rows.len() < limit {
+            let slice = match self.child.execute()? {
+                Some(slice) => slice,
+                None => break,
+            };
+            for mut tuple in slice.tuple_iter().take(limit - rows.len()) {
+                let idx: i32 = tuple.pop().unwrap().into();
+                let page_id: i32 = tuple.pop().unwrap().into();
+                let mut new = tuple.clone();
                 for (idx, datum) in &self.column_idx_with_values {
-                    tuple[*idx] = datum.clone();
+                    new[*idx] = datum.clone();
                 }
-                self.buffer.push(tuple)
+                rows.push(UpdatedRow {
+                    record_id: (page_id as usize, idx as usize),
+                    old: tuple,
+                    new,
+                });
             }
         }
-        let mut output = Slice::new(self.bpm.clone(), self.schema());
-        while !self.buffer.is_empty() {
-            if output.insert(self.buffer.last().unwrap()).is_ok() {
-                self.buffer.pop();
-            } else {
-                break;
+        // stage-1: validate
+        self.validate(&rows)?;
+        // stage-2: update
+        self.update_foreign_ref_cnt(&rows)?;
+        for index in &mut self.indexes {
+            for row in &rows {
+                let old_key = project(&index.exprs, &row.old);
+                if old_key != project(&index.exprs, &row.new) {
+                    index.remove(&old_key)?;
+                }
             }
         }
-        if output.count() > 0 {
-            Ok(Some(output))
-        } else {
-            Ok(None)
+        for row in &rows {
+            let record_id = self.table.update(row.record_id, row.new.clone())?;
+            for index in &mut self.indexes {
+                let old_key = project(&index.exprs, &row.old);
+                let new_key = project(&index.exprs, &row.new);
+                if old_key != new_key {
+                    index.insert(&new_key, record_id)?;
+                } else if record_id != row.record_id {
+                    index.remove(&old_key)?;
+                    index.insert(&new_key, record_id)?;
+                }
+            }
         }
+        Ok(Some(Slice::new_as_count(
+            self.bpm.clone(),
+            "Updated",
+            rows.len(),
+        )?))
     }
 }
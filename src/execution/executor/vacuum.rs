@@ -0,0 +1,101 @@
+use super::analyze::{insert_stats, stats_of, stats_schema};
+use crate::catalog::CatalogManagerRef;
+use crate::datum::DataType;
+use crate::execution::{ExecutionError, Executor};
+use crate::expr::ExprImpl;
+use crate::storage::BufferPoolManagerRef;
+use crate::table::{Schema, SchemaRef, Slice};
+use itertools::Itertools;
+use std::rc::Rc;
+
+pub struct VacuumExecutor {
+    table_name: String,
+    /// refresh the stats of the columns with the same scan
+    analyze: bool,
+    catalog: CatalogManagerRef,
+    bpm: BufferPoolManagerRef,
+    executed: bool,
+}
+
+impl VacuumExecutor {
+    pub fn new(
+        table_name: String,
+        analyze: bool,
+        catalog: CatalogManagerRef,
+        bpm: BufferPoolManagerRef,
+    ) -> Self {
+        Self {
+            table_name,
+            analyze,
+            catalog,
+            bpm,
+            executed: false,
+        }
+    }
+}
+
+impl Executor for VacuumExecutor {
+    /// the stats like analyze if analyzed, otherwise the slices before and after
+    fn schema(&self) -> SchemaRef {
+        match self.analyze {
+            true => stats_schema(),
+            false => Rc::new(Schema::from_type_and_names(&[
+                (DataType::new_as_int(false), "slices_before".to_string()),
+                (DataType::new_as_int(false), "slices_after".to_string()),
+            ])),
+        }
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        self.executed = true;
+        let mut table = self.catalog.borrow().find_table(&self.table_name)?;
+        // the tuples are kept with their ref_cnt, since the keys referring them do not change
+        let mut slices_before = 0;
+        let mut tuples = vec![];
+        let mut ref_cnts = vec![];
+        for slice in table.iter() {
+            slices_before += 1;
+            for ((_, idx), tuple) in slice.record_iter() {
+                ref_cnts.push(slice.ref_cnt_at(idx)?);
+                tuples.push(tuple);
+            }
+        }
+        table.truncate()?;
+        for (tuple, ref_cnt) in tuples.iter().zip(ref_cnts) {
+            let record_id = table.insert(tuple.clone())?;
+            if ref_cnt > 0 {
+                table.set_ref_cnt_of(record_id, ref_cnt)?;
+            }
+        }
+        // the record ids have changed, so the indexes are rebuilt
+        let mut indexes = self
+            .catalog
+            .borrow()
+            .find_indexes_by_table(&self.table_name)?;
+        for index in indexes.iter_mut() {
+            index.clear()?;
+        }
+        let mut slices_after = 0;
+        for slice in table.iter() {
+            slices_after += 1;
+            let record_ids = slice.record_iter().map(|(id, _)| id).collect_vec();
+            for index in indexes.iter_mut() {
+                let rows = ExprImpl::batch_eval(&index.exprs, Some(&slice))?;
+                for (row, record_id) in rows.iter().zip(record_ids.iter()) {
+                    index.insert(row, *record_id)?;
+                }
+            }
+        }
+        let mut output = Slice::new(self.bpm.clone(), self.schema());
+        if self.analyze {
+            let stats = stats_of(&table.schema, &tuples);
+            table.set_stats(&stats)?;
+            insert_stats(&mut output, &table.schema, stats)?;
+        } else {
+            output.insert(&[(slices_before as i32).into(), (slices_after as i32).into()])?;
+        }
+        Ok(Some(output))
+    }
+}
@@ -2,13 +2,14 @@ use crate::execution::{ExecutionError, Executor};
 use crate::expr::ExprImpl;
 use crate::storage::BufferPoolManagerRef;
 use crate::table::{SchemaError, SchemaRef, Slice};
-use itertools::Itertools;
 use log::info;
 
 pub struct ValuesExecutor {
     values: Vec<Vec<ExprImpl>>,
     schema: SchemaRef,
     bpm: BufferPoolManagerRef,
+    /// index of the next tuple to output
+    offset: usize,
     executed: bool,
 }
 
@@ -18,6 +19,7 @@ impl ValuesExecutor {
             values,
             schema,
             bpm,
+            offset: 0,
             executed: false,
         }
     }
@@ -30,16 +32,21 @@ impl Executor for ValuesExecutor {
     fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
         if !self.executed {
             let mut slice = Slice::new(self.bpm.clone(), self.schema.clone());
-            for tuple in self.values.iter_mut() {
+            while let Some(tuple) = self.values.get(self.offset) {
                 if tuple.len() != self.schema.columns.len() {
                     return Err(ExecutionError::Schema(SchemaError::NotMatch));
                 }
                 let datums = tuple
-                    .iter_mut()
-                    .map(|e| e.eval(None).remove(0))
-                    .collect_vec();
+                    .iter()
+                    .map(|e| Ok(e.eval(None)?.remove(0)))
+                    .collect::<Result<Vec<_>, ExecutionError>>()?;
                 info!("generate tuple {:?}", datums);
-                slice.insert(&datums)?;
+                match slice.insert(&datums) {
+                    Ok(_) => self.offset += 1,
+                    // the rest goes to the next slice
+                    Err(_) if slice.count() > 0 => return Ok(Some(slice)),
+                    Err(err) => return Err(err.into()),
+                }
             }
             self.executed = true;
             Ok(Some(slice))
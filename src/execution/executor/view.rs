@@ -0,0 +1,87 @@
+use crate::catalog::CatalogManagerRef;
+use crate::datum::DataType;
+use crate::execution::{ExecutionError, Executor};
+use crate::storage::BufferPoolManagerRef;
+use crate::table::{Schema, SchemaRef, Slice};
+use std::rc::Rc;
+
+pub struct CreateViewExecutor {
+    view_name: String,
+    definition: String,
+    catalog: CatalogManagerRef,
+    bpm: BufferPoolManagerRef,
+    executed: bool,
+}
+
+pub struct DropViewExecutor {
+    view_name: String,
+    catalog: CatalogManagerRef,
+    bpm: BufferPoolManagerRef,
+    executed: bool,
+}
+
+impl CreateViewExecutor {
+    pub fn new(
+        view_name: String,
+        definition: String,
+        catalog: CatalogManagerRef,
+        bpm: BufferPoolManagerRef,
+    ) -> Self {
+        Self {
+            view_name,
+            definition,
+            catalog,
+            bpm,
+            executed: false,
+        }
+    }
+}
+
+impl DropViewExecutor {
+    pub fn new(view_name: String, catalog: CatalogManagerRef, bpm: BufferPoolManagerRef) -> Self {
+        Self {
+            view_name,
+            catalog,
+            bpm,
+            executed: false,
+        }
+    }
+}
+
+impl Executor for CreateViewExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[(
+            DataType::new_as_varchar(false),
+            "view".to_string(),
+        )]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        self.executed = true;
+        self.catalog
+            .borrow_mut()
+            .create_view(&self.view_name, &self.definition)?;
+        let slice = Slice::new_as_message(self.bpm.clone(), "view", &self.view_name)?;
+        Ok(Some(slice))
+    }
+}
+
+impl Executor for DropViewExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[(
+            DataType::new_as_varchar(false),
+            "view".to_string(),
+        )]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        self.executed = true;
+        self.catalog.borrow_mut().remove_view(&self.view_name)?;
+        let slice = Slice::new_as_message(self.bpm.clone(), "view", &self.view_name)?;
+        Ok(Some(slice))
+    }
+}
@@ -0,0 +1,76 @@
+use crate::datum::DataType;
+use crate::execution::{ExecutionError, Executor};
+use crate::storage::BufferPoolManagerRef;
+use crate::table::{Schema, SchemaRef, Slice};
+use log::info;
+use std::rc::Rc;
+
+pub struct WalExecutor {
+    enabled: bool,
+    bpm: BufferPoolManagerRef,
+    executed: bool,
+}
+
+impl WalExecutor {
+    pub fn new(enabled: bool, bpm: BufferPoolManagerRef) -> Self {
+        Self {
+            enabled,
+            bpm,
+            executed: false,
+        }
+    }
+}
+
+pub struct CheckpointExecutor {
+    bpm: BufferPoolManagerRef,
+    executed: bool,
+}
+
+impl CheckpointExecutor {
+    pub fn new(bpm: BufferPoolManagerRef) -> Self {
+        Self {
+            bpm,
+            executed: false,
+        }
+    }
+}
+
+impl Executor for WalExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[(
+            DataType::new_as_varchar(false),
+            "wal".to_string(),
+        )]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        self.executed = true;
+        self.bpm.borrow_mut().set_wal(self.enabled)?;
+        let state = if self.enabled { "on" } else { "off" };
+        let slice = Slice::new_as_message(self.bpm.clone(), "wal", state)?;
+        Ok(Some(slice))
+    }
+}
+
+impl Executor for CheckpointExecutor {
+    fn schema(&self) -> SchemaRef {
+        Rc::new(Schema::from_type_and_names(&[(
+            DataType::new_as_int(false),
+            "Truncated".to_string(),
+        )]))
+    }
+    fn execute(&mut self) -> Result<Option<Slice>, ExecutionError> {
+        if self.executed {
+            return Ok(None);
+        }
+        self.executed = true;
+        let cnt = self.bpm.borrow().wal_num_records();
+        // the output is flushed as well, so nothing is left for the log
+        let slice = Slice::new_as_count(self.bpm.clone(), "Truncated", cnt)?;
+        let flushed = self.bpm.borrow_mut().checkpoint()?;
+        info!("checkpoint, {} pages flushed", flushed);
+        Ok(Some(slice))
+    }
+}
@@ -1,11 +1,16 @@
 use crate::catalog::{CatalogError, CatalogManagerRef};
 use crate::datum::Datum;
+use crate::db::SettingsRef;
+use crate::error::ErrorCode;
+use crate::expr::ExprError;
 use crate::index::{BPTIndex, IndexError};
 use crate::planner::Plan;
-use crate::storage::BufferPoolManagerRef;
+use crate::storage::{BufferPoolManagerRef, StorageError};
 use crate::table::{SchemaError, Table, TableError};
 use itertools::Itertools;
 use log::info;
+use std::cell::Cell;
+use std::rc::Rc;
 use thiserror::Error;
 
 mod executor;
@@ -15,6 +20,9 @@ pub use executor::*;
 pub struct Engine {
     bpm: BufferPoolManagerRef,
     catalog: CatalogManagerRef,
+    /// whether inserts and deletes keep the references of foreign keys
+    foreign_keys: Rc<Cell<bool>>,
+    settings: SettingsRef,
 }
 
 impl Engine {
@@ -43,6 +51,15 @@ impl Engine {
                 plan.table_name,
                 plan.schema,
             ))),
+            Plan::CreateTableAs(plan) => {
+                let child = self.build(*plan.child)?;
+                Ok(ExecutorImpl::CreateTableAs(CreateTableAsExecutor::new(
+                    self.bpm.clone(),
+                    self.catalog.clone(),
+                    plan.table_name,
+                    child,
+                )))
+            }
             Plan::Values(plan) => Ok(ExecutorImpl::Values(ValuesExecutor::new(
                 plan.values,
                 plan.schema,
@@ -50,12 +67,19 @@ impl Engine {
             ))),
             Plan::Update(plan) => {
                 let table = self.catalog.borrow().find_table(&plan.table_name)?;
+                let indexes = self
+                    .catalog
+                    .borrow()
+                    .find_indexes_by_table(&plan.table_name)?;
                 let child = self.build(*plan.child)?;
                 Ok(ExecutorImpl::Update(UpdateExecutor::new(
                     plan.idx_with_values,
-                    table.schema.clone(),
+                    table,
+                    indexes,
                     self.bpm.clone(),
                     child,
+                    plan.limit,
+                    plan.checks,
                 )))
             }
             Plan::Insert(plan) => {
@@ -70,6 +94,9 @@ impl Engine {
                     table,
                     indexes,
                     Box::new(child),
+                    self.foreign_keys.get(),
+                    plan.returning,
+                    plan.checks,
                     self.bpm.clone(),
                 )))
             }
@@ -93,6 +120,7 @@ impl Engine {
                 let child = self.build(*plan.child)?;
                 Ok(ExecutorImpl::Project(ProjectExecutor::new(
                     plan.exprs,
+                    plan.aliases,
                     Box::new(child),
                     self.bpm.clone(),
                 )))
@@ -105,6 +133,11 @@ impl Engine {
                     plan.exprs,
                 )))
             }
+            // the child is built but never executed, only its schema is needed
+            Plan::Empty(plan) => {
+                let child = self.build(*plan.child)?;
+                Ok(ExecutorImpl::Empty(EmptyExecutor::new(child.schema())))
+            }
             Plan::AddIndex(plan) => Ok(ExecutorImpl::AddIndex(AddIndexExecutor::new(
                 self.bpm.clone(),
                 self.catalog.clone(),
@@ -123,6 +156,13 @@ impl Engine {
                 plan.table_name,
                 plan.unique_set,
             ))),
+            Plan::RenameColumn(plan) => Ok(ExecutorImpl::RenameColumn(RenameColumnExecutor::new(
+                self.bpm.clone(),
+                self.catalog.clone(),
+                plan.table_name,
+                plan.column_name,
+                plan.new_column_name,
+            ))),
             Plan::AddForeign(plan) => Ok(ExecutorImpl::AddForeign(AddForeignExecutor::new(
                 self.bpm.clone(),
                 self.catalog.clone(),
@@ -134,20 +174,37 @@ impl Engine {
             Plan::IndexScan(plan) => {
                 let table = Table::open(plan.table_page_id, self.bpm.clone());
                 let index =
-                    BPTIndex::open(self.bpm.clone(), plan.index_page_id, table.schema.as_ref());
-                let begin_datums = plan.begin_datums.unwrap_or_else(|| index.first_key());
-                let end_datums = plan.end_datums.unwrap_or_else(|| index.last_key());
+                    BPTIndex::open(self.bpm.clone(), plan.index_page_id, table.schema.as_ref())?;
                 Ok(ExecutorImpl::IndexScan(IndexScanExecutor::new(
                     table,
                     index,
-                    begin_datums,
-                    end_datums,
+                    plan.begin_datums,
+                    plan.end_datums,
+                    self.bpm.clone(),
+                    plan.with_record_id,
+                )))
+            }
+            Plan::IndexSkipScan(plan) => {
+                let table = Table::open(plan.table_page_id, self.bpm.clone());
+                let index =
+                    BPTIndex::open(self.bpm.clone(), plan.index_page_id, table.schema.as_ref())?;
+                Ok(ExecutorImpl::IndexSkipScan(IndexSkipScanExecutor::new(
+                    table,
+                    index,
+                    plan.begin_datums,
+                    plan.end_datums,
                     self.bpm.clone(),
                     plan.with_record_id,
                 )))
             }
+            Plan::RowIdFetch(plan) => Ok(ExecutorImpl::RowIdFetch(RowIdFetchExecutor::new(
+                Table::open(plan.table_page_id, self.bpm.clone()),
+                plan.record_id,
+                self.bpm.clone(),
+            ))),
             Plan::DropTable(plan) => Ok(ExecutorImpl::DropTable(DropTableExecutor::new(
                 plan.table_name,
+                plan.cascade,
                 self.catalog.clone(),
                 self.bpm.clone(),
             ))),
@@ -165,12 +222,14 @@ impl Engine {
                     .map(|page_id| {
                         BPTIndex::open(self.bpm.clone(), *page_id, table.schema.as_ref())
                     })
-                    .collect_vec();
+                    .collect::<Result<Vec<_>, _>>()?;
                 Ok(ExecutorImpl::Delete(DeleteExecutor::new(
                     Box::new(child),
                     indexes,
                     table,
+                    self.foreign_keys.get(),
                     self.bpm.clone(),
+                    plan.limit,
                 )))
             }
             Plan::NestedLoopJoin(plan) => {
@@ -183,17 +242,30 @@ impl Engine {
                     self.bpm.clone(),
                     children,
                     plan.schema,
+                    plan.exprs,
                 )))
             }
+            Plan::LoadFromFile(plan) if plan.binary => Ok(ExecutorImpl::LoadBinary(
+                LoadBinaryExecutor::new(plan.schema, plan.file_name, self.bpm.clone())?,
+            )),
             Plan::LoadFromFile(plan) => Ok(ExecutorImpl::LoadFromFile(LoadFromFileExecutor::new(
                 plan.schema.clone(),
                 plan.file_name,
+                plan.rejects_file_name,
                 self.bpm.clone(),
             ))),
+            Plan::LoadSummary(plan) => match self.build(*plan.child)? {
+                ExecutorImpl::Insert(insert) => Ok(ExecutorImpl::LoadSummary(
+                    LoadSummaryExecutor::new(insert, self.bpm.clone()),
+                )),
+                _ => unreachable!(),
+            },
             Plan::Agg(plan) => {
                 let child = self.build(*plan.child)?;
                 Ok(ExecutorImpl::Agg(AggExecutor::new(
                     plan.exprs_with_action,
+                    plan.aliases,
+                    plan.distincts,
                     plan.group_by_expr,
                     child,
                     self.bpm.clone(),
@@ -216,6 +288,149 @@ impl Engine {
                 self.bpm.clone(),
                 self.catalog.clone(),
             ))),
+            Plan::Exists(plan) => {
+                let child = self.build(*plan.child)?;
+                let subquery = self.build(*plan.subquery)?;
+                Ok(ExecutorImpl::Exists(ExistsExecutor::new(
+                    self.bpm.clone(),
+                    Box::new(child),
+                    Box::new(subquery),
+                    plan.exprs,
+                    plan.schema,
+                )))
+            }
+            Plan::DistinctOn(plan) => {
+                let child = self.build(*plan.child)?;
+                Ok(ExecutorImpl::DistinctOn(DistinctOnExecutor::new(
+                    self.bpm.clone(),
+                    Box::new(child),
+                    plan.exprs,
+                )))
+            }
+            Plan::Sort(plan) => {
+                let child = self.build(*plan.child)?;
+                Ok(ExecutorImpl::Sort(SortExecutor::new(
+                    Box::new(child),
+                    plan.exprs_with_desc,
+                    self.bpm.clone(),
+                    SORT_BUFFER_SIZE,
+                )))
+            }
+            Plan::Limit(plan) => {
+                let child = self.build(*plan.child)?;
+                Ok(ExecutorImpl::Limit(LimitExecutor::new(
+                    self.bpm.clone(),
+                    Box::new(child),
+                    plan.limit,
+                )))
+            }
+            Plan::TopN(plan) => {
+                let child = self.build(*plan.child)?;
+                Ok(ExecutorImpl::TopN(TopNExecutor::new(
+                    Box::new(child),
+                    plan.exprs_with_desc,
+                    self.bpm.clone(),
+                    plan.limit,
+                )))
+            }
+            Plan::SetOp(plan) => {
+                let lhs = self.build(*plan.lhs)?;
+                let rhs = self.build(*plan.rhs)?;
+                Ok(ExecutorImpl::SetOp(SetOpExecutor::new(
+                    lhs,
+                    rhs,
+                    plan.op,
+                    self.bpm.clone(),
+                )?))
+            }
+            // the columns are only renamed by the alias, the tuples pass through as they are
+            Plan::Derived(plan) => self.build(*plan.child),
+            Plan::TruncateTable(plan) => {
+                Ok(ExecutorImpl::TruncateTable(TruncateTableExecutor::new(
+                    plan.table_name,
+                    plan.cascade,
+                    self.catalog.clone(),
+                    self.bpm.clone(),
+                )))
+            }
+            Plan::IndexStats(plan) => {
+                let table = Table::open(plan.table_page_id, self.bpm.clone());
+                let index =
+                    BPTIndex::open(self.bpm.clone(), plan.index_page_id, table.schema.as_ref())?;
+                Ok(ExecutorImpl::IndexStats(IndexStatsExecutor::new(
+                    index,
+                    self.bpm.clone(),
+                )))
+            }
+            Plan::TableSize(plan) => {
+                let table = Table::open(plan.table_page_id, self.bpm.clone());
+                let indexes = plan
+                    .index_page_ids
+                    .into_iter()
+                    .map(|page_id| BPTIndex::open(self.bpm.clone(), page_id, table.schema.as_ref()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(ExecutorImpl::TableSize(TableSizeExecutor::new(
+                    table,
+                    indexes,
+                    self.bpm.clone(),
+                )))
+            }
+            Plan::Analyze(plan) => {
+                let table = self.catalog.borrow().find_table(&plan.table_name)?;
+                Ok(ExecutorImpl::Analyze(AnalyzeExecutor::new(
+                    table,
+                    self.bpm.clone(),
+                )))
+            }
+            Plan::Vacuum(plan) => Ok(ExecutorImpl::Vacuum(VacuumExecutor::new(
+                plan.table_name,
+                plan.analyze,
+                self.catalog.clone(),
+                self.bpm.clone(),
+            ))),
+            Plan::ShowWarnings(warnings) => Ok(ExecutorImpl::ShowWarnings(
+                ShowWarningsExecutor::new(warnings, self.bpm.clone()),
+            )),
+            Plan::ShowHistory(records) => Ok(ExecutorImpl::ShowHistory(ShowHistoryExecutor::new(
+                records,
+                self.bpm.clone(),
+            ))),
+            Plan::Explain(lines) => Ok(ExecutorImpl::Explain(ExplainExecutor::new(
+                lines,
+                self.bpm.clone(),
+            ))),
+            Plan::CreateView(plan) => Ok(ExecutorImpl::CreateView(CreateViewExecutor::new(
+                plan.view_name,
+                plan.definition,
+                self.catalog.clone(),
+                self.bpm.clone(),
+            ))),
+            Plan::DropView(plan) => Ok(ExecutorImpl::DropView(DropViewExecutor::new(
+                plan.view_name,
+                self.catalog.clone(),
+                self.bpm.clone(),
+            ))),
+            Plan::ForeignKeys(enabled) => Ok(ExecutorImpl::ForeignKeys(ForeignKeysExecutor::new(
+                enabled,
+                self.foreign_keys.clone(),
+                self.catalog.clone(),
+                self.bpm.clone(),
+            ))),
+            Plan::Wal(enabled) => Ok(ExecutorImpl::Wal(WalExecutor::new(
+                enabled,
+                self.bpm.clone(),
+            ))),
+            Plan::Checkpoint => Ok(ExecutorImpl::Checkpoint(CheckpointExecutor::new(
+                self.bpm.clone(),
+            ))),
+            Plan::Set(setting) => Ok(ExecutorImpl::Set(SetExecutor::new(
+                setting,
+                self.settings.clone(),
+                self.bpm.clone(),
+            ))),
+            Plan::ListFreePages => Ok(ExecutorImpl::FreePages(FreePagesExecutor::new(
+                self.bpm.clone(),
+            ))),
             Plan::ShowTables => {
                 if self.catalog.borrow().current_database() == None {
                     return Err(ExecutionError::Catalog(CatalogError::NotUsingDatabase));
@@ -227,8 +442,17 @@ impl Engine {
             }
         }
     }
-    pub fn new(catalog: CatalogManagerRef, bpm: BufferPoolManagerRef) -> Self {
-        Self { bpm, catalog }
+    pub fn new(
+        catalog: CatalogManagerRef,
+        bpm: BufferPoolManagerRef,
+        settings: SettingsRef,
+    ) -> Self {
+        Self {
+            bpm,
+            catalog,
+            foreign_keys: Rc::new(Cell::new(true)),
+            settings,
+        }
     }
     pub fn execute(&mut self, plan: Plan) -> Result<Table, ExecutionError> {
         let mut executor = self.build(plan)?;
@@ -236,6 +460,7 @@ impl Engine {
         while let Some(slice) = executor.execute()? {
             slices.push(slice);
         }
+        self.bpm.borrow_mut().commit()?;
         let schema = executor.schema();
         Ok(Table::from_slice(slices, schema, self.bpm.clone()))
     }
@@ -251,6 +476,38 @@ pub enum ExecutionError {
     Schema(#[from] SchemaError),
     #[error("IndexError: {0}")]
     Index(#[from] IndexError),
+    #[error("StorageError: {0}")]
+    Storage(#[from] StorageError),
+    #[error("ExprError: {0}")]
+    Expr(#[from] ExprError),
     #[error("Insert Duplicated Key: {0:?}")]
     InsertDuplicatedKey(Vec<Datum>),
+    #[error("Update Duplicated Key: {0:?}")]
+    UpdateDuplicatedKey(Vec<Datum>),
+    #[error("Table {0} Is Referenced By {1}")]
+    TableReferenced(String, String),
+    #[error("Foreign Key Of Table {0} Refers To Missing Key {1:?}")]
+    ForeignKeyViolation(String, Vec<Datum>),
+    #[error("Sum {0} Is Out Of The Range Of Int")]
+    SumOverflow(i64),
+    #[error("Check Constraint {0} Is Violated")]
+    CheckViolation(String),
+}
+
+impl ExecutionError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Catalog(err) => err.code(),
+            Self::Table(err) => err.code(),
+            Self::Schema(err) => err.code(),
+            Self::Index(err) => err.code(),
+            Self::Storage(err) => err.code(),
+            Self::Expr(err) => err.code(),
+            Self::InsertDuplicatedKey(_) | Self::UpdateDuplicatedKey(_) => ErrorCode::DuplicateKey,
+            Self::SumOverflow(_) => ErrorCode::InvalidArgument,
+            Self::TableReferenced(..) | Self::ForeignKeyViolation(..) | Self::CheckViolation(_) => {
+                ErrorCode::ConstraintViolation
+            }
+        }
+    }
 }
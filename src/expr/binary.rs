@@ -1,7 +1,9 @@
 use crate::datum::{DataType, Datum};
-use crate::expr::{Expr, ExprImpl};
+use crate::expr::{Expr, ExprError, ExprImpl, RowErrors};
 use crate::table::Slice;
 use itertools::Itertools;
+use ordered_float::NotNan;
+use std::cmp::Ordering;
 use std::fmt;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -11,45 +13,88 @@ pub enum BinaryOp {
     GreaterThan,
     LessThanOrEqual,
     GreaterThanOrEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+fn as_float(d: &Datum) -> Option<f32> {
+    match d {
+        Datum::Int(v) => v.map(|v| v as f32),
+        Datum::Float(v) => v.map(|v| v.into_inner()),
+        _ => None,
+    }
+}
+
+/// arithmetic over numbers, int is promoted to float when mixed, overflow yields null
+fn arith(
+    l: &Datum,
+    r: &Datum,
+    int_op: fn(i32, i32) -> Option<i32>,
+    float_op: fn(f32, f32) -> f32,
+) -> Datum {
+    match (l, r) {
+        (Datum::Int(l), Datum::Int(r)) => match (l, r) {
+            (Some(l), Some(r)) => Datum::Int(int_op(*l, *r)),
+            _ => Datum::Int(None),
+        },
+        _ => match (as_float(l), as_float(r)) {
+            (Some(l), Some(r)) => Datum::Float(NotNan::new(float_op(l, r)).ok()),
+            _ => Datum::Float(None),
+        },
+    }
+}
+
+/// comparison in three-valued logic, null compared with anything is null
+fn compare(l: &Datum, r: &Datum, pred: fn(Ordering) -> bool) -> Datum {
+    match (l, r) {
+        _ if l.is_null() || r.is_null() => Datum::Bool(None),
+        // an int compared with a float is promoted to float
+        (Datum::Int(_), Datum::Float(_)) | (Datum::Float(_), Datum::Int(_)) => {
+            Datum::Bool(as_float(l).partial_cmp(&as_float(r)).map(pred))
+        }
+        // a string compared with a value of another type is read as that type, it compares
+        // as null if it is not one
+        (Datum::VarChar(_), _) if !matches!(r, Datum::VarChar(_)) => {
+            match l.cast_to(&r.data_type()) {
+                Some(l) => compare(&l, r, pred),
+                None => Datum::Bool(None),
+            }
+        }
+        (_, Datum::VarChar(_)) if !matches!(l, Datum::VarChar(_)) => {
+            match r.cast_to(&l.data_type()) {
+                Some(r) => compare(l, &r, pred),
+                None => Datum::Bool(None),
+            }
+        }
+        _ => Datum::Bool(Some(pred(l.cmp(r)))),
+    }
 }
 
 impl BinaryOp {
+    pub fn is_arithmetic(&self) -> bool {
+        matches!(
+            self,
+            Self::Add | Self::Subtract | Self::Multiply | Self::Divide
+        )
+    }
     pub fn gen_func(&self) -> fn(&Datum, &Datum) -> Datum {
         match self {
-            Self::Equal => |l, r| {
-                if l == r {
-                    Datum::Bool(Some(true))
-                } else {
-                    Datum::Bool(Some(false))
-                }
-            },
-            Self::LessThan => |l, r| {
-                if l < r {
-                    Datum::Bool(Some(true))
-                } else {
-                    Datum::Bool(Some(false))
-                }
-            },
-            Self::LessThanOrEqual => |l, r| {
-                if l <= r {
-                    Datum::Bool(Some(true))
-                } else {
-                    Datum::Bool(Some(false))
-                }
-            },
-            Self::GreaterThan => |l, r| {
-                if l > r {
-                    Datum::Bool(Some(true))
-                } else {
-                    Datum::Bool(Some(false))
-                }
-            },
-            Self::GreaterThanOrEqual => |l, r| {
-                if l >= r {
-                    Datum::Bool(Some(true))
-                } else {
-                    Datum::Bool(Some(false))
-                }
+            Self::Equal => |l, r| compare(l, r, Ordering::is_eq),
+            Self::LessThan => |l, r| compare(l, r, Ordering::is_lt),
+            Self::LessThanOrEqual => |l, r| compare(l, r, Ordering::is_le),
+            Self::GreaterThan => |l, r| compare(l, r, Ordering::is_gt),
+            Self::GreaterThanOrEqual => |l, r| compare(l, r, Ordering::is_ge),
+            Self::Add => |l, r| arith(l, r, i32::checked_add, |l, r| l + r),
+            Self::Subtract => |l, r| arith(l, r, i32::checked_sub, |l, r| l - r),
+            Self::Multiply => |l, r| arith(l, r, i32::checked_mul, |l, r| l * r),
+            // the quotient of a zero divisor is null, unless it is reported as an error
+            Self::Divide => |l, r| {
+                arith(l, r, i32::checked_div, |l, r| match r == 0.0 {
+                    true => f32::NAN,
+                    false => l / r,
+                })
             },
         }
     }
@@ -65,6 +110,10 @@ impl fmt::Display for BinaryExpr {
             BinaryOp::LessThanOrEqual => write!(f, "{} <= {}", lhs, rhs),
             BinaryOp::GreaterThan => write!(f, "{} > {}", lhs, rhs),
             BinaryOp::GreaterThanOrEqual => write!(f, "{} >= {}", lhs, rhs),
+            BinaryOp::Add => write!(f, "{} + {}", lhs, rhs),
+            BinaryOp::Subtract => write!(f, "{} - {}", lhs, rhs),
+            BinaryOp::Multiply => write!(f, "{} * {}", lhs, rhs),
+            BinaryOp::Divide => write!(f, "{} / {}", lhs, rhs),
         }
     }
 }
@@ -75,39 +124,60 @@ pub struct BinaryExpr {
     rhs: Box<ExprImpl>,
     op: BinaryOp,
     desc: String,
+    /// whether a zero divisor is an error rather than a null quotient
+    strict_division: bool,
 }
 
 impl BinaryExpr {
-    pub fn new(lhs: Box<ExprImpl>, rhs: Box<ExprImpl>, op: BinaryOp) -> Self {
+    pub fn new(
+        lhs: Box<ExprImpl>,
+        rhs: Box<ExprImpl>,
+        op: BinaryOp,
+        strict_division: bool,
+    ) -> Self {
         Self {
             lhs,
             rhs,
             op,
             desc: "".to_string(),
+            strict_division,
         }
     }
+    pub fn children(&self) -> Vec<&ExprImpl> {
+        vec![self.lhs.as_ref(), self.rhs.as_ref()]
+    }
+    pub fn op(&self) -> &BinaryOp {
+        &self.op
+    }
+    pub fn children_mut(&mut self) -> Vec<&mut ExprImpl> {
+        vec![self.lhs.as_mut(), self.rhs.as_mut()]
+    }
+    /// the range of the expr the comparison bounds, a null bounds nothing as no value
+    /// compares true with it
     pub fn get_bound(&self, expr: &ExprImpl) -> (Option<Datum>, Option<Datum>) {
-        if expr == self.lhs.as_ref() {
-            let datum = if let ExprImpl::Constant(c) = self.rhs.as_ref() {
-                c.get_value()
-            } else {
-                return (None, None);
+        if self.op.is_arithmetic() {
+            (None, None)
+        } else if expr == self.lhs.as_ref() {
+            let datum = match self.rhs.as_ref() {
+                ExprImpl::Constant(c) if !c.get_value().is_null() => c.get_value(),
+                _ => return (None, None),
             };
             match self.op {
                 BinaryOp::Equal => (Some(datum.clone()), Some(datum)),
                 BinaryOp::LessThan | BinaryOp::LessThanOrEqual => (None, Some(datum)),
                 BinaryOp::GreaterThan | BinaryOp::GreaterThanOrEqual => (Some(datum), None),
+                _ => unreachable!(),
             }
         } else if expr == self.rhs.as_ref() {
-            let datum = if let ExprImpl::Constant(c) = self.lhs.as_ref() {
-                c.get_value()
-            } else {
-                return (None, None);
+            let datum = match self.lhs.as_ref() {
+                ExprImpl::Constant(c) if !c.get_value().is_null() => c.get_value(),
+                _ => return (None, None),
             };
             match self.op {
                 BinaryOp::Equal => (Some(datum.clone()), Some(datum)),
                 BinaryOp::LessThan | BinaryOp::LessThanOrEqual => (Some(datum), None),
                 BinaryOp::GreaterThan | BinaryOp::GreaterThanOrEqual => (None, Some(datum)),
+                _ => unreachable!(),
             }
         } else {
             (None, None)
@@ -116,9 +186,17 @@ impl BinaryExpr {
 }
 
 impl Expr for BinaryExpr {
-    fn eval(&self, slice: Option<&Slice>) -> Vec<Datum> {
-        let datums_lhs = self.lhs.eval(slice);
-        let datums_rhs = self.rhs.eval(slice);
+    fn eval(&self, slice: Option<&Slice>, row_errors: &mut RowErrors) -> Vec<Datum> {
+        let datums_lhs = self.lhs.eval_rows(slice, row_errors);
+        let datums_rhs = self.rhs.eval_rows(slice, row_errors);
+        if self.op == BinaryOp::Divide && self.strict_division {
+            row_errors.extend(
+                datums_rhs
+                    .iter()
+                    .positions(|d| d.is_zero())
+                    .map(|idx| (idx, ExprError::DivisionByZero)),
+            );
+        }
         let func = self.op.gen_func();
         let datums = datums_lhs
             .iter()
@@ -133,7 +211,13 @@ impl Expr for BinaryExpr {
             | BinaryOp::LessThan
             | BinaryOp::GreaterThan
             | BinaryOp::LessThanOrEqual
-            | BinaryOp::GreaterThanOrEqual => DataType::new_as_bool(false),
+            | BinaryOp::GreaterThanOrEqual => DataType::new_as_bool(true),
+            BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide => {
+                match (self.lhs.return_type(), self.rhs.return_type()) {
+                    (DataType::Int(_), DataType::Int(_)) => DataType::new_as_int(true),
+                    _ => DataType::new_as_float(true),
+                }
+            }
         }
     }
 }
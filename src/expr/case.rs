@@ -0,0 +1,128 @@
+use crate::datum::{DataType, Datum};
+use crate::expr::{Expr, ExprError, ExprImpl, RowErrors};
+use crate::table::Slice;
+use itertools::Itertools;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CaseExpr {
+    /// conditions with the results, the first true condition wins
+    branches: Vec<(ExprImpl, ExprImpl)>,
+    /// the result if no condition is true, null if absent
+    else_expr: Option<Box<ExprImpl>>,
+    return_type: DataType,
+}
+
+impl fmt::Display for CaseExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "case")?;
+        for (condition, result) in &self.branches {
+            write!(f, " when {} then {}", condition, result)?;
+        }
+        if let Some(else_expr) = &self.else_expr {
+            write!(f, " else {}", else_expr)?;
+        }
+        write!(f, " end")
+    }
+}
+
+impl CaseExpr {
+    pub fn new(
+        branches: Vec<(ExprImpl, ExprImpl)>,
+        else_expr: Option<Box<ExprImpl>>,
+    ) -> Result<Self, ExprError> {
+        if branches
+            .iter()
+            .any(|(condition, _)| !matches!(condition.return_type(), DataType::Bool(_)))
+        {
+            return Err(ExprError::NotMatch);
+        }
+        let mut types = branches
+            .iter()
+            .map(|(_, result)| result.return_type())
+            .chain(else_expr.iter().map(|e| e.return_type()));
+        let mut return_type = types.next().unwrap();
+        for data_type in types {
+            return_type = return_type
+                .unify(&data_type)
+                .ok_or(ExprError::IncompatibleTypes(return_type, data_type))?;
+        }
+        // without else, the result is null if no condition is true
+        if else_expr.is_none() {
+            return_type = return_type.with_nullable(true);
+        }
+        Ok(Self {
+            branches,
+            else_expr,
+            return_type,
+        })
+    }
+    pub fn children_mut(&mut self) -> Vec<&mut ExprImpl> {
+        self.branches
+            .iter_mut()
+            .flat_map(|(condition, result)| vec![condition, result])
+            .chain(self.else_expr.iter_mut().map(|e| e.as_mut()))
+            .collect()
+    }
+}
+
+impl Expr for CaseExpr {
+    fn eval(&self, slice: Option<&Slice>, row_errors: &mut RowErrors) -> Vec<Datum> {
+        let len = slice.map_or(1, |slice| slice.count());
+        // the branch each row takes, none for else
+        let mut taken = vec![None; len];
+        for (branch, (condition, _)) in self.branches.iter().enumerate() {
+            let mut condition_row_errors = RowErrors::new();
+            let datums = condition.eval_rows(slice, &mut condition_row_errors);
+            // a condition is only reached by the rows no condition before takes
+            row_errors.extend(
+                condition_row_errors
+                    .into_iter()
+                    .filter(|(idx, _)| taken[*idx].is_none()),
+            );
+            for (taken, datum) in taken.iter_mut().zip(datums) {
+                // a null condition is not true
+                if taken.is_none() && datum == Datum::Bool(Some(true)) {
+                    *taken = Some(branch);
+                }
+            }
+        }
+        // only the result a row takes counts its errors
+        let mut eval_result = |result: &ExprImpl, branch: Option<usize>| {
+            let mut result_row_errors = RowErrors::new();
+            let datums = result.eval_rows(slice, &mut result_row_errors);
+            row_errors.extend(
+                result_row_errors
+                    .into_iter()
+                    .filter(|(idx, _)| taken[*idx] == branch),
+            );
+            datums
+        };
+        let results = self
+            .branches
+            .iter()
+            .enumerate()
+            .map(|(branch, (_, result))| eval_result(result, Some(branch)))
+            .collect_vec();
+        let else_results = self.else_expr.as_ref().map(|e| eval_result(e, None));
+        (0..len)
+            .map(|idx| {
+                let datum = match taken[idx] {
+                    Some(branch) => results[branch][idx].clone(),
+                    None => else_results
+                        .as_ref()
+                        .map_or_else(|| Datum::null_of(&self.return_type), |e| e[idx].clone()),
+                };
+                match (datum, self.return_type) {
+                    (Datum::Int(v), DataType::Float(_)) => {
+                        v.map_or(Datum::Float(None), |v| Datum::from(v as f32))
+                    }
+                    (datum, _) => datum,
+                }
+            })
+            .collect_vec()
+    }
+    fn return_type(&self) -> DataType {
+        self.return_type
+    }
+}
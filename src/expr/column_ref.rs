@@ -1,5 +1,5 @@
 use crate::datum::{DataType, Datum};
-use crate::expr::Expr;
+use crate::expr::{Expr, RowErrors};
 use crate::table::Slice;
 use itertools::Itertools;
 
@@ -27,7 +27,7 @@ impl ColumnRefExpr {
 }
 
 impl Expr for ColumnRefExpr {
-    fn eval(&self, slice: Option<&Slice>) -> Vec<Datum> {
+    fn eval(&self, slice: Option<&Slice>, _: &mut RowErrors) -> Vec<Datum> {
         if let Some(slice) = slice {
             slice
                 .tuple_iter()
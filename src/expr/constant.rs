@@ -1,5 +1,5 @@
 use crate::datum::{DataType, Datum};
-use crate::expr::Expr;
+use crate::expr::{Expr, RowErrors};
 use crate::table::Slice;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -18,7 +18,7 @@ impl ConstantExpr {
 }
 
 impl Expr for ConstantExpr {
-    fn eval(&self, slice: Option<&Slice>) -> Vec<Datum> {
+    fn eval(&self, slice: Option<&Slice>, _: &mut RowErrors) -> Vec<Datum> {
         if let Some(slice) = slice {
             vec![self.value.clone(); slice.count()]
         } else {
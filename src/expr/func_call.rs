@@ -0,0 +1,377 @@
+use crate::datum::{DataType, Datum};
+use crate::expr::{Expr, ExprError, ExprImpl, RowErrors};
+use crate::storage::PAGE_SIZE;
+use crate::table::Slice;
+use chrono::{Datelike, Duration, NaiveDate};
+use itertools::Itertools;
+use std::fmt;
+use std::mem::discriminant;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Func {
+    DateAdd,
+    Trim,
+    LTrim,
+    RTrim,
+    Replace,
+    Instr,
+    Length,
+    OctetLength,
+    Year,
+    Month,
+    Day,
+    DateTrunc,
+    Repeat,
+    Sign,
+    Mod,
+    ToChar,
+}
+
+/// the longest string repeat makes, in bytes, it must fit in a slice with the rest of its tuple
+const MAX_REPEAT_LEN: usize = PAGE_SIZE / 4;
+
+/// whether repeating the string n times makes one longer than allowed
+fn repeat_too_long(value: &str, n: i32) -> bool {
+    value
+        .len()
+        .checked_mul(n.max(0) as usize)
+        .map_or(true, |len| len > MAX_REPEAT_LEN)
+}
+
+/// the units date_trunc truncates to
+const DATE_TRUNC_UNITS: [&str; 3] = ["year", "month", "day"];
+
+/// the first day of the unit the date falls in, none if the unit is unknown
+fn date_trunc(unit: &str, date: NaiveDate) -> Option<NaiveDate> {
+    match unit.to_lowercase().as_str() {
+        "year" => NaiveDate::from_ymd_opt(date.year(), 1, 1),
+        "month" => NaiveDate::from_ymd_opt(date.year(), date.month(), 1),
+        "day" => Some(date),
+        _ => None,
+    }
+}
+
+/// the tokens of a to_char format with the chrono specifiers they stand for, longest first
+const TO_CHAR_TOKENS: [(&str, &str); 4] =
+    [("YYYY", "%Y"), ("YY", "%y"), ("MM", "%m"), ("DD", "%d")];
+
+/// the chrono format of a to_char one, none if it has an unknown token
+fn to_chrono_format(format: &str) -> Option<String> {
+    let mut chrono_format = String::new();
+    let mut rest = format;
+    while let Some(c) = rest.chars().next() {
+        if let Some((token, spec)) = TO_CHAR_TOKENS.iter().find(|(token, _)| {
+            rest.get(..token.len())
+                .map_or(false, |prefix| prefix.eq_ignore_ascii_case(token))
+        }) {
+            chrono_format.push_str(spec);
+            rest = &rest[token.len()..];
+            continue;
+        }
+        // other letters are taken as misspelled tokens, the rest is kept as is
+        match c {
+            c if c.is_alphabetic() => return None,
+            '%' => chrono_format.push_str("%%"),
+            c => chrono_format.push(c),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    Some(chrono_format)
+}
+
+impl Func {
+    pub fn from_name(name: &str) -> Result<Self, ExprError> {
+        match name.to_lowercase().as_str() {
+            "date_add" => Ok(Self::DateAdd),
+            "trim" => Ok(Self::Trim),
+            "ltrim" => Ok(Self::LTrim),
+            "rtrim" => Ok(Self::RTrim),
+            "replace" => Ok(Self::Replace),
+            "instr" => Ok(Self::Instr),
+            "length" => Ok(Self::Length),
+            "octet_length" => Ok(Self::OctetLength),
+            "year" => Ok(Self::Year),
+            "month" => Ok(Self::Month),
+            "day" => Ok(Self::Day),
+            "date_trunc" => Ok(Self::DateTrunc),
+            "repeat" => Ok(Self::Repeat),
+            "sign" => Ok(Self::Sign),
+            "mod" => Ok(Self::Mod),
+            "to_char" => Ok(Self::ToChar),
+            _ => Err(ExprError::UnknownFunction(name.to_owned())),
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::DateAdd => "date_add",
+            Self::Trim => "trim",
+            Self::LTrim => "ltrim",
+            Self::RTrim => "rtrim",
+            Self::Replace => "replace",
+            Self::Instr => "instr",
+            Self::Length => "length",
+            Self::OctetLength => "octet_length",
+            Self::Year => "year",
+            Self::Month => "month",
+            Self::Day => "day",
+            Self::DateTrunc => "date_trunc",
+            Self::Repeat => "repeat",
+            Self::Sign => "sign",
+            Self::Mod => "mod",
+            Self::ToChar => "to_char",
+        }
+    }
+    /// expected types of the arguments, also used as the hints of constant arguments
+    pub fn arg_types(&self) -> Vec<DataType> {
+        match self {
+            Self::DateAdd => vec![DataType::new_as_date(true), DataType::new_as_int(true)],
+            Self::Trim | Self::LTrim | Self::RTrim => {
+                vec![
+                    DataType::new_as_varchar(true),
+                    DataType::new_as_varchar(true),
+                ]
+            }
+            Self::Replace => vec![DataType::new_as_varchar(true); 3],
+            Self::Instr => vec![DataType::new_as_varchar(true); 2],
+            Self::Length | Self::OctetLength => vec![DataType::new_as_varchar(true)],
+            Self::Year | Self::Month | Self::Day => vec![DataType::new_as_date(true)],
+            Self::DateTrunc => vec![DataType::new_as_varchar(true), DataType::new_as_date(true)],
+            Self::Repeat => vec![DataType::new_as_varchar(true), DataType::new_as_int(true)],
+            Self::Sign => vec![DataType::new_as_float(true)],
+            Self::Mod => vec![DataType::new_as_float(true); 2],
+            Self::ToChar => vec![DataType::new_as_date(true), DataType::new_as_varchar(true)],
+        }
+    }
+    /// the float arguments of these also take ints, and are left untyped by hints
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Self::Sign | Self::Mod)
+    }
+    /// the trailing arguments after these are optional
+    pub fn num_required_args(&self) -> usize {
+        match self {
+            Self::Trim | Self::LTrim | Self::RTrim => 1,
+            _ => self.arg_types().len(),
+        }
+    }
+    pub fn return_type(&self, arg_types: &[DataType]) -> DataType {
+        match self {
+            Self::DateAdd | Self::DateTrunc => DataType::new_as_date(true),
+            Self::Trim
+            | Self::LTrim
+            | Self::RTrim
+            | Self::Replace
+            | Self::Repeat
+            | Self::ToChar => DataType::new_as_varchar(true),
+            Self::Instr
+            | Self::Length
+            | Self::OctetLength
+            | Self::Year
+            | Self::Month
+            | Self::Day
+            | Self::Sign => DataType::new_as_int(true),
+            // ints stay ints, unless a float is mixed in
+            Self::Mod => match arg_types.iter().any(|ty| matches!(ty, DataType::Float(_))) {
+                true => DataType::new_as_float(true),
+                false => DataType::new_as_int(true),
+            },
+        }
+    }
+    /// trim the chars matched from the sides of the string the func trims
+    fn trim(&self, value: &str, pattern: impl Fn(char) -> bool) -> String {
+        match self {
+            Self::Trim => value.trim_matches(pattern),
+            Self::LTrim => value.trim_start_matches(pattern),
+            Self::RTrim => value.trim_end_matches(pattern),
+            _ => unreachable!(),
+        }
+        .to_owned()
+    }
+    fn call(&self, args: &[Datum]) -> Datum {
+        match self {
+            Self::DateAdd => match (&args[0], &args[1]) {
+                (Datum::Date(Some(date)), Datum::Int(Some(days))) => {
+                    Datum::Date(date.checked_add_signed(Duration::days(*days as i64)))
+                }
+                _ => Datum::Date(None),
+            },
+            Self::Trim | Self::LTrim | Self::RTrim => match (&args[0], args.get(1)) {
+                (Datum::VarChar(Some(value)), None) => {
+                    Datum::VarChar(Some(self.trim(value, char::is_whitespace)))
+                }
+                (Datum::VarChar(Some(value)), Some(Datum::VarChar(Some(chars)))) => {
+                    Datum::VarChar(Some(self.trim(value, |c| chars.contains(c))))
+                }
+                _ => Datum::VarChar(None),
+            },
+            Self::Replace => match (&args[0], &args[1], &args[2]) {
+                (Datum::VarChar(Some(value)), Datum::VarChar(Some(from)), _) if from.is_empty() => {
+                    Datum::VarChar(Some(value.clone()))
+                }
+                (
+                    Datum::VarChar(Some(value)),
+                    Datum::VarChar(Some(from)),
+                    Datum::VarChar(Some(to)),
+                ) => Datum::VarChar(Some(value.replace(from.as_str(), to))),
+                _ => Datum::VarChar(None),
+            },
+            // 1-based position in chars, 0 if not found
+            Self::Instr => match (&args[0], &args[1]) {
+                (Datum::VarChar(Some(value)), Datum::VarChar(Some(sub))) => Datum::Int(Some(
+                    value
+                        .find(sub.as_str())
+                        .map_or(0, |idx| value[..idx].chars().count() as i32 + 1),
+                )),
+                _ => Datum::Int(None),
+            },
+            // chars are unicode scalar values, while octets are the bytes of utf-8 stored
+            Self::Length => match &args[0] {
+                Datum::VarChar(Some(value)) => Datum::Int(Some(value.chars().count() as i32)),
+                _ => Datum::Int(None),
+            },
+            Self::OctetLength => match &args[0] {
+                Datum::VarChar(Some(value)) => Datum::Int(Some(value.len() as i32)),
+                _ => Datum::Int(None),
+            },
+            Self::Year | Self::Month | Self::Day => match &args[0] {
+                Datum::Date(Some(date)) => Datum::Int(Some(match self {
+                    Self::Year => date.year(),
+                    Self::Month => date.month() as i32,
+                    _ => date.day() as i32,
+                })),
+                _ => Datum::Int(None),
+            },
+            Self::DateTrunc => match (&args[0], &args[1]) {
+                (Datum::VarChar(Some(unit)), Datum::Date(Some(date))) => {
+                    Datum::Date(date_trunc(unit, *date))
+                }
+                _ => Datum::Date(None),
+            },
+            // a count below one gives the empty string, a too long result is reported as an error
+            Self::Repeat => match (&args[0], &args[1]) {
+                (Datum::VarChar(Some(value)), Datum::Int(Some(n)))
+                    if !repeat_too_long(value, *n) =>
+                {
+                    Datum::VarChar(Some(value.repeat((*n).max(0) as usize)))
+                }
+                _ => Datum::VarChar(None),
+            },
+            Self::Sign => match &args[0] {
+                Datum::Int(Some(v)) => Datum::Int(Some(v.signum())),
+                Datum::Float(Some(v)) if v.into_inner() == 0.0 => Datum::Int(Some(0)),
+                Datum::Float(Some(v)) => Datum::Int(Some(v.into_inner().signum() as i32)),
+                _ => Datum::Int(None),
+            },
+            // the result takes the sign of the dividend, a zero divisor gives null
+            Self::Mod => match (&args[0], &args[1]) {
+                (Datum::Int(Some(lhs)), Datum::Int(Some(rhs))) => Datum::Int(lhs.checked_rem(*rhs)),
+                (Datum::Int(_), Datum::Int(_)) => Datum::Int(None),
+                (lhs, rhs) => match (
+                    lhs.cast_to(&DataType::new_as_float(true)),
+                    rhs.cast_to(&DataType::new_as_float(true)),
+                ) {
+                    (Some(Datum::Float(Some(lhs))), Some(Datum::Float(Some(rhs))))
+                        if rhs.into_inner() != 0.0 =>
+                    {
+                        Datum::Float((lhs.into_inner() % rhs.into_inner()).try_into().ok())
+                    }
+                    _ => Datum::Float(None),
+                },
+            },
+            Self::ToChar => match (&args[0], &args[1]) {
+                (Datum::Date(Some(date)), Datum::VarChar(Some(format))) => Datum::VarChar(
+                    to_chrono_format(format).map(|format| date.format(&format).to_string()),
+                ),
+                _ => Datum::VarChar(None),
+            },
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FuncCallExpr {
+    func: Func,
+    args: Vec<ExprImpl>,
+    /// whether a zero divisor of mod is an error rather than a null
+    strict_division: bool,
+}
+
+impl fmt::Display for FuncCallExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}({})",
+            self.func.name(),
+            self.args.iter().map(|arg| arg.to_string()).join(", ")
+        )
+    }
+}
+
+impl FuncCallExpr {
+    pub fn new(func: Func, args: Vec<ExprImpl>, strict_division: bool) -> Result<Self, ExprError> {
+        let arg_types = func.arg_types();
+        if args.len() < func.num_required_args()
+            || args.len() > arg_types.len()
+            || args.iter().zip(arg_types.iter()).any(|(arg, ty)| {
+                let arg_type = arg.return_type();
+                let numeric = func.is_numeric() && matches!(arg_type, DataType::Int(_));
+                !numeric && discriminant(&arg_type) != discriminant(ty)
+            })
+        {
+            return Err(ExprError::InvalidArguments(func.name().to_owned()));
+        }
+        // a constant unit is checked here rather than turning every date into null
+        if let (Func::DateTrunc, Some(Datum::VarChar(Some(unit)))) = (func, args[0].as_constant()) {
+            if !DATE_TRUNC_UNITS.contains(&unit.to_lowercase().as_str()) {
+                return Err(ExprError::InvalidArguments(func.name().to_owned()));
+            }
+        }
+        if let (Func::ToChar, Some(Datum::VarChar(Some(format)))) = (func, args[1].as_constant()) {
+            if to_chrono_format(&format).is_none() {
+                return Err(ExprError::InvalidArguments(func.name().to_owned()));
+            }
+        }
+        Ok(Self {
+            func,
+            args,
+            strict_division,
+        })
+    }
+    pub fn children_mut(&mut self) -> Vec<&mut ExprImpl> {
+        self.args.iter_mut().collect()
+    }
+}
+
+impl Expr for FuncCallExpr {
+    fn eval(&self, slice: Option<&Slice>, row_errors: &mut RowErrors) -> Vec<Datum> {
+        let columns = self
+            .args
+            .iter()
+            .map(|arg| arg.eval_rows(slice, row_errors))
+            .collect_vec();
+        let len = columns.iter().map(|c| c.len()).max().unwrap_or(0);
+        (0..len)
+            .map(|idx| {
+                let args = columns.iter().map(|c| c[idx].clone()).collect_vec();
+                if self.func == Func::Mod && self.strict_division && args[1].is_zero() {
+                    row_errors.insert(idx, ExprError::DivisionByZero);
+                }
+                // a constant count is reported while executing as well, like a column one
+                if let (Func::Repeat, Datum::VarChar(Some(value)), Datum::Int(Some(n))) =
+                    (self.func, &args[0], &args[1])
+                {
+                    if repeat_too_long(value, *n) {
+                        row_errors.insert(
+                            idx,
+                            ExprError::InvalidArguments(self.func.name().to_owned()),
+                        );
+                    }
+                }
+                self.func.call(&args)
+            })
+            .collect_vec()
+    }
+    fn return_type(&self) -> DataType {
+        let arg_types = self.args.iter().map(|arg| arg.return_type()).collect_vec();
+        self.func.return_type(&arg_types)
+    }
+}
@@ -0,0 +1,117 @@
+use crate::datum::{DataType, Datum};
+use crate::expr::{Expr, ExprError, ExprImpl, RowErrors};
+use crate::table::Slice;
+use itertools::Itertools;
+use std::fmt;
+
+/// how greatest and least treat null arguments
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NullAggregation {
+    /// null if any argument is null
+    Strict,
+    /// nulls are skipped, null only if all arguments are
+    Skip,
+}
+
+impl NullAggregation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Strict => "strict",
+            Self::Skip => "skip",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct GreatestExpr {
+    args: Vec<ExprImpl>,
+    /// the smallest argument instead of the greatest
+    least: bool,
+    null_aggregation: NullAggregation,
+    return_type: DataType,
+}
+
+impl fmt::Display for GreatestExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}({})",
+            self.name(),
+            self.args.iter().map(|arg| arg.to_string()).join(", ")
+        )
+    }
+}
+
+impl GreatestExpr {
+    pub fn new(
+        args: Vec<ExprImpl>,
+        least: bool,
+        null_aggregation: NullAggregation,
+    ) -> Result<Self, ExprError> {
+        let name = if least { "least" } else { "greatest" };
+        let mut types = args.iter().map(|arg| arg.return_type());
+        let mut return_type = types
+            .next()
+            .ok_or_else(|| ExprError::InvalidArguments(name.to_owned()))?;
+        for data_type in types {
+            return_type = return_type
+                .unify(&data_type)
+                .ok_or(ExprError::IncompatibleTypes(return_type, data_type))?;
+        }
+        Ok(Self {
+            args,
+            least,
+            null_aggregation,
+            return_type,
+        })
+    }
+    pub fn name(&self) -> &'static str {
+        match self.least {
+            true => "least",
+            false => "greatest",
+        }
+    }
+    pub fn children_mut(&mut self) -> Vec<&mut ExprImpl> {
+        self.args.iter_mut().collect()
+    }
+    fn pick(&self, args: Vec<Datum>) -> Datum {
+        let null = Datum::null_of(&self.return_type);
+        if self.null_aggregation == NullAggregation::Strict && args.iter().any(|d| d.is_null()) {
+            return null;
+        }
+        let args = args.into_iter().filter(|d| !d.is_null());
+        match self.least {
+            true => args.min(),
+            false => args.max(),
+        }
+        .unwrap_or(null)
+    }
+}
+
+impl Expr for GreatestExpr {
+    fn eval(&self, slice: Option<&Slice>, row_errors: &mut RowErrors) -> Vec<Datum> {
+        let columns = self
+            .args
+            .iter()
+            .map(|arg| arg.eval_rows(slice, row_errors))
+            .collect_vec();
+        (0..slice.map_or(1, |slice| slice.count()))
+            .map(|idx| {
+                // ints are widened to compare with floats
+                let args = columns
+                    .iter()
+                    .map(|c| match (&c[idx], self.return_type) {
+                        (Datum::Int(v), DataType::Float(_)) => {
+                            v.map_or(Datum::Float(None), |v| Datum::from(v as f32))
+                        }
+                        (datum, _) => datum.clone(),
+                    })
+                    .collect_vec();
+                self.pick(args)
+            })
+            .collect_vec()
+    }
+    fn return_type(&self) -> DataType {
+        self.return_type
+    }
+}
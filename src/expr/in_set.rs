@@ -0,0 +1,72 @@
+use crate::datum::{DataType, Datum};
+use crate::expr::{BinaryOp, Expr, ExprImpl, RowErrors};
+use crate::table::Slice;
+use itertools::Itertools;
+use std::collections::HashSet;
+use std::fmt;
+use std::mem::discriminant;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct InExpr {
+    child: Box<ExprImpl>,
+    set: HashSet<Datum>,
+    /// a value not in the set is unknown rather than absent if the values hold a null
+    has_null: bool,
+}
+
+impl fmt::Display for InExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} in ({})",
+            self.child,
+            self.set
+                .iter()
+                .sorted()
+                .map(|d| d.to_string())
+                .chain(self.has_null.then(|| "null".to_string()))
+                .join(", ")
+        )
+    }
+}
+
+impl InExpr {
+    pub fn new(child: Box<ExprImpl>, values: &[Datum]) -> Self {
+        Self {
+            child,
+            set: values.iter().filter(|d| !d.is_null()).cloned().collect(),
+            has_null: values.iter().any(|d| d.is_null()),
+        }
+    }
+    pub fn children_mut(&mut self) -> Vec<&mut ExprImpl> {
+        vec![self.child.as_mut()]
+    }
+}
+
+impl Expr for InExpr {
+    /// a value of another type matches as it does by =, e.g. an int and an equal float
+    fn eval(&self, slice: Option<&Slice>, row_errors: &mut RowErrors) -> Vec<Datum> {
+        let equal = BinaryOp::Equal.gen_func();
+        self.child
+            .eval_rows(slice, row_errors)
+            .into_iter()
+            .map(|d| {
+                if d.is_null() {
+                    return Datum::Bool(None);
+                }
+                let found = self.set.contains(&d)
+                    || self.set.iter().any(|v| {
+                        discriminant(v) != discriminant(&d)
+                            && equal(&d, v) == Datum::Bool(Some(true))
+                    });
+                match (found, self.has_null) {
+                    (false, true) => Datum::Bool(None),
+                    (found, _) => found.into(),
+                }
+            })
+            .collect_vec()
+    }
+    fn return_type(&self) -> DataType {
+        DataType::new_as_bool(true)
+    }
+}
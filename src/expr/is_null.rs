@@ -0,0 +1,43 @@
+use crate::datum::{DataType, Datum};
+use crate::expr::{Expr, ExprImpl, RowErrors};
+use crate::table::Slice;
+use itertools::Itertools;
+use std::fmt;
+
+/// never null itself, unlike comparing with null
+#[derive(Debug, PartialEq, Clone)]
+pub struct IsNullExpr {
+    child: Box<ExprImpl>,
+    negated: bool,
+}
+
+impl fmt::Display for IsNullExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.negated {
+            true => write!(f, "{} is not null", self.child),
+            false => write!(f, "{} is null", self.child),
+        }
+    }
+}
+
+impl IsNullExpr {
+    pub fn new(child: Box<ExprImpl>, negated: bool) -> Self {
+        Self { child, negated }
+    }
+    pub fn children_mut(&mut self) -> Vec<&mut ExprImpl> {
+        vec![self.child.as_mut()]
+    }
+}
+
+impl Expr for IsNullExpr {
+    fn eval(&self, slice: Option<&Slice>, row_errors: &mut RowErrors) -> Vec<Datum> {
+        self.child
+            .eval_rows(slice, row_errors)
+            .into_iter()
+            .map(|d| (d.is_null() != self.negated).into())
+            .collect_vec()
+    }
+    fn return_type(&self) -> DataType {
+        DataType::new_as_bool(false)
+    }
+}
@@ -1,5 +1,5 @@
 use crate::datum::{DataType, Datum};
-use crate::expr::{Expr, ExprImpl};
+use crate::expr::{Expr, ExprImpl, RowErrors};
 use crate::table::Slice;
 use itertools::Itertools;
 use like::Like;
@@ -24,11 +24,14 @@ impl LikeExpr {
             pattern: pattern.to_owned(),
         }
     }
+    pub fn children_mut(&mut self) -> Vec<&mut ExprImpl> {
+        vec![self.child.as_mut()]
+    }
 }
 
 impl Expr for LikeExpr {
-    fn eval(&self, slice: Option<&Slice>) -> Vec<Datum> {
-        let datums = self.child.eval(slice);
+    fn eval(&self, slice: Option<&Slice>, row_errors: &mut RowErrors) -> Vec<Datum> {
+        let datums = self.child.eval_rows(slice, row_errors);
         datums
             .into_iter()
             .map(|d| match d {
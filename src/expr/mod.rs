@@ -1,24 +1,51 @@
-use crate::catalog::{CatalogError, CatalogManagerRef};
+use crate::catalog::CatalogError;
 use crate::datum::{DataType, Datum};
-use crate::parser::ast::{ConstantValue, ExprNode};
+use crate::db::Settings;
+use crate::error::ErrorCode;
+use crate::parser::ast::{ConstantValue, ExprNode, InValues};
 use crate::table::{Schema, SchemaError, Slice};
 use itertools::Itertools;
+use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::fmt;
 use thiserror::Error;
 
 pub use self::like::LikeExpr;
 pub use binary::{BinaryExpr, BinaryOp};
+pub use case::CaseExpr;
 pub use column_ref::ColumnRefExpr;
 pub use constant::ConstantExpr;
+pub use func_call::{Func, FuncCallExpr};
+pub use greatest::{GreatestExpr, NullAggregation};
+pub use in_set::InExpr;
+pub use is_null::IsNullExpr;
+pub use not::NotExpr;
+pub use regexp::RegexpExpr;
 
 mod binary;
+mod case;
 mod column_ref;
 mod constant;
+mod func_call;
+mod greatest;
+mod in_set;
+mod is_null;
 mod like;
+mod not;
+mod regexp;
+
+#[cfg(test)]
+thread_local! {
+    /// evals of the non-leaf exprs, to check how often a subtree is evaluated
+    pub static EVALS: std::cell::Cell<usize> = std::cell::Cell::new(0);
+}
+
+/// errors met at the rows of the slice, like a zero divisor, it is only an error if the value of
+/// the row is used
+pub type RowErrors = BTreeMap<usize, ExprError>;
 
 pub trait Expr {
-    fn eval(&self, slice: Option<&Slice>) -> Vec<Datum>;
+    fn eval(&self, slice: Option<&Slice>, row_errors: &mut RowErrors) -> Vec<Datum>;
     fn return_type(&self) -> DataType;
 }
 
@@ -28,6 +55,13 @@ pub enum ExprImpl {
     ColumnRef(ColumnRefExpr),
     Binary(BinaryExpr),
     Like(LikeExpr),
+    Regexp(RegexpExpr),
+    FuncCall(FuncCallExpr),
+    Greatest(GreatestExpr),
+    In(InExpr),
+    Case(CaseExpr),
+    Not(NotExpr),
+    IsNull(IsNullExpr),
 }
 
 impl fmt::Display for ExprImpl {
@@ -35,16 +69,60 @@ impl fmt::Display for ExprImpl {
         match self {
             Self::Constant(expr) => write!(f, "{}", expr.get_value()),
             Self::Like(expr) => write!(f, "{}", expr),
+            Self::Regexp(expr) => write!(f, "{}", expr),
+            Self::FuncCall(expr) => write!(f, "{}", expr),
+            Self::Greatest(expr) => write!(f, "{}", expr),
+            Self::In(expr) => write!(f, "{}", expr),
+            Self::Case(expr) => write!(f, "{}", expr),
+            Self::Not(expr) => write!(f, "{}", expr),
+            Self::IsNull(expr) => write!(f, "{}", expr),
             Self::Binary(expr) => write!(f, "{}", expr),
             Self::ColumnRef(expr) => write!(f, "{}", expr.as_return_type_and_column_name().1),
         }
     }
 }
 
+/// what storing a fractional real into an int column does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FractionalInt {
+    /// the statement fails
+    Error,
+    /// the fraction is dropped with a warning
+    Truncate,
+}
+
+impl FractionalInt {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Truncate => "truncate",
+        }
+    }
+}
+
+/// the int a real is stored as, it must be integral and fit in the range of int
+pub fn int_of_real(value: f64) -> Result<i32, ExprError> {
+    if value.fract() != 0.0 {
+        return Err(ExprError::NotIntegral(value));
+    }
+    if value < i32::MIN as f64 || value > i32::MAX as f64 {
+        return Err(ExprError::IntOverflow(value));
+    }
+    Ok(value as i32)
+}
+
 impl ExprImpl {
-    pub fn batch_eval(exprs: &[ExprImpl], slice: Option<&Slice>) -> Vec<Vec<Datum>> {
-        exprs.iter().map(|e| e.eval(slice)).fold(
-            vec![vec![]; slice.unwrap().count()],
+    /// rows of the exprs evaluated over the slice, or a single row without slice
+    pub fn batch_eval(
+        exprs: &[ExprImpl],
+        slice: Option<&Slice>,
+    ) -> Result<Vec<Vec<Datum>>, ExprError> {
+        let columns = exprs
+            .iter()
+            .map(|e| e.eval(slice))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(columns.into_iter().fold(
+            vec![vec![]; slice.map_or(1, |slice| slice.count())],
             |rows, column| {
                 rows.into_iter()
                     .zip(column.into_iter())
@@ -54,14 +132,36 @@ impl ExprImpl {
                     })
                     .collect_vec()
             },
-        )
+        ))
+    }
+    /// values of the expr over the slice, or a single one without slice
+    pub fn eval(&self, slice: Option<&Slice>) -> Result<Vec<Datum>, ExprError> {
+        let mut row_errors = RowErrors::new();
+        let datums = self.eval_rows(slice, &mut row_errors);
+        match row_errors.into_iter().next() {
+            None => Ok(datums),
+            Some((_, err)) => Err(err),
+        }
     }
-    pub fn eval(&self, slice: Option<&Slice>) -> Vec<Datum> {
+    /// values of the expr, the errors of the rows are recorded for the caller to decide whether
+    /// their values are used
+    pub fn eval_rows(&self, slice: Option<&Slice>, row_errors: &mut RowErrors) -> Vec<Datum> {
+        #[cfg(test)]
+        if !matches!(self, ExprImpl::Constant(_) | ExprImpl::ColumnRef(_)) {
+            EVALS.with(|evals| evals.set(evals.get() + 1));
+        }
         match self {
-            ExprImpl::Constant(expr) => expr.eval(slice),
-            ExprImpl::ColumnRef(expr) => expr.eval(slice),
-            ExprImpl::Binary(expr) => expr.eval(slice),
-            ExprImpl::Like(expr) => expr.eval(slice),
+            ExprImpl::Constant(expr) => expr.eval(slice, row_errors),
+            ExprImpl::ColumnRef(expr) => expr.eval(slice, row_errors),
+            ExprImpl::Binary(expr) => expr.eval(slice, row_errors),
+            ExprImpl::Like(expr) => expr.eval(slice, row_errors),
+            ExprImpl::Regexp(expr) => expr.eval(slice, row_errors),
+            ExprImpl::FuncCall(expr) => expr.eval(slice, row_errors),
+            ExprImpl::Greatest(expr) => expr.eval(slice, row_errors),
+            ExprImpl::In(expr) => expr.eval(slice, row_errors),
+            ExprImpl::Case(expr) => expr.eval(slice, row_errors),
+            ExprImpl::Not(expr) => expr.eval(slice, row_errors),
+            ExprImpl::IsNull(expr) => expr.eval(slice, row_errors),
         }
     }
     pub fn return_type(&self) -> DataType {
@@ -70,61 +170,103 @@ impl ExprImpl {
             ExprImpl::ColumnRef(expr) => expr.return_type(),
             ExprImpl::Binary(expr) => expr.return_type(),
             ExprImpl::Like(expr) => expr.return_type(),
+            ExprImpl::Regexp(expr) => expr.return_type(),
+            ExprImpl::FuncCall(expr) => expr.return_type(),
+            ExprImpl::Greatest(expr) => expr.return_type(),
+            ExprImpl::In(expr) => expr.return_type(),
+            ExprImpl::Case(expr) => expr.return_type(),
+            ExprImpl::Not(expr) => expr.return_type(),
+            ExprImpl::IsNull(expr) => expr.return_type(),
+        }
+    }
+    fn children_mut(&mut self) -> Vec<&mut ExprImpl> {
+        match self {
+            ExprImpl::Constant(_) | ExprImpl::ColumnRef(_) => vec![],
+            ExprImpl::Binary(expr) => expr.children_mut(),
+            ExprImpl::Like(expr) => expr.children_mut(),
+            ExprImpl::Regexp(expr) => expr.children_mut(),
+            ExprImpl::FuncCall(expr) => expr.children_mut(),
+            ExprImpl::Greatest(expr) => expr.children_mut(),
+            ExprImpl::In(expr) => expr.children_mut(),
+            ExprImpl::Case(expr) => expr.children_mut(),
+            ExprImpl::Not(expr) => expr.children_mut(),
+            ExprImpl::IsNull(expr) => expr.children_mut(),
+        }
+    }
+    /// the value of the expr if it is a constant
+    pub fn as_constant(&self) -> Option<Datum> {
+        match self {
+            ExprImpl::Constant(expr) => Some(expr.get_value()),
+            _ => None,
+        }
+    }
+    /// evaluate the subtrees without column refs into constants
+    pub fn fold(&mut self) {
+        if matches!(self, ExprImpl::Constant(_) | ExprImpl::ColumnRef(_)) {
+            return;
+        }
+        let mut children = self.children_mut();
+        children.iter_mut().for_each(|child| child.fold());
+        if children.iter().all(|child| child.as_constant().is_some()) {
+            // a division by zero is kept to be reported while executing
+            if let Ok(mut values) = self.eval(None) {
+                let value = values.remove(0);
+                *self = ExprImpl::Constant(ConstantExpr::new(value, self.return_type()));
+            }
         }
     }
     pub fn from_ast(
         node: &ExprNode,
-        catalog: CatalogManagerRef,
+        settings: &Settings,
         schema: &Schema,
         return_type_hint: Option<DataType>,
     ) -> Result<Self, ExprError> {
         match node {
             ExprNode::Constant(node) => match &node.value {
-                ConstantValue::Real(value) => match return_type_hint.unwrap() {
-                    DataType::Int(_) => Ok(ExprImpl::Constant(ConstantExpr::new(
+                ConstantValue::Real(value) => match return_type_hint
+                    // a number which is no int is compared as a float even with an int column
+                    .filter(|hint| !matches!(hint, DataType::Int(_)) || int_of_real(*value).is_ok())
+                    .unwrap_or_else(|| {
+                        // a number without context is an int unless it has a fraction
+                        if int_of_real(*value).is_ok() {
+                            DataType::new_as_int(false)
+                        } else {
+                            DataType::new_as_float(false)
+                        }
+                    }) {
+                    data_type @ DataType::Int(_) => Ok(ExprImpl::Constant(ConstantExpr::new(
                         Datum::Int(Some(*value as i32)),
-                        return_type_hint.unwrap(),
+                        data_type,
                     ))),
-                    DataType::Float(_) => Ok(ExprImpl::Constant(ConstantExpr::new(
+                    data_type @ DataType::Float(_) => Ok(ExprImpl::Constant(ConstantExpr::new(
                         Datum::Float(Some((*value as f32).try_into().unwrap())),
-                        return_type_hint.unwrap(),
+                        data_type,
                     ))),
                     _ => Err(ExprError::NotMatch),
                 },
                 ConstantValue::String(value) => Ok(ExprImpl::Constant(ConstantExpr::new(
                     value.as_str().into(),
-                    return_type_hint.unwrap(),
+                    return_type_hint.unwrap_or_else(|| DataType::new_as_varchar(false)),
                 ))),
                 ConstantValue::Bool(value) => Ok(ExprImpl::Constant(ConstantExpr::new(
                     Datum::Bool(Some(*value)),
-                    return_type_hint.unwrap(),
+                    return_type_hint.unwrap_or_else(|| DataType::new_as_bool(false)),
                 ))),
                 ConstantValue::Date(value) => Ok(ExprImpl::Constant(ConstantExpr::new(
                     Datum::Date(Some(*value)),
-                    return_type_hint.unwrap(),
+                    return_type_hint.unwrap_or_else(|| DataType::new_as_date(false)),
                 ))),
-                ConstantValue::Null => Ok(match return_type_hint.unwrap() {
-                    DataType::Int(_) => ExprImpl::Constant(ConstantExpr::new(
-                        Datum::Int(None),
-                        return_type_hint.unwrap(),
-                    )),
-                    DataType::VarChar(_) => ExprImpl::Constant(ConstantExpr::new(
-                        Datum::VarChar(None),
-                        return_type_hint.unwrap(),
-                    )),
-                    DataType::Bool(_) => ExprImpl::Constant(ConstantExpr::new(
-                        Datum::Bool(None),
-                        return_type_hint.unwrap(),
-                    )),
-                    DataType::Date(_) => ExprImpl::Constant(ConstantExpr::new(
-                        Datum::Bool(None),
-                        return_type_hint.unwrap(),
-                    )),
-                    DataType::Float(_) => ExprImpl::Constant(ConstantExpr::new(
-                        Datum::Float(None),
-                        return_type_hint.unwrap(),
-                    )),
-                }),
+                ConstantValue::Evaluated(value, data_type) => Ok(ExprImpl::Constant(
+                    ConstantExpr::new(value.clone(), *data_type),
+                )),
+                ConstantValue::Null => {
+                    // a null without context is typed as a nullable int
+                    let data_type = return_type_hint.unwrap_or_else(|| DataType::new_as_int(true));
+                    Ok(ExprImpl::Constant(ConstantExpr::new(
+                        Datum::null_of(&data_type),
+                        data_type,
+                    )))
+                }
             },
             ExprNode::ColumnRef(node) => {
                 let idx = schema
@@ -138,22 +280,174 @@ impl ExprImpl {
                 )))
             }
             ExprNode::Binary(node) => {
-                let lhs =
-                    Self::from_ast(node.lhs.as_ref(), catalog.clone(), schema, return_type_hint)?;
-                let rhs = Self::from_ast(node.rhs.as_ref(), catalog, schema, return_type_hint)?;
+                // a null operand without context takes the type of the other side
+                let (lhs, rhs) = if return_type_hint.is_none() && node.lhs.is_null() {
+                    let rhs = Self::from_ast(node.rhs.as_ref(), settings, schema, None)?;
+                    let lhs = Self::from_ast(
+                        node.lhs.as_ref(),
+                        settings,
+                        schema,
+                        Some(rhs.return_type()),
+                    )?;
+                    (lhs, rhs)
+                } else {
+                    let lhs =
+                        Self::from_ast(node.lhs.as_ref(), settings, schema, return_type_hint)?;
+                    let rhs_type_hint = match return_type_hint {
+                        None if node.rhs.is_null() => Some(lhs.return_type()),
+                        hint => hint,
+                    };
+                    let rhs = Self::from_ast(node.rhs.as_ref(), settings, schema, rhs_type_hint)?;
+                    (lhs, rhs)
+                };
+                // only numbers take part in arithmetic
+                let is_number = |expr: &ExprImpl| {
+                    matches!(expr.return_type(), DataType::Int(_) | DataType::Float(_))
+                };
+                if node.op.is_arithmetic() && !(is_number(&lhs) && is_number(&rhs)) {
+                    return Err(ExprError::IncompatibleTypes(
+                        lhs.return_type(),
+                        rhs.return_type(),
+                    ));
+                }
                 Ok(ExprImpl::Binary(BinaryExpr::new(
                     Box::new(lhs),
                     Box::new(rhs),
                     node.op.clone(),
+                    settings.strict_division,
                 )))
             }
             ExprNode::Like(node) => {
-                let child = Self::from_ast(node.child.as_ref(), catalog, schema, return_type_hint)?;
+                let child =
+                    Self::from_ast(node.child.as_ref(), settings, schema, return_type_hint)?;
                 Ok(ExprImpl::Like(LikeExpr::new(
                     &node.pattern,
                     Box::new(child),
                 )))
             }
+            ExprNode::Regexp(node) => {
+                let child =
+                    Self::from_ast(node.child.as_ref(), settings, schema, return_type_hint)?;
+                Ok(ExprImpl::Regexp(RegexpExpr::new(
+                    &node.pattern,
+                    Box::new(child),
+                )?))
+            }
+            ExprNode::Not(node) => {
+                let hint = Some(DataType::new_as_bool(true));
+                let child = Self::from_ast(node.child.as_ref(), settings, schema, hint)?;
+                Ok(ExprImpl::Not(NotExpr::new(Box::new(child))?))
+            }
+            ExprNode::IsNull(node) => {
+                let child = Self::from_ast(node.child.as_ref(), settings, schema, None)?;
+                Ok(ExprImpl::IsNull(IsNullExpr::new(
+                    Box::new(child),
+                    node.negated,
+                )))
+            }
+            ExprNode::FuncCall(node)
+                if matches!(node.func_name.to_lowercase().as_str(), "greatest" | "least") =>
+            {
+                // the arguments decide the type themselves like the results of case, a null
+                // argument takes the type of the first other argument
+                let null_type_hint = node
+                    .args
+                    .iter()
+                    .find(|arg| !arg.is_null())
+                    .map(|arg| Self::from_ast(arg, settings, schema, None))
+                    .transpose()?
+                    .map(|arg| arg.return_type())
+                    .or(return_type_hint);
+                let args = node
+                    .args
+                    .iter()
+                    .map(|arg| {
+                        let hint = if arg.is_null() { null_type_hint } else { None };
+                        Self::from_ast(arg, settings, schema, hint)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let null_aggregation = settings.null_aggregation;
+                Ok(ExprImpl::Greatest(GreatestExpr::new(
+                    args,
+                    node.func_name.to_lowercase() == "least",
+                    null_aggregation,
+                )?))
+            }
+            ExprNode::FuncCall(node) => {
+                let func = Func::from_name(&node.func_name)?;
+                let args = node
+                    .args
+                    .iter()
+                    .zip(func.arg_types())
+                    .map(|(arg, arg_type)| {
+                        let hint = Some(arg_type).filter(|_| !func.is_numeric());
+                        Self::from_ast(arg, settings, schema, hint)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if args.len() != node.args.len() {
+                    return Err(ExprError::InvalidArguments(func.name().to_owned()));
+                }
+                Ok(ExprImpl::FuncCall(FuncCallExpr::new(
+                    func,
+                    args,
+                    settings.strict_division,
+                )?))
+            }
+            ExprNode::In(node) => match &node.values {
+                InValues::Evaluated(values) => {
+                    let child =
+                        Self::from_ast(node.child.as_ref(), settings, schema, return_type_hint)?;
+                    Ok(ExprImpl::In(InExpr::new(Box::new(child), values)))
+                }
+                InValues::Subquery(_) => Err(ExprError::UnexpectedSubquery),
+            },
+            ExprNode::Case(node) => {
+                let conditions = node
+                    .branches
+                    .iter()
+                    .map(|(condition, _)| Self::from_ast(condition, settings, schema, None))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let result_nodes = node
+                    .branches
+                    .iter()
+                    .map(|(_, result)| result)
+                    .chain(node.else_expr.iter().map(|e| e.as_ref()))
+                    .collect_vec();
+                // the results decide the type themselves, a null result takes the type of
+                // the first other result
+                let mut results = result_nodes
+                    .iter()
+                    .map(|result| match result.is_null() {
+                        true => Ok(None),
+                        false => Self::from_ast(result, settings, schema, None).map(Some),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let null_type_hint = results
+                    .iter()
+                    .flatten()
+                    .next()
+                    .map(|result| result.return_type())
+                    .or(return_type_hint);
+                for (result, node) in results.iter_mut().zip(result_nodes.iter()) {
+                    if result.is_none() {
+                        *result = Some(Self::from_ast(node, settings, schema, null_type_hint)?);
+                    }
+                }
+                let mut results = results.into_iter().map(Option::unwrap).collect_vec();
+                let else_expr = match node.else_expr.is_some() {
+                    true => results.pop().map(Box::new),
+                    false => None,
+                };
+                Ok(ExprImpl::Case(CaseExpr::new(
+                    conditions
+                        .into_iter()
+                        .zip(results.into_iter())
+                        .collect_vec(),
+                    else_expr,
+                )?))
+            }
+            ExprNode::Exists(_) | ExprNode::Subquery(_) => Err(ExprError::UnexpectedSubquery),
+            ExprNode::Row(_) => Err(ExprError::UnexpectedRow),
         }
     }
 }
@@ -168,4 +462,43 @@ pub enum ExprError {
     SchemaError(#[from] SchemaError),
     #[error("Not Match")]
     NotMatch,
+    #[error("Subquery Not Allowed Here")]
+    UnexpectedSubquery,
+    #[error("Row Value Not Allowed Here")]
+    UnexpectedRow,
+    #[error("Unknown Function: {0}")]
+    UnknownFunction(String),
+    #[error("Invalid Arguments of Function: {0}")]
+    InvalidArguments(String),
+    #[error("Invalid Pattern: {0}")]
+    InvalidPattern(String),
+    #[error("Incompatible Types: {0} And {1}")]
+    IncompatibleTypes(DataType, DataType),
+    #[error("Value {0} Is Not An Integer")]
+    NotIntegral(f64),
+    #[error("Value {0} Is Out Of The Range Of Int")]
+    IntOverflow(f64),
+    #[error("Can Not Cast {0} To {1}")]
+    InvalidCast(Datum, DataType),
+    #[error("Division By Zero")]
+    DivisionByZero,
+}
+
+impl ExprError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::TableNameNotFound => ErrorCode::NotFound,
+            Self::CatalogError(err) => err.code(),
+            Self::SchemaError(err) => err.code(),
+            Self::NotMatch | Self::IncompatibleTypes(..) => ErrorCode::TypeMismatch,
+            Self::UnexpectedSubquery | Self::UnexpectedRow => ErrorCode::Unsupported,
+            Self::UnknownFunction(_)
+            | Self::InvalidArguments(_)
+            | Self::InvalidPattern(_)
+            | Self::NotIntegral(_)
+            | Self::IntOverflow(_)
+            | Self::InvalidCast(..)
+            | Self::DivisionByZero => ErrorCode::InvalidArgument,
+        }
+    }
 }
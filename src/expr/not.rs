@@ -0,0 +1,70 @@
+use crate::datum::{DataType, Datum};
+use crate::expr::{Expr, ExprError, ExprImpl, RowErrors};
+use crate::table::Slice;
+use itertools::Itertools;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct NotExpr {
+    child: Box<ExprImpl>,
+}
+
+impl fmt::Display for NotExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "not ({})", self.child)
+    }
+}
+
+impl NotExpr {
+    pub fn new(child: Box<ExprImpl>) -> Result<Self, ExprError> {
+        if !matches!(child.return_type(), DataType::Bool(_)) {
+            return Err(ExprError::NotMatch);
+        }
+        Ok(Self { child })
+    }
+    pub fn children_mut(&mut self) -> Vec<&mut ExprImpl> {
+        vec![self.child.as_mut()]
+    }
+}
+
+impl Expr for NotExpr {
+    /// not null is still null
+    fn eval(&self, slice: Option<&Slice>, row_errors: &mut RowErrors) -> Vec<Datum> {
+        self.child
+            .eval_rows(slice, row_errors)
+            .into_iter()
+            .map(|d| match d {
+                Datum::Bool(value) => Datum::Bool(value.map(|v| !v)),
+                _ => unreachable!(),
+            })
+            .collect_vec()
+    }
+    fn return_type(&self) -> DataType {
+        self.child.return_type()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::ConstantExpr;
+
+    #[test]
+    fn test_not() {
+        let not_of = |value: Option<bool>| {
+            let child = ExprImpl::Constant(ConstantExpr::new(
+                Datum::Bool(value),
+                DataType::new_as_bool(true),
+            ));
+            NotExpr::new(Box::new(child))
+                .unwrap()
+                .eval(None, &mut RowErrors::new())
+                .remove(0)
+        };
+        assert_eq!(not_of(Some(true)), Datum::Bool(Some(false)));
+        assert_eq!(not_of(Some(false)), Datum::Bool(Some(true)));
+        assert_eq!(not_of(None), Datum::Bool(None));
+        let child = ExprImpl::Constant(ConstantExpr::new(1.into(), DataType::new_as_int(false)));
+        assert!(NotExpr::new(Box::new(child)).is_err());
+    }
+}
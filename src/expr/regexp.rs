@@ -0,0 +1,89 @@
+use crate::datum::{DataType, Datum};
+use crate::expr::{Expr, ExprError, ExprImpl, RowErrors};
+use crate::table::Slice;
+use itertools::Itertools;
+use regex::Regex;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct RegexpExpr {
+    child: Box<ExprImpl>,
+    /// compiled once while planning, shared by all the rows
+    regex: Regex,
+}
+
+impl PartialEq for RegexpExpr {
+    fn eq(&self, other: &Self) -> bool {
+        self.child == other.child && self.regex.as_str() == other.regex.as_str()
+    }
+}
+
+impl fmt::Display for RegexpExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} regexp {}", self.child, self.regex.as_str())
+    }
+}
+
+impl RegexpExpr {
+    pub fn new(pattern: &str, child: Box<ExprImpl>) -> Result<Self, ExprError> {
+        if !matches!(child.return_type(), DataType::VarChar(_)) {
+            return Err(ExprError::NotMatch);
+        }
+        let regex =
+            Regex::new(pattern).map_err(|_| ExprError::InvalidPattern(pattern.to_owned()))?;
+        Ok(Self { child, regex })
+    }
+    pub fn children_mut(&mut self) -> Vec<&mut ExprImpl> {
+        vec![self.child.as_mut()]
+    }
+}
+
+impl Expr for RegexpExpr {
+    fn eval(&self, slice: Option<&Slice>, row_errors: &mut RowErrors) -> Vec<Datum> {
+        self.child
+            .eval_rows(slice, row_errors)
+            .into_iter()
+            .map(|d| match d {
+                Datum::VarChar(Some(d)) => self.regex.is_match(d.as_str()).into(),
+                _ => Datum::Bool(None),
+            })
+            .collect_vec()
+    }
+    fn return_type(&self) -> DataType {
+        DataType::new_as_bool(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::ConstantExpr;
+
+    fn regexp_of(pattern: &str, value: Option<&str>) -> Datum {
+        let child = ExprImpl::Constant(ConstantExpr::new(
+            Datum::VarChar(value.map(|v| v.to_owned())),
+            DataType::new_as_varchar(true),
+        ));
+        RegexpExpr::new(pattern, Box::new(child))
+            .unwrap()
+            .eval(None, &mut RowErrors::new())
+            .remove(0)
+    }
+
+    #[test]
+    fn test_regexp() {
+        assert_eq!(regexp_of("^a.*z$", Some("abcz")), Datum::Bool(Some(true)));
+        assert_eq!(regexp_of("^a.*z$", Some("abcza")), Datum::Bool(Some(false)));
+        assert_eq!(regexp_of("b+c", Some("abbbcd")), Datum::Bool(Some(true)));
+        assert_eq!(regexp_of("b+c", Some("acd")), Datum::Bool(Some(false)));
+        assert_eq!(regexp_of("b+c", None), Datum::Bool(None));
+        let child = ExprImpl::Constant(ConstantExpr::new(
+            "abc".into(),
+            DataType::new_as_varchar(false),
+        ));
+        assert!(matches!(
+            RegexpExpr::new("(a", Box::new(child)),
+            Err(ExprError::InvalidPattern(_))
+        ));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::datum::Datum;
-use crate::index::{IndexError, IndexNode, IndexNodeMeta};
+use crate::index::{IndexError, IndexNode, IndexNodeMeta, LeafNode};
 use crate::storage::{BufferPoolManagerRef, PageID, PageRef, SlottedPage};
 use crate::table::SchemaRef;
 use itertools::Itertools;
@@ -119,13 +119,16 @@ impl InternalNode {
         page_id: PageID,
     ) -> Result<Self, IndexError> {
         let page = bpm.borrow_mut().fetch(page_id).unwrap();
-        unsafe {
-            let slotted = &*(page.borrow().buffer.as_ptr() as *const InternalPage);
-            if slotted.meta().common.is_leaf {
-                return Err(IndexError::NotInternalIndexNode);
-            }
+        if LeafNode::is_leaf(&page) {
+            bpm.borrow_mut().unpin(page_id).unwrap();
+            return Err(IndexError::NotInternalIndexNode);
         }
-        Ok(Self { page, bpm, schema })
+        Ok(Self::from_page(bpm, schema, page))
+    }
+
+    /// wrap an already pinned internal page, which is unpinned when the node is dropped
+    pub fn from_page(bpm: BufferPoolManagerRef, schema: SchemaRef, page: PageRef) -> Self {
+        Self { page, bpm, schema }
     }
 
     pub fn len(&self) -> usize {
@@ -139,6 +142,7 @@ impl InternalNode {
     pub fn key_at(&self, idx: usize) -> Vec<Datum> {
         let internal_page = self.internal_page();
         Datum::tuple_from_bytes_with_schema(internal_page.data_at(idx), self.schema.as_ref())
+            .unwrap()
     }
 
     pub fn index_of(&self, key: &[Datum]) -> isize {
@@ -168,7 +172,12 @@ impl InternalNode {
         let internal_page = self.internal_page_mut();
         let key_page_id_set = internal_page
             .key_data_iter()
-            .map(|(k, d)| (Datum::tuple_from_bytes_with_schema(d, schema.as_ref()), *k))
+            .map(|(k, d)| {
+                (
+                    Datum::tuple_from_bytes_with_schema(d, schema.as_ref()).unwrap(),
+                    *k,
+                )
+            })
             .collect_vec();
         let meta = *internal_page.meta();
         internal_page.reset(&meta);
@@ -3,6 +3,7 @@ use crate::index::{IndexError, IndexNodeMeta, RecordID};
 use crate::storage::{BufferPoolManagerRef, PageID, PageRef, SlottedPage};
 use crate::table::SchemaRef;
 use itertools::Itertools;
+use std::cmp::Ordering;
 
 impl Drop for LeafNode {
     fn drop(&mut self) {
@@ -78,18 +79,33 @@ impl LeafNode {
         page_id: PageID,
     ) -> Result<Self, IndexError> {
         let page = bpm.borrow_mut().fetch(page_id).unwrap();
-        unsafe {
-            let slotted = &*(page.borrow().buffer.as_ptr() as *const LeafPage);
-            if !slotted.meta().common.is_leaf {
-                return Err(IndexError::NotLeafIndexNode);
-            }
+        if !Self::is_leaf(&page) {
+            bpm.borrow_mut().unpin(page_id).unwrap();
+            return Err(IndexError::NotLeafIndexNode);
         }
-        Ok(Self { page, bpm, schema })
+        Ok(Self::from_page(bpm, schema, page))
+    }
+
+    /// whether the page holds a leaf node, every node starts with the same common meta
+    pub fn is_leaf(page: &PageRef) -> bool {
+        let slotted = unsafe { &*(page.borrow().buffer.as_ptr() as *const LeafPage) };
+        slotted.meta().common.is_leaf
+    }
+
+    /// wrap an already pinned leaf page, which is unpinned when the node is dropped
+    pub fn from_page(bpm: BufferPoolManagerRef, schema: SchemaRef, page: PageRef) -> Self {
+        Self { page, bpm, schema }
     }
 
     pub fn key_at(&self, idx: usize) -> Vec<Datum> {
         let leaf_page = self.leaf_page();
-        Datum::tuple_from_bytes_with_schema(leaf_page.data_at(idx), self.schema.as_ref())
+        Datum::tuple_from_bytes_with_schema(leaf_page.data_at(idx), self.schema.as_ref()).unwrap()
+    }
+
+    /// order of the key at idx against the key, without decoding the key at idx
+    fn cmp_key_at(&self, idx: usize, key: &[Datum]) -> Ordering {
+        let leaf_page = self.leaf_page();
+        Datum::cmp_tuple_bytes_with_key(leaf_page.data_at(idx), self.schema.as_ref(), key)
     }
 
     pub fn record_id_at(&self, idx: usize) -> RecordID {
@@ -111,15 +127,15 @@ impl LeafNode {
         let mut mid;
         while left + 1 < right {
             mid = (left + right) / 2;
-            if self.key_at(mid).as_slice() < key {
+            if self.cmp_key_at(mid, key) == Ordering::Less {
                 left = mid;
             } else {
                 right = mid;
             }
         }
-        if self.key_at(left).as_slice() >= key {
+        if self.cmp_key_at(left, key) != Ordering::Less {
             Some(left)
-        } else if self.key_at(right).as_slice() >= key {
+        } else if self.cmp_key_at(right, key) != Ordering::Less {
             Some(right)
         } else {
             None
@@ -129,7 +145,7 @@ impl LeafNode {
     pub fn index_of(&self, key: &[Datum]) -> Option<usize> {
         let lower_bound_idx = self.lower_bound(key);
         if let Some(idx) = lower_bound_idx {
-            if self.key_at(idx) == key {
+            if self.cmp_key_at(idx, key) == Ordering::Equal {
                 Some(idx)
             } else {
                 None
@@ -148,7 +164,7 @@ impl LeafNode {
             .map(|(record_id, bytes)| {
                 (
                     *record_id,
-                    Datum::tuple_from_bytes_with_schema(bytes, schema.as_ref()),
+                    Datum::tuple_from_bytes_with_schema(bytes, schema.as_ref()).unwrap(),
                 )
             })
             .collect_vec();
@@ -1,8 +1,12 @@
 use crate::datum::Datum;
+use crate::error::ErrorCode;
 use crate::expr::{ColumnRefExpr, ExprImpl};
-use crate::storage::{BufferPoolManagerRef, PageID, PageRef, SlottedPageError, PAGE_SIZE};
+use crate::storage::{
+    BufferPoolManagerRef, PageID, PageRef, SlottedPageError, StorageError, PAGE_SIZE,
+};
 use crate::table::{Schema, SchemaRef};
 use itertools::Itertools;
+use std::collections::VecDeque;
 use std::convert::TryInto;
 use std::ops::Range;
 use std::rc::Rc;
@@ -23,11 +27,13 @@ enum IndexNode {
 }
 
 impl IndexNode {
+    /// fetch the page once and wrap it as the kind of node it holds
     pub fn open(bpm: BufferPoolManagerRef, schema: SchemaRef, page_id: PageID) -> Self {
-        if let Ok(leaf) = LeafNode::open(bpm.clone(), schema.clone(), page_id) {
-            IndexNode::Leaf(leaf)
+        let page = bpm.borrow_mut().fetch(page_id).unwrap();
+        if LeafNode::is_leaf(&page) {
+            IndexNode::Leaf(LeafNode::from_page(bpm, schema, page))
         } else {
-            IndexNode::Internal(InternalNode::open(bpm, schema, page_id).unwrap())
+            IndexNode::Internal(InternalNode::from_page(bpm, schema, page))
         }
     }
     pub fn store_stat(&self) -> (usize, usize) {
@@ -168,10 +174,22 @@ impl Drop for BPTIndex {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct IndexStats {
+    pub height: usize,
+    pub num_internal_nodes: usize,
+    pub num_leaf_nodes: usize,
+    pub num_keys: usize,
+}
+
 ///
 /// Index Format:
 ///
-///     | page_id_of_root | len_of_indexed_column | column_idx[0] | ... |
+///     | page_id_of_root | magic | format_version | len_of_indexed_column | column_idx[0] | ... |
+///
+/// format_version is bumped whenever the layout after it changes, so that an index page written
+/// in an older format is rejected instead of being misread. A page without the magic predates
+/// the format versions, the magic read as its length of indexed columns is never a real one.
 ///
 /// IndexSchema here is used as the key schema, the page layout of index page is mostly same as
 /// Slice.
@@ -194,11 +212,25 @@ pub struct IndexIter {
     leaf: LeafNode,
     bpm: BufferPoolManagerRef,
     idx: usize,
+    /// the upper key and whether it is inclusive
+    upper: Option<(Vec<Datum>, bool)>,
+    done: bool,
 }
 
 impl IndexIter {
     pub fn new(leaf: LeafNode, bpm: BufferPoolManagerRef, idx: usize) -> Self {
-        Self { leaf, bpm, idx }
+        Self {
+            leaf,
+            bpm,
+            idx,
+            upper: None,
+            done: false,
+        }
+    }
+    /// stop once the keys exceed the upper key
+    pub fn with_upper_bound(mut self, key: Vec<Datum>, inclusive: bool) -> Self {
+        self.upper = Some((key, inclusive));
+        self
     }
 }
 
@@ -206,6 +238,9 @@ impl Iterator for IndexIter {
     type Item = (Vec<Datum>, RecordID);
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
         let len = self.leaf.len();
         if self.idx == len {
             let next_page_id = self.leaf.meta().common.next_page_id;
@@ -219,6 +254,12 @@ impl Iterator for IndexIter {
             }
         }
         let datums = self.leaf.key_at(self.idx);
+        if let Some((upper, inclusive)) = &self.upper {
+            if datums > *upper || (!inclusive && datums == *upper) {
+                self.done = true;
+                return None;
+            }
+        }
         let record_id = self.leaf.record_id_at(self.idx);
         self.idx += 1;
         Some((datums, record_id))
@@ -227,7 +268,14 @@ impl Iterator for IndexIter {
 
 impl BPTIndex {
     const PAGE_ID_OF_ROOT: Range<usize> = 0..4;
-    const LEN_OF_INDEXED_COLUMN_IDS: Range<usize> = 4..8;
+    const MAGIC: Range<usize> = 4..8;
+    const OFFSET_OF_FORMAT_VERSION: usize = 8;
+    const LEN_OF_INDEXED_COLUMN_IDS: Range<usize> = 9..13;
+    const START_OF_INDEXED_COLUMN_IDS: usize = 13;
+    const INDEX_MAGIC: u32 = 0x4954_5042;
+    /// the version reported for a page without the magic
+    const LEGACY_FORMAT_VERSION: u8 = 0;
+    const CURRENT_FORMAT_VERSION: u8 = 1;
 
     pub fn get_page_id(&self) -> PageID {
         self.page.borrow().page_id.unwrap()
@@ -237,13 +285,93 @@ impl BPTIndex {
         todo!()
     }
 
+    /// page ids of all the nodes, from the root level by level
+    fn node_page_ids(&self) -> Vec<PageID> {
+        let schema = Rc::new(self.get_key_schema());
+        let mut page_ids = vec![];
+        let mut queue = VecDeque::from(vec![self.get_page_id_of_root()]);
+        while let Some(page_id) = queue.pop_front() {
+            page_ids.push(page_id);
+            if let Ok(internal_node) = InternalNode::open(self.bpm.clone(), schema.clone(), page_id)
+            {
+                queue.extend(internal_node.meta().leftmost);
+                queue.extend((0..internal_node.len()).map(|idx| internal_node.page_id_at(idx)));
+            }
+        }
+        page_ids
+    }
+
+    /// walk down from the root level by level, counting the nodes and keys
+    pub fn stats(&self) -> IndexStats {
+        let schema = Rc::new(self.get_key_schema());
+        let mut stats = IndexStats {
+            height: 0,
+            num_internal_nodes: 0,
+            num_leaf_nodes: 0,
+            num_keys: 0,
+        };
+        let mut level = vec![self.get_page_id_of_root()];
+        while !level.is_empty() {
+            stats.height += 1;
+            let mut next_level = vec![];
+            for page_id in level {
+                let internal_node = match IndexNode::open(self.bpm.clone(), schema.clone(), page_id)
+                {
+                    IndexNode::Leaf(leaf_node) => {
+                        stats.num_leaf_nodes += 1;
+                        stats.num_keys += leaf_node.len();
+                        continue;
+                    }
+                    IndexNode::Internal(internal_node) => internal_node,
+                };
+                stats.num_internal_nodes += 1;
+                next_level.extend(internal_node.meta().leftmost);
+                next_level
+                    .extend((0..internal_node.len()).map(|idx| internal_node.page_id_at(idx)));
+            }
+            level = next_level;
+        }
+        stats
+    }
+
+    /// bytes used by the meta page and all the nodes
+    pub fn disk_footprint(&self) -> usize {
+        let schema = Rc::new(self.get_key_schema());
+        let meta_bytes = Self::START_OF_INDEXED_COLUMN_IDS + 4 * self.exprs.len();
+        let node_bytes = self
+            .node_page_ids()
+            .into_iter()
+            .map(|page_id| {
+                IndexNode::open(self.bpm.clone(), schema.clone(), page_id)
+                    .store_stat()
+                    .0
+            })
+            .sum::<usize>();
+        meta_bytes + node_bytes
+    }
+
+    /// remove all the keys, the index keeps its page id
+    pub fn clear(&mut self) -> Result<(), IndexError> {
+        let page_ids = self.node_page_ids();
+        let leaf_node = LeafNode::new(self.bpm.clone(), Rc::new(self.get_key_schema()));
+        self.set_page_id_of_root(leaf_node.page_id());
+        self.page.borrow_mut().is_dirty = true;
+        for page_id in page_ids {
+            self.bpm.borrow_mut().free(page_id)?;
+        }
+        Ok(())
+    }
+
     pub fn new(bpm: BufferPoolManagerRef, exprs: Vec<ExprImpl>) -> Self {
         let page = bpm.borrow_mut().alloc().unwrap();
         let schema = Rc::new(Schema::from_exprs(&exprs));
         let leaf_node = LeafNode::new(bpm.clone(), schema);
         let page_id_of_root = leaf_node.page_id();
         // set page_id_of_root
-        page.borrow_mut().buffer[0..4].copy_from_slice(&(page_id_of_root as u32).to_le_bytes());
+        page.borrow_mut().buffer[Self::PAGE_ID_OF_ROOT]
+            .copy_from_slice(&(page_id_of_root as u32).to_le_bytes());
+        page.borrow_mut().buffer[Self::MAGIC].copy_from_slice(&Self::INDEX_MAGIC.to_le_bytes());
+        page.borrow_mut().buffer[Self::OFFSET_OF_FORMAT_VERSION] = Self::CURRENT_FORMAT_VERSION;
         let indexed_columns_ids = exprs
             .iter()
             .map(|e| {
@@ -261,35 +389,63 @@ impl BPTIndex {
             .into_iter()
             .flat_map(|idx| (idx as u32).to_le_bytes())
             .collect_vec();
-        let len = bytes.len();
-        page.borrow_mut().buffer[8..8 + len].copy_from_slice(&bytes);
+        let start = Self::START_OF_INDEXED_COLUMN_IDS;
+        page.borrow_mut().buffer[start..start + bytes.len()].copy_from_slice(&bytes);
         page.borrow_mut().is_dirty = true;
         Self { bpm, page, exprs }
     }
 
-    pub fn open(bpm: BufferPoolManagerRef, page_id: PageID, table_schema: &Schema) -> Self {
-        let page = bpm.borrow_mut().fetch(page_id).unwrap();
-        let len_of_indexed_column_ids = u32::from_le_bytes(
-            page.borrow().buffer[Self::LEN_OF_INDEXED_COLUMN_IDS]
-                .try_into()
-                .unwrap(),
-        ) as usize;
-        let exprs = (0..len_of_indexed_column_ids)
-            .into_iter()
-            .map(|idx| {
-                let start = 8 + 4 * idx;
-                let end = start + 4;
-                let column_idx =
-                    u32::from_le_bytes(page.borrow().buffer[start..end].try_into().unwrap())
-                        as usize;
-                ExprImpl::ColumnRef(ColumnRefExpr::new(
-                    column_idx,
-                    table_schema.columns[column_idx].data_type,
-                    table_schema.columns[column_idx].desc.clone(),
-                ))
-            })
-            .collect_vec();
-        Self { bpm, page, exprs }
+    pub fn open(
+        bpm: BufferPoolManagerRef,
+        page_id: PageID,
+        table_schema: &Schema,
+    ) -> Result<Self, IndexError> {
+        let page = bpm.borrow_mut().fetch(page_id)?;
+        match Self::get_exprs(&page, table_schema) {
+            Ok(exprs) => Ok(Self { bpm, page, exprs }),
+            // the page is not held by an index to unpin it on drop
+            Err(err) => {
+                bpm.borrow_mut().unpin(page_id)?;
+                Err(err)
+            }
+        }
+    }
+
+    /// decode the indexed exprs from the index meta page according to its format version
+    pub fn get_exprs(page: &PageRef, table_schema: &Schema) -> Result<Vec<ExprImpl>, IndexError> {
+        let page = page.borrow();
+        let magic = u32::from_le_bytes(page.buffer[Self::MAGIC].try_into().unwrap());
+        if magic != Self::INDEX_MAGIC {
+            return Err(IndexError::UnknownFormatVersion(
+                Self::LEGACY_FORMAT_VERSION,
+            ));
+        }
+        match page.buffer[Self::OFFSET_OF_FORMAT_VERSION] {
+            1 => {
+                let len_of_indexed_column_ids = u32::from_le_bytes(
+                    page.buffer[Self::LEN_OF_INDEXED_COLUMN_IDS]
+                        .try_into()
+                        .unwrap(),
+                ) as usize;
+                let exprs = (0..len_of_indexed_column_ids)
+                    .into_iter()
+                    .map(|idx| {
+                        let start = Self::START_OF_INDEXED_COLUMN_IDS + 4 * idx;
+                        let end = start + 4;
+                        let column_idx =
+                            u32::from_le_bytes(page.buffer[start..end].try_into().unwrap())
+                                as usize;
+                        ExprImpl::ColumnRef(ColumnRefExpr::new(
+                            column_idx,
+                            table_schema.columns[column_idx].data_type,
+                            table_schema.columns[column_idx].desc.clone(),
+                        ))
+                    })
+                    .collect_vec();
+                Ok(exprs)
+            }
+            version => Err(IndexError::UnknownFormatVersion(version)),
+        }
     }
 
     pub fn get_page_id_of_root(&self) -> PageID {
@@ -364,14 +520,11 @@ impl BPTIndex {
         let mut page_id_of_current_node = self.get_page_id_of_root();
         let schema = Rc::new(self.get_key_schema());
         loop {
-            if let Ok(leaf_node) =
-                LeafNode::open(self.bpm.clone(), schema.clone(), page_id_of_current_node)
-            {
-                break Some(leaf_node);
-            }
             let internal_node =
-                InternalNode::open(self.bpm.clone(), schema.clone(), page_id_of_current_node)
-                    .unwrap();
+                match IndexNode::open(self.bpm.clone(), schema.clone(), page_id_of_current_node) {
+                    IndexNode::Leaf(leaf_node) => break Some(leaf_node),
+                    IndexNode::Internal(internal_node) => internal_node,
+                };
             let branch_idx = internal_node.index_of(key);
             if branch_idx == -1 {
                 if let Some(page_id) = internal_node.meta().leftmost {
@@ -388,8 +541,9 @@ impl BPTIndex {
     pub fn iter_start_from(&self, key: &[Datum]) -> Option<IndexIter> {
         let leaf = self.find_leaf(key);
         if let Some(leaf) = leaf {
-            leaf.lower_bound(key)
-                .map(|idx| IndexIter::new(leaf, self.bpm.clone(), idx))
+            // all the keys of the leaf are less, the greater ones start from the next leaf
+            let idx = leaf.lower_bound(key).unwrap_or_else(|| leaf.len());
+            Some(IndexIter::new(leaf, self.bpm.clone(), idx))
         } else {
             None
         }
@@ -439,7 +593,7 @@ impl BPTIndex {
         first_leaf.key_at(first_leaf.len() - 1)
     }
 
-    pub fn first_key(&self) -> Vec<Datum> {
+    fn first_leaf(&self) -> LeafNode {
         let mut page_id_of_current_node = self.get_page_id_of_root();
         let schema = Rc::new(self.get_key_schema());
         let first_leaf = loop {
@@ -457,7 +611,83 @@ impl BPTIndex {
                 internal_node.page_id_at(0)
             }
         };
-        first_leaf.key_at(0)
+        first_leaf
+    }
+
+    pub fn first_key(&self) -> Vec<Datum> {
+        self.first_leaf().key_at(0)
+    }
+
+    /// iterate from the first leaf, without probing any key
+    pub fn iter(&self) -> IndexIter {
+        IndexIter::new(self.first_leaf(), self.bpm.clone(), 0)
+    }
+
+    /// split the keys into at most n ranges holding about the same number of keys, each from its
+    /// begin key inclusive to its end key exclusive, the first one has no begin and the last one
+    /// no end, so the ranges are contiguous and cover the whole key space, fewer if there are
+    /// fewer nodes, the sizes are sampled from the upper levels, the leaves are not read
+    #[allow(dead_code)]
+    pub fn split_range_into(
+        &self,
+        n: usize,
+    ) -> Result<Vec<(Option<Vec<Datum>>, Option<Vec<Datum>>)>, IndexError> {
+        const SAMPLES_PER_RANGE: usize = 8;
+        let schema = Rc::new(self.get_key_schema());
+        // the nodes of a level with the smallest key each one holds, none for the first one,
+        // the subtrees of a level are about the same size
+        let mut level = vec![(None, self.get_page_id_of_root())];
+        while level.len() < n * SAMPLES_PER_RANGE {
+            let mut children = vec![];
+            for (begin, page_id) in level.iter() {
+                let node = match InternalNode::open(self.bpm.clone(), schema.clone(), *page_id) {
+                    Ok(node) => node,
+                    // every leaf is at the same level
+                    Err(IndexError::NotInternalIndexNode) => break,
+                    Err(err) => return Err(err),
+                };
+                let first = children.len();
+                children.extend(node.meta().leftmost.map(|page_id| (begin.clone(), page_id)));
+                children.extend(
+                    (0..node.len()).map(|idx| (Some(node.key_at(idx)), node.page_id_at(idx))),
+                );
+                // keys below the first separator go to the first child
+                if let Some(child) = children.get_mut(first) {
+                    child.0 = begin.clone();
+                }
+            }
+            if children.len() <= level.len() {
+                break;
+            }
+            level = children;
+        }
+        let mut bounds = vec![None];
+        bounds.extend(
+            (1..n)
+                .map(|k| k * level.len() / n)
+                .filter(|position| *position > 0)
+                .dedup()
+                .map(|position| level[position].0.clone()),
+        );
+        bounds.push(None);
+        Ok(bounds.into_iter().tuple_windows().collect_vec())
+    }
+
+    /// check the keys of every node and along the chain of leaves are ordered
+    #[allow(dead_code)]
+    pub fn sanity_check(&self) {
+        let schema = Rc::new(self.get_key_schema());
+        let mut page_ids = vec![self.get_page_id_of_root()];
+        while let Some(page_id) = page_ids.pop() {
+            let node = IndexNode::open(self.bpm.clone(), schema.clone(), page_id);
+            node.sanity_check();
+            if let IndexNode::Internal(node) = &node {
+                page_ids.extend((0..node.len()).map(|idx| node.page_id_at(idx)));
+            }
+        }
+        for ((lhs, _), (rhs, _)) in self.iter().tuple_windows() {
+            assert!(lhs < rhs);
+        }
     }
 
     pub fn find(&self, key: &[Datum]) -> Option<RecordID> {
@@ -583,6 +813,28 @@ pub enum IndexError {
     Duplicated,
     #[error("Page Error: {0}")]
     PageError(#[from] SlottedPageError),
+    #[error("Storage Error: {0}")]
+    Storage(#[from] StorageError),
+    #[error(
+        "Unknown Index Format Version {0}, Expect {}",
+        BPTIndex::CURRENT_FORMAT_VERSION
+    )]
+    UnknownFormatVersion(u8),
+}
+
+impl IndexError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Duplicated => ErrorCode::DuplicateKey,
+            Self::PageError(err) => err.code(),
+            Self::Storage(err) => err.code(),
+            Self::NotInternalIndexNode
+            | Self::NotLeafIndexNode
+            | Self::KeyNotFound
+            | Self::NodeOutOfSpace
+            | Self::UnknownFormatVersion(_) => ErrorCode::Internal,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -707,4 +959,269 @@ mod tests {
         };
         remove_file(filename).unwrap();
     }
+
+    #[test]
+    fn test_iter_with_upper_bound() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(200);
+            let filename = bpm.borrow().filename();
+            let exprs = vec![ExprImpl::ColumnRef(ColumnRefExpr::new(
+                0,
+                DataType::new_as_int(false),
+                "v1".to_string(),
+            ))];
+            let mut index = BPTIndex::new(bpm, exprs);
+            for idx in 0..10000usize {
+                index
+                    .insert(&[Datum::Int(Some(idx as i32))], (idx, idx))
+                    .unwrap();
+            }
+            let keys_of = |iter: IndexIter| iter.map(|(key, _)| key).collect_vec();
+            let iter = index
+                .iter_start_from(&[Datum::Int(Some(100))])
+                .unwrap()
+                .with_upper_bound(vec![Datum::Int(Some(200))], true);
+            assert_eq!(
+                keys_of(iter),
+                (100..=200)
+                    .map(|idx| vec![Datum::Int(Some(idx))])
+                    .collect_vec()
+            );
+            let iter = index
+                .iter()
+                .with_upper_bound(vec![Datum::Int(Some(200))], false);
+            assert_eq!(
+                keys_of(iter),
+                (0..200)
+                    .map(|idx| vec![Datum::Int(Some(idx))])
+                    .collect_vec()
+            );
+            // the iterator stops in the leaf holding the first exceeding key
+            let mut iter = index
+                .iter()
+                .with_upper_bound(vec![Datum::Int(Some(200))], true);
+            assert_eq!(iter.by_ref().count(), 201);
+            assert!(iter.next().is_none());
+            assert_eq!(
+                iter.leaf.page_id(),
+                index.find_leaf(&[Datum::Int(Some(201))]).unwrap().page_id()
+            );
+            let mut unbounded = index.iter();
+            assert_eq!(unbounded.by_ref().count(), 10000);
+            assert_ne!(iter.leaf.page_id(), unbounded.leaf.page_id());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_bool_key_order() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(20);
+            let filename = bpm.borrow().filename();
+            let exprs = vec![ExprImpl::ColumnRef(ColumnRefExpr::new(
+                0,
+                DataType::new_as_bool(true),
+                "v1".to_string(),
+            ))];
+            let mut index = BPTIndex::new(bpm, exprs);
+            index.insert(&[Datum::Bool(Some(true))], (0, 0)).unwrap();
+            index.insert(&[Datum::Bool(None)], (1, 0)).unwrap();
+            index.insert(&[Datum::Bool(Some(false))], (2, 0)).unwrap();
+            assert!(index.insert(&[Datum::Bool(Some(true))], (3, 0)).is_err());
+            let keys = index
+                .iter_start_from(&index.first_key())
+                .unwrap()
+                .map(|(key, _)| key)
+                .collect_vec();
+            assert_eq!(
+                keys,
+                vec![
+                    vec![Datum::Bool(None)],
+                    vec![Datum::Bool(Some(false))],
+                    vec![Datum::Bool(Some(true))],
+                ]
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_format_version() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(20);
+            let filename = bpm.borrow().filename();
+            let schema = Schema::from_type_and_names(&[
+                (DataType::new_as_int(false), "v1".to_string()),
+                (DataType::new_as_varchar(false), "v2".to_string()),
+            ]);
+            let exprs = vec![ExprImpl::ColumnRef(ColumnRefExpr::new(
+                1,
+                DataType::new_as_varchar(false),
+                "v2".to_string(),
+            ))];
+            let index = BPTIndex::new(bpm.clone(), exprs.clone());
+            let page_id = index.get_page_id();
+            assert_eq!(BPTIndex::get_exprs(&index.page, &schema).unwrap(), exprs);
+            assert_eq!(
+                BPTIndex::open(bpm.clone(), page_id, &schema).unwrap().exprs,
+                exprs
+            );
+            index.page.borrow_mut().buffer[BPTIndex::OFFSET_OF_FORMAT_VERSION] = 42;
+            let err = BPTIndex::get_exprs(&index.page, &schema).unwrap_err();
+            assert!(matches!(err, IndexError::UnknownFormatVersion(42)));
+            assert_eq!(err.to_string(), "Unknown Index Format Version 42, Expect 1");
+            assert!(matches!(
+                BPTIndex::open(bpm, page_id, &schema),
+                Err(IndexError::UnknownFormatVersion(42))
+            ));
+            index.page.borrow_mut().buffer[BPTIndex::OFFSET_OF_FORMAT_VERSION] = 1;
+            let meta = index.page.borrow().buffer;
+            // the legacy layout of one column, | page_id_of_root | len | column_idx[0] |
+            index.page.borrow_mut().buffer[4..8].copy_from_slice(&1u32.to_le_bytes());
+            index.page.borrow_mut().buffer[8..12].copy_from_slice(&1u32.to_le_bytes());
+            assert!(matches!(
+                BPTIndex::get_exprs(&index.page, &schema),
+                Err(IndexError::UnknownFormatVersion(0))
+            ));
+            index.page.borrow_mut().buffer = meta;
+            assert_eq!(BPTIndex::get_exprs(&index.page, &schema).unwrap(), exprs);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_index_stats() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(2000);
+            let filename = bpm.borrow().filename();
+            let exprs = vec![ExprImpl::ColumnRef(ColumnRefExpr::new(
+                0,
+                DataType::new_as_int(false),
+                "v1".to_string(),
+            ))];
+            let mut index = BPTIndex::new(bpm.clone(), exprs);
+            assert_eq!(
+                index.stats(),
+                IndexStats {
+                    height: 1,
+                    num_internal_nodes: 0,
+                    num_leaf_nodes: 1,
+                    num_keys: 0,
+                }
+            );
+            for idx in 0..40000usize {
+                index
+                    .insert(&[Datum::Int(Some(idx as i32))], (idx, idx))
+                    .unwrap();
+            }
+            let stats = index.stats();
+            assert_eq!(stats.num_keys, 40000);
+            assert!(stats.height > 1);
+            assert!(stats.num_internal_nodes < stats.num_leaf_nodes);
+            // leaves are chained together
+            let mut num_leaf_nodes = 1;
+            let mut leaf_node = index.first_leaf();
+            while let Some(page_id) = leaf_node.meta().common.next_page_id {
+                leaf_node = LeafNode::open(bpm.clone(), leaf_node.schema.clone(), page_id).unwrap();
+                num_leaf_nodes += 1;
+            }
+            assert_eq!(stats.num_leaf_nodes, num_leaf_nodes);
+            // the footprint is the meta plus the used bytes of each node page
+            let page_ids = index.node_page_ids();
+            assert_eq!(
+                page_ids.len(),
+                stats.num_internal_nodes + stats.num_leaf_nodes
+            );
+            let schema = Rc::new(index.get_key_schema());
+            let used = page_ids
+                .into_iter()
+                .map(|page_id| {
+                    IndexNode::open(bpm.clone(), schema.clone(), page_id)
+                        .store_stat()
+                        .0
+                })
+                .sum::<usize>();
+            assert_eq!(
+                index.disk_footprint(),
+                BPTIndex::START_OF_INDEXED_COLUMN_IDS + 4 + used
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_find_fetches_once_per_level() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(2000);
+            let filename = bpm.borrow().filename();
+            let exprs = vec![ExprImpl::ColumnRef(ColumnRefExpr::new(
+                0,
+                DataType::new_as_int(false),
+                "v1".to_string(),
+            ))];
+            let mut index = BPTIndex::new(bpm.clone(), exprs);
+            for idx in 0..40000usize {
+                index
+                    .insert(&[Datum::Int(Some(idx as i32))], (idx, idx))
+                    .unwrap();
+            }
+            let height = index.stats().height;
+            assert!(height > 1);
+            for idx in (0..40000usize).step_by(997) {
+                let num_fetches = bpm.borrow().num_fetches();
+                assert_eq!(
+                    index.find(&[Datum::Int(Some(idx as i32))]),
+                    Some((idx, idx))
+                );
+                assert_eq!(bpm.borrow().num_fetches() - num_fetches, height);
+            }
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_split_range_into() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(20);
+            let filename = bpm.borrow().filename();
+            let exprs = vec![ExprImpl::ColumnRef(ColumnRefExpr::new(
+                0,
+                DataType::new_as_int(false),
+                "v1".to_string(),
+            ))];
+            let mut index = BPTIndex::new(bpm, exprs);
+            assert_eq!(index.split_range_into(4).unwrap(), vec![(None, None)]);
+            for v in 0..4000 {
+                index.insert(&[v.into()], (v as usize, 0)).unwrap();
+            }
+            assert!(index.stats().num_leaf_nodes > 4);
+            let ranges = index.split_range_into(4).unwrap();
+            assert_eq!(ranges.len(), 4);
+            assert_eq!(ranges[0].0, None);
+            assert_eq!(ranges[3].1, None);
+            for (lhs, rhs) in ranges.iter().tuple_windows() {
+                assert!(lhs.1.is_some());
+                assert_eq!(lhs.1, rhs.0);
+            }
+            // each range holds about a quarter of the keys
+            for (begin, end) in ranges {
+                let count = index
+                    .iter()
+                    .filter(|(key, _)| {
+                        begin.as_ref().map_or(true, |begin| key >= begin)
+                            && end.as_ref().map_or(true, |end| key < end)
+                    })
+                    .count();
+                assert!((500..1500).contains(&count));
+            }
+            assert_eq!(index.split_range_into(1).unwrap(), vec![(None, None)]);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
 }
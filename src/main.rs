@@ -5,6 +5,7 @@
 
 mod catalog;
 mod datum;
+mod error;
 mod execution;
 mod expr;
 mod index;
@@ -16,32 +17,85 @@ mod table;
 mod db;
 
 use crate::db::NaiveDB;
+use crate::table::Table;
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
 
+use std::fs::File;
+use std::io::{self, Write};
 use std::time::Instant;
 
 #[macro_use]
 extern crate lalrpop_util;
 lalrpop_mod!(#[allow(clippy::all)] pub sql);
 
+/// where query results go, stdout unless redirected by `\output file`
+#[derive(Default)]
+struct Output {
+    redirect: Option<Box<dyn Write>>,
+}
+
+impl Output {
+    /// `\output file` redirects to file, `\output` switches back to stdout
+    fn set(&mut self, filename: &str) -> io::Result<()> {
+        self.redirect = match filename.trim() {
+            "" => None,
+            filename => Some(Box::new(File::create(filename)?)),
+        };
+        Ok(())
+    }
+    fn render(&mut self, table: &Table, null_display: &str, bool_as_int: bool) -> io::Result<()> {
+        match self.redirect.as_mut() {
+            Some(writer) => render(table, null_display, bool_as_int, writer),
+            None => render(table, null_display, bool_as_int, &mut io::stdout()),
+        }
+    }
+}
+
+/// the argument of an `\output` command, the name must end at whitespace or the end of line
+fn output_arg(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix("\\output")
+        .filter(|arg| arg.is_empty() || arg.starts_with(char::is_whitespace))
+}
+
+fn render(
+    table: &Table,
+    null_display: &str,
+    bool_as_int: bool,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    writeln!(writer, "{}", table.render(null_display, bool_as_int))?;
+    writer.flush()
+}
+
 fn main() {
     env_logger::init();
     let mut db = NaiveDB::new();
     let mut rl = Editor::<()>::new();
+    let mut output = Output::default();
     loop {
         let readline = rl.readline("naive_db > ");
         match readline {
             Ok(line) => {
                 rl.add_history_entry(line.as_str());
+                if let Some(filename) = output_arg(&line) {
+                    if let Err(err) = output.set(filename) {
+                        eprintln!("Error: {}", err);
+                    }
+                    continue;
+                }
                 let start = Instant::now();
                 match db.run(line.as_str()) {
                     Ok(res) => {
-                        println!("{}", res);
+                        if let Err(err) = output.render(&res, &db.null_display(), db.bool_as_int())
+                        {
+                            eprintln!("Error: {}", err);
+                        }
                         println!("Elapsed Time: {:?}", start.elapsed())
                     }
                     Err(err) => {
-                        println!("Error: {}", err);
+                        eprintln!("Error {}: {}", err.code() as u32, err);
                     }
                 }
             }
@@ -59,3 +113,35 @@ fn main() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{read_to_string, remove_file};
+
+    #[test]
+    fn test_output_redirect() {
+        let filename = {
+            let mut db = NaiveDB::new_random();
+            let filename = db.filename();
+            let table = db.run("create database d;").unwrap();
+            let mut buffer = vec![];
+            render(&table, "NULL", false, &mut buffer).unwrap();
+            let rendered = String::from_utf8(buffer).unwrap();
+            assert!(rendered.contains("database"));
+            let output_filename = format!("{}.txt", filename);
+            let mut output = Output::default();
+            output.set(&format!(" {}", output_filename)).unwrap();
+            output.render(&table, "NULL", false).unwrap();
+            output.set("").unwrap();
+            assert!(output.redirect.is_none());
+            assert_eq!(read_to_string(&output_filename).unwrap(), rendered);
+            remove_file(output_filename).unwrap();
+            assert_eq!(output_arg("\\output"), Some(""));
+            assert_eq!(output_arg(" \\output  out.txt "), Some("  out.txt"));
+            assert_eq!(output_arg("\\outputfoo"), None);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+}
@@ -1,4 +1,4 @@
-use crate::datum::DataType;
+use crate::datum::{DataType, Datum};
 use crate::expr::BinaryOp;
 use chrono::NaiveDate;
 use std::string::ToString;
@@ -8,8 +8,12 @@ pub enum Statement {
     CreateDatabase(CreateDatabaseStmt),
     ShowDatabases,
     ShowTables,
+    ShowWarnings,
+    ShowHistory,
     UseDatabase(UseDatabaseStmt),
     CreateTable(CreateTableStmt),
+    CreateTableAs(CreateTableAsStmt),
+    CreateView(CreateViewStmt),
     Insert(InsertStmt),
     Desc(DescStmt),
     Select(SelectStmt),
@@ -17,7 +21,10 @@ pub enum Statement {
     AddPrimary(AddPrimaryStmt),
     AddForeign(AddForeignStmt),
     AddUnique(AddUniqueStmt),
+    RenameColumn(RenameColumnStmt),
     DropTable(DropTableStmt),
+    DropView(DropViewStmt),
+    TruncateTable(TruncateTableStmt),
     Delete(DeleteStmt),
     LoadFromFile(LoadFromFileStmt),
     DropDatabase(DropDatabaseStmt),
@@ -25,6 +32,51 @@ pub enum Statement {
     DropPrimary(DropPrimaryStmt),
     DropForeign(DropForeignStmt),
     Update(UpdateStmt),
+    Pragma(PragmaStmt),
+    Analyze(AnalyzeStmt),
+    Vacuum(VacuumStmt),
+    Checkpoint,
+    Explain(SelectStmt),
+}
+
+#[derive(Debug)]
+pub enum PragmaArg {
+    /// table name with the indexed column names
+    Index(String, Vec<String>),
+    /// value assigned to a setting
+    Value(String),
+    Table(String),
+}
+
+#[derive(Debug)]
+pub struct PragmaStmt {
+    pub name: String,
+    pub arg: Option<PragmaArg>,
+}
+
+#[derive(Debug)]
+pub struct CreateViewStmt {
+    pub view_name: String,
+    /// position of the select query in the sql
+    pub span: (usize, usize),
+    pub definition: String,
+}
+
+#[derive(Debug)]
+pub struct DropViewStmt {
+    pub view_name: String,
+}
+
+#[derive(Debug)]
+pub struct AnalyzeStmt {
+    pub table_name: String,
+}
+
+#[derive(Debug)]
+pub struct VacuumStmt {
+    pub table_name: String,
+    /// refresh the stats as well
+    pub analyze: bool,
 }
 
 #[derive(Debug)]
@@ -38,7 +90,10 @@ pub enum AggAction {
     Sum,
     Avg,
     Max,
+    Min,
     Cnt,
+    BoolAnd,
+    BoolOr,
     No,
 }
 
@@ -48,22 +103,29 @@ impl ToString for AggAction {
             Self::Sum => "sum".to_owned(),
             Self::Avg => "average".to_owned(),
             Self::Max => "max".to_owned(),
+            Self::Min => "min".to_owned(),
             Self::Cnt => "count".to_owned(),
+            Self::BoolAnd => "bool_and".to_owned(),
+            Self::BoolOr => "bool_or".to_owned(),
             _ => unreachable!(),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AggTarget {
     All,
     Expr(ExprNode),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AggItem {
     pub action: AggAction,
     pub target: AggTarget,
+    /// only the unique values are aggregated
+    pub distinct: bool,
+    /// name of the output column
+    pub alias: Option<String>,
 }
 
 #[derive(Debug)]
@@ -76,11 +138,20 @@ pub struct UpdateStmt {
     pub table_name: String,
     pub set_exprs: Vec<ExprNode>,
     pub where_exprs: Vec<ExprNode>,
+    /// at most this many rows are updated
+    pub limit: Option<usize>,
 }
 
 #[derive(Debug)]
 pub struct DropTableStmt {
     pub table_name: String,
+    pub cascade: bool,
+}
+
+#[derive(Debug)]
+pub struct TruncateTableStmt {
+    pub table_name: String,
+    pub cascade: bool,
 }
 
 #[derive(Debug)]
@@ -101,41 +172,120 @@ pub enum ConstantValue {
     Real(f64),
     Bool(bool),
     Date(NaiveDate),
+    /// value already evaluated by planner, e.g. scalar subquery
+    Evaluated(Datum, DataType),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ConstantExprNode {
     pub value: ConstantValue,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ColumnRefExprNode {
     pub table_name: Option<String>,
     pub column_name: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BinaryExprNode {
     pub lhs: Box<ExprNode>,
     pub rhs: Box<ExprNode>,
     pub op: BinaryOp,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LikeExprNode {
     pub child: Box<ExprNode>,
     pub pattern: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct NotExprNode {
+    pub child: Box<ExprNode>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IsNullExprNode {
+    pub child: Box<ExprNode>,
+    /// is not null
+    pub negated: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct RegexpExprNode {
+    pub child: Box<ExprNode>,
+    pub pattern: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct FuncCallExprNode {
+    pub func_name: String,
+    pub args: Vec<ExprNode>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExistsExprNode {
+    pub subquery: Box<SelectStmt>,
+}
+
+/// values of an in expr, the subquery is replaced by its result while planning
+#[derive(Debug, Clone)]
+pub enum InValues {
+    Subquery(Box<SelectStmt>),
+    Evaluated(Vec<Datum>),
+}
+
+#[derive(Debug, Clone)]
+pub struct InExprNode {
+    pub child: Box<ExprNode>,
+    pub values: InValues,
+}
+
+#[derive(Debug, Clone)]
+pub struct CaseExprNode {
+    /// conditions with the results
+    pub branches: Vec<(ExprNode, ExprNode)>,
+    pub else_expr: Option<Box<ExprNode>>,
+}
+
+/// a row constructor like (1, 2), only compared with rowid
+#[derive(Debug, Clone)]
+pub struct RowExprNode {
+    pub values: Vec<ExprNode>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubqueryExprNode {
+    pub subquery: Box<SelectStmt>,
+}
+
+#[derive(Debug, Clone)]
 pub enum ExprNode {
     Constant(ConstantExprNode),
     ColumnRef(ColumnRefExprNode),
     Binary(BinaryExprNode),
     Like(LikeExprNode),
+    Regexp(RegexpExprNode),
+    FuncCall(FuncCallExprNode),
+    Exists(ExistsExprNode),
+    In(InExprNode),
+    Case(CaseExprNode),
+    Subquery(SubqueryExprNode),
+    Row(RowExprNode),
+    Not(NotExprNode),
+    IsNull(IsNullExprNode),
 }
 
 impl ExprNode {
+    pub fn is_null(&self) -> bool {
+        matches!(
+            self,
+            Self::Constant(ConstantExprNode {
+                value: ConstantValue::Null
+            })
+        )
+    }
     pub fn ref_what_column(&self) -> Option<String> {
         match self {
             Self::Constant(_) => None,
@@ -148,14 +298,84 @@ impl ExprNode {
             }
             Self::ColumnRef(c) => Some(c.column_name.to_owned()),
             Self::Like(c) => c.child.ref_what_column(),
+            Self::Not(c) => c.child.ref_what_column(),
+            Self::IsNull(c) => c.child.ref_what_column(),
+            Self::Regexp(c) => c.child.ref_what_column(),
+            Self::FuncCall(c) => c.args.iter().find_map(|arg| arg.ref_what_column()),
+            Self::In(c) => c.child.ref_what_column(),
+            Self::Case(c) => c
+                .branches
+                .iter()
+                .flat_map(|(condition, result)| vec![condition, result])
+                .chain(c.else_expr.iter().map(|e| e.as_ref()))
+                .find_map(|node| node.ref_what_column()),
+            Self::Row(c) => c.values.iter().find_map(|value| value.ref_what_column()),
+            Self::Exists(_) | Self::Subquery(_) => None,
+        }
+    }
+    /// column refs of this expression, not including the ones inside subqueries
+    pub fn column_refs_mut(&mut self) -> Vec<&mut ColumnRefExprNode> {
+        match self {
+            Self::Constant(_) | Self::Exists(_) | Self::Subquery(_) => vec![],
+            Self::ColumnRef(c) => vec![c],
+            Self::Binary(b) => {
+                let mut refs = b.lhs.column_refs_mut();
+                refs.extend(b.rhs.column_refs_mut());
+                refs
+            }
+            Self::Like(c) => c.child.column_refs_mut(),
+            Self::Not(c) => c.child.column_refs_mut(),
+            Self::IsNull(c) => c.child.column_refs_mut(),
+            Self::Regexp(c) => c.child.column_refs_mut(),
+            Self::In(c) => c.child.column_refs_mut(),
+            Self::FuncCall(c) => c
+                .args
+                .iter_mut()
+                .flat_map(|arg| arg.column_refs_mut())
+                .collect(),
+            Self::Case(c) => c
+                .branches
+                .iter_mut()
+                .flat_map(|(condition, result)| vec![condition, result])
+                .chain(c.else_expr.iter_mut().map(|e| e.as_mut()))
+                .flat_map(|node| node.column_refs_mut())
+                .collect(),
+            Self::Row(c) => c
+                .values
+                .iter_mut()
+                .flat_map(|value| value.column_refs_mut())
+                .collect(),
+        }
+    }
+    /// direct sub expressions, not including the ones inside subqueries
+    pub fn children_mut(&mut self) -> Vec<&mut ExprNode> {
+        match self {
+            Self::Constant(_) | Self::ColumnRef(_) | Self::Exists(_) | Self::Subquery(_) => {
+                vec![]
+            }
+            Self::Binary(b) => vec![b.lhs.as_mut(), b.rhs.as_mut()],
+            Self::Like(c) => vec![c.child.as_mut()],
+            Self::Not(c) => vec![c.child.as_mut()],
+            Self::IsNull(c) => vec![c.child.as_mut()],
+            Self::Regexp(c) => vec![c.child.as_mut()],
+            Self::In(c) => vec![c.child.as_mut()],
+            Self::FuncCall(c) => c.args.iter_mut().collect(),
+            Self::Row(c) => c.values.iter_mut().collect(),
+            Self::Case(c) => c
+                .branches
+                .iter_mut()
+                .flat_map(|(condition, result)| vec![condition, result])
+                .chain(c.else_expr.iter_mut().map(|e| e.as_mut()))
+                .collect(),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Selectors {
     All,
-    Exprs(Vec<ExprNode>),
+    /// exprs with the names of the output columns
+    Exprs(Vec<(ExprNode, Option<String>)>),
     Agg(Vec<AggItem>),
 }
 
@@ -163,14 +383,53 @@ pub enum Selectors {
 pub struct DeleteStmt {
     pub table_name: String,
     pub where_exprs: Vec<ExprNode>,
+    /// at most this many rows are deleted
+    pub limit: Option<usize>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SelectStmt {
+    /// the tables selected from, none if selecting from a subquery
     pub table_names: Vec<String>,
+    /// the subquery in from, aka derived table
+    pub from_subquery: Option<Box<SelectStmt>>,
+    /// the name the columns of the subquery in from are qualified by
+    pub subquery_alias: Option<String>,
+    /// only the first row of each distinct value of these exprs is kept
+    pub distinct_on: Vec<ExprNode>,
     pub selectors: Selectors,
     pub where_exprs: Vec<ExprNode>,
     pub group_by_expr: Option<ExprNode>,
+    pub order_by: Vec<OrderByItem>,
+    /// at most this many rows are output
+    pub limit: Option<usize>,
+    /// queries combined with this one, from left to right
+    pub set_ops: Vec<(SetOp, SelectStmt)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetOp {
+    Union,
+    UnionAll,
+    Intersect,
+    Except,
+}
+
+impl ToString for SetOp {
+    fn to_string(&self) -> String {
+        match self {
+            Self::Union => "union".to_owned(),
+            Self::UnionAll => "union all".to_owned(),
+            Self::Intersect => "intersect".to_owned(),
+            Self::Except => "except".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OrderByItem {
+    pub expr: ExprNode,
+    pub desc: bool,
 }
 
 #[derive(Debug)]
@@ -194,16 +453,27 @@ pub struct CreateTableStmt {
     pub fields: Vec<Field>,
 }
 
+#[derive(Debug)]
+pub struct CreateTableAsStmt {
+    pub table_name: String,
+    pub select: SelectStmt,
+}
+
 #[derive(Debug)]
 pub struct InsertStmt {
     pub table_name: String,
     pub values: Vec<Vec<ExprNode>>,
+    /// output the record ids of the inserted tuples rather than the count
+    pub returning: bool,
 }
 
 #[derive(Debug)]
 pub struct LoadFromFileStmt {
     pub table_name: String,
     pub file_name: String,
+    pub binary: bool,
+    /// where the lines failed to load are written, csv only
+    pub rejects_file_name: Option<String>,
 }
 
 #[derive(Debug)]
@@ -229,12 +499,20 @@ pub struct UniqueField {
     pub column_names: Vec<String>,
 }
 
+#[derive(Debug)]
+pub struct CheckField {
+    /// position of the check expr in the sql
+    pub span: (usize, usize),
+    pub definition: String,
+}
+
 #[derive(Debug)]
 pub enum Field {
     Normal(NormalField),
     Primary(PrimaryField),
     Foreign(ForeignField),
     Unique(UniqueField),
+    Check(CheckField),
 }
 
 #[derive(Debug)]
@@ -255,6 +533,13 @@ pub struct AddUniqueStmt {
     pub column_names: Vec<String>,
 }
 
+#[derive(Debug)]
+pub struct RenameColumnStmt {
+    pub table_name: String,
+    pub column_name: String,
+    pub new_column_name: String,
+}
+
 #[derive(Debug)]
 pub struct AddForeignStmt {
     pub table_name: String,
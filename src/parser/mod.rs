@@ -1,12 +1,32 @@
 use crate::db::NaiveDBError;
-use crate::sql::StatementParser;
-use ast::Statement;
+use crate::sql::{ExprParser, StatementParser};
+use ast::{ExprNode, Field, Statement};
 
 pub mod ast;
 
 pub fn parse(sql: &str) -> Result<Statement, NaiveDBError> {
     let stmt_parser = StatementParser::new();
-    stmt_parser
+    let mut stmt = stmt_parser
+        .parse(sql)
+        .map_err(|e| NaiveDBError::Parse(e.to_string()))?;
+    match &mut stmt {
+        Statement::CreateView(stmt) => {
+            stmt.definition = sql[stmt.span.0..stmt.span.1].to_owned();
+        }
+        Statement::CreateTable(stmt) => {
+            for field in stmt.fields.iter_mut() {
+                if let Field::Check(field) = field {
+                    field.definition = sql[field.span.0..field.span.1].to_owned();
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(stmt)
+}
+
+pub fn parse_expr(sql: &str) -> Result<ExprNode, NaiveDBError> {
+    ExprParser::new()
         .parse(sql)
         .map_err(|e| NaiveDBError::Parse(e.to_string()))
 }
@@ -38,6 +58,13 @@ mod tests {
         assert!(sql::ExprParser::new().parse("123").is_ok());
         assert!(sql::ExprParser::new().parse("'hello'").is_ok());
         assert!(sql::ExprParser::new().parse("222hh").is_err());
+        // func call expr
+        assert!(sql::ExprParser::new().parse("date_add(v1, 7)").is_ok());
+        assert!(sql::ExprParser::new().parse("date_add(v1, 7").is_err());
+        // regexp expr
+        assert!(sql::ExprParser::new()
+            .parse("v2 regexp '^a[0-9]*.z$'")
+            .is_ok());
     }
 
     #[test]
@@ -46,19 +73,44 @@ mod tests {
         assert!(sql::StatementsParser::new()
             .parse("create table sample(v1 int not null, primary key (v1), foreign key (v2) references t (v1));")
             .is_ok());
+        assert!(sql::StatementsParser::new()
+            .parse("create table sample(v1 int not null, check (v1 > 0 and v1 < 10));")
+            .is_ok());
         // insert into
         assert!(sql::StatementsParser::new()
             .parse("insert into demo values (1, 2, 'hello'), (2, 3, 'world');")
             .is_ok());
         // desc
         assert!(sql::StatementsParser::new().parse("desc sample;").is_ok());
+        // pragma
+        assert!(sql::StatementsParser::new()
+            .parse("pragma index_stats t (v1, v2);")
+            .is_ok());
         // select from
         assert!(sql::StatementsParser::new()
             .parse("select * from t;")
             .is_ok());
+        assert!(sql::StatementsParser::new()
+            .parse("select * from t where v1 > 1 order by v1 desc, v2;")
+            .is_ok());
         assert!(sql::StatementsParser::new()
             .parse("select v1, v2, v3 from t;")
             .is_ok());
+        assert!(sql::StatementsParser::new()
+            .parse("select * from (select v1, count(*) c from t group by v1) where c > 2;")
+            .is_ok());
+        assert!(sql::StatementsParser::new()
+            .parse("select s.v1 from (select v1 from t) as s;")
+            .is_ok());
+        assert!(sql::StatementsParser::new()
+            .parse("select v1 from a union select v1 from b union all select v2 from c;")
+            .is_ok());
+        assert!(sql::StatementsParser::new()
+            .parse("select v1 from a union all;")
+            .is_err());
+        assert!(sql::StatementsParser::new()
+            .parse("select v1 from a intersect select v1 from b except select v2 from c;")
+            .is_ok());
         assert!(sql::StatementsParser::new()
             .parse("delete from t where v1 = 2;")
             .is_ok());
@@ -66,6 +118,9 @@ mod tests {
         assert!(sql::StatementsParser::new()
             .parse("select * from t where v1 = 3;")
             .is_ok());
+        assert!(sql::StatementsParser::new()
+            .parse("select * from t where false and TRUE;")
+            .is_ok());
         // add index
         assert!(sql::StatementsParser::new()
             .parse("alter table t add index (v1, v2);")
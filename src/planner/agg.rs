@@ -1,5 +1,5 @@
 use crate::datum::DataType;
-use crate::expr::{ConstantExpr, ExprImpl};
+use crate::expr::{ConstantExpr, ExprError, ExprImpl};
 use crate::parser::ast::{AggAction, AggItem, AggTarget, ExprNode};
 use crate::planner::{Plan, PlanError, Planner};
 use crate::table::Schema;
@@ -8,6 +8,10 @@ use itertools::Itertools;
 #[derive(Debug)]
 pub struct AggPlan {
     pub exprs_with_action: Vec<(ExprImpl, AggAction)>,
+    /// names of the output columns given by the query
+    pub aliases: Vec<Option<String>>,
+    /// whether each aggregate only reduces the unique values
+    pub distincts: Vec<bool>,
     pub group_by_expr: Option<ExprImpl>,
     pub child: Box<Plan>,
 }
@@ -20,6 +24,8 @@ impl Planner {
         group_by_expr: Option<ExprNode>,
         child: Plan,
     ) -> Result<Plan, PlanError> {
+        let aliases = items.iter().map(|item| item.alias.clone()).collect_vec();
+        let distincts = items.iter().map(|item| item.distinct).collect_vec();
         let exprs_with_action = items
             .into_iter()
             .map(|item| {
@@ -28,17 +34,25 @@ impl Planner {
                         ExprImpl::Constant(ConstantExpr::new(1.into(), DataType::new_as_int(false)))
                     }
                     AggTarget::Expr(expr) => {
-                        ExprImpl::from_ast(&expr, self.catalog.clone(), schema, None).unwrap()
+                        ExprImpl::from_ast(&expr, &self.settings.borrow(), schema, None).unwrap()
                     }
                 };
-                (expr, item.action)
+                // bool_and and bool_or only reduce bools
+                if matches!(item.action, AggAction::BoolAnd | AggAction::BoolOr)
+                    && !matches!(expr.return_type(), DataType::Bool(_))
+                {
+                    return Err(ExprError::NotMatch);
+                }
+                Ok((expr, item.action))
             })
-            .collect_vec();
+            .collect::<Result<Vec<_>, _>>()?;
         let group_by_expr = group_by_expr
             .as_ref()
-            .map(|node| ExprImpl::from_ast(node, self.catalog.clone(), schema, None).unwrap());
+            .map(|node| ExprImpl::from_ast(node, &self.settings.borrow(), schema, None).unwrap());
         Ok(Plan::Agg(AggPlan {
             exprs_with_action,
+            aliases,
+            distincts,
             group_by_expr,
             child: Box::new(child),
         }))
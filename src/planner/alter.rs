@@ -1,6 +1,8 @@
 use crate::catalog::CatalogError;
 use crate::expr::ExprImpl;
-use crate::parser::ast::{AddForeignStmt, AddIndexStmt, AddPrimaryStmt, AddUniqueStmt};
+use crate::parser::ast::{
+    AddForeignStmt, AddIndexStmt, AddPrimaryStmt, AddUniqueStmt, RenameColumnStmt,
+};
 use crate::planner::{Plan, PlanError, Planner};
 
 #[derive(Debug)]
@@ -29,6 +31,13 @@ pub struct AddForeignPlan {
     pub ref_column_names: Vec<String>,
 }
 
+#[derive(Debug)]
+pub struct RenameColumnPlan {
+    pub table_name: String,
+    pub column_name: String,
+    pub new_column_name: String,
+}
+
 impl Planner {
     pub fn plan_add_index(&self, stmt: AddIndexStmt) -> Result<Plan, PlanError> {
         let table = self.catalog.borrow().find_table(&stmt.table_name).unwrap();
@@ -36,7 +45,7 @@ impl Planner {
             .exprs
             .into_iter()
             .map(|node| {
-                ExprImpl::from_ast(&node, self.catalog.clone(), table.schema.as_ref(), None)
+                ExprImpl::from_ast(&node, &self.settings.borrow(), table.schema.as_ref(), None)
             })
             .collect::<Result<_, _>>()?;
         Ok(Plan::AddIndex(AddIndexPlan {
@@ -67,6 +76,20 @@ impl Planner {
             column_names: stmt.column_names,
         }))
     }
+    pub fn plan_rename_column(&self, stmt: RenameColumnStmt) -> Result<Plan, PlanError> {
+        let table = self.catalog.borrow().find_table(&stmt.table_name)?;
+        // the check constraints are kept as their text, so they must still bind after renaming
+        let mut schema = (*table.schema).clone();
+        schema.rename_column(&stmt.column_name, &stmt.new_column_name)?;
+        if self.plan_checks(&schema).is_err() {
+            return Err(PlanError::ColumnInCheck(stmt.column_name));
+        }
+        Ok(Plan::RenameColumn(RenameColumnPlan {
+            table_name: stmt.table_name,
+            column_name: stmt.column_name,
+            new_column_name: stmt.new_column_name,
+        }))
+    }
     pub fn plan_add_foreign(&self, stmt: AddForeignStmt) -> Result<Plan, PlanError> {
         Ok(Plan::AddForeign(AddForeignPlan {
             table_name: stmt.table_name,
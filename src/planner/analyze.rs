@@ -0,0 +1,29 @@
+use crate::parser::ast::{AnalyzeStmt, VacuumStmt};
+use crate::planner::{Plan, PlanError, Planner};
+
+#[derive(Debug)]
+pub struct AnalyzePlan {
+    pub table_name: String,
+}
+
+#[derive(Debug)]
+pub struct VacuumPlan {
+    pub table_name: String,
+    pub analyze: bool,
+}
+
+impl Planner {
+    pub fn plan_analyze(&self, stmt: AnalyzeStmt) -> Result<Plan, PlanError> {
+        let _ = self.catalog.borrow().find_table(&stmt.table_name)?;
+        Ok(Plan::Analyze(AnalyzePlan {
+            table_name: stmt.table_name,
+        }))
+    }
+    pub fn plan_vacuum(&self, stmt: VacuumStmt) -> Result<Plan, PlanError> {
+        let _ = self.catalog.borrow().find_table(&stmt.table_name)?;
+        Ok(Plan::Vacuum(VacuumPlan {
+            table_name: stmt.table_name,
+            analyze: stmt.analyze,
+        }))
+    }
+}
@@ -1,4 +1,8 @@
-use crate::parser::ast::{CreateTableStmt, Field};
+use crate::catalog::CatalogError;
+use crate::datum::DataType;
+use crate::expr::ExprImpl;
+use crate::parser::ast::{CreateTableAsStmt, CreateTableStmt, Field};
+use crate::parser::parse_expr;
 use crate::planner::{Plan, PlanError, Planner};
 use crate::table::{Schema, SchemaError};
 use itertools::Itertools;
@@ -9,6 +13,12 @@ pub struct CreateTablePlan {
     pub schema: Schema,
 }
 
+#[derive(Debug)]
+pub struct CreateTableAsPlan {
+    pub table_name: String,
+    pub child: Box<Plan>,
+}
+
 impl Planner {
     pub fn plan_create_table(&self, stmt: CreateTableStmt) -> Result<Plan, PlanError> {
         let slice = stmt
@@ -70,9 +80,43 @@ impl Planner {
                 schema.unique.push(unique_set);
             }
         }
+        // check field
+        for field in &stmt.fields {
+            if let Field::Check(check) = field {
+                schema.checks.push(check.definition.clone());
+            }
+        }
+        self.plan_checks(&schema)?;
         Ok(Plan::CreateTable(CreateTablePlan {
             table_name: stmt.table_name,
             schema,
         }))
     }
+    /// the check constraints of the schema, each must be a boolean expr over its columns
+    pub fn plan_checks(&self, schema: &Schema) -> Result<Vec<ExprImpl>, PlanError> {
+        schema
+            .checks
+            .iter()
+            .map(|definition| {
+                let node = parse_expr(definition)
+                    .map_err(|_| PlanError::InvalidCheck(definition.clone()))?;
+                let expr = ExprImpl::from_ast(&node, &self.settings.borrow(), schema, None)?;
+                match expr.return_type() {
+                    DataType::Bool(_) => Ok(expr),
+                    _ => Err(PlanError::InvalidCheck(definition.clone())),
+                }
+            })
+            .collect()
+    }
+    /// the columns of the table are the ones the select outputs
+    pub fn plan_create_table_as(&self, stmt: CreateTableAsStmt) -> Result<Plan, PlanError> {
+        if self.catalog.borrow().find_table(&stmt.table_name).is_ok() {
+            return Err(CatalogError::Duplicated.into());
+        }
+        let child = self.plan_select(stmt.select)?;
+        Ok(Plan::CreateTableAs(CreateTableAsPlan {
+            table_name: stmt.table_name,
+            child: Box::new(child),
+        }))
+    }
 }
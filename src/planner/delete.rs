@@ -8,6 +8,7 @@ pub struct DeletePlan {
     pub child: Box<Plan>,
     pub index_page_ids: Vec<PageID>,
     pub table_page_id: PageID,
+    pub limit: Option<usize>,
 }
 
 impl Planner {
@@ -15,10 +16,11 @@ impl Planner {
         &self,
         table_name: &str,
         where_exprs: &[ExprNode],
+        limit: Option<usize>,
     ) -> Result<Plan, PlanError> {
-        let plan = self.plan_scan(table_name, where_exprs, true);
+        let plan = self.plan_scan(table_name, where_exprs, true)?;
         let table = self.catalog.borrow().find_table(table_name)?;
-        let plan = self.plan_filter(table.schema.as_ref(), where_exprs, plan);
+        let plan = self.plan_filter(table.schema.as_ref(), where_exprs, plan)?;
         let indexes = self
             .catalog
             .borrow()
@@ -37,6 +39,7 @@ impl Planner {
                 .find_table(table_name)
                 .unwrap()
                 .page_id(),
+            limit,
         }))
     }
 }
@@ -0,0 +1,57 @@
+use crate::execution::{agg_schema, project_schema, scan_schema, set_op_schema};
+use crate::planner::{Plan, PlanError, Planner};
+use crate::table::{SchemaRef, Table};
+
+/// the subquery in from, its columns are qualified by the alias
+#[derive(Debug)]
+pub struct DerivedPlan {
+    pub alias: Option<String>,
+    pub schema: SchemaRef,
+    pub child: Box<Plan>,
+}
+
+impl Planner {
+    /// the schema of the tuples a query plan outputs, the same as its executor gives
+    pub fn schema_of(&self, plan: &Plan) -> Result<SchemaRef, PlanError> {
+        match plan {
+            Plan::SeqScan(plan) => {
+                let table = self.catalog.borrow().find_table(&plan.table_name)?;
+                Ok(scan_schema(&table.schema, plan.with_record_id))
+            }
+            Plan::IndexScan(plan) => {
+                let table = Table::open(plan.table_page_id, self.bpm.clone());
+                Ok(scan_schema(&table.schema, plan.with_record_id))
+            }
+            Plan::IndexSkipScan(plan) => {
+                let table = Table::open(plan.table_page_id, self.bpm.clone());
+                Ok(scan_schema(&table.schema, plan.with_record_id))
+            }
+            Plan::RowIdFetch(plan) => {
+                let table = Table::open(plan.table_page_id, self.bpm.clone());
+                Ok(table.schema.clone())
+            }
+            Plan::Values(plan) => Ok(plan.schema.clone()),
+            Plan::NestedLoopJoin(plan) => Ok(plan.schema.clone()),
+            Plan::Derived(plan) => Ok(plan.schema.clone()),
+            Plan::Project(plan) => Ok(project_schema(&plan.exprs, &plan.aliases)),
+            Plan::Agg(plan) => Ok(agg_schema(
+                &plan.exprs_with_action,
+                &plan.aliases,
+                &plan.distincts,
+            )),
+            Plan::SetOp(plan) => Ok(set_op_schema(
+                &self.schema_of(&plan.lhs)?,
+                &self.schema_of(&plan.rhs)?,
+            )?),
+            Plan::Filter(plan) => self.schema_of(&plan.child),
+            Plan::Empty(plan) => self.schema_of(&plan.child),
+            Plan::Exists(plan) => self.schema_of(&plan.child),
+            Plan::Sort(plan) => self.schema_of(&plan.child),
+            Plan::Limit(plan) => self.schema_of(&plan.child),
+            Plan::TopN(plan) => self.schema_of(&plan.child),
+            Plan::DistinctOn(plan) => self.schema_of(&plan.child),
+            // only the plans of a select are asked
+            _ => unreachable!(),
+        }
+    }
+}
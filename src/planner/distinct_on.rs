@@ -0,0 +1,56 @@
+use crate::expr::ExprImpl;
+use crate::parser::ast::ExprNode;
+use crate::planner::{Plan, PlanError, Planner, SortPlan};
+use crate::table::Schema;
+use itertools::Itertools;
+use std::collections::HashSet;
+
+/// the child is sorted by the exprs first, so the rows of a group are adjacent
+#[derive(Debug)]
+pub struct DistinctOnPlan {
+    pub exprs: Vec<ExprImpl>,
+    pub child: Box<Plan>,
+}
+
+impl Planner {
+    /// keep the first row of each group, the leading sort keys must be the exprs of distinct
+    /// on, or the rows are sorted by them if not sorted at all
+    pub fn plan_distinct_on(
+        &self,
+        table_names: &[String],
+        schema: &Schema,
+        distinct_on: Vec<ExprNode>,
+        child: Plan,
+    ) -> Result<Plan, PlanError> {
+        if distinct_on.is_empty() {
+            return Ok(child);
+        }
+        let exprs = distinct_on
+            .into_iter()
+            .map(|node| self.plan_sort_key(table_names, schema, node))
+            .collect::<Result<Vec<_>, _>>()?;
+        let child = match child {
+            Plan::Sort(plan) => {
+                let keys: HashSet<_> = exprs.iter().map(|e| e.to_string()).collect();
+                let leading: HashSet<_> = plan
+                    .exprs_with_desc
+                    .iter()
+                    .take(exprs.len())
+                    .map(|(e, _)| e.to_string())
+                    .collect();
+                if keys != leading {
+                    return Err(PlanError::DistinctOnOrderBy);
+                }
+                Plan::Sort(plan)
+            }
+            child => Plan::Sort(SortPlan {
+                exprs_with_desc: exprs.iter().map(|e| (e.clone(), false)).collect_vec(),
+                child: Box::new(child),
+            }),
+        };
+        Ok(Plan::DistinctOn(DistinctOnPlan {
+            exprs,
+            child: Box::new(child),
+        }))
+    }
+}
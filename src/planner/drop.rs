@@ -1,6 +1,7 @@
 use crate::expr::ExprImpl;
 use crate::parser::ast::{
     DropDatabaseStmt, DropForeignStmt, DropIndexStmt, DropPrimaryStmt, DropTableStmt,
+    TruncateTableStmt,
 };
 use crate::planner::{Plan, PlanError, Planner};
 use crate::table::SchemaError;
@@ -9,6 +10,13 @@ use itertools::Itertools;
 #[derive(Debug)]
 pub struct DropTablePlan {
     pub table_name: String,
+    pub cascade: bool,
+}
+
+#[derive(Debug)]
+pub struct TruncateTablePlan {
+    pub table_name: String,
+    pub cascade: bool,
 }
 
 #[derive(Debug)]
@@ -37,6 +45,14 @@ impl Planner {
     pub fn plan_drop_table(&self, stmt: DropTableStmt) -> Result<Plan, PlanError> {
         Ok(Plan::DropTable(DropTablePlan {
             table_name: stmt.table_name,
+            cascade: stmt.cascade,
+        }))
+    }
+    pub fn plan_truncate_table(&self, stmt: TruncateTableStmt) -> Result<Plan, PlanError> {
+        let _ = self.catalog.borrow().find_table(&stmt.table_name)?;
+        Ok(Plan::TruncateTable(TruncateTablePlan {
+            table_name: stmt.table_name,
+            cascade: stmt.cascade,
         }))
     }
     pub fn plan_drop_database(&self, stmt: DropDatabaseStmt) -> Result<Plan, PlanError> {
@@ -66,7 +82,7 @@ impl Planner {
         let exprs = stmt
             .exprs
             .into_iter()
-            .map(|e| ExprImpl::from_ast(&e, self.catalog.clone(), &table.schema, None).unwrap())
+            .map(|e| ExprImpl::from_ast(&e, &self.settings.borrow(), &table.schema, None).unwrap())
             .collect_vec();
         Ok(Plan::DropIndex(DropIndexPlan {
             table_name: stmt.table_name,
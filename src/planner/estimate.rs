@@ -0,0 +1,247 @@
+use crate::datum::Datum;
+use crate::expr::{BinaryOp, ExprImpl};
+use crate::index::BPTIndex;
+use crate::planner::{LimitPlan, Plan, Planner, TopNPlan};
+use crate::table::{ColumnStats, Table};
+use chrono::Datelike;
+
+/// selectivity of a predicate the stats tell nothing about
+const DEFAULT_SELECTIVITY: f64 = 1.0 / 3.0;
+
+/// the estimated output of a plan node, from the stats collected by the last analyze
+#[derive(Debug, Clone)]
+pub struct Estimate {
+    pub rows: f64,
+    /// rows before any predicate, the predicates of a filter are estimated against these
+    table_rows: f64,
+    /// stats of the output columns, kept while the tuples are still those of a single table
+    columns: Option<Vec<ColumnStats>>,
+}
+
+fn as_number(datum: &Datum) -> Option<f64> {
+    match datum {
+        Datum::Int(Some(v)) => Some(*v as f64),
+        Datum::Float(Some(v)) => Some(v.into_inner() as f64),
+        Datum::Date(Some(v)) => Some(v.num_days_from_ce() as f64),
+        _ => None,
+    }
+}
+
+fn non_null_fraction(stats: &ColumnStats) -> f64 {
+    match stats.num_rows {
+        0 => 0.0,
+        rows => (rows - stats.num_nulls) as f64 / rows as f64,
+    }
+}
+
+/// the values are taken as evenly spread over the distinct ones
+fn equal_selectivity(stats: &ColumnStats) -> f64 {
+    match stats.num_distinct {
+        0 => 0.0,
+        distinct => non_null_fraction(stats) / distinct as f64,
+    }
+}
+
+/// the values are taken as evenly spread between min and max
+fn range_selectivity(stats: &ColumnStats, begin: Option<&Datum>, end: Option<&Datum>) -> f64 {
+    let (min, max) = match (as_number(&stats.min), as_number(&stats.max)) {
+        (Some(min), Some(max)) => (min, max),
+        _ => return DEFAULT_SELECTIVITY,
+    };
+    let bound = |datum: Option<&Datum>, default: f64| match datum {
+        Some(datum) => as_number(datum),
+        None => Some(default),
+    };
+    let (begin, end) = match (bound(begin, min), bound(end, max)) {
+        (Some(begin), Some(end)) => (begin.max(min), end.min(max)),
+        _ => return DEFAULT_SELECTIVITY,
+    };
+    let fraction = match max - min {
+        width if width <= 0.0 => (begin <= end) as i32 as f64,
+        width => ((end - begin) / width).max(0.0),
+    };
+    fraction * non_null_fraction(stats)
+}
+
+/// the fraction of tuples passing the predicate
+fn selectivity(expr: &ExprImpl, columns: &[ColumnStats]) -> f64 {
+    let binary = match expr {
+        ExprImpl::Binary(binary) => binary,
+        _ => return DEFAULT_SELECTIVITY,
+    };
+    let (column_ref, datum, op) = match (binary.children()[0], binary.children()[1]) {
+        (ExprImpl::ColumnRef(column_ref), ExprImpl::Constant(c)) => {
+            (column_ref, c.get_value(), binary.op().clone())
+        }
+        // the constant on the left flips the comparison
+        (ExprImpl::Constant(c), ExprImpl::ColumnRef(column_ref)) => {
+            let op = match binary.op() {
+                BinaryOp::LessThan => BinaryOp::GreaterThan,
+                BinaryOp::LessThanOrEqual => BinaryOp::GreaterThanOrEqual,
+                BinaryOp::GreaterThan => BinaryOp::LessThan,
+                BinaryOp::GreaterThanOrEqual => BinaryOp::LessThanOrEqual,
+                op => op.clone(),
+            };
+            (column_ref, c.get_value(), op)
+        }
+        _ => return DEFAULT_SELECTIVITY,
+    };
+    let stats = match columns.get(column_ref.as_idx()) {
+        Some(stats) => stats,
+        None => return DEFAULT_SELECTIVITY,
+    };
+    match op {
+        BinaryOp::Equal => equal_selectivity(stats),
+        BinaryOp::LessThan | BinaryOp::LessThanOrEqual => {
+            range_selectivity(stats, None, Some(&datum))
+        }
+        BinaryOp::GreaterThan | BinaryOp::GreaterThanOrEqual => {
+            range_selectivity(stats, Some(&datum), None)
+        }
+        _ => DEFAULT_SELECTIVITY,
+    }
+}
+
+impl Estimate {
+    fn of_table(table: &Table) -> Option<Self> {
+        let columns = table.stats()?;
+        let rows = columns.first().map_or(0, |stats| stats.num_rows) as f64;
+        Some(Self {
+            rows,
+            table_rows: rows,
+            columns: Some(columns),
+        })
+    }
+    /// the same rows with the columns no longer those of the table
+    fn without_columns(self) -> Self {
+        Self {
+            columns: None,
+            ..self
+        }
+    }
+}
+
+impl Planner {
+    /// estimated rows output by the plan, none if a table under it is not analyzed
+    pub fn estimate(&self, plan: &Plan) -> Option<Estimate> {
+        match plan {
+            Plan::SeqScan(plan) => {
+                let table = self.catalog.borrow().find_table(&plan.table_name).ok()?;
+                Estimate::of_table(&table)
+            }
+            // only the leading expr of the index is bounded for sure
+            Plan::IndexScan(plan) => {
+                let table = Table::open(plan.table_page_id, self.bpm.clone());
+                let estimate = Estimate::of_table(&table)?;
+                let index =
+                    BPTIndex::open(self.bpm.clone(), plan.index_page_id, &table.schema).ok()?;
+                let begin = plan.begin_datums.as_ref().and_then(|d| d.first());
+                let end = plan.end_datums.as_ref().and_then(|d| d.first());
+                let stats = match (index.exprs.first(), estimate.columns.as_ref()) {
+                    (Some(ExprImpl::ColumnRef(column_ref)), Some(columns)) => {
+                        columns.get(column_ref.as_idx())
+                    }
+                    _ => None,
+                };
+                let selectivity = match stats {
+                    Some(stats) if begin.is_some() && begin == end => equal_selectivity(stats),
+                    Some(stats) => range_selectivity(stats, begin, end),
+                    None => 1.0,
+                };
+                Some(Estimate {
+                    rows: estimate.rows * selectivity,
+                    ..estimate
+                })
+            }
+            Plan::IndexSkipScan(plan) => {
+                Estimate::of_table(&Table::open(plan.table_page_id, self.bpm.clone()))
+            }
+            // all the predicates are kept in the filter, even the ones bounding the scan
+            Plan::Filter(plan) => {
+                let estimate = self.estimate(&plan.child)?;
+                let selectivity = match &estimate.columns {
+                    Some(columns) => plan
+                        .exprs
+                        .iter()
+                        .map(|expr| selectivity(expr, columns))
+                        .product(),
+                    None => DEFAULT_SELECTIVITY.powi(plan.exprs.len() as i32),
+                };
+                Some(Estimate {
+                    rows: estimate.rows.min(estimate.table_rows * selectivity),
+                    ..estimate
+                })
+            }
+            Plan::Project(plan) => Some(self.estimate(&plan.child)?.without_columns()),
+            Plan::Sort(plan) => self.estimate(&plan.child),
+            Plan::Limit(LimitPlan { limit, child }) | Plan::TopN(TopNPlan { limit, child, .. }) => {
+                let estimate = self.estimate(child)?;
+                Some(Estimate {
+                    rows: estimate.rows.min(*limit as f64),
+                    ..estimate
+                })
+            }
+            Plan::DistinctOn(plan) => Some(self.estimate(&plan.child)?.without_columns()),
+            Plan::Derived(plan) => Some(self.estimate(&plan.child)?.without_columns()),
+            Plan::Agg(plan) => {
+                let estimate = self.estimate(&plan.child)?;
+                let rows = match (&plan.group_by_expr, &estimate.columns) {
+                    (None, _) => 1.0,
+                    (Some(ExprImpl::ColumnRef(column_ref)), Some(columns)) => columns
+                        .get(column_ref.as_idx())
+                        .map_or(estimate.rows, |stats| {
+                            (stats.num_distinct as f64).min(estimate.rows)
+                        }),
+                    (Some(_), _) => estimate.rows,
+                };
+                Some(Estimate {
+                    rows,
+                    table_rows: rows,
+                    columns: None,
+                })
+            }
+            Plan::NestedLoopJoin(plan) => {
+                let rows = plan
+                    .children
+                    .iter()
+                    .map(|child| self.estimate(child).map(|estimate| estimate.rows))
+                    .product::<Option<f64>>()?;
+                let rows = rows * DEFAULT_SELECTIVITY.powi(plan.exprs.len() as i32);
+                Some(Estimate {
+                    rows,
+                    table_rows: rows,
+                    columns: None,
+                })
+            }
+            Plan::Empty(plan) => Some(Estimate {
+                rows: 0.0,
+                table_rows: 0.0,
+                ..self.estimate(&plan.child)?
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_selectivity() {
+        let stats = ColumnStats {
+            min: 0.into(),
+            max: 100.into(),
+            num_nulls: 50,
+            num_rows: 100,
+            num_distinct: 10,
+        };
+        assert_eq!(range_selectivity(&stats, None, Some(&25.into())), 0.125);
+        assert_eq!(
+            range_selectivity(&stats, Some(&50.into()), Some(&200.into())),
+            0.25
+        );
+        assert_eq!(range_selectivity(&stats, Some(&200.into()), None), 0.0);
+        assert_eq!(equal_selectivity(&stats), 0.05);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::expr::ExprImpl;
+use crate::parser::ast::{ColumnRefExprNode, SelectStmt, Selectors};
+use crate::planner::{Plan, PlanError, Planner};
+use crate::table::{Schema, SchemaError, SchemaRef};
+use std::rc::Rc;
+
+#[derive(Debug)]
+pub struct ExistsPlan {
+    pub child: Box<Plan>,
+    pub subquery: Box<Plan>,
+    /// correlated predicates, evaluated on outer tuple ++ inner tuple
+    pub exprs: Vec<ExprImpl>,
+    pub schema: SchemaRef,
+}
+
+impl Planner {
+    pub fn table_name_of(
+        &self,
+        column_ref: &ColumnRefExprNode,
+        table_names: &[String],
+    ) -> Option<String> {
+        match &column_ref.table_name {
+            Some(table_name) => table_names.iter().find(|name| *name == table_name).cloned(),
+            None => table_names
+                .iter()
+                .find(|name| {
+                    let table = self.catalog.borrow().find_table(name).unwrap();
+                    table
+                        .schema
+                        .index_by_column_name(&column_ref.column_name)
+                        .is_some()
+                })
+                .cloned(),
+        }
+    }
+
+    pub fn plan_exists(
+        &self,
+        outer_table_names: &[String],
+        subquery: SelectStmt,
+        child: Plan,
+    ) -> Result<Plan, PlanError> {
+        if subquery.from_subquery.is_some() {
+            return Err(PlanError::UnsupportedDerivedTable);
+        }
+        if !subquery.set_ops.is_empty() {
+            return Err(PlanError::UnsupportedSetOp);
+        }
+        for table_name in &subquery.table_names {
+            let _ = self.catalog.borrow().find_table(table_name)?;
+        }
+        let schema = Rc::new(Schema::from_type_and_names(
+            &[
+                self.type_and_names_of(outer_table_names, true),
+                self.type_and_names_of(&subquery.table_names, true),
+            ]
+            .concat(),
+        ));
+        // predicates only referring the inner tables are pushed into the subquery, the
+        // others are evaluated against each outer tuple
+        let mut local_exprs = vec![];
+        let mut correlated_exprs = vec![];
+        for mut expr in subquery.where_exprs {
+            let mut correlated = false;
+            for column_ref in expr.column_refs_mut() {
+                if self
+                    .table_name_of(column_ref, &subquery.table_names)
+                    .is_none()
+                {
+                    self.table_name_of(column_ref, outer_table_names)
+                        .ok_or(SchemaError::ColumnNotFound)?;
+                    correlated = true;
+                }
+            }
+            if !correlated {
+                local_exprs.push(expr);
+                continue;
+            }
+            for column_ref in expr.column_refs_mut() {
+                let table_name = self
+                    .table_name_of(column_ref, &subquery.table_names)
+                    .or_else(|| self.table_name_of(column_ref, outer_table_names))
+                    .unwrap();
+                column_ref.column_name = format!("{}.{}", table_name, column_ref.column_name);
+                column_ref.table_name = None;
+            }
+            correlated_exprs.push(expr);
+        }
+        let subquery_plan = self.plan_select(SelectStmt {
+            table_names: subquery.table_names,
+            from_subquery: None,
+            subquery_alias: None,
+            distinct_on: vec![],
+            selectors: Selectors::All,
+            where_exprs: local_exprs,
+            group_by_expr: None,
+            order_by: vec![],
+            limit: None,
+            set_ops: vec![],
+        })?;
+        let exprs = correlated_exprs
+            .iter()
+            .map(|node| {
+                let return_type_hint = node.ref_what_column().and_then(|column_name| {
+                    schema
+                        .columns
+                        .iter()
+                        .find(|c| c.desc == column_name)
+                        .map(|c| c.data_type)
+                });
+                ExprImpl::from_ast(node, &self.settings.borrow(), &schema, return_type_hint)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Plan::Exists(ExistsPlan {
+            child: Box::new(child),
+            subquery: Box::new(subquery_plan),
+            exprs,
+            schema,
+        }))
+    }
+}
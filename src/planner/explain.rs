@@ -0,0 +1,140 @@
+use crate::expr::ExprImpl;
+use crate::parser::ast::{AggAction, SelectStmt};
+use crate::planner::{Plan, PlanError, Planner};
+use itertools::Itertools;
+
+fn join_exprs(exprs: &[ExprImpl], sep: &str) -> String {
+    exprs.iter().map(|e| e.to_string()).join(sep)
+}
+
+fn sort_keys(exprs_with_desc: &[(ExprImpl, bool)]) -> String {
+    exprs_with_desc
+        .iter()
+        .map(|(expr, desc)| match desc {
+            true => format!("{} desc", expr),
+            false => expr.to_string(),
+        })
+        .join(", ")
+}
+
+impl Plan {
+    /// a line for the node itself, children are not included
+    fn describe(&self) -> String {
+        match self {
+            Plan::SeqScan(plan) => format!("SeqScan {}", plan.table_name),
+            Plan::IndexScan(plan) => format!(
+                "IndexScan index {} of table {}, from {:?} to {:?}",
+                plan.index_page_id, plan.table_page_id, plan.begin_datums, plan.end_datums
+            ),
+            Plan::IndexSkipScan(plan) => format!(
+                "IndexSkipScan index {} of table {}, from {:?} to {:?} for each leading key",
+                plan.index_page_id, plan.table_page_id, plan.begin_datums, plan.end_datums
+            ),
+            Plan::RowIdFetch(plan) => format!(
+                "RowIdFetch table {} at ({}, {})",
+                plan.table_page_id, plan.record_id.0, plan.record_id.1
+            ),
+            Plan::Filter(plan) => format!("Filter {}", join_exprs(&plan.exprs, " and ")),
+            Plan::Project(plan) => format!("Project {}", join_exprs(&plan.exprs, ", ")),
+            // the nested loop join is the only join algorithm, it checks the predicates
+            // on every pair of tuples
+            Plan::NestedLoopJoin(plan) => match plan.exprs.is_empty() {
+                true => "NestedLoopJoin, cross product".to_string(),
+                false => format!("NestedLoopJoin on {}", join_exprs(&plan.exprs, " and ")),
+            },
+            Plan::Agg(plan) => {
+                let items = plan
+                    .exprs_with_action
+                    .iter()
+                    .zip(plan.distincts.iter())
+                    .map(|((expr, action), distinct)| match (action, distinct) {
+                        (AggAction::No, _) => expr.to_string(),
+                        (action, true) => format!("{}(distinct {})", action.to_string(), expr),
+                        (action, false) => format!("{}({})", action.to_string(), expr),
+                    })
+                    .join(", ");
+                match &plan.group_by_expr {
+                    Some(expr) => format!("Agg {} group by {}", items, expr),
+                    None => format!("Agg {}", items),
+                }
+            }
+            Plan::Sort(plan) => format!("Sort {}", sort_keys(&plan.exprs_with_desc)),
+            Plan::Limit(plan) => format!("Limit {}", plan.limit),
+            Plan::TopN(plan) => format!(
+                "TopN {} by {}",
+                plan.limit,
+                sort_keys(&plan.exprs_with_desc)
+            ),
+            Plan::DistinctOn(plan) => format!("DistinctOn {}", join_exprs(&plan.exprs, ", ")),
+            Plan::Exists(plan) => match plan.exprs.is_empty() {
+                true => "Exists".to_string(),
+                false => format!("Exists on {}", join_exprs(&plan.exprs, " and ")),
+            },
+            // the child is never executed, so it is not shown
+            Plan::Empty(_) => "Empty, the predicates are always false".to_string(),
+            Plan::Values(plan) => format!("Values {} rows", plan.values.len()),
+            Plan::SetOp(plan) => format!("SetOp {}", plan.op.to_string()),
+            Plan::Derived(plan) => match &plan.alias {
+                Some(alias) => format!("Derived {}", alias),
+                None => "Derived".to_string(),
+            },
+            // the name of the variant
+            plan => format!("{:?}", plan)
+                .split(|c: char| !c.is_alphanumeric())
+                .next()
+                .unwrap()
+                .to_string(),
+        }
+    }
+    /// notes listed under the node, before the children
+    fn notes(&self) -> Vec<String> {
+        match self {
+            Plan::SeqScan(plan) => plan.index_choices.clone(),
+            Plan::IndexScan(plan) => plan.index_choices.clone(),
+            Plan::IndexSkipScan(plan) => plan.index_choices.clone(),
+            _ => vec![],
+        }
+    }
+    fn children(&self) -> Vec<&Plan> {
+        match self {
+            Plan::Filter(plan) => vec![plan.child.as_ref()],
+            Plan::Project(plan) => vec![plan.child.as_ref()],
+            Plan::NestedLoopJoin(plan) => plan.children.iter().collect_vec(),
+            Plan::Agg(plan) => vec![plan.child.as_ref()],
+            Plan::Sort(plan) => vec![plan.child.as_ref()],
+            Plan::Limit(plan) => vec![plan.child.as_ref()],
+            Plan::TopN(plan) => vec![plan.child.as_ref()],
+            Plan::DistinctOn(plan) => vec![plan.child.as_ref()],
+            Plan::Exists(plan) => vec![plan.child.as_ref(), plan.subquery.as_ref()],
+            Plan::SetOp(plan) => vec![plan.lhs.as_ref(), plan.rhs.as_ref()],
+            Plan::Derived(plan) => vec![plan.child.as_ref()],
+            _ => vec![],
+        }
+    }
+    /// the plan tree, one node a line, children indented under the parent, the estimated
+    /// rows are shown for the nodes with one
+    pub fn explain(&self, estimate: &dyn Fn(&Plan) -> Option<f64>) -> Vec<String> {
+        let mut lines = vec![match estimate(self) {
+            Some(rows) => format!("{} (rows ~{})", self.describe(), rows.round()),
+            None => self.describe(),
+        }];
+        lines.extend(self.notes().into_iter().map(|note| format!("  - {}", note)));
+        for child in self.children() {
+            lines.extend(
+                child
+                    .explain(estimate)
+                    .into_iter()
+                    .map(|line| format!("  {}", line)),
+            );
+        }
+        lines
+    }
+}
+
+impl Planner {
+    pub fn plan_explain(&self, stmt: SelectStmt) -> Result<Plan, PlanError> {
+        let plan = self.plan_select(stmt)?;
+        let lines = plan.explain(&|plan| self.estimate(plan).map(|estimate| estimate.rows));
+        Ok(Plan::Explain(lines))
+    }
+}
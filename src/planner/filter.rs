@@ -1,6 +1,7 @@
-use crate::expr::ExprImpl;
+use crate::datum::{DataType, Datum};
+use crate::expr::{ExprError, ExprImpl};
 use crate::parser::ast::ExprNode;
-use crate::planner::{Plan, Planner};
+use crate::planner::{Plan, PlanError, Planner};
 use crate::table::Schema;
 use itertools::Itertools;
 
@@ -10,8 +11,29 @@ pub struct FilterPlan {
     pub child: Box<Plan>,
 }
 
+/// no tuples, the child is never executed and only gives the schema
+#[derive(Debug)]
+pub struct EmptyPlan {
+    pub child: Box<Plan>,
+}
+
+/// whether a predicate is folded into false or null, so that no tuple passes
+pub fn always_false(exprs: &[ExprImpl]) -> bool {
+    exprs.iter().any(|expr| {
+        matches!(
+            expr.as_constant(),
+            Some(Datum::Bool(Some(false))) | Some(Datum::Bool(None))
+        )
+    })
+}
+
 impl Planner {
-    pub fn plan_filter(&self, schema: &Schema, where_exprs: &[ExprNode], plan: Plan) -> Plan {
+    /// predicates over the schema, always-true ones are dropped
+    pub fn plan_predicates(
+        &self,
+        schema: &Schema,
+        where_exprs: &[ExprNode],
+    ) -> Result<Vec<ExprImpl>, PlanError> {
         let exprs = where_exprs
             .iter()
             .map(|node| {
@@ -21,18 +43,48 @@ impl Planner {
                         .iter()
                         .find(|c| c.desc == column_name)
                         .map(|c| c.data_type)
+                } else if node.is_null() {
+                    // a bare null predicate is an unknown bool
+                    Some(DataType::new_as_bool(true))
                 } else {
                     None
                 };
-                ExprImpl::from_ast(node, self.catalog.clone(), schema, return_type_hint).unwrap()
+                let mut expr =
+                    ExprImpl::from_ast(node, &self.settings.borrow(), schema, return_type_hint)?;
+                expr.fold();
+                match expr.return_type() {
+                    DataType::Bool(_) => Ok(expr),
+                    data_type => Err(ExprError::IncompatibleTypes(
+                        data_type,
+                        DataType::new_as_bool(true),
+                    )
+                    .into()),
+                }
             })
-            .collect_vec();
+            .collect::<Result<Vec<_>, PlanError>>()?;
+        Ok(exprs
+            .into_iter()
+            .filter(|expr| expr.as_constant() != Some(Datum::Bool(Some(true))))
+            .collect_vec())
+    }
+    pub fn plan_filter(
+        &self,
+        schema: &Schema,
+        where_exprs: &[ExprNode],
+        plan: Plan,
+    ) -> Result<Plan, PlanError> {
+        let exprs = self.plan_predicates(schema, where_exprs)?;
+        if always_false(&exprs) {
+            return Ok(Plan::Empty(EmptyPlan {
+                child: Box::new(plan),
+            }));
+        }
         match exprs.is_empty() {
-            true => plan,
-            false => Plan::Filter(FilterPlan {
+            true => Ok(plan),
+            false => Ok(Plan::Filter(FilterPlan {
                 exprs,
                 child: Box::new(plan),
-            }),
+            })),
         }
     }
 }
@@ -1,3 +1,4 @@
+use crate::expr::ExprImpl;
 use crate::parser::ast::InsertStmt;
 use crate::planner::{Plan, PlanError, Planner};
 
@@ -5,18 +6,30 @@ use crate::planner::{Plan, PlanError, Planner};
 pub struct InsertPlan {
     pub table_name: String,
     pub child: Box<Plan>,
+    /// output the record ids of the inserted tuples rather than the count
+    pub returning: bool,
+    /// check constraints of the table
+    pub checks: Vec<ExprImpl>,
 }
 
 impl Planner {
     pub fn plan_insert_from_values(&self, stmt: InsertStmt) -> Result<Plan, PlanError> {
         let table = self.catalog.borrow().find_table(&stmt.table_name)?;
         let child = self.plan_values(stmt.values, table.schema.clone())?;
-        self.plan_insert(&stmt.table_name, child)
+        self.plan_insert(&stmt.table_name, child, stmt.returning)
     }
-    pub fn plan_insert(&self, table_name: &str, child: Plan) -> Result<Plan, PlanError> {
+    pub fn plan_insert(
+        &self,
+        table_name: &str,
+        child: Plan,
+        returning: bool,
+    ) -> Result<Plan, PlanError> {
+        let table = self.catalog.borrow().find_table(table_name)?;
         Ok(Plan::Insert(InsertPlan {
             table_name: table_name.to_owned(),
             child: Box::new(child),
+            returning,
+            checks: self.plan_checks(&table.schema)?,
         }))
     }
 }
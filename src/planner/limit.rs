@@ -0,0 +1,34 @@
+use crate::expr::ExprImpl;
+use crate::planner::{Plan, Planner};
+
+#[derive(Debug)]
+pub struct LimitPlan {
+    pub limit: usize,
+    pub child: Box<Plan>,
+}
+
+/// a sort followed by a limit, only the leading tuples are kept while sorting
+#[derive(Debug)]
+pub struct TopNPlan {
+    pub exprs_with_desc: Vec<(ExprImpl, bool)>,
+    pub limit: usize,
+    pub child: Box<Plan>,
+}
+
+impl Planner {
+    /// the sort right under the limit becomes a top-n
+    pub fn plan_limit(&self, limit: Option<usize>, child: Plan) -> Plan {
+        match (limit, child) {
+            (None, child) => child,
+            (Some(limit), Plan::Sort(plan)) => Plan::TopN(TopNPlan {
+                exprs_with_desc: plan.exprs_with_desc,
+                limit,
+                child: plan.child,
+            }),
+            (Some(limit), child) => Plan::Limit(LimitPlan {
+                limit,
+                child: Box::new(child),
+            }),
+        }
+    }
+}
@@ -1,11 +1,21 @@
 use crate::parser::ast::LoadFromFileStmt;
-use crate::planner::{InsertPlan, Plan, PlanError, Planner};
+use crate::planner::{Plan, PlanError, Planner};
 use crate::table::SchemaRef;
 
 #[derive(Debug)]
 pub struct LoadFromFilePlan {
     pub schema: SchemaRef,
     pub file_name: String,
+    /// dumped by Table::dump_binary rather than csv
+    pub binary: bool,
+    /// where the lines failed to load are written
+    pub rejects_file_name: Option<String>,
+}
+
+/// the counts of loaded and rejected lines of the insert from a csv file
+#[derive(Debug)]
+pub struct LoadSummaryPlan {
+    pub child: Box<Plan>,
 }
 
 impl Planner {
@@ -14,10 +24,15 @@ impl Planner {
         let load_plan = Plan::LoadFromFile(LoadFromFilePlan {
             schema: table.schema.clone(),
             file_name: stmt.file_name,
+            binary: stmt.binary,
+            rejects_file_name: stmt.rejects_file_name,
         });
-        Ok(Plan::Insert(InsertPlan {
-            table_name: stmt.table_name,
-            child: Box::new(load_plan),
-        }))
+        let insert_plan = self.plan_insert(&stmt.table_name, load_plan, false)?;
+        match stmt.binary {
+            true => Ok(insert_plan),
+            false => Ok(Plan::LoadSummary(LoadSummaryPlan {
+                child: Box::new(insert_plan),
+            })),
+        }
     }
 }
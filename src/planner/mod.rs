@@ -1,63 +1,105 @@
 use crate::catalog::{CatalogError, CatalogManagerRef};
+use crate::db::{Setting, SettingsRef};
+use crate::error::ErrorCode;
+use crate::execution::ExecutionError;
 use crate::expr::ExprError;
 use crate::parser::ast::Statement;
+use crate::storage::BufferPoolManagerRef;
 use crate::table::SchemaError;
 use log::info;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::time::Duration;
 use thiserror::Error;
 
 pub use agg::AggPlan;
-pub use alter::{AddForeignPlan, AddIndexPlan, AddPrimaryPlan, AddUniquePlan};
+pub use alter::{AddForeignPlan, AddIndexPlan, AddPrimaryPlan, AddUniquePlan, RenameColumnPlan};
+pub use analyze::{AnalyzePlan, VacuumPlan};
 pub use create_database::CreateDatabasePlan;
-pub use create_table::CreateTablePlan;
+pub use create_table::{CreateTableAsPlan, CreateTablePlan};
 pub use delete::DeletePlan;
+pub use derived::DerivedPlan;
 pub use desc::DescPlan;
-pub use drop::{DropDatabasePlan, DropForeignPlan, DropIndexPlan, DropPrimaryPlan, DropTablePlan};
-pub use filter::FilterPlan;
+pub use distinct_on::DistinctOnPlan;
+pub use drop::{
+    DropDatabasePlan, DropForeignPlan, DropIndexPlan, DropPrimaryPlan, DropTablePlan,
+    TruncateTablePlan,
+};
+pub use exists::ExistsPlan;
+pub use filter::{EmptyPlan, FilterPlan};
 pub use insert::InsertPlan;
-pub use load_from_file::LoadFromFilePlan;
+pub use limit::{LimitPlan, TopNPlan};
+pub use load_from_file::{LoadFromFilePlan, LoadSummaryPlan};
 pub use nested_loop_join::NestedLoopJoinPlan;
-pub use scan::{IndexScanPlan, SeqScanPlan};
+pub use pragma::{IndexStatsPlan, TableSizePlan};
+pub use rowid::RowIdFetchPlan;
+pub use scan::{IndexScanPlan, IndexSkipScanPlan, SeqScanPlan};
 pub use select::ProjectPlan;
+pub use set_op::SetOpPlan;
+pub use sort::SortPlan;
 pub use update::UpdatePlan;
 pub use use_database::UseDatabasePlan;
 pub use values::ValuesPlan;
+pub use view::{CreateViewPlan, DropViewPlan};
 
 mod agg;
 mod alter;
+mod analyze;
 mod create_database;
 mod create_table;
 mod delete;
+mod derived;
 mod desc;
+mod distinct_on;
 mod drop;
+mod estimate;
+mod exists;
+mod explain;
 mod filter;
 mod insert;
+mod limit;
 mod load_from_file;
 mod nested_loop_join;
+mod pragma;
+mod rowid;
 mod scan;
 mod select;
+mod set_op;
+mod sort;
+mod subquery;
 mod update;
 mod use_database;
 mod values;
+mod view;
 
 #[derive(Debug)]
 pub enum Plan {
     CreateDatabase(CreateDatabasePlan),
     ShowDatabases,
     ShowTables,
+    ShowWarnings(Vec<String>),
+    ShowHistory(Vec<StatementRecord>),
     UseDatabase(UseDatabasePlan),
     DropDatabase(DropDatabasePlan),
     CreateTable(CreateTablePlan),
+    CreateTableAs(CreateTableAsPlan),
+    CreateView(CreateViewPlan),
+    DropView(DropViewPlan),
     Values(ValuesPlan),
     Insert(InsertPlan),
     Desc(DescPlan),
     SeqScan(SeqScanPlan),
     Project(ProjectPlan),
     Filter(FilterPlan),
+    Empty(EmptyPlan),
     AddIndex(AddIndexPlan),
     AddUnique(AddUniquePlan),
+    RenameColumn(RenameColumnPlan),
     AddPrimary(AddPrimaryPlan),
     AddForeign(AddForeignPlan),
     IndexScan(IndexScanPlan),
+    IndexSkipScan(IndexSkipScanPlan),
+    RowIdFetch(RowIdFetchPlan),
     DropTable(DropTablePlan),
     DropForeign(DropForeignPlan),
     DropIndex(DropIndexPlan),
@@ -65,17 +107,88 @@ pub enum Plan {
     Delete(DeletePlan),
     NestedLoopJoin(NestedLoopJoinPlan),
     LoadFromFile(LoadFromFilePlan),
+    LoadSummary(LoadSummaryPlan),
     Agg(AggPlan),
     Update(UpdatePlan),
+    Exists(ExistsPlan),
+    Sort(SortPlan),
+    Limit(LimitPlan),
+    TopN(TopNPlan),
+    DistinctOn(DistinctOnPlan),
+    SetOp(SetOpPlan),
+    Derived(DerivedPlan),
+    TruncateTable(TruncateTablePlan),
+    IndexStats(IndexStatsPlan),
+    Analyze(AnalyzePlan),
+    Vacuum(VacuumPlan),
+    /// turn the checks of foreign keys on or off
+    ForeignKeys(bool),
+    /// turn the write-ahead log on or off
+    Wal(bool),
+    Checkpoint,
+    /// change a setting of the session
+    Set(Setting),
+    /// page ids on the free chain
+    ListFreePages,
+    TableSize(TableSizePlan),
+    /// lines of the rendered plan tree
+    Explain(Vec<String>),
+}
+
+/// how many of the last statements are kept for show history
+pub const HISTORY_SIZE: usize = 32;
+
+/// a statement run successfully, with how long it took and the rows it returned
+#[derive(Debug, Clone)]
+pub struct StatementRecord {
+    pub sql: String,
+    pub elapsed: Duration,
+    pub rows: usize,
 }
 
 pub struct Planner {
     catalog: CatalogManagerRef,
+    bpm: BufferPoolManagerRef,
+    settings: SettingsRef,
+    /// non-fatal notes of the last statement
+    warnings: RefCell<Vec<String>>,
+    /// the last statements, the oldest first
+    history: RefCell<VecDeque<StatementRecord>>,
 }
 
 impl Planner {
-    pub fn new(catalog: CatalogManagerRef) -> Self {
-        Self { catalog }
+    pub fn new(
+        catalog: CatalogManagerRef,
+        bpm: BufferPoolManagerRef,
+        settings: SettingsRef,
+    ) -> Self {
+        Self {
+            catalog,
+            bpm,
+            settings,
+            warnings: RefCell::new(vec![]),
+            history: RefCell::new(VecDeque::new()),
+        }
+    }
+    pub fn warn(&self, warning: String) {
+        info!("warning: {}", warning);
+        self.warnings.borrow_mut().push(warning);
+    }
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.borrow().clone()
+    }
+    pub fn clear_warnings(&self) {
+        self.warnings.borrow_mut().clear();
+    }
+    pub fn record(&self, record: StatementRecord) {
+        let mut history = self.history.borrow_mut();
+        if history.len() == HISTORY_SIZE {
+            history.pop_front();
+        }
+        history.push_back(record);
+    }
+    pub fn history(&self) -> Vec<StatementRecord> {
+        self.history.borrow().iter().cloned().collect()
     }
     pub fn plan(&self, stmt: Statement) -> Result<Plan, PlanError> {
         info!("plan with statement {:#?}", stmt);
@@ -83,8 +196,13 @@ impl Planner {
             Statement::CreateDatabase(stmt) => self.plan_create_database(stmt),
             Statement::ShowDatabases => Ok(Plan::ShowDatabases),
             Statement::ShowTables => Ok(Plan::ShowTables),
+            Statement::ShowWarnings => Ok(Plan::ShowWarnings(self.warnings())),
+            Statement::ShowHistory => Ok(Plan::ShowHistory(self.history())),
             Statement::UseDatabase(stmt) => self.plan_use_database(stmt),
             Statement::CreateTable(stmt) => self.plan_create_table(stmt),
+            Statement::CreateTableAs(stmt) => self.plan_create_table_as(stmt),
+            Statement::CreateView(stmt) => self.plan_create_view(stmt),
+            Statement::DropView(stmt) => self.plan_drop_view(stmt),
             Statement::Insert(stmt) => self.plan_insert_from_values(stmt),
             Statement::Desc(stmt) => self.plan_desc(stmt),
             Statement::Select(stmt) => self.plan_select(stmt),
@@ -92,14 +210,23 @@ impl Planner {
             Statement::AddPrimary(stmt) => self.plan_add_primary(stmt),
             Statement::AddForeign(stmt) => self.plan_add_foreign(stmt),
             Statement::AddUnique(stmt) => self.plan_add_unique(stmt),
+            Statement::RenameColumn(stmt) => self.plan_rename_column(stmt),
             Statement::DropTable(stmt) => self.plan_drop_table(stmt),
             Statement::DropDatabase(stmt) => self.plan_drop_database(stmt),
             Statement::DropPrimary(stmt) => self.plan_drop_primary(stmt),
             Statement::DropForeign(stmt) => self.plan_drop_foreign(stmt),
             Statement::DropIndex(stmt) => self.plan_drop_index(stmt),
-            Statement::Delete(stmt) => self.plan_delete(&stmt.table_name, &stmt.where_exprs),
+            Statement::Delete(stmt) => {
+                self.plan_delete(&stmt.table_name, &stmt.where_exprs, stmt.limit)
+            }
             Statement::LoadFromFile(stmt) => self.plan_load_from_file(stmt),
             Statement::Update(stmt) => self.plan_update(stmt),
+            Statement::TruncateTable(stmt) => self.plan_truncate_table(stmt),
+            Statement::Pragma(stmt) => self.plan_pragma(stmt),
+            Statement::Analyze(stmt) => self.plan_analyze(stmt),
+            Statement::Vacuum(stmt) => self.plan_vacuum(stmt),
+            Statement::Checkpoint => Ok(Plan::Checkpoint),
+            Statement::Explain(stmt) => self.plan_explain(stmt),
         }
     }
 }
@@ -112,15 +239,73 @@ pub enum PlanError {
     Schema(#[from] SchemaError),
     #[error("ExprError: {0}")]
     Expr(#[from] ExprError),
+    #[error("ExecutionError: {0}")]
+    Execution(#[from] ExecutionError),
+    #[error("Scalar Subquery Returns {0} Rows and {1} Columns")]
+    NotScalarSubquery(usize, usize),
+    #[error("Subquery Of In Returns {0} Columns")]
+    NotSingleColumnSubquery(usize),
+    #[error("Unknown Pragma: {0}")]
+    UnknownPragma(String),
+    #[error("Invalid Value Of Pragma {0}: {1}")]
+    InvalidPragmaValue(String, String),
+    #[error("View {0} Is Not A Filter Over A Single Table")]
+    InvalidView(String),
+    #[error("View {0} Can Not Be Joined")]
+    ViewInJoin(String),
+    #[error("Subquery In From Is Not Supported Here")]
+    UnsupportedDerivedTable,
+    #[error("Position {0} In ORDER BY Is Out Of Range")]
+    OrderByPositionOutOfRange(i64),
+    #[error("Set Operation Is Not Supported Here")]
+    UnsupportedSetOp,
+    #[error("DISTINCT ON Is Not Supported With Aggregation")]
+    DistinctOnAgg,
+    #[error("DISTINCT ON Expressions Must Match The Leading ORDER BY Expressions")]
+    DistinctOnOrderBy,
+    #[error("Rowid Must Be Compared With A Page And A Slot Of Non-negative Int Constants")]
+    InvalidRowId,
+    #[error("Check Constraint {0} Is Not A Boolean Expression")]
+    InvalidCheck(String),
+    #[error("Column {0} Is Referenced By A Check Constraint")]
+    ColumnInCheck(String),
+}
+
+impl PlanError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Catalog(err) => err.code(),
+            Self::Schema(err) => err.code(),
+            Self::Expr(err) => err.code(),
+            Self::Execution(err) => err.code(),
+            Self::NotScalarSubquery(..)
+            | Self::NotSingleColumnSubquery(_)
+            | Self::ViewInJoin(_)
+            | Self::UnsupportedDerivedTable
+            | Self::UnsupportedSetOp
+            | Self::DistinctOnAgg => ErrorCode::Unsupported,
+            Self::UnknownPragma(_)
+            | Self::InvalidPragmaValue(..)
+            | Self::InvalidView(_)
+            | Self::OrderByPositionOutOfRange(_)
+            | Self::DistinctOnOrderBy
+            | Self::InvalidRowId
+            | Self::InvalidCheck(_)
+            | Self::ColumnInCheck(_) => ErrorCode::InvalidArgument,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::catalog::CatalogManager;
+    use crate::db::Settings;
     use crate::parser::ast::{CreateDatabaseStmt, Statement};
     use crate::planner::{Plan, Planner};
     use crate::storage::BufferPoolManager;
+    use std::cell::RefCell;
     use std::fs::remove_file;
+    use std::rc::Rc;
 
     #[test]
     fn test_gen_create_database_plan() {
@@ -128,7 +313,8 @@ mod tests {
             let bpm = BufferPoolManager::new_random_shared(5);
             let catalog = CatalogManager::new_shared(bpm.clone());
             let filename = bpm.borrow().filename();
-            let planner = Planner::new(catalog);
+            let settings = Rc::new(RefCell::new(Settings::default()));
+            let planner = Planner::new(catalog, bpm.clone(), settings);
             let stmt = Statement::CreateDatabase(CreateDatabaseStmt {
                 database_name: "sample_database".to_string(),
             });
@@ -1,20 +1,41 @@
-use super::{Plan, Planner};
+use super::filter::always_false;
+use super::{EmptyPlan, Plan, PlanError, Planner};
+use crate::expr::ExprImpl;
+use crate::parser::ast::ExprNode;
 use crate::table::SchemaRef;
 
 #[derive(Debug)]
 pub struct NestedLoopJoinPlan {
     pub children: Vec<Plan>,
     pub schema: SchemaRef,
+    /// predicates over the joined tuples, applied while joining
+    pub exprs: Vec<ExprImpl>,
 }
 
 impl Planner {
-    pub fn plan_nested_loop_join(&self, mut plans: Vec<Plan>, schema: SchemaRef) -> Plan {
+    pub fn plan_nested_loop_join(
+        &self,
+        mut plans: Vec<Plan>,
+        schema: SchemaRef,
+        where_exprs: &[ExprNode],
+    ) -> Result<Plan, PlanError> {
         match plans.len() {
-            1 => plans.remove(0),
-            _ => Plan::NestedLoopJoin(NestedLoopJoinPlan {
-                children: plans,
-                schema,
-            }),
+            1 => self.plan_filter(&schema, where_exprs, plans.remove(0)),
+            _ => {
+                let exprs = self.plan_predicates(&schema, where_exprs)?;
+                let always_false = always_false(&exprs);
+                let plan = Plan::NestedLoopJoin(NestedLoopJoinPlan {
+                    exprs,
+                    children: plans,
+                    schema,
+                });
+                match always_false {
+                    true => Ok(Plan::Empty(EmptyPlan {
+                        child: Box::new(plan),
+                    })),
+                    false => Ok(plan),
+                }
+            }
         }
     }
 }
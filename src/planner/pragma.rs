@@ -0,0 +1,109 @@
+use crate::catalog::CatalogError;
+use crate::db::Setting;
+use crate::expr::{FractionalInt, NullAggregation};
+use crate::parser::ast::{PragmaArg, PragmaStmt};
+use crate::planner::{Plan, PlanError, Planner};
+use crate::storage::{PageID, Synchronous};
+
+#[derive(Debug)]
+pub struct IndexStatsPlan {
+    pub table_page_id: PageID,
+    pub index_page_id: PageID,
+}
+
+#[derive(Debug)]
+pub struct TableSizePlan {
+    pub table_page_id: PageID,
+    pub index_page_ids: Vec<PageID>,
+}
+
+impl Planner {
+    pub fn plan_pragma(&self, stmt: PragmaStmt) -> Result<Plan, PlanError> {
+        let name = stmt.name.to_lowercase();
+        match (name.as_str(), stmt.arg) {
+            ("index_stats", Some(PragmaArg::Index(table_name, column_names))) => {
+                let table = self.catalog.borrow().find_table(&table_name)?;
+                let index = self
+                    .catalog
+                    .borrow()
+                    .find_indexes_by_table(&table_name)?
+                    .into_iter()
+                    .find(|index| {
+                        index
+                            .exprs
+                            .iter()
+                            .map(|e| e.to_string())
+                            .eq(column_names.iter().cloned())
+                    })
+                    .ok_or(CatalogError::EntryNotFound)?;
+                Ok(Plan::IndexStats(IndexStatsPlan {
+                    table_page_id: table.page_id(),
+                    index_page_id: index.get_page_id(),
+                }))
+            }
+            ("table_size", Some(PragmaArg::Table(table_name))) => {
+                let table = self.catalog.borrow().find_table(&table_name)?;
+                let index_page_ids = self
+                    .catalog
+                    .borrow()
+                    .find_indexes_by_table(&table_name)?
+                    .iter()
+                    .map(|index| index.get_page_id())
+                    .collect();
+                Ok(Plan::TableSize(TableSizePlan {
+                    table_page_id: table.page_id(),
+                    index_page_ids,
+                }))
+            }
+            ("foreign_keys", Some(PragmaArg::Value(value))) => {
+                match value.to_lowercase().as_str() {
+                    "on" => Ok(Plan::ForeignKeys(true)),
+                    "off" => Ok(Plan::ForeignKeys(false)),
+                    _ => Err(PlanError::InvalidPragmaValue(stmt.name, value)),
+                }
+            }
+            ("wal", Some(PragmaArg::Value(value))) => match value.to_lowercase().as_str() {
+                "on" => Ok(Plan::Wal(true)),
+                "off" => Ok(Plan::Wal(false)),
+                _ => Err(PlanError::InvalidPragmaValue(stmt.name, value)),
+            },
+            ("division_by_zero", Some(PragmaArg::Value(value))) => {
+                match value.to_lowercase().as_str() {
+                    "error" => Ok(Plan::Set(Setting::DivisionByZero(true))),
+                    "null" => Ok(Plan::Set(Setting::DivisionByZero(false))),
+                    _ => Err(PlanError::InvalidPragmaValue(stmt.name, value)),
+                }
+            }
+            ("fractional_int", Some(PragmaArg::Value(value))) => {
+                match value.to_lowercase().as_str() {
+                    "error" => Ok(Plan::Set(Setting::FractionalInt(FractionalInt::Error))),
+                    "truncate" => Ok(Plan::Set(Setting::FractionalInt(FractionalInt::Truncate))),
+                    _ => Err(PlanError::InvalidPragmaValue(stmt.name, value)),
+                }
+            }
+            ("synchronous", Some(PragmaArg::Value(value))) => match value.to_lowercase().as_str() {
+                "off" => Ok(Plan::Set(Setting::Synchronous(Synchronous::Off))),
+                "normal" => Ok(Plan::Set(Setting::Synchronous(Synchronous::Normal))),
+                "full" => Ok(Plan::Set(Setting::Synchronous(Synchronous::Full))),
+                _ => Err(PlanError::InvalidPragmaValue(stmt.name, value)),
+            },
+            ("list_free_pages", None) => Ok(Plan::ListFreePages),
+            ("null_display", Some(PragmaArg::Value(value))) => {
+                Ok(Plan::Set(Setting::NullDisplay(value)))
+            }
+            ("bool_as_int", Some(PragmaArg::Value(value))) => match value.to_lowercase().as_str() {
+                "on" => Ok(Plan::Set(Setting::BoolAsInt(true))),
+                "off" => Ok(Plan::Set(Setting::BoolAsInt(false))),
+                _ => Err(PlanError::InvalidPragmaValue(stmt.name, value)),
+            },
+            ("null_aggregation", Some(PragmaArg::Value(value))) => {
+                match value.to_lowercase().as_str() {
+                    "strict" => Ok(Plan::Set(Setting::NullAggregation(NullAggregation::Strict))),
+                    "skip" => Ok(Plan::Set(Setting::NullAggregation(NullAggregation::Skip))),
+                    _ => Err(PlanError::InvalidPragmaValue(stmt.name, value)),
+                }
+            }
+            _ => Err(PlanError::UnknownPragma(stmt.name)),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::datum::{DataType, Datum};
+use crate::expr::{BinaryOp, ExprImpl};
+use crate::index::RecordID;
+use crate::parser::ast::{ExprNode, RowExprNode};
+use crate::planner::{EmptyPlan, Plan, PlanError, Planner};
+use crate::storage::PageID;
+
+/// fetch the tuple at the record id without any scan
+#[derive(Debug)]
+pub struct RowIdFetchPlan {
+    pub table_page_id: PageID,
+    pub record_id: RecordID,
+}
+
+/// names of the pseudo column of the record id, unless the table has a column of the name
+const ROWID_NAMES: [&str; 2] = ["rowid", "ctid"];
+
+/// the row compared with rowid, as in rowid = (page, slot)
+fn rowid_row<'a>(node: &'a ExprNode, columns: &[String]) -> Option<&'a RowExprNode> {
+    let is_rowid = |node: &ExprNode| match node {
+        ExprNode::ColumnRef(column_ref) => {
+            ROWID_NAMES.contains(&column_ref.column_name.to_lowercase().as_str())
+                && !columns.contains(&column_ref.column_name)
+        }
+        _ => false,
+    };
+    match node {
+        ExprNode::Binary(node) if node.op == BinaryOp::Equal => {
+            match (node.lhs.as_ref(), node.rhs.as_ref()) {
+                (lhs, ExprNode::Row(row)) if is_rowid(lhs) => Some(row),
+                (ExprNode::Row(row), rhs) if is_rowid(rhs) => Some(row),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+impl Planner {
+    /// take the predicates on rowid out of the where exprs, the tuple at the record id is
+    /// fetched directly, nothing if the predicates name different records
+    pub fn plan_rowid_fetch(
+        &self,
+        table_name: &str,
+        where_exprs: &mut Vec<ExprNode>,
+    ) -> Result<Option<Plan>, PlanError> {
+        let table = self.catalog.borrow().find_table(table_name)?;
+        let columns = table
+            .schema
+            .columns
+            .iter()
+            .map(|c| c.desc.clone())
+            .collect::<Vec<_>>();
+        let mut record_ids = vec![];
+        let mut rest = vec![];
+        for node in where_exprs.drain(..) {
+            let row = match rowid_row(&node, &columns) {
+                Some(row) => row,
+                None => {
+                    rest.push(node);
+                    continue;
+                }
+            };
+            if row.values.len() != 2 {
+                return Err(PlanError::InvalidRowId);
+            }
+            let mut record_id = vec![];
+            for value in &row.values {
+                let mut expr = ExprImpl::from_ast(
+                    value,
+                    &self.settings.borrow(),
+                    &table.schema,
+                    Some(DataType::new_as_int(false)),
+                )?;
+                expr.fold();
+                match expr.as_constant() {
+                    Some(Datum::Int(Some(v))) if v >= 0 => record_id.push(v as usize),
+                    _ => return Err(PlanError::InvalidRowId),
+                }
+            }
+            record_ids.push((record_id[0], record_id[1]));
+        }
+        *where_exprs = rest;
+        let record_id = match record_ids.first() {
+            Some(record_id) => *record_id,
+            None => return Ok(None),
+        };
+        let plan = Plan::RowIdFetch(RowIdFetchPlan {
+            table_page_id: table.page_id(),
+            record_id,
+        });
+        match record_ids.iter().all(|r| *r == record_id) {
+            true => Ok(Some(plan)),
+            false => Ok(Some(Plan::Empty(EmptyPlan {
+                child: Box::new(plan),
+            }))),
+        }
+    }
+}
@@ -1,7 +1,7 @@
-use crate::datum::Datum;
-use crate::expr::ExprImpl;
+use crate::datum::{DataType, Datum};
+use crate::expr::{ExprError, ExprImpl};
 use crate::parser::ast::ExprNode;
-use crate::planner::{Plan, Planner};
+use crate::planner::{Plan, PlanError, Planner};
 use crate::storage::PageID;
 use itertools::Itertools;
 
@@ -12,12 +12,110 @@ pub struct IndexScanPlan {
     pub table_page_id: PageID,
     pub index_page_id: PageID,
     pub with_record_id: bool,
+    /// how each candidate index is bounded by the predicates, shown by explain
+    pub index_choices: Vec<String>,
+}
+
+/// a skip scan is only chosen when analyze found fewer distinct leading keys than this,
+/// as it seeks once per key
+const MAX_SKIP_SCAN_KEYS: usize = 32;
+
+/// an index scan seeking once per distinct leading key, the exprs after the leading one
+/// are bounded on both sides
+#[derive(Debug)]
+pub struct IndexSkipScanPlan {
+    pub begin_datums: Vec<Datum>,
+    pub end_datums: Vec<Datum>,
+    pub table_page_id: PageID,
+    pub index_page_id: PageID,
+    pub with_record_id: bool,
+    /// how each candidate index is bounded by the predicates, shown by explain
+    pub index_choices: Vec<String>,
 }
 
 #[derive(Debug)]
 pub struct SeqScanPlan {
     pub table_name: String,
     pub with_record_id: bool,
+    /// how each candidate index is bounded by the predicates, shown by explain
+    pub index_choices: Vec<String>,
+}
+
+/// an index is applicable once all of its exprs are bounded from the same side, the
+/// tightest applicable one is chosen
+fn describe_index_choice(
+    index_exprs: &[ExprImpl],
+    begin: &[Option<Datum>],
+    end: &[Option<Datum>],
+    applicable: bool,
+    chosen: bool,
+) -> String {
+    let unbounded = |bounds: &[Option<Datum>]| {
+        index_exprs
+            .iter()
+            .zip(bounds.iter())
+            .filter(|(_, bound)| bound.is_none())
+            .map(|(expr, _)| expr.to_string())
+            .join(", ")
+    };
+    let index = format!("index ({})", index_exprs.iter().join(", "));
+    match (applicable, chosen) {
+        (_, true) => format!("{} chosen", index),
+        (true, false) => format!("{} not chosen, another index is at least as tight", index),
+        (false, _) => format!(
+            "{} not chosen, no lower bound on {}, no upper bound on {}",
+            index,
+            unbounded(begin),
+            unbounded(end)
+        ),
+    }
+}
+
+/// the distance between the bounds if they are numbers or dates
+fn width_between(begin: &Datum, end: &Datum) -> Option<f64> {
+    match (begin, end) {
+        (Datum::Int(Some(b)), Datum::Int(Some(e))) => Some(*e as f64 - *b as f64),
+        (Datum::Float(Some(b)), Datum::Float(Some(e))) => {
+            Some(e.into_inner() as f64 - b.into_inner() as f64)
+        }
+        (Datum::Date(Some(b)), Datum::Date(Some(e))) => Some((*e - *b).num_days() as f64),
+        _ => None,
+    }
+}
+
+/// how loose the range is, the smaller the tighter: a range bounded on both sides beats
+/// one bounded on a single side, then more leading exprs pinned to a value, then the
+/// narrower width of the first expr not pinned
+fn looseness(begin: &Option<Vec<Datum>>, end: &Option<Vec<Datum>>) -> (usize, usize, f64) {
+    let (begin, end) = match (begin, end) {
+        (Some(begin), Some(end)) => (begin, end),
+        _ => return (1, 0, f64::INFINITY),
+    };
+    let pinned = begin
+        .iter()
+        .zip(end.iter())
+        .take_while(|(b, e)| b == e)
+        .count();
+    let width = match (begin.get(pinned), end.get(pinned)) {
+        (Some(b), Some(e)) => width_between(b, e).unwrap_or(f64::INFINITY),
+        _ => 0.0,
+    };
+    (0, begin.len() - pinned, width)
+}
+
+/// coerce the bound to the type of the indexed expr, a float bound of an int expr is rounded
+/// outward so the range only widens, the filter above the scan drops the extra rows, other
+/// bounds are cast and it is an error if they can not be
+fn coerce_bound(datum: Datum, data_type: DataType, is_begin: bool) -> Result<Datum, ExprError> {
+    match (datum, data_type) {
+        (Datum::Float(Some(v)), DataType::Int(_)) => match is_begin {
+            true => Ok(Datum::Int(Some(v.into_inner().floor() as i32))),
+            false => Ok(Datum::Int(Some(v.into_inner().ceil() as i32))),
+        },
+        (datum, data_type) => datum
+            .cast_to(&data_type)
+            .ok_or(ExprError::InvalidCast(datum, data_type)),
+    }
 }
 
 impl Planner {
@@ -26,8 +124,8 @@ impl Planner {
         table_name: &str,
         where_exprs: &[ExprNode],
         with_record_id: bool,
-    ) -> Plan {
-        let table = self.catalog.borrow().find_table(table_name).unwrap();
+    ) -> Result<Plan, PlanError> {
+        let table = self.catalog.borrow().find_table(table_name)?;
         let mut indexes = self
             .catalog
             .borrow()
@@ -46,12 +144,20 @@ impl Planner {
                 } else {
                     None
                 };
-                ExprImpl::from_ast(node, self.catalog.clone(), &table.schema, return_type_hint)
-                    .unwrap()
+                let mut expr = ExprImpl::from_ast(
+                    node,
+                    &self.settings.borrow(),
+                    &table.schema,
+                    return_type_hint,
+                )?;
+                expr.fold();
+                Ok(expr)
             })
-            .collect_vec();
-        let mut index_scan = None;
-        for index in indexes.iter_mut() {
+            .collect::<Result<Vec<_>, PlanError>>()?;
+        let table_page_id = table.page_id();
+        let mut candidates = vec![];
+        let mut bounds_per_index = vec![];
+        for (index_idx, index) in indexes.iter_mut().enumerate() {
             let index_exprs = &mut index.exprs;
             let mut begin: Vec<Option<Datum>> = vec![None; index_exprs.len()];
             let mut end: Vec<Option<Datum>> = vec![None; index_exprs.len()];
@@ -59,48 +165,146 @@ impl Planner {
                 for where_expr in &where_exprs {
                     if let ExprImpl::Binary(binary_expr) = where_expr {
                         let bound = binary_expr.get_bound(index_expr);
+                        let data_type = index_expr.return_type();
                         if let Some(d) = bound.0 {
-                            begin[idx] = Some(d);
+                            begin[idx] = Some(coerce_bound(d, data_type, true)?);
                         }
                         if let Some(d) = bound.1 {
-                            end[idx] = Some(d);
+                            end[idx] = Some(coerce_bound(d, data_type, false)?);
                         }
                     }
                 }
             }
-            let begin = if begin.iter().all(|b| matches!(b, Some(_))) {
-                Some(begin.into_iter().map(|b| b.unwrap()).collect_vec())
+            let begin_datums = if begin.iter().all(|b| matches!(b, Some(_))) {
+                Some(begin.iter().cloned().map(|b| b.unwrap()).collect_vec())
             } else {
                 None
             };
-            let end = if end.iter().all(|b| matches!(b, Some(_))) {
-                Some(end.into_iter().map(|b| b.unwrap()).collect_vec())
+            let end_datums = if end.iter().all(|b| matches!(b, Some(_))) {
+                Some(end.iter().cloned().map(|b| b.unwrap()).collect_vec())
             } else {
                 None
             };
-            if begin.is_some() || end.is_some() {
-                index_scan = Some(Plan::IndexScan(IndexScanPlan {
-                    begin_datums: begin,
-                    end_datums: end,
-                    table_page_id: self
-                        .catalog
-                        .borrow()
-                        .find_table(table_name)
-                        .unwrap()
-                        .page_id(),
-                    index_page_id: index.get_page_id(),
-                    with_record_id,
-                }));
-                break;
+            if begin_datums.is_some() || end_datums.is_some() {
+                candidates.push((
+                    looseness(&begin_datums, &end_datums),
+                    index_idx,
+                    IndexScanPlan {
+                        begin_datums,
+                        end_datums,
+                        table_page_id,
+                        index_page_id: index.get_page_id(),
+                        with_record_id,
+                        index_choices: vec![],
+                    },
+                ));
             }
+            bounds_per_index.push((begin, end));
         }
-        if let Some(index_scan) = index_scan {
-            index_scan
-        } else {
-            Plan::SeqScan(SeqScanPlan {
+        // the first found wins among the equally tight ones
+        let index_scan = candidates
+            .into_iter()
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap().then(a.1.cmp(&b.1)))
+            .map(|(_, index_idx, plan)| (index_idx, plan));
+        // without any range, skip scan the first index with only the leading expr unbounded,
+        // if the stats tell the leading expr has few distinct values
+        let stats = table.stats();
+        let few_leading_keys = |index_expr: &ExprImpl| match (index_expr, &stats) {
+            (ExprImpl::ColumnRef(column_ref), Some(stats)) => {
+                stats[column_ref.as_idx()].num_distinct < MAX_SKIP_SCAN_KEYS
+            }
+            _ => false,
+        };
+        let skip_scan = match index_scan {
+            Some(_) => None,
+            None => bounds_per_index
+                .iter()
+                .zip(indexes.iter())
+                .position(|((begin, end), index)| {
+                    few_leading_keys(&index.exprs[0])
+                        && begin.len() > 1
+                        && begin[0].is_none()
+                        && end[0].is_none()
+                        && begin[1..]
+                            .iter()
+                            .chain(end[1..].iter())
+                            .all(|b| b.is_some())
+                })
+                .map(|index_idx| {
+                    let (begin, end) = &bounds_per_index[index_idx];
+                    let skip_bounds = |bounds: &[Option<Datum>]| {
+                        bounds[1..]
+                            .iter()
+                            .cloned()
+                            .map(|b| b.unwrap())
+                            .collect_vec()
+                    };
+                    (
+                        index_idx,
+                        IndexSkipScanPlan {
+                            begin_datums: skip_bounds(begin),
+                            end_datums: skip_bounds(end),
+                            table_page_id,
+                            index_page_id: indexes[index_idx].get_page_id(),
+                            with_record_id,
+                            index_choices: vec![],
+                        },
+                    )
+                }),
+        };
+        let chosen_idx = index_scan
+            .as_ref()
+            .map(|(idx, _)| *idx)
+            .or_else(|| skip_scan.as_ref().map(|(idx, _)| *idx));
+        // without predicates no index is a candidate
+        let index_choices = match where_exprs.is_empty() {
+            true => vec![],
+            false => indexes
+                .iter()
+                .zip(bounds_per_index.iter())
+                .enumerate()
+                .map(|(index_idx, (index, (begin, end)))| {
+                    let applicable =
+                        begin.iter().all(|b| b.is_some()) || end.iter().all(|b| b.is_some());
+                    let chosen = chosen_idx == Some(index_idx);
+                    describe_index_choice(&index.exprs, begin, end, applicable, chosen)
+                })
+                .collect_vec(),
+        };
+        Ok(match (index_scan, skip_scan) {
+            (Some((_, plan)), _) => Plan::IndexScan(IndexScanPlan {
+                index_choices,
+                ..plan
+            }),
+            (None, Some((_, plan))) => Plan::IndexSkipScan(IndexSkipScanPlan {
+                index_choices,
+                ..plan
+            }),
+            (None, None) => Plan::SeqScan(SeqScanPlan {
                 table_name: table_name.to_owned(),
                 with_record_id,
-            })
-        }
+                index_choices,
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coerce_bound() {
+        let int = DataType::new_as_int(false);
+        let float = DataType::new_as_float(false);
+        let bound = |datum, data_type, is_begin| coerce_bound(datum, data_type, is_begin).unwrap();
+        assert_eq!(bound(Datum::from(4.5f32), int, true), 4.into());
+        assert_eq!(bound(Datum::from(4.5f32), int, false), 5.into());
+        assert_eq!(bound(Datum::from(5.0f32), int, false), 5.into());
+        assert_eq!(bound(3.into(), float, true), Datum::from(3.0f32));
+        assert_eq!(bound(3.into(), int, true), 3.into());
+        assert_eq!(bound("5".into(), int, true), 5.into());
+        assert_eq!(bound(Datum::VarChar(None), int, true), Datum::Int(None));
+        assert!(coerce_bound("five".into(), int, true).is_err());
     }
 }
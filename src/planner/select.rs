@@ -1,18 +1,90 @@
 use crate::catalog::CatalogManagerRef;
+use crate::datum::DataType;
+use crate::execution::agg_schema;
 use crate::expr::ExprImpl;
-use crate::parser::ast::{ColumnRefExprNode, ExprNode, SelectStmt, Selectors};
-use crate::planner::{Plan, PlanError, Planner};
+use crate::parser::ast::{ColumnRefExprNode, ExprNode, OrderByItem, SelectStmt, Selectors};
+use crate::planner::{DerivedPlan, Plan, PlanError, Planner};
 use crate::table::{Schema, SchemaError};
 use itertools::Itertools;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct ProjectPlan {
     pub exprs: Vec<ExprImpl>,
+    pub aliases: Vec<Option<String>>,
     pub child: Box<Plan>,
 }
 
+/// push the expr down to the only table it refers, or evaluate it after the join with
+/// the column names qualified
+fn route_by_column_refs(
+    mut expr: ExprNode,
+    column_to_table: &HashMap<String, String>,
+    table_name_with_exprs: &mut [(String, Vec<ExprNode>)],
+    overall_exprs: &mut Vec<ExprNode>,
+) -> Result<(), PlanError> {
+    let table_names = expr
+        .column_refs_mut()
+        .into_iter()
+        .map(|column_ref| {
+            column_ref
+                .table_name
+                .clone()
+                .or_else(|| column_to_table.get(&column_ref.column_name).cloned())
+                .ok_or(SchemaError::ColumnNotFound)
+        })
+        .collect::<Result<BTreeSet<_>, _>>()?;
+    if table_names.len() == 1 {
+        let table_name = table_names.into_iter().next().unwrap();
+        let (_, exprs) = table_name_with_exprs
+            .iter_mut()
+            .find(|(name, _)| *name == table_name)
+            .ok_or(SchemaError::ColumnNotFound)?;
+        exprs.push(expr);
+        return Ok(());
+    }
+    for column_ref in expr.column_refs_mut() {
+        let table_name = column_ref
+            .table_name
+            .take()
+            .unwrap_or_else(|| column_to_table[&column_ref.column_name].clone());
+        column_ref.column_name = format!("{}.{}", table_name, column_ref.column_name);
+    }
+    overall_exprs.push(expr);
+    Ok(())
+}
+
+/// aliases of the select list with the exprs they name
+fn aliases_of(selectors: &Selectors) -> HashMap<String, ExprNode> {
+    match selectors {
+        Selectors::Exprs(exprs) => exprs
+            .iter()
+            .filter_map(|(expr, alias)| alias.clone().map(|alias| (alias, expr.clone())))
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
+
+/// replace the column refs naming an alias rather than a column with the aliased expr
+fn substitute_aliases(
+    node: &mut ExprNode,
+    aliases: &HashMap<String, ExprNode>,
+    columns: &HashSet<String>,
+) {
+    if let ExprNode::ColumnRef(column_ref) = node {
+        if column_ref.table_name.is_none() && !columns.contains(&column_ref.column_name) {
+            if let Some(expr) = aliases.get(&column_ref.column_name) {
+                *node = expr.clone();
+            }
+        }
+        return;
+    }
+    for child in node.children_mut() {
+        substitute_aliases(child, aliases, columns);
+    }
+}
+
 #[allow(clippy::type_complexity)]
 fn pair_table_name_with_filter(
     table_names: &[String],
@@ -68,7 +140,12 @@ fn pair_table_name_with_filter(
                         .ok_or(SchemaError::ColumnNotFound)?;
                     exprs.push(ExprNode::Binary(expr));
                 }
-                _ => todo!(),
+                _ => route_by_column_refs(
+                    ExprNode::Binary(expr),
+                    &column_to_table,
+                    &mut table_name_with_exprs,
+                    &mut overall_exprs,
+                )?,
             },
             ExprNode::Like(expr) => match expr.child.as_ref() {
                 ExprNode::ColumnRef(cf) => {
@@ -82,9 +159,19 @@ fn pair_table_name_with_filter(
                         .unwrap();
                     exprs.push(ExprNode::Like(expr));
                 }
-                _ => todo!(),
+                _ => route_by_column_refs(
+                    ExprNode::Like(expr),
+                    &column_to_table,
+                    &mut table_name_with_exprs,
+                    &mut overall_exprs,
+                )?,
             },
-            _ => todo!(),
+            expr => route_by_column_refs(
+                expr,
+                &column_to_table,
+                &mut table_name_with_exprs,
+                &mut overall_exprs,
+            )?,
         }
     }
     Ok((
@@ -94,54 +181,168 @@ fn pair_table_name_with_filter(
 }
 
 impl Planner {
+    /// columns of the joined tables, prefixed with table name if use_table_name
+    pub fn type_and_names_of(
+        &self,
+        table_names: &[String],
+        use_table_name: bool,
+    ) -> Vec<(DataType, String)> {
+        table_names
+            .iter()
+            .flat_map(|table_name| {
+                let table = self.catalog.borrow().find_table(table_name).unwrap();
+                table
+                    .schema
+                    .to_type_and_names()
+                    .into_iter()
+                    .map(|(data_type, column_name)| {
+                        if use_table_name {
+                            (data_type, format!("{}.{}", table_name, column_name))
+                        } else {
+                            (data_type, column_name)
+                        }
+                    })
+                    .collect_vec()
+                    .into_iter()
+            })
+            .collect_vec()
+    }
     pub fn plan_select(&self, stmt: SelectStmt) -> Result<Plan, PlanError> {
+        if !stmt.set_ops.is_empty() {
+            return self.plan_set_op(stmt);
+        }
+        let stmt = self.expand_views(stmt)?;
+        if stmt.from_subquery.is_some() {
+            return self.plan_derived_select(stmt);
+        }
         for table_name in &stmt.table_names {
             let _ = self.catalog.borrow().find_table(table_name)?;
         }
+        let (exists_exprs, mut where_exprs): (Vec<_>, Vec<_>) = stmt
+            .where_exprs
+            .into_iter()
+            .partition(|expr| matches!(expr, ExprNode::Exists(_)));
+        let aliases = aliases_of(&stmt.selectors);
+        let columns: HashSet<_> = self
+            .type_and_names_of(&stmt.table_names, false)
+            .into_iter()
+            .map(|(_, column_name)| column_name)
+            .collect();
+        for expr in where_exprs.iter_mut() {
+            substitute_aliases(expr, &aliases, &columns);
+            self.plan_scalar_subqueries(expr)?;
+        }
+        // a single table is fetched at the record id if rowid is compared with one
+        let mut rowid_fetch = match stmt.table_names.as_slice() {
+            [table_name] => self.plan_rowid_fetch(table_name, &mut where_exprs)?,
+            _ => None,
+        };
         let (table_with_filter_expr, overall) =
-            pair_table_name_with_filter(&stmt.table_names, stmt.where_exprs, self.catalog.clone())?;
+            pair_table_name_with_filter(&stmt.table_names, where_exprs, self.catalog.clone())?;
         let scan_plans = table_with_filter_expr
             .into_iter()
             .map(|(table_name, exprs)| {
-                let plan = self.plan_scan(&table_name, &exprs, false);
+                let plan = match rowid_fetch.take() {
+                    Some(plan) => plan,
+                    None => self.plan_scan(&table_name, &exprs, false)?,
+                };
                 let table = self.catalog.borrow().find_table(&table_name).unwrap();
                 if !exprs.is_empty() {
                     self.plan_filter(&table.schema, &exprs, plan)
                 } else {
-                    plan
+                    Ok(plan)
                 }
             })
-            .collect_vec();
+            .collect::<Result<Vec<_>, PlanError>>()?;
         let use_table_name = stmt.table_names.len() > 1;
         let schema = Rc::new(Schema::from_type_and_names(
-            &stmt
-                .table_names
-                .iter()
-                .flat_map(|table_name| {
-                    let table = self.catalog.borrow().find_table(table_name).unwrap();
-                    table
-                        .schema
-                        .to_type_and_names()
-                        .into_iter()
-                        .map(|(data_type, column_name)| {
-                            if use_table_name {
-                                (data_type, format!("{}.{}", table_name, column_name))
-                            } else {
-                                (data_type, column_name)
-                            }
-                        })
-                        .collect_vec()
-                        .into_iter()
-                })
-                .collect_vec(),
+            &self.type_and_names_of(&stmt.table_names, use_table_name),
         ));
-        let join_plan = self.plan_nested_loop_join(scan_plans, schema.clone());
-        let filter_plan = self.plan_filter(&schema, &overall, join_plan);
-        match stmt.selectors {
+        let filter_plan = self.plan_nested_loop_join(scan_plans, schema.clone(), &overall)?;
+        let filter_plan =
+            exists_exprs
+                .into_iter()
+                .try_fold(filter_plan, |plan, expr| match expr {
+                    ExprNode::Exists(node) => {
+                        self.plan_exists(&stmt.table_names, *node.subquery, plan)
+                    }
+                    _ => unreachable!(),
+                })?;
+        self.plan_output(
+            &stmt.table_names,
+            &schema,
+            stmt.distinct_on,
+            stmt.selectors,
+            stmt.group_by_expr,
+            stmt.order_by,
+            stmt.limit,
+            filter_plan,
+        )
+    }
+    /// the subquery in from is planned as the child, the query selects from its tuples
+    fn plan_derived_select(&self, mut stmt: SelectStmt) -> Result<Plan, PlanError> {
+        let subquery = stmt.from_subquery.take().unwrap();
+        let child = self.plan_select(*subquery)?;
+        let schema = self.schema_of(&child)?;
+        let mut where_exprs = stmt.where_exprs;
+        let aliases = aliases_of(&stmt.selectors);
+        let columns: HashSet<_> = schema.columns.iter().map(|c| c.desc.clone()).collect();
+        for expr in where_exprs.iter_mut() {
+            if matches!(expr, ExprNode::Exists(_)) {
+                return Err(PlanError::UnsupportedDerivedTable);
+            }
+            substitute_aliases(expr, &aliases, &columns);
+            self.plan_scalar_subqueries(expr)?;
+            // the columns are only qualified by the alias of the subquery
+            for column_ref in expr.column_refs_mut() {
+                if column_ref
+                    .table_name
+                    .take()
+                    .filter(|name| stmt.subquery_alias.as_ref() != Some(name))
+                    .is_some()
+                {
+                    return Err(SchemaError::ColumnNotFound.into());
+                }
+            }
+        }
+        let derived_plan = Plan::Derived(DerivedPlan {
+            alias: stmt.subquery_alias,
+            schema: schema.clone(),
+            child: Box::new(child),
+        });
+        let filter_plan = self.plan_filter(&schema, &where_exprs, derived_plan)?;
+        self.plan_output(
+            &[],
+            &schema,
+            stmt.distinct_on,
+            stmt.selectors,
+            stmt.group_by_expr,
+            stmt.order_by,
+            stmt.limit,
+            filter_plan,
+        )
+    }
+    /// the selectors over the rows, sorted or aggregated, and limited at last
+    #[allow(clippy::too_many_arguments)]
+    fn plan_output(
+        &self,
+        table_names: &[String],
+        schema: &Schema,
+        distinct_on: Vec<ExprNode>,
+        selectors: Selectors,
+        group_by_expr: Option<ExprNode>,
+        order_by: Vec<OrderByItem>,
+        limit: Option<usize>,
+        filter_plan: Plan,
+    ) -> Result<Plan, PlanError> {
+        let use_table_name = table_names.len() > 1;
+        match selectors {
             Selectors::Exprs(exprs) => {
+                let (exprs, aliases): (Vec<_>, Vec<_>) = exprs.into_iter().unzip();
                 let exprs: Vec<_> = exprs
                     .into_iter()
-                    .map(|node| {
+                    .map(|mut node| {
+                        self.plan_scalar_subqueries(&mut node)?;
                         let node = match node {
                             ExprNode::ColumnRef(cr) => {
                                 if use_table_name {
@@ -159,18 +360,47 @@ impl Planner {
                             }
                             node => node,
                         };
-                        ExprImpl::from_ast(&node, self.catalog.clone(), &schema, None)
+                        Ok(ExprImpl::from_ast(
+                            &node,
+                            &self.settings.borrow(),
+                            schema,
+                            None,
+                        )?)
                     })
-                    .collect::<Result<_, _>>()?;
+                    .collect::<Result<_, PlanError>>()?;
+                let sort_plan =
+                    self.plan_sort(table_names, schema, order_by, &exprs, filter_plan)?;
+                let sort_plan =
+                    self.plan_distinct_on(table_names, schema, distinct_on, sort_plan)?;
+                // the projection keeps the number of rows, so the limit goes under it
                 Ok(Plan::Project(ProjectPlan {
                     exprs,
-                    child: Box::new(filter_plan),
+                    aliases,
+                    child: Box::new(self.plan_limit(limit, sort_plan)),
                 }))
             }
-            Selectors::All => Ok(filter_plan),
-            Selectors::Agg(items) => Ok(self
-                .plan_agg(&schema, items, stmt.group_by_expr, filter_plan)
-                .unwrap()),
+            Selectors::All => {
+                let exprs = schema.project_by(&(0..schema.columns.len()).collect_vec());
+                let sort_plan =
+                    self.plan_sort(table_names, schema, order_by, &exprs, filter_plan)?;
+                let distinct_on_plan =
+                    self.plan_distinct_on(table_names, schema, distinct_on, sort_plan)?;
+                Ok(self.plan_limit(limit, distinct_on_plan))
+            }
+            Selectors::Agg(_) if !distinct_on.is_empty() => Err(PlanError::DistinctOnAgg),
+            // the aggregated rows are sorted by the output columns
+            Selectors::Agg(items) => {
+                let agg_plan = self.plan_agg(schema, items, group_by_expr, filter_plan)?;
+                let agg_schema = match &agg_plan {
+                    Plan::Agg(plan) => {
+                        agg_schema(&plan.exprs_with_action, &plan.aliases, &plan.distincts)
+                    }
+                    _ => unreachable!(),
+                };
+                let exprs = agg_schema.project_by(&(0..agg_schema.columns.len()).collect_vec());
+                let sort_plan = self.plan_sort(&[], &agg_schema, order_by, &exprs, agg_plan)?;
+                Ok(self.plan_limit(limit, sort_plan))
+            }
         }
     }
 }
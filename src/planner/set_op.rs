@@ -0,0 +1,26 @@
+use crate::parser::ast::{SelectStmt, SetOp};
+use crate::planner::{Plan, PlanError, Planner};
+
+#[derive(Debug)]
+pub struct SetOpPlan {
+    pub op: SetOp,
+    pub lhs: Box<Plan>,
+    pub rhs: Box<Plan>,
+}
+
+impl Planner {
+    /// the queries are combined from left to right, the column types are checked while
+    /// building the executor as the schema of a plan is only known there
+    pub fn plan_set_op(&self, mut stmt: SelectStmt) -> Result<Plan, PlanError> {
+        let set_ops = std::mem::take(&mut stmt.set_ops);
+        set_ops
+            .into_iter()
+            .try_fold(self.plan_select(stmt)?, |lhs, (op, rhs)| {
+                Ok(Plan::SetOp(SetOpPlan {
+                    op,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(self.plan_select(rhs)?),
+                }))
+            })
+    }
+}
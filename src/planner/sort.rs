@@ -0,0 +1,86 @@
+use crate::expr::ExprImpl;
+use crate::parser::ast::{ConstantValue, ExprNode, OrderByItem};
+use crate::planner::{Plan, PlanError, Planner};
+use crate::table::{Schema, SchemaError};
+
+#[derive(Debug)]
+pub struct SortPlan {
+    pub exprs_with_desc: Vec<(ExprImpl, bool)>,
+    pub child: Box<Plan>,
+}
+
+/// an integer literal refers a selected column by its position, counted from 1
+fn position_of(node: &ExprNode) -> Option<i64> {
+    match node {
+        ExprNode::Constant(node) => match node.value {
+            ConstantValue::Real(value) if value.fract() == 0.0 => Some(value as i64),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl Planner {
+    /// an expr to sort by, the column names are qualified if over the joined tables
+    pub fn plan_sort_key(
+        &self,
+        table_names: &[String],
+        schema: &Schema,
+        mut node: ExprNode,
+    ) -> Result<ExprImpl, PlanError> {
+        self.plan_scalar_subqueries(&mut node)?;
+        if table_names.len() > 1 {
+            for column_ref in node.column_refs_mut() {
+                let table_name = self
+                    .table_name_of(column_ref, table_names)
+                    .ok_or(SchemaError::ColumnNotFound)?;
+                column_ref.column_name = format!("{}.{}", table_name, column_ref.column_name);
+                column_ref.table_name = None;
+            }
+        }
+        let return_type_hint = node.ref_what_column().and_then(|column_name| {
+            schema
+                .columns
+                .iter()
+                .find(|c| c.desc == column_name)
+                .map(|c| c.data_type)
+        });
+        Ok(ExprImpl::from_ast(
+            &node,
+            &self.settings.borrow(),
+            schema,
+            return_type_hint,
+        )?)
+    }
+    /// the selected exprs are over the schema as well, so sorting by them happens below
+    /// the projection
+    pub fn plan_sort(
+        &self,
+        table_names: &[String],
+        schema: &Schema,
+        items: Vec<OrderByItem>,
+        selected: &[ExprImpl],
+        child: Plan,
+    ) -> Result<Plan, PlanError> {
+        if items.is_empty() {
+            return Ok(child);
+        }
+        let exprs_with_desc = items
+            .into_iter()
+            .map(|item| {
+                if let Some(position) = position_of(&item.expr) {
+                    if position < 1 || position as usize > selected.len() {
+                        return Err(PlanError::OrderByPositionOutOfRange(position));
+                    }
+                    return Ok((selected[position as usize - 1].clone(), item.desc));
+                }
+                let expr = self.plan_sort_key(table_names, schema, item.expr)?;
+                Ok((expr, item.desc))
+            })
+            .collect::<Result<_, PlanError>>()?;
+        Ok(Plan::Sort(SortPlan {
+            exprs_with_desc,
+            child: Box::new(child),
+        }))
+    }
+}
@@ -0,0 +1,108 @@
+use crate::datum::{DataType, Datum};
+use crate::execution::Engine;
+use crate::parser::ast::{ConstantExprNode, ConstantValue, ExprNode, InValues, SelectStmt};
+use crate::planner::{PlanError, Planner};
+use crate::table::Table;
+use itertools::Itertools;
+
+impl Planner {
+    /// run an uncorrelated subquery and collect all of its tuples
+    pub fn eval_subquery(&self, subquery: SelectStmt) -> Result<Table, PlanError> {
+        let plan = self.plan_select(subquery)?;
+        let mut engine = Engine::new(
+            self.catalog.clone(),
+            self.bpm.clone(),
+            self.settings.clone(),
+        );
+        Ok(engine.execute(plan)?)
+    }
+
+    fn eval_scalar_subquery(&self, subquery: SelectStmt) -> Result<(Datum, DataType), PlanError> {
+        let table = self.eval_subquery(subquery)?;
+        let tuples = table
+            .iter()
+            .flat_map(|s| s.tuple_iter().collect_vec())
+            .collect_vec();
+        let num_columns = table.schema.columns.len();
+        if num_columns != 1 || tuples.len() > 1 {
+            return Err(PlanError::NotScalarSubquery(tuples.len(), num_columns));
+        }
+        let data_type = table.schema.columns[0].data_type;
+        table.erase();
+        let datum = tuples
+            .into_iter()
+            .next()
+            .map(|mut tuple| tuple.remove(0))
+            .unwrap_or_else(|| Datum::null_of(&data_type));
+        Ok((datum, data_type))
+    }
+
+    /// evaluate the subquery of an in expr into its values, one column is expected
+    fn eval_in_subquery(&self, subquery: SelectStmt) -> Result<Vec<Datum>, PlanError> {
+        let table = self.eval_subquery(subquery)?;
+        let num_columns = table.schema.columns.len();
+        if num_columns != 1 {
+            table.erase();
+            return Err(PlanError::NotSingleColumnSubquery(num_columns));
+        }
+        let values = table
+            .iter()
+            .flat_map(|s| s.tuple_iter().collect_vec())
+            .map(|mut tuple| tuple.remove(0))
+            .collect_vec();
+        table.erase();
+        Ok(values)
+    }
+
+    /// replace every scalar subquery inside the expression with its value
+    pub fn plan_scalar_subqueries(&self, node: &mut ExprNode) -> Result<(), PlanError> {
+        match node {
+            ExprNode::Subquery(_) => {
+                let placeholder = ExprNode::Constant(ConstantExprNode {
+                    value: ConstantValue::Null,
+                });
+                if let ExprNode::Subquery(node_) = std::mem::replace(node, placeholder) {
+                    let (datum, data_type) = self.eval_scalar_subquery(*node_.subquery)?;
+                    *node = ExprNode::Constant(ConstantExprNode {
+                        value: ConstantValue::Evaluated(datum, data_type),
+                    });
+                }
+                Ok(())
+            }
+            ExprNode::Binary(node) => {
+                self.plan_scalar_subqueries(node.lhs.as_mut())?;
+                self.plan_scalar_subqueries(node.rhs.as_mut())
+            }
+            ExprNode::Like(node) => self.plan_scalar_subqueries(node.child.as_mut()),
+            ExprNode::Not(node) => self.plan_scalar_subqueries(node.child.as_mut()),
+            ExprNode::IsNull(node) => self.plan_scalar_subqueries(node.child.as_mut()),
+            ExprNode::Regexp(node) => self.plan_scalar_subqueries(node.child.as_mut()),
+            ExprNode::In(node) => {
+                self.plan_scalar_subqueries(node.child.as_mut())?;
+                let values = std::mem::replace(&mut node.values, InValues::Evaluated(vec![]));
+                node.values = match values {
+                    InValues::Subquery(subquery) => {
+                        InValues::Evaluated(self.eval_in_subquery(*subquery)?)
+                    }
+                    values => values,
+                };
+                Ok(())
+            }
+            ExprNode::FuncCall(node) => node
+                .args
+                .iter_mut()
+                .try_for_each(|arg| self.plan_scalar_subqueries(arg)),
+            ExprNode::Row(node) => node
+                .values
+                .iter_mut()
+                .try_for_each(|value| self.plan_scalar_subqueries(value)),
+            ExprNode::Case(node) => node
+                .branches
+                .iter_mut()
+                .flat_map(|(condition, result)| vec![condition, result])
+                .chain(node.else_expr.iter_mut().map(|e| e.as_mut()))
+                .try_for_each(|node| self.plan_scalar_subqueries(node)),
+            ExprNode::Constant(_) | ExprNode::ColumnRef(_) | ExprNode::Exists(_) => Ok(()),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::datum::{DataType, Datum};
+use crate::expr::ExprImpl;
 use crate::parser::ast::{ConstantValue, ExprNode, UpdateStmt};
 use crate::planner::{Plan, PlanError, Planner};
 use crate::table::SchemaError;
@@ -8,12 +9,14 @@ pub struct UpdatePlan {
     pub table_name: String,
     pub idx_with_values: Vec<(usize, Datum)>,
     pub child: Box<Plan>,
+    pub limit: Option<usize>,
+    /// check constraints of the table
+    pub checks: Vec<ExprImpl>,
 }
 
 impl Planner {
     pub fn plan_update(&self, stmt: UpdateStmt) -> Result<Plan, PlanError> {
         let table = self.catalog.borrow().find_table(&stmt.table_name)?;
-        let delete_plan = self.plan_delete(&stmt.table_name, &stmt.where_exprs)?;
         let idx_with_values: Vec<(usize, Datum)> = stmt
             .set_exprs
             .iter()
@@ -25,9 +28,11 @@ impl Planner {
                             .index_by_column_name(&column_ref.column_name)
                             .ok_or(SchemaError::ColumnNotFound)?;
                         match (table.schema.columns[idx].data_type, &value.value) {
-                            (DataType::Int(_), ConstantValue::Real(value)) => {
-                                Ok((idx, (*value as i32).into()))
-                            }
+                            (DataType::Int(_), ConstantValue::Real(value)) => Ok((
+                                idx,
+                                self.int_to_store(*value, &table.schema.columns[idx])?
+                                    .into(),
+                            )),
                             (DataType::VarChar(_), ConstantValue::String(value)) => {
                                 Ok((idx, value.as_str().into()))
                             }
@@ -37,6 +42,9 @@ impl Planner {
                             (DataType::Date(_), ConstantValue::Date(value)) => {
                                 Ok((idx, (*value).into()))
                             }
+                            (DataType::Bool(_), ConstantValue::Bool(value)) => {
+                                Ok((idx, (*value).into()))
+                            }
                             _ => todo!(),
                         }
                     }
@@ -44,12 +52,15 @@ impl Planner {
                 },
                 _ => todo!(),
             })
-            .collect::<Result<_, SchemaError>>()?;
-        let update_plan = Plan::Update(UpdatePlan {
-            table_name: stmt.table_name.clone(),
+            .collect::<Result<_, PlanError>>()?;
+        let plan = self.plan_scan(&stmt.table_name, &stmt.where_exprs, true)?;
+        let plan = self.plan_filter(table.schema.as_ref(), &stmt.where_exprs, plan)?;
+        Ok(Plan::Update(UpdatePlan {
+            table_name: stmt.table_name,
             idx_with_values,
-            child: Box::new(delete_plan),
-        });
-        self.plan_insert(&stmt.table_name, update_plan)
+            child: Box::new(plan),
+            limit: stmt.limit,
+            checks: self.plan_checks(&table.schema)?,
+        }))
     }
 }
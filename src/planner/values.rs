@@ -1,7 +1,9 @@
-use crate::expr::ExprImpl;
-use crate::parser::ast::ExprNode;
+use crate::datum::{DataType, Datum};
+use crate::expr::{int_of_real, ConstantExpr, ExprError, ExprImpl, FractionalInt};
+use crate::parser::ast::{ConstantExprNode, ConstantValue, ExprNode};
 use crate::planner::{Plan, PlanError, Planner};
-use crate::table::{SchemaError, SchemaRef};
+use crate::table::{Column, SchemaError, SchemaRef};
+use std::mem::discriminant;
 
 #[derive(Debug)]
 pub struct ValuesPlan {
@@ -10,6 +12,22 @@ pub struct ValuesPlan {
 }
 
 impl Planner {
+    /// the int a real is stored as in the int column, a fraction is truncated with a warning
+    /// unless it is an error
+    pub fn int_to_store(&self, value: f64, column: &Column) -> Result<i32, PlanError> {
+        match int_of_real(value) {
+            Err(ExprError::NotIntegral(_))
+                if self.settings.borrow().fractional_int == FractionalInt::Truncate =>
+            {
+                self.warn(format!(
+                    "Value {} Truncated To Fit Int Column {}",
+                    value, column.desc
+                ));
+                int_of_real(value.trunc()).map_err(|e| e.into())
+            }
+            result => result.map_err(|e| e.into()),
+        }
+    }
     pub fn plan_values(
         &self,
         values: Vec<Vec<ExprNode>>,
@@ -25,13 +43,40 @@ impl Planner {
                         .into_iter()
                         .zip(schema.columns.iter())
                         .map(|(node, col)| {
-                            ExprImpl::from_ast(
+                            if let (
+                                ExprNode::Constant(ConstantExprNode {
+                                    value: ConstantValue::Real(value),
+                                }),
+                                DataType::Int(_),
+                            ) = (&node, col.data_type)
+                            {
+                                let value = self.int_to_store(*value, col)?;
+                                return Ok(ExprImpl::Constant(ConstantExpr::new(
+                                    Datum::Int(Some(value)),
+                                    col.data_type,
+                                )));
+                            }
+                            let expr = ExprImpl::from_ast(
                                 &node,
-                                self.catalog.clone(),
+                                &self.settings.borrow(),
                                 &schema,
                                 Some(col.data_type),
-                            )
-                            .map_err(|e| e.into())
+                            )?;
+                            // a value of another type is not stored as the type of the column
+                            match expr.as_constant() {
+                                Some(datum)
+                                    if !datum.is_null()
+                                        && discriminant(&datum)
+                                            != discriminant(&Datum::null_of(&col.data_type)) =>
+                                {
+                                    Err(ExprError::IncompatibleTypes(
+                                        datum.data_type(),
+                                        col.data_type,
+                                    )
+                                    .into())
+                                }
+                                _ => Ok(expr),
+                            }
                         })
                         .collect::<Result<_, PlanError>>()
                 }
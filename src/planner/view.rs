@@ -0,0 +1,164 @@
+use crate::parser::ast::{
+    AggTarget, ColumnRefExprNode, CreateViewStmt, DropViewStmt, ExprNode, SelectStmt, Selectors,
+    Statement,
+};
+use crate::parser::parse;
+use crate::planner::{Plan, PlanError, Planner};
+use crate::table::SchemaError;
+use itertools::Itertools;
+use std::collections::HashSet;
+
+#[derive(Debug)]
+pub struct CreateViewPlan {
+    pub view_name: String,
+    pub definition: String,
+}
+
+#[derive(Debug)]
+pub struct DropViewPlan {
+    pub view_name: String,
+}
+
+fn parse_view(view_name: &str, definition: &str) -> Result<SelectStmt, PlanError> {
+    match parse(&format!("{};", definition)) {
+        Ok(Statement::Select(stmt)) => Ok(stmt),
+        _ => Err(PlanError::InvalidView(view_name.to_owned())),
+    }
+}
+
+/// columns the view exposes, none if all columns of the underlying table
+fn visible_columns(
+    view_name: &str,
+    view: &SelectStmt,
+) -> Result<Option<HashSet<String>>, PlanError> {
+    match &view.selectors {
+        _ if view.group_by_expr.is_some()
+            || !view.set_ops.is_empty()
+            || !view.distinct_on.is_empty()
+            || view.limit.is_some() =>
+        {
+            Err(PlanError::InvalidView(view_name.to_owned()))
+        }
+        Selectors::All => Ok(None),
+        Selectors::Exprs(exprs) => Ok(Some(
+            exprs
+                .iter()
+                .map(|expr| match expr {
+                    (ExprNode::ColumnRef(column_ref), None) => Ok(column_ref.column_name.clone()),
+                    _ => Err(PlanError::InvalidView(view_name.to_owned())),
+                })
+                .collect::<Result<_, _>>()?,
+        )),
+        Selectors::Agg(_) => Err(PlanError::InvalidView(view_name.to_owned())),
+    }
+}
+
+fn column_refs_of_select(stmt: &mut SelectStmt) -> Vec<&mut ColumnRefExprNode> {
+    let mut column_refs = vec![];
+    match &mut stmt.selectors {
+        Selectors::All => {}
+        Selectors::Exprs(exprs) => column_refs.extend(
+            exprs
+                .iter_mut()
+                .flat_map(|(expr, _)| expr.column_refs_mut()),
+        ),
+        Selectors::Agg(items) => {
+            column_refs.extend(items.iter_mut().flat_map(|item| match &mut item.target {
+                AggTarget::All => vec![],
+                AggTarget::Expr(expr) => expr.column_refs_mut(),
+            }))
+        }
+    }
+    column_refs.extend(
+        stmt.distinct_on
+            .iter_mut()
+            .flat_map(|expr| expr.column_refs_mut()),
+    );
+    column_refs.extend(
+        stmt.where_exprs
+            .iter_mut()
+            .flat_map(|expr| expr.column_refs_mut()),
+    );
+    column_refs.extend(
+        stmt.group_by_expr
+            .iter_mut()
+            .flat_map(|expr| expr.column_refs_mut()),
+    );
+    column_refs.extend(
+        stmt.order_by
+            .iter_mut()
+            .flat_map(|item| item.expr.column_refs_mut()),
+    );
+    column_refs
+}
+
+impl Planner {
+    pub fn plan_create_view(&self, stmt: CreateViewStmt) -> Result<Plan, PlanError> {
+        let view = parse_view(&stmt.view_name, &stmt.definition)?;
+        visible_columns(&stmt.view_name, &view)?;
+        let _ = self.plan_select(view)?;
+        Ok(Plan::CreateView(CreateViewPlan {
+            view_name: stmt.view_name,
+            definition: stmt.definition,
+        }))
+    }
+    pub fn plan_drop_view(&self, stmt: DropViewStmt) -> Result<Plan, PlanError> {
+        let _ = self.catalog.borrow().find_view(&stmt.view_name)?;
+        Ok(Plan::DropView(DropViewPlan {
+            view_name: stmt.view_name,
+        }))
+    }
+    /// merge the views selected from into the query, the view only filters and projects
+    /// the table under it, so its where exprs join those of the query
+    pub fn expand_views(&self, mut stmt: SelectStmt) -> Result<SelectStmt, PlanError> {
+        let views = stmt
+            .table_names
+            .iter()
+            .filter_map(|name| {
+                let definition = self.catalog.borrow().find_view(name).ok()?;
+                Some((name.clone(), definition))
+            })
+            .collect_vec();
+        if views.is_empty() || stmt.from_subquery.is_some() {
+            return Ok(stmt);
+        }
+        if stmt.table_names.len() > 1 {
+            return Err(PlanError::ViewInJoin(views[0].0.clone()));
+        }
+        let (view_name, definition) = views.into_iter().next().unwrap();
+        let mut view = parse_view(&view_name, &definition)?;
+        let visible = visible_columns(&view_name, &view)?;
+        for column_ref in column_refs_of_select(&mut stmt) {
+            if column_ref.table_name.as_ref() == Some(&view_name) {
+                column_ref.table_name = None;
+            }
+            let hidden =
+                matches!(&visible, Some(visible) if !visible.contains(&column_ref.column_name));
+            if column_ref.table_name.is_some() || hidden {
+                return Err(SchemaError::ColumnNotFound.into());
+            }
+        }
+        view.where_exprs.extend(stmt.where_exprs);
+        let is_agg = matches!(stmt.selectors, Selectors::Agg(_));
+        let merged = SelectStmt {
+            table_names: view.table_names,
+            from_subquery: view.from_subquery,
+            subquery_alias: view.subquery_alias,
+            distinct_on: stmt.distinct_on,
+            selectors: match stmt.selectors {
+                Selectors::All => view.selectors,
+                selectors => selectors,
+            },
+            where_exprs: view.where_exprs,
+            group_by_expr: stmt.group_by_expr,
+            order_by: if stmt.order_by.is_empty() && !is_agg {
+                view.order_by
+            } else {
+                stmt.order_by
+            },
+            limit: stmt.limit,
+            set_ops: vec![],
+        };
+        self.expand_views(merged)
+    }
+}
@@ -2,8 +2,10 @@ use super::{FrameID, PageID, StorageError};
 use crate::storage::clock::ClockReplacer;
 use crate::storage::disk::DiskManager;
 use crate::storage::page::{Page, PageRef};
+use crate::storage::wal::WriteAheadLog;
 use crate::storage::PAGE_ID_OF_METADATA;
 use itertools::Itertools;
+use log::warn;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::convert::TryInto;
@@ -14,17 +16,60 @@ pub struct BufferPoolManager {
     replacer: ClockReplacer,
     buf: Vec<PageRef>,
     page_table: HashMap<PageID, FrameID>,
+    detect_leak: bool,
+    /// calls of fetch since the pool was created
+    num_fetches: usize,
+    /// pages are logged here before they are written, if enabled
+    wal: Option<WriteAheadLog>,
 }
 
+/// how eagerly the pages changed by a statement are written into the database file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Synchronous {
+    /// written back on eviction or drop only
+    Off,
+    /// written back after each statement changing data
+    Normal,
+    /// written back and synced to the disk after each statement changing data
+    Full,
+}
+
+impl Synchronous {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Normal => "normal",
+            Self::Full => "full",
+        }
+    }
+}
+
+/// records in the log which trigger a checkpoint on commit
+const WAL_CHECKPOINT_RECORDS: usize = 1024;
+
 pub type BufferPoolManagerRef = Rc<RefCell<BufferPoolManager>>;
 
 impl Drop for BufferPoolManager {
     fn drop(&mut self) {
+        if self.wal.is_some() {
+            self.checkpoint().unwrap();
+        }
         for &frame_id in self.page_table.values() {
             if self.buf[frame_id].borrow().is_dirty {
                 self.disk.write(self.buf[frame_id].clone()).unwrap();
             }
         }
+        if self.detect_leak {
+            let pinned = self.page_pin_summary();
+            if !pinned.is_empty() {
+                warn!("pages still pinned at shutdown: {:?}", pinned);
+            }
+            debug_assert!(
+                pinned.is_empty(),
+                "pages still pinned at shutdown: {:?}",
+                pinned
+            );
+        }
     }
 }
 
@@ -58,7 +103,20 @@ impl BufferPoolManager {
     pub fn new_with_name(size: usize, name: String) -> Self {
         Self::new_with_disk(size, DiskManager::new_with_name(name).unwrap())
     }
-    pub fn new_with_disk(size: usize, disk: DiskManager) -> Self {
+    pub fn new_with_disk(size: usize, mut disk: DiskManager) -> Self {
+        // redo the logged writes lost by the last crash
+        let wal = match WriteAheadLog::exists(&disk.filename()) {
+            true => {
+                let mut wal = WriteAheadLog::open(&disk.filename()).unwrap();
+                let cnt = wal.replay(&mut disk).unwrap();
+                if cnt > 0 {
+                    warn!("recovered {} pages from the write-ahead log", cnt);
+                }
+                wal.truncate().unwrap();
+                Some(wal)
+            }
+            false => None,
+        };
         let buf = (0..size)
             .map(|_| Rc::new(RefCell::new(Page::new())))
             .collect_vec();
@@ -67,6 +125,9 @@ impl BufferPoolManager {
             replacer: ClockReplacer::new(size),
             buf,
             page_table: HashMap::new(),
+            detect_leak: false,
+            num_fetches: 0,
+            wal,
         };
         if bpm.num_pages().unwrap() == PAGE_ID_OF_METADATA {
             let page = bpm.alloc().unwrap();
@@ -85,13 +146,179 @@ impl BufferPoolManager {
     pub fn filename(&self) -> String {
         self.disk.filename()
     }
+    /// report pages still pinned when the pool is dropped
+    #[allow(dead_code)]
+    pub fn detect_leak_on_drop(&mut self, detect_leak: bool) {
+        self.detect_leak = detect_leak;
+    }
+    #[allow(dead_code)]
+    pub fn num_fetches(&self) -> usize {
+        self.num_fetches
+    }
+    /// (page_id, pin_count) of every page with a nonzero pin count
+    pub fn page_pin_summary(&self) -> Vec<(PageID, usize)> {
+        self.page_table
+            .iter()
+            .map(|(&page_id, &frame_id)| (page_id, self.buf[frame_id].borrow().pin_count))
+            .filter(|(_, pin_count)| *pin_count > 0)
+            .sorted()
+            .collect_vec()
+    }
+    /// write the page back if it is cached and dirty
+    pub fn flush(&mut self, page_id: PageID) -> Result<(), StorageError> {
+        if let Some(&frame_id) = self.page_table.get(&page_id) {
+            let page = self.buf[frame_id].clone();
+            if page.borrow().is_dirty {
+                self.write_back(page.clone())?;
+                page.borrow_mut().is_dirty = false;
+            }
+        }
+        Ok(())
+    }
     pub fn clear(&mut self) -> Result<(), StorageError> {
+        if let Some(wal) = self.wal.as_mut() {
+            wal.truncate()?;
+        }
         self.disk.clear()
     }
+    /// log the page before it is written, so a torn write can be redone once the
+    /// statement changing it commits
+    fn write_back(&mut self, page: PageRef) -> Result<(), StorageError> {
+        if let Some(wal) = self.wal.as_mut() {
+            wal.log(&[page.clone()])?;
+        }
+        self.disk.write(page)
+    }
+    fn dirty_pages(&self) -> Vec<PageRef> {
+        self.page_table
+            .iter()
+            .sorted()
+            .map(|(_, &frame_id)| self.buf[frame_id].clone())
+            .filter(|page| page.borrow().is_dirty)
+            .collect_vec()
+    }
+    #[allow(dead_code)]
+    pub fn wal_enabled(&self) -> bool {
+        self.wal.is_some()
+    }
+    /// turn the write-ahead log on or off, it stays on across restarts
+    /// as long as the log file exists
+    pub fn set_wal(&mut self, enabled: bool) -> Result<(), StorageError> {
+        match (enabled, self.wal.is_some()) {
+            (true, false) => {
+                let wal = WriteAheadLog::open(&self.disk.filename())?;
+                self.wal = Some(wal);
+                self.commit()?;
+            }
+            (false, true) => {
+                self.checkpoint()?;
+                let wal = self.wal.take().unwrap();
+                std::fs::remove_file(wal.filename())?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+    /// log the images of all dirty pages with a commit marker, the changes so far
+    /// survive a crash once it returns
+    pub fn commit(&mut self) -> Result<(), StorageError> {
+        if self.wal.is_none() {
+            return Ok(());
+        }
+        let pages = self.dirty_pages();
+        self.wal.as_mut().unwrap().commit(&pages)?;
+        if self.wal.as_ref().unwrap().num_records() >= WAL_CHECKPOINT_RECORDS {
+            self.checkpoint()?;
+        }
+        Ok(())
+    }
+    /// write all dirty pages into the database file, returns the number of pages written
+    pub fn flush_all(&mut self) -> Result<usize, StorageError> {
+        let cnt = self.write_dirty_pages()?;
+        self.disk.sync()?;
+        Ok(cnt)
+    }
+    fn write_dirty_pages(&mut self) -> Result<usize, StorageError> {
+        let pages = self.dirty_pages();
+        for page in pages.iter() {
+            self.disk.write(page.clone())?;
+            page.borrow_mut().is_dirty = false;
+        }
+        Ok(pages.len())
+    }
+    /// write the changes of a statement back as the mode asks, returns the number of
+    /// pages written
+    pub fn sync_changes(&mut self, synchronous: Synchronous) -> Result<usize, StorageError> {
+        match synchronous {
+            Synchronous::Off => Ok(0),
+            Synchronous::Normal => self.write_dirty_pages(),
+            Synchronous::Full => self.flush_all(),
+        }
+    }
+    /// records a recovery would replay now
+    pub fn wal_num_records(&self) -> usize {
+        self.wal.as_ref().map_or(0, |wal| wal.num_records())
+    }
+    /// flush all dirty pages, then the log is no longer needed
+    pub fn checkpoint(&mut self) -> Result<usize, StorageError> {
+        let cnt = self.flush_all()?;
+        if let Some(wal) = self.wal.as_mut() {
+            wal.truncate()?;
+        }
+        Ok(cnt)
+    }
+    /// grow the pool with empty frames, or shrink it by writing back and dropping
+    /// unpinned pages, the pinned ones are moved into the frames kept
+    #[allow(dead_code)]
+    pub fn resize(&mut self, size: usize) -> Result<(), StorageError> {
+        let is_pinned = |page: &PageRef| page.borrow().pin_count > 0;
+        let num_pinned = self.buf.iter().filter(|page| is_pinned(page)).count();
+        if size < num_pinned {
+            return Err(StorageError::PoolTooSmall(size, num_pinned));
+        }
+        if size > self.buf.len() {
+            self.buf
+                .resize_with(size, || Rc::new(RefCell::new(Page::new())));
+            self.replacer.resize(size);
+            return Ok(());
+        }
+        let mut unpinned_kept = (0..size)
+            .filter(|&frame_id| !is_pinned(&self.buf[frame_id]))
+            .collect_vec()
+            .into_iter();
+        for frame_id in size..self.buf.len() {
+            if !is_pinned(&self.buf[frame_id]) {
+                continue;
+            }
+            let kept_frame_id = unpinned_kept.next().unwrap();
+            self.buf.swap(frame_id, kept_frame_id);
+            self.replacer.swap(frame_id, kept_frame_id);
+            for frame_id in [frame_id, kept_frame_id] {
+                if let Some(page_id) = self.buf[frame_id].borrow().page_id {
+                    self.page_table.insert(page_id, frame_id);
+                }
+            }
+        }
+        for frame_id in size..self.buf.len() {
+            let page = self.buf[frame_id].clone();
+            if page.borrow().is_dirty {
+                self.write_back(page.clone())?;
+                page.borrow_mut().is_dirty = false;
+            }
+        }
+        for page in self.buf.split_off(size) {
+            if let Some(page_id) = page.borrow().page_id {
+                self.page_table.remove(&page_id);
+            }
+        }
+        self.replacer.resize(size);
+        Ok(())
+    }
     pub fn fetch(&mut self, page_id: PageID) -> Result<PageRef, StorageError> {
         if page_id >= self.num_pages()? {
             return Err(StorageError::PageIDOutOfBound(page_id));
         }
+        self.num_fetches += 1;
         // if we can find this page in buffer
         if let Some(&frame_id) = self.page_table.get(&page_id) {
             let page = self.buf[frame_id].clone();
@@ -106,7 +333,7 @@ impl BufferPoolManager {
         if let Some(this_page_id) = this_page_id {
             // write back
             if page.borrow_mut().is_dirty {
-                self.disk.write(page.clone())?;
+                self.write_back(page.clone())?;
             }
             // erase from page_table
             self.page_table.remove(&this_page_id);
@@ -145,14 +372,7 @@ impl BufferPoolManager {
             if page.borrow().pin_count != 1 {
                 return Err(StorageError::FreePinnedPage(page_id));
             }
-            // get page_id of next free page
-            let page_id_of_next_free_page =
-                u32::from_le_bytes(page.borrow().buffer[0..4].try_into().unwrap()) as PageID;
-            let page_id_of_next_free_page = match page_id_of_next_free_page {
-                0 => None,
-                page_id => Some(page_id),
-            };
-            self.set_page_id_of_first_free_page(page_id_of_next_free_page);
+            self.set_page_id_of_first_free_page(next_free_page_of(&page));
             page
         } else {
             // ask replacer for a new frame_id
@@ -163,7 +383,7 @@ impl BufferPoolManager {
             if let Some(this_page_id) = this_page_id {
                 // write back
                 if page.borrow_mut().is_dirty {
-                    self.disk.write(page.clone())?;
+                    self.write_back(page.clone())?;
                 }
                 // remove from page_table
                 self.page_table.remove(&this_page_id);
@@ -195,12 +415,36 @@ impl BufferPoolManager {
     pub fn num_pages(&self) -> Result<usize, StorageError> {
         self.disk.num_pages()
     }
+    /// page ids on the free chain, from the first free page of the metadata
+    pub fn free_page_ids(&mut self) -> Result<Vec<PageID>, StorageError> {
+        let num_pages = self.num_pages()?;
+        let mut page_ids = vec![];
+        let mut page_id = self.get_page_id_of_first_free_page();
+        // a chain longer than the file must have a cycle
+        while let Some(this_page_id) = page_id.filter(|_| page_ids.len() < num_pages) {
+            let page = self.fetch(this_page_id)?;
+            page_id = next_free_page_of(&page);
+            self.unpin(this_page_id)?;
+            page_ids.push(this_page_id);
+        }
+        Ok(page_ids)
+    }
+}
+
+/// a free page links to the next one with its leading 4 bytes, 0 ends the chain
+fn next_free_page_of(page: &PageRef) -> Option<PageID> {
+    match u32::from_le_bytes(page.borrow().buffer[0..4].try_into().unwrap()) as PageID {
+        0 => None,
+        page_id => Some(page_id),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::datum::{DataType, Datum};
     use crate::storage::PAGE_SIZE;
+    use crate::table::{Schema, Slice};
     use rand::Rng;
     use std::fs::remove_file;
 
@@ -293,11 +537,153 @@ mod tests {
                 bpm.borrow_mut().unpin(page_id).unwrap();
             }
             assert_eq!(num_pages, bpm.borrow().num_pages().unwrap());
+            assert!(bpm.borrow_mut().free_page_ids().unwrap().is_empty());
+            filename
+        };
+        remove_file(filename).unwrap()
+    }
+
+    #[test]
+    fn free_page_ids_test() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(10);
+            let filename = bpm.borrow().filename();
+            let mut page_ids = vec![];
+            for _ in 0..50 {
+                let page = bpm.borrow_mut().alloc().unwrap();
+                let page_id = page.borrow().page_id.unwrap();
+                bpm.borrow_mut().unpin(page_id).unwrap();
+                page_ids.push(page_id);
+            }
+            assert!(bpm.borrow_mut().free_page_ids().unwrap().is_empty());
+            let freed = page_ids.iter().step_by(3).cloned().collect_vec();
+            for page_id in freed.iter() {
+                bpm.borrow_mut().free(*page_id).unwrap();
+            }
+            // the last freed page is the head of the chain
+            let free_page_ids = bpm.borrow_mut().free_page_ids().unwrap();
+            assert_eq!(free_page_ids, freed.into_iter().rev().collect_vec());
+            assert!(bpm.borrow().page_pin_summary().is_empty());
             filename
         };
         remove_file(filename).unwrap()
     }
 
+    #[test]
+    fn pin_summary_test() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(10);
+            let filename = bpm.borrow().filename();
+            let page_ids = (0..5)
+                .map(|_| {
+                    let page = bpm.borrow_mut().alloc().unwrap();
+                    let page_id = page.borrow().page_id;
+                    page_id.unwrap()
+                })
+                .collect_vec();
+            let _page = bpm.borrow_mut().fetch(page_ids[0]).unwrap();
+            assert_eq!(
+                bpm.borrow().page_pin_summary(),
+                vec![(1, 2), (2, 1), (3, 1), (4, 1), (5, 1)]
+            );
+            for page_id in page_ids.iter() {
+                bpm.borrow_mut().unpin(*page_id).unwrap();
+            }
+            // leaked on purpose
+            assert_eq!(bpm.borrow().page_pin_summary(), vec![(1, 1)]);
+            bpm.borrow_mut().unpin(page_ids[0]).unwrap();
+            assert!(bpm.borrow().page_pin_summary().is_empty());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn no_pin_after_drop_test() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(10);
+            bpm.borrow_mut().detect_leak_on_drop(true);
+            let filename = bpm.borrow().filename();
+            {
+                let schema = Rc::new(Schema::from_type_and_names(&[(
+                    DataType::new_as_int(false),
+                    "v1".to_string(),
+                )]));
+                let mut slice = Slice::new(bpm.clone(), schema);
+                slice.insert(&[Datum::Int(Some(1))]).unwrap();
+                assert!(!bpm.borrow().page_pin_summary().is_empty());
+            }
+            assert!(bpm.borrow().page_pin_summary().is_empty());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "pages still pinned at shutdown")]
+    fn leak_detect_test() {
+        let mut bpm = BufferPoolManager::new_random(10);
+        bpm.detect_leak_on_drop(true);
+        remove_file(bpm.filename()).unwrap();
+        // alloc without unpin
+        let page = bpm.alloc().unwrap();
+        let page_id = page.borrow().page_id.unwrap();
+        assert_eq!(bpm.page_pin_summary(), vec![(page_id, 1)]);
+    }
+
+    #[test]
+    fn resize_test() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(5);
+            let filename = bpm.borrow().filename();
+            let page_ids = (0..20)
+                .map(|idx| {
+                    let page = bpm.borrow_mut().alloc().unwrap();
+                    page.borrow_mut().buffer[0] = idx as u8;
+                    page.borrow_mut().is_dirty = true;
+                    let page_id = page.borrow().page_id.unwrap();
+                    bpm.borrow_mut().unpin(page_id).unwrap();
+                    page_id
+                })
+                .collect_vec();
+            assert_eq!(bpm.borrow().page_table.len(), 5);
+            // all the pages stay resident in the grown pool
+            bpm.borrow_mut().resize(30).unwrap();
+            for &page_id in page_ids.iter() {
+                bpm.borrow_mut().fetch(page_id).unwrap();
+                bpm.borrow_mut().unpin(page_id).unwrap();
+            }
+            assert!(page_ids
+                .iter()
+                .all(|page_id| bpm.borrow().page_table.contains_key(page_id)));
+            for &page_id in page_ids.iter() {
+                let page = bpm.borrow_mut().fetch(page_id).unwrap();
+                let value = page.borrow().buffer[0] + 1;
+                page.borrow_mut().buffer[1] = value;
+                page.borrow_mut().is_dirty = true;
+                bpm.borrow_mut().unpin(page_id).unwrap();
+            }
+            // the pinned pages are kept, the others are written back
+            let pinned = bpm.borrow_mut().fetch(page_ids[19]).unwrap();
+            assert!(matches!(
+                bpm.borrow_mut().resize(0),
+                Err(StorageError::PoolTooSmall(0, 1))
+            ));
+            bpm.borrow_mut().resize(3).unwrap();
+            assert_eq!(bpm.borrow().page_table.len(), 3);
+            assert_eq!(pinned.borrow().pin_count, 1);
+            bpm.borrow_mut().unpin(page_ids[19]).unwrap();
+            for (idx, &page_id) in page_ids.iter().enumerate() {
+                let page = bpm.borrow_mut().fetch(page_id).unwrap();
+                assert_eq!(page.borrow().buffer[0..2], [idx as u8, idx as u8 + 1]);
+                bpm.borrow_mut().unpin(page_id).unwrap();
+            }
+            assert!(bpm.borrow().page_pin_summary().is_empty());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
     #[test]
     fn stress_test() {
         let filename = {
@@ -312,4 +698,80 @@ mod tests {
         };
         remove_file(filename).unwrap();
     }
+
+    #[test]
+    fn wal_recovery_test() {
+        let (filename, page_id1, page_id2) = {
+            let mut bpm = BufferPoolManager::new_random(10);
+            let filename = bpm.filename();
+            bpm.set_wal(true).unwrap();
+            let page1 = bpm.alloc().unwrap();
+            let page2 = bpm.alloc().unwrap();
+            let page_id1 = page1.borrow().page_id.unwrap();
+            let page_id2 = page2.borrow().page_id.unwrap();
+            page1.borrow_mut().buffer[0..4].copy_from_slice(&[1, 2, 3, 4]);
+            page1.borrow_mut().is_dirty = true;
+            page2.borrow_mut().buffer[0..4].copy_from_slice(&[5, 6, 7, 8]);
+            page2.borrow_mut().is_dirty = true;
+            bpm.unpin(page_id1).unwrap();
+            bpm.unpin(page_id2).unwrap();
+            bpm.commit().unwrap();
+            // not committed, lost by the crash
+            let page2 = bpm.fetch(page_id2).unwrap();
+            page2.borrow_mut().buffer[0] = 0;
+            page2.borrow_mut().is_dirty = true;
+            bpm.unpin(page_id2).unwrap();
+            // crash, the dirty pages are never written back
+            std::mem::forget(bpm);
+            (filename, page_id1, page_id2)
+        };
+        {
+            let mut bpm = BufferPoolManager::new_with_name(10, filename.clone());
+            assert!(bpm.wal_enabled());
+            let page1 = bpm.fetch(page_id1).unwrap();
+            let page2 = bpm.fetch(page_id2).unwrap();
+            assert_eq!(page1.borrow().buffer[0..4], [1, 2, 3, 4]);
+            assert_eq!(page2.borrow().buffer[0..4], [5, 6, 7, 8]);
+            bpm.unpin(page_id1).unwrap();
+            bpm.unpin(page_id2).unwrap();
+            bpm.set_wal(false).unwrap();
+        }
+        assert!(!WriteAheadLog::exists(&filename));
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn wal_checkpoint_test() {
+        let (filename, page_id) = {
+            let mut bpm = BufferPoolManager::new_random(10);
+            let filename = bpm.filename();
+            bpm.set_wal(true).unwrap();
+            let page = bpm.alloc().unwrap();
+            let page_id = page.borrow().page_id.unwrap();
+            page.borrow_mut().buffer[0..4].copy_from_slice(&[1, 2, 3, 4]);
+            page.borrow_mut().is_dirty = true;
+            bpm.unpin(page_id).unwrap();
+            bpm.commit().unwrap();
+            assert!(bpm.wal_num_records() > 0);
+            assert!(bpm.checkpoint().unwrap() > 0);
+            assert_eq!(bpm.wal_num_records(), 0);
+            std::mem::forget(bpm);
+            (filename, page_id)
+        };
+        assert_eq!(
+            std::fs::metadata(WriteAheadLog::filename_of(&filename))
+                .unwrap()
+                .len(),
+            0
+        );
+        {
+            // nothing to replay, the pages are already in the database file
+            let mut bpm = BufferPoolManager::new_with_name(10, filename.clone());
+            let page = bpm.fetch(page_id).unwrap();
+            assert_eq!(page.borrow().buffer[0..4], [1, 2, 3, 4]);
+            bpm.unpin(page_id).unwrap();
+            bpm.set_wal(false).unwrap();
+        }
+        remove_file(filename).unwrap();
+    }
 }
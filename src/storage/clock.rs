@@ -57,6 +57,21 @@ impl ClockReplacer {
         self.clock.fill(ClockItem::default());
     }
 
+    /// grow with unpinned frames, or drop the trailing frames which must be unpinned
+    pub fn resize(&mut self, size: usize) {
+        assert!(self.clock.iter().skip(size).all(|item| !item.pinned));
+        self.clock.resize(size, ClockItem::default());
+        self.num_unpinned = self.clock.iter().filter(|item| !item.pinned).count();
+        if self.handle >= size {
+            self.handle = 0;
+        }
+    }
+
+    /// exchange the states of two frames, as their pages are moved
+    pub fn swap(&mut self, lhs: FrameID, rhs: FrameID) {
+        self.clock.swap(lhs, rhs);
+    }
+
     /// we only unpin a frame when the pin count is 0
     pub fn unpin(&mut self, frame_id: FrameID) {
         assert!(frame_id < self.clock.len());
@@ -123,4 +138,23 @@ mod tests {
         assert_eq!(replacer.victim().unwrap(), 1);
         assert_eq!(replacer.victim().unwrap(), 3);
     }
+
+    #[test]
+    fn resize_clock_replacer_test() {
+        let mut replacer = ClockReplacer::new(3);
+        assert_eq!(replacer.victim().unwrap(), 0);
+        replacer.resize(5);
+        assert_eq!(replacer.victim().unwrap(), 1);
+        assert_eq!(replacer.victim().unwrap(), 2);
+        assert_eq!(replacer.victim().unwrap(), 3);
+        assert_eq!(replacer.victim().unwrap(), 4);
+        assert!(replacer.victim().is_err());
+        // move the pinned frame 4 into the unpinned frame 1 before dropping it
+        replacer.unpin(1);
+        replacer.swap(1, 4);
+        replacer.resize(4);
+        assert!(replacer.victim().is_err());
+        replacer.unpin(2);
+        assert_eq!(replacer.victim().unwrap(), 2);
+    }
 }
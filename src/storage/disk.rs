@@ -1,26 +1,52 @@
 use super::*;
 use crate::storage::page::PageRef;
+use std::borrow::Cow;
+use std::convert::TryInto;
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
 use uuid::Uuid;
 
+/// a page takes its image and the checksum of the image on disk
+const FRAME_SIZE: usize = PAGE_SIZE + CHECKSUM_SIZE;
+
+///
+/// File Header:
+///
+///     the bytes after the first free page id in the metadata page
+///
+///     | magic | format version |
+///
+/// a file without the magic predates the header, its frames are the bare page images
+///
+const HEADER_MAGIC: u32 = 0x3142_444E;
+const OFFSET_OF_MAGIC: usize = 4;
+const OFFSET_OF_FORMAT_VERSION: usize = 8;
+const END_OF_HEADER: usize = 12;
+
+/// frames of the bare page images
+const FORMAT_VERSION_WITHOUT_CHECKSUM: u32 = 0;
+/// frames of the page images followed by their checksums
+const FORMAT_VERSION_WITH_CHECKSUM: u32 = 1;
+
 #[allow(dead_code)]
 pub struct DiskManager {
     file: File,
     filename: String,
+    format_version: u32,
 }
 
 impl DiskManager {
     pub fn new_with_name(name: String) -> Result<Self, StorageError> {
-        let file = OpenOptions::new()
+        let mut file = OpenOptions::new()
             .read(true)
             .write(true)
             .create(true)
             .open(name.clone())?;
-
+        let format_version = Self::format_version_of(&mut file)?;
         Ok(DiskManager {
             file,
             filename: name,
+            format_version,
         })
     }
     pub fn new() -> Result<Self, StorageError> {
@@ -32,37 +58,101 @@ impl DiskManager {
         let filename = format!("naive.test.{}.db", Uuid::new_v4());
         Self::new_with_name(filename)
     }
+    /// the format of the file read from the header, an empty file takes the latest one
+    fn format_version_of(file: &mut File) -> Result<u32, StorageError> {
+        if file.metadata()?.len() == 0 {
+            return Ok(FORMAT_VERSION_WITH_CHECKSUM);
+        }
+        let mut header = [0u8; END_OF_HEADER];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+        let magic = u32::from_le_bytes(
+            header[OFFSET_OF_MAGIC..OFFSET_OF_FORMAT_VERSION]
+                .try_into()
+                .unwrap(),
+        );
+        if magic != HEADER_MAGIC {
+            return Ok(FORMAT_VERSION_WITHOUT_CHECKSUM);
+        }
+        match u32::from_le_bytes(
+            header[OFFSET_OF_FORMAT_VERSION..END_OF_HEADER]
+                .try_into()
+                .unwrap(),
+        ) {
+            FORMAT_VERSION_WITH_CHECKSUM => Ok(FORMAT_VERSION_WITH_CHECKSUM),
+            version => Err(StorageError::UnknownFormatVersion(version)),
+        }
+    }
+    fn has_checksum(&self) -> bool {
+        self.format_version == FORMAT_VERSION_WITH_CHECKSUM
+    }
+    fn frame_size(&self) -> usize {
+        match self.has_checksum() {
+            true => FRAME_SIZE,
+            false => PAGE_SIZE,
+        }
+    }
     #[allow(dead_code)]
     pub fn filename(&self) -> String {
         self.filename.clone()
     }
     pub fn clear(&mut self) -> Result<(), StorageError> {
-        self.file.set_len(0).map_err(StorageError::IOError)
+        self.file.set_len(0).map_err(StorageError::IOError)?;
+        self.format_version = FORMAT_VERSION_WITH_CHECKSUM;
+        Ok(())
     }
     /// Fetch content from disk to memory, the actual data should be
     /// hold in the buffer pool, so we should NOT allocate memory in this
     /// stage.
+    /// the image is checked against the checksum written with it
     pub fn read(&mut self, page_id: PageID, page: PageRef) -> Result<(), StorageError> {
-        let offset = (page_id as usize) * PAGE_SIZE;
+        let offset = (page_id as usize) * self.frame_size();
         self.file.seek(SeekFrom::Start(offset as u64))?;
         page.borrow_mut().page_id = Some(page_id);
         page.borrow_mut().is_dirty = false;
         self.file.read_exact(page.borrow_mut().buffer.as_mut())?;
+        if self.has_checksum() {
+            let mut checksum = [0u8; CHECKSUM_SIZE];
+            self.file.read_exact(&mut checksum)?;
+            if u32::from_le_bytes(checksum) != checksum_of(&page.borrow().buffer) {
+                return Err(StorageError::ChecksumMismatch(page_id));
+            }
+        }
         Ok(())
     }
     pub fn write(&mut self, page: PageRef) -> Result<(), StorageError> {
-        let offset = page.borrow_mut().page_id.unwrap() * PAGE_SIZE;
+        let page_id = page.borrow().page_id.unwrap();
+        self.write_at(page_id, &page.borrow().buffer)
+    }
+    /// write the image of a page with its checksum, the file grows if the page is beyond
+    /// the end, the metadata page carries the header of the format
+    pub fn write_at(&mut self, page_id: PageID, buffer: &[u8]) -> Result<(), StorageError> {
+        let offset = page_id * self.frame_size();
         self.file.seek(SeekFrom::Start(offset as u64))?;
-        self.file.write_all(&page.borrow_mut().buffer)?;
+        if !self.has_checksum() {
+            self.file.write_all(buffer)?;
+            return Ok(());
+        }
+        let mut image = Cow::from(buffer);
+        if page_id == PAGE_ID_OF_METADATA {
+            let image = image.to_mut();
+            image[OFFSET_OF_MAGIC..OFFSET_OF_FORMAT_VERSION]
+                .copy_from_slice(&HEADER_MAGIC.to_le_bytes());
+            image[OFFSET_OF_FORMAT_VERSION..END_OF_HEADER]
+                .copy_from_slice(&self.format_version.to_le_bytes());
+        }
+        self.file.write_all(&image)?;
+        self.file.write_all(&checksum_of(&image).to_le_bytes())?;
         Ok(())
     }
+    pub fn sync(&mut self) -> Result<(), StorageError> {
+        self.file.sync_all().map_err(StorageError::IOError)
+    }
+    /// the new page is zeroed, and written so that its checksum is in place
     pub fn allocate(&mut self, page: PageRef) -> Result<(), StorageError> {
-        let meta = self.file.metadata()?;
-        let len = meta.len() as usize;
-        assert_eq!(len % PAGE_SIZE, 0);
-        self.file.set_len((len + PAGE_SIZE) as u64)?;
-        let page_id = len / PAGE_SIZE;
-        self.file.read_exact(page.borrow_mut().buffer.as_mut())?;
+        let page_id = self.num_pages()?;
+        page.borrow_mut().buffer.fill(0);
+        self.write_at(page_id, &page.borrow().buffer)?;
         page.borrow_mut().page_id = Some(page_id);
         page.borrow_mut().is_dirty = false;
         page.borrow_mut().pin_count = 1;
@@ -70,9 +160,9 @@ impl DiskManager {
     }
     pub fn num_pages(&self) -> Result<usize, StorageError> {
         let meta = self.file.metadata()?;
-        let len = meta.len();
-        assert_eq!(len % (PAGE_SIZE as u64), 0);
-        Ok((len / (PAGE_SIZE as u64)) as usize)
+        let len = meta.len() as usize;
+        assert_eq!(len % self.frame_size(), 0);
+        Ok(len / self.frame_size())
     }
 }
 
@@ -93,6 +183,9 @@ mod tests {
             let filename = disk_manager.filename();
             // clear content
             disk_manager.clear().unwrap();
+            // the metadata page carries the header
+            let meta_page = Rc::new(RefCell::new(Page::new()));
+            disk_manager.allocate(meta_page).unwrap();
             // allocate three pages
             let page1 = Rc::new(RefCell::new(Page::new()));
             let page2 = Rc::new(RefCell::new(Page::new()));
@@ -100,10 +193,10 @@ mod tests {
             disk_manager.allocate(page1.clone()).unwrap();
             disk_manager.allocate(page2.clone()).unwrap();
             disk_manager.allocate(page3.clone()).unwrap();
-            // since it's empty, page_id should increase from 0
-            assert_eq!(page1.borrow().page_id.unwrap(), 0);
-            assert_eq!(page2.borrow().page_id.unwrap(), 1);
-            assert_eq!(page3.borrow().page_id.unwrap(), 2);
+            // since it's empty, page_id should increase from 1
+            assert_eq!(page1.borrow().page_id.unwrap(), 1);
+            assert_eq!(page2.borrow().page_id.unwrap(), 2);
+            assert_eq!(page3.borrow().page_id.unwrap(), 3);
             // write random values
             let mut rng = rand::thread_rng();
             for i in 0..PAGE_SIZE {
@@ -138,4 +231,86 @@ mod tests {
         };
         remove_file(filename).unwrap();
     }
+
+    #[test]
+    fn checksum_mismatch_test() {
+        let filename = {
+            let mut disk_manager = DiskManager::new_random().unwrap();
+            let filename = disk_manager.filename();
+            assert_eq!(checksum_of(b"123456789"), 0xCBF4_3926);
+            let page = Rc::new(RefCell::new(Page::new()));
+            disk_manager.allocate(page.clone()).unwrap();
+            disk_manager.allocate(page.clone()).unwrap();
+            page.borrow_mut().buffer[100] = 42;
+            disk_manager.write(page.clone()).unwrap();
+            disk_manager.read(0, page.clone()).unwrap();
+            disk_manager.read(1, page.clone()).unwrap();
+            assert_eq!(page.borrow().buffer[100], 42);
+            // flip a byte of the second page behind the disk manager
+            let mut file = OpenOptions::new().write(true).open(&filename).unwrap();
+            file.seek(SeekFrom::Start((FRAME_SIZE + 100) as u64))
+                .unwrap();
+            file.write_all(&[43]).unwrap();
+            drop(file);
+            assert!(disk_manager.read(0, page.clone()).is_ok());
+            assert!(matches!(
+                disk_manager.read(1, page.clone()),
+                Err(StorageError::ChecksumMismatch(1))
+            ));
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn format_version_test() {
+        let filename = {
+            let mut disk_manager = DiskManager::new_random().unwrap();
+            let filename = disk_manager.filename();
+            let page = Rc::new(RefCell::new(Page::new()));
+            disk_manager.allocate(page.clone()).unwrap();
+            disk_manager.allocate(page.clone()).unwrap();
+            page.borrow_mut().buffer[100] = 42;
+            disk_manager.write(page.clone()).unwrap();
+            drop(disk_manager);
+            // the header is kept across reopening
+            let mut disk_manager = DiskManager::new_with_name(filename.clone()).unwrap();
+            assert!(disk_manager.has_checksum());
+            assert_eq!(disk_manager.num_pages().unwrap(), 2);
+            disk_manager.read(1, page.clone()).unwrap();
+            assert_eq!(page.borrow().buffer[100], 42);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn read_file_without_checksum_test() {
+        let filename = {
+            let filename = DiskManager::new_random().unwrap().filename();
+            // two bare page images, as written before the checksums
+            let mut file = OpenOptions::new().write(true).open(&filename).unwrap();
+            let mut image = [0u8; PAGE_SIZE];
+            file.write_all(&image).unwrap();
+            image[100] = 42;
+            file.write_all(&image).unwrap();
+            drop(file);
+            let mut disk_manager = DiskManager::new_with_name(filename.clone()).unwrap();
+            assert!(!disk_manager.has_checksum());
+            assert_eq!(disk_manager.num_pages().unwrap(), 2);
+            let page = Rc::new(RefCell::new(Page::new()));
+            disk_manager.read(1, page.clone()).unwrap();
+            assert_eq!(page.borrow().buffer[100], 42);
+            // the file keeps its format as it grows
+            disk_manager.allocate(page.clone()).unwrap();
+            assert_eq!(page.borrow().page_id, Some(2));
+            disk_manager.write(page.clone()).unwrap();
+            assert_eq!(
+                std::fs::metadata(&filename).unwrap().len(),
+                (3 * PAGE_SIZE) as u64
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
 }
@@ -1,3 +1,4 @@
+use crate::error::ErrorCode;
 use thiserror::Error;
 
 mod buffer;
@@ -5,10 +6,11 @@ mod clock;
 mod disk;
 mod page;
 mod slotted;
+mod wal;
 
-pub use buffer::{BufferPoolManager, BufferPoolManagerRef};
+pub use buffer::{BufferPoolManager, BufferPoolManagerRef, Synchronous};
 
-pub use page::{Page, PageRef};
+pub use page::{checksum_of, Page, PageRef, CHECKSUM_SIZE};
 pub use slotted::{KeyDataIter, SlotIndexIter, SlottedPage, SlottedPageError};
 
 pub const PAGE_SIZE: usize = 16384;
@@ -34,4 +36,16 @@ pub enum StorageError {
     PageIDOutOfBound(PageID),
     #[error("Free Pinned Page: {0}")]
     FreePinnedPage(PageID),
+    #[error("Checksum Mismatch Of Page {0}")]
+    ChecksumMismatch(PageID),
+    #[error("Unknown Format Version Of File: {0}")]
+    UnknownFormatVersion(u32),
+    #[error("Pool Of {0} Frames Smaller Than {1} Pinned Pages")]
+    PoolTooSmall(usize, usize),
+}
+
+impl StorageError {
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::Internal
+    }
 }
@@ -12,6 +12,36 @@ pub struct Page {
 
 pub type PageRef = Rc<RefCell<Page>>;
 
+/// bytes of the checksum stored after each page on disk, unless the file predates checksums
+pub const CHECKSUM_SIZE: usize = 4;
+
+/// crc32 of each byte, reflected with the polynomial 0xEDB88320
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let mut crc = byte as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+            bit += 1;
+        }
+        table[byte] = crc;
+        byte += 1;
+    }
+    table
+};
+
+/// crc32 of the page image
+pub fn checksum_of(buffer: &[u8]) -> u32 {
+    !buffer.iter().fold(!0u32, |crc, byte| {
+        CRC32_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
 impl Default for Page {
     fn default() -> Self {
         Self::new()
@@ -1,3 +1,4 @@
+use crate::error::ErrorCode;
 use crate::storage::PAGE_SIZE;
 use itertools::Itertools;
 use std::convert::TryInto;
@@ -202,6 +203,12 @@ where
     pub fn key_data_iter(&self) -> KeyDataIter<Key> {
         KeyDataIter::new(self.idx_iter(), &self.bytes)
     }
+    /// whether the slot is in range and holds data
+    pub fn is_used_at(&self, idx: usize) -> bool {
+        idx < self.capacity()
+            && idx / 8 < self.bitmap.len()
+            && self.bitmap[idx / 8] & (1 << (idx % 8)) != 0
+    }
     pub fn key_at(&self, idx: usize) -> &Key {
         unsafe { &*self.key_ptr_at(idx) }
     }
@@ -270,8 +277,12 @@ where
         let idx = self.index_of(key).ok_or(SlottedPageError::KeyNotFound)?;
         self.remove_at(idx)
     }
+    /// put the data in a new slot after all the others, freed slots are never reused
     pub fn append(&mut self, key: &Key, data: &[u8]) -> Result<(), SlottedPageError> {
         let idx = self.capacity();
+        if idx >= self.bitmap.len() * 8 {
+            return Err(SlottedPageError::OutOfSpace);
+        }
         if self.head + size_of::<Key>() + 16 > self.tail - data.len() {
             return Err(SlottedPageError::OutOfSpace);
         }
@@ -284,13 +295,16 @@ where
         self.insert_at(idx, key, data)?;
         Ok(())
     }
+    /// put the data in the first freed slot if any, so the slots are not in insertion order
+    /// after removes, see append for that
     pub fn insert(&mut self, key: &Key, data: &[u8]) -> Result<usize, SlottedPageError> {
         let idx = self.find_first_empty_slot();
         if idx * (size_of::<Key>() + 16) >= self.head {
-            self.head += size_of::<Key>() + 16;
-            if self.head + data.len() > self.tail {
+            // check before growing the slots so a failed insert leaves the page unchanged
+            if self.head + size_of::<Key>() + 16 + data.len() > self.tail {
                 return Err(SlottedPageError::OutOfSpace);
             }
+            self.head += size_of::<Key>() + 16;
         }
         self.insert_at(idx, key, data)?;
         Ok(idx)
@@ -361,6 +375,18 @@ where
         self.bitmap[pos / 8] ^= 1 << (pos % 8);
         Ok(())
     }
+    /// give the trailing empty slots back to the free space, the used slots keep their
+    /// indexes, returns the bytes reclaimed
+    pub fn compact(&mut self) -> usize {
+        let len_of_slot = size_of::<Key>() + 16;
+        let capacity = self.idx_iter().last().map_or(0, |idx| idx + 1);
+        let head = capacity * len_of_slot;
+        let reclaimed = self.head - head;
+        // a slot grown into the free space later must start empty
+        self.bytes[head..self.head].fill(0);
+        self.head = head;
+        reclaimed
+    }
     pub fn count(&self) -> usize {
         let mut cnt = 0;
         for byte in self.bitmap {
@@ -382,6 +408,12 @@ pub enum SlottedPageError {
     KeyNotFound,
 }
 
+impl SlottedPageError {
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::Internal
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -430,6 +462,48 @@ mod tests {
         slotted.move_backward(1).unwrap();
     }
 
+    #[test]
+    fn compact() {
+        let mut bytes = [0u8; PAGE_SIZE];
+        let slotted = unsafe { &mut *(bytes.as_mut_ptr() as *mut SlottedPage<Meta, Key>) };
+        slotted.reset(&Meta { next_page_id: None });
+        for page_id in 0..4 {
+            slotted.insert(&Key { page_id }, &[1u8, 2u8]).unwrap();
+        }
+        slotted.remove_at(1).unwrap();
+        slotted.remove_at(3).unwrap();
+        slotted.remove_at(2).unwrap();
+        let (_, free) = slotted.store_stat();
+        assert_eq!(slotted.compact(), 3 * (size_of::<Key>() + 16));
+        assert_eq!(slotted.store_stat().1, free + 3 * (size_of::<Key>() + 16));
+        assert_eq!(slotted.capacity(), 1);
+        assert_eq!(slotted.key_at(0), &Key { page_id: 0 });
+        assert_eq!(slotted.compact(), 0);
+        assert_eq!(slotted.insert(&Key { page_id: 4 }, &[3u8]).unwrap(), 1);
+        assert_eq!(slotted.data_at(1), &[3u8]);
+    }
+
+    #[test]
+    fn append_after_remove() {
+        let mut bytes = [0u8; PAGE_SIZE];
+        let slotted = unsafe { &mut *(bytes.as_mut_ptr() as *mut SlottedPage<Meta, Key>) };
+        slotted.reset(&Meta { next_page_id: None });
+        for page_id in 0..3 {
+            slotted.append(&Key { page_id }, &[1u8]).unwrap();
+        }
+        slotted.remove_at(0).unwrap();
+        slotted.append(&Key { page_id: 3 }, &[2u8]).unwrap();
+        assert_eq!(slotted.capacity(), 4);
+        assert_eq!(slotted.key_at(3), &Key { page_id: 3 });
+        assert_eq!(
+            slotted
+                .idx_iter()
+                .map(|idx| slotted.key_at(idx).page_id)
+                .collect_vec(),
+            vec![1, 2, 3]
+        );
+    }
+
     #[test]
     fn chaos() {
         let mut bytes = [0u8; PAGE_SIZE];
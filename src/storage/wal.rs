@@ -0,0 +1,178 @@
+use super::*;
+use crate::storage::disk::DiskManager;
+use crate::storage::page::PageRef;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// bytes of a record, the page id followed by the page image
+const RECORD_SIZE: usize = 4 + PAGE_SIZE;
+/// the page id of the record marking the records before it committed, its image is empty
+const COMMIT_MARKER: u32 = u32::MAX;
+
+/// Page-level physical log, every record holds the image of a page, and
+/// a commit marker follows the records of each statement, so replaying the
+/// records up to the last marker in order redoes all the committed writes.
+pub struct WriteAheadLog {
+    file: File,
+    filename: String,
+    num_records: usize,
+    /// records after the last commit marker
+    num_uncommitted: usize,
+}
+
+impl WriteAheadLog {
+    /// the log of the database file
+    pub fn filename_of(db_filename: &str) -> String {
+        format!("{}.wal", db_filename)
+    }
+    pub fn exists(db_filename: &str) -> bool {
+        Path::new(&Self::filename_of(db_filename)).exists()
+    }
+    pub fn open(db_filename: &str) -> Result<Self, StorageError> {
+        let filename = Self::filename_of(db_filename);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(filename.clone())?;
+        let num_records = file.metadata()?.len() as usize / RECORD_SIZE;
+        Ok(Self {
+            file,
+            filename,
+            num_records,
+            num_uncommitted: 0,
+        })
+    }
+    pub fn filename(&self) -> String {
+        self.filename.clone()
+    }
+    pub fn num_records(&self) -> usize {
+        self.num_records
+    }
+    fn append(&mut self, page_id: u32, image: &[u8]) -> Result<(), StorageError> {
+        self.file
+            .seek(SeekFrom::Start((self.num_records * RECORD_SIZE) as u64))?;
+        self.file.write_all(&page_id.to_le_bytes())?;
+        self.file.write_all(image)?;
+        self.num_records += 1;
+        Ok(())
+    }
+    /// append the images of the pages, they are durable once it returns, but
+    /// only replayed after a later commit
+    pub fn log(&mut self, pages: &[PageRef]) -> Result<(), StorageError> {
+        if pages.is_empty() {
+            return Ok(());
+        }
+        for page in pages {
+            let page = page.borrow();
+            self.append(page.page_id.unwrap() as u32, &page.buffer)?;
+        }
+        self.num_uncommitted += pages.len();
+        self.file.sync_data()?;
+        Ok(())
+    }
+    /// append the images of the pages followed by a commit marker, the pages
+    /// and those logged before are replayed once it returns
+    pub fn commit(&mut self, pages: &[PageRef]) -> Result<(), StorageError> {
+        if pages.is_empty() && self.num_uncommitted == 0 {
+            return Ok(());
+        }
+        for page in pages {
+            let page = page.borrow();
+            self.append(page.page_id.unwrap() as u32, &page.buffer)?;
+        }
+        self.append(COMMIT_MARKER, &[0u8; PAGE_SIZE])?;
+        self.num_uncommitted = 0;
+        self.file.sync_data()?;
+        Ok(())
+    }
+    /// write the committed images into the database file, the records after
+    /// the last commit marker, a torn one included, belong to a statement never
+    /// committed so they are skipped, returns the number of pages written
+    pub fn replay(&mut self, disk: &mut DiskManager) -> Result<usize, StorageError> {
+        let mut page_ids = vec![];
+        let mut page_id = [0u8; 4];
+        for idx in 0..self.num_records {
+            self.file
+                .seek(SeekFrom::Start((idx * RECORD_SIZE) as u64))?;
+            self.file.read_exact(&mut page_id)?;
+            page_ids.push(u32::from_le_bytes(page_id));
+        }
+        let num_committed = page_ids
+            .iter()
+            .rposition(|&page_id| page_id == COMMIT_MARKER)
+            .map_or(0, |pos| pos + 1);
+        self.file.seek(SeekFrom::Start(0))?;
+        let mut record = vec![0u8; RECORD_SIZE];
+        let mut cnt = 0;
+        for &page_id in &page_ids[..num_committed] {
+            self.file.read_exact(&mut record)?;
+            if page_id != COMMIT_MARKER {
+                disk.write_at(page_id as PageID, &record[4..])?;
+                cnt += 1;
+            }
+        }
+        disk.sync()?;
+        Ok(cnt)
+    }
+    /// drop all the records, the pages must be durable in the database file
+    pub fn truncate(&mut self) -> Result<(), StorageError> {
+        self.file.set_len(0)?;
+        self.file.sync_data()?;
+        self.num_records = 0;
+        self.num_uncommitted = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::page::Page;
+    use std::cell::RefCell;
+    use std::fs::remove_file;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_log_replay() {
+        let mut disk = DiskManager::new_random().unwrap();
+        let filename = disk.filename();
+        let page1 = Rc::new(RefCell::new(Page::new()));
+        let page2 = Rc::new(RefCell::new(Page::new()));
+        disk.allocate(page1.clone()).unwrap();
+        disk.allocate(page2.clone()).unwrap();
+        page1.borrow_mut().buffer[0] = 1;
+        page2.borrow_mut().buffer[0] = 2;
+        {
+            let mut wal = WriteAheadLog::open(&filename).unwrap();
+            wal.commit(&[page1.clone(), page2.clone()]).unwrap();
+            page1.borrow_mut().buffer[0] = 3;
+            wal.log(&[page1.clone()]).unwrap();
+            wal.commit(&[]).unwrap();
+            assert_eq!(wal.num_records(), 5);
+            // logged without a commit, like a page evicted by a statement never committed
+            page2.borrow_mut().buffer[0] = 4;
+            wal.log(&[page2.clone()]).unwrap();
+            assert_eq!(wal.num_records(), 6);
+        }
+        // a torn record is left by a crash while logging
+        {
+            let mut file = OpenOptions::new()
+                .append(true)
+                .open(WriteAheadLog::filename_of(&filename))
+                .unwrap();
+            file.write_all(&[0u8; 16]).unwrap();
+        }
+        let mut wal = WriteAheadLog::open(&filename).unwrap();
+        assert_eq!(wal.replay(&mut disk).unwrap(), 3);
+        disk.read(0, page1.clone()).unwrap();
+        disk.read(1, page2.clone()).unwrap();
+        assert_eq!(page1.borrow().buffer[0], 3);
+        assert_eq!(page2.borrow().buffer[0], 2);
+        wal.truncate().unwrap();
+        assert_eq!(wal.num_records(), 0);
+        remove_file(wal.filename()).unwrap();
+        remove_file(filename).unwrap();
+    }
+}
@@ -1,4 +1,5 @@
-use crate::datum::{DataType, Datum};
+use crate::datum::{DataType, Datum, DatumError};
+use crate::error::ErrorCode;
 use crate::index::RecordID;
 use crate::storage::{
     BufferPoolManagerRef, PageID, PageRef, SlottedPage, SlottedPageError, StorageError,
@@ -6,24 +7,34 @@ use crate::storage::{
 use itertools::Itertools;
 use prettytable::{Cell, Row, Table as PrintTable};
 
+use std::convert::TryInto;
 use std::fmt;
 use std::rc::Rc;
 use thiserror::Error;
 
 mod schema;
 mod slice;
+mod stats;
 
 pub use schema::{Column, Schema, SchemaError, SchemaRef};
-pub use slice::{Slice, SlotIter, TupleIter};
+pub use slice::{ResultBuilder, Slice, SlotIter, TupleIter};
+pub use stats::ColumnStats;
 
 #[derive(Copy, Clone)]
 pub struct TableMeta {
     pub page_id_of_first_slice: PageID,
+    /// tuples are appended to the last slice, a new one is linked after it once full
+    pub page_id_of_last_slice: PageID,
     pub page_id_of_primary_index: Option<PageID>,
 }
 
 type TablePage = SlottedPage<TableMeta, ()>;
 
+/// the schema is stored at the first slot of the table page, the stats at the second, the
+/// append-only flag at the third, a missing slot means no stats or the flag off
+const SLOT_OF_STATS: usize = 1;
+const SLOT_OF_APPEND_ONLY: usize = 2;
+
 pub struct Table {
     pub schema: SchemaRef,
     page: PageRef,
@@ -39,6 +50,13 @@ impl Drop for Table {
 
 impl fmt::Display for Table {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render("NULL", false))
+    }
+}
+
+impl Table {
+    /// the tuples as a text table, with nulls shown as null_display
+    pub fn render(&self, null_display: &str, bool_as_int: bool) -> String {
         let mut table = PrintTable::new();
         let header = self
             .schema
@@ -52,11 +70,11 @@ impl fmt::Display for Table {
             .for_each(|tuple| {
                 let tuple = tuple
                     .iter()
-                    .map(|d| Cell::new(d.to_string().as_str()))
+                    .map(|d| Cell::new(&d.render(null_display, bool_as_int)))
                     .collect_vec();
                 table.add_row(Row::new(tuple));
             });
-        write!(f, "{}", table)
+        table.to_string()
     }
 }
 
@@ -72,7 +90,8 @@ impl Iterator for TableIter {
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(slice) = &self.slice {
             if let Some(next_page_id) = slice.meta().unwrap().next_page_id {
-                let next_slice = Slice::open(self.bpm.clone(), self.schema.clone(), next_page_id);
+                let next_slice =
+                    Slice::open(self.bpm.clone(), self.schema.clone(), next_page_id).unwrap();
                 std::mem::replace(&mut self.slice, Some(next_slice))
             } else {
                 std::mem::replace(&mut self.slice, None)
@@ -117,6 +136,7 @@ impl Table {
             let table_page = &mut *(page.borrow_mut().buffer.as_mut_ptr() as *mut TablePage);
             table_page.reset(&TableMeta {
                 page_id_of_first_slice: slice.page_id(),
+                page_id_of_last_slice: slice.page_id(),
                 page_id_of_primary_index: None,
             });
             table_page.append(&(), &schema.to_bytes()).unwrap();
@@ -124,58 +144,143 @@ impl Table {
         page.borrow_mut().is_dirty = true;
         Self { schema, bpm, page }
     }
-    pub fn set_schema(&mut self, schema: SchemaRef) {
+    /// replace the schema, rejected if the stored tuples would be read differently
+    pub fn set_schema(&mut self, schema: SchemaRef) -> Result<(), TableError> {
+        if !self.schema.same_layout(&schema) {
+            return Err(TableError::IncompatibleSchema);
+        }
         self.page.borrow_mut().is_dirty = true;
+        // stats are stale once the columns change
+        if self.has_stats() {
+            self.table_page_mut().remove_at(SLOT_OF_STATS).unwrap();
+        }
         let table_page_mut = self.table_page_mut();
         table_page_mut.remove_at(0).unwrap();
         table_page_mut
             .insert_at(0, &(), &schema.to_bytes())
             .unwrap();
         self.schema = schema;
+        Ok(())
+    }
+    fn has_stats(&self) -> bool {
+        self.table_page().idx_iter().any(|idx| idx == SLOT_OF_STATS)
+    }
+    /// stats of each column collected by the last analyze
+    pub fn stats(&self) -> Option<Vec<ColumnStats>> {
+        if !self.has_stats() {
+            return None;
+        }
+        let schema = ColumnStats::schema_of(self.schema.as_ref());
+        let tuple =
+            Datum::tuple_from_bytes_with_schema(self.table_page().data_at(SLOT_OF_STATS), &schema)
+                .unwrap();
+        Some(ColumnStats::from_tuple(tuple))
+    }
+    pub fn set_stats(&mut self, stats: &[ColumnStats]) -> Result<(), TableError> {
+        let bytes = Datum::bytes_from_tuple(&ColumnStats::to_tuple(stats));
+        if self.has_stats() {
+            self.table_page_mut().remove_at(SLOT_OF_STATS)?;
+        }
+        let table_page_mut = self.table_page_mut();
+        // the slot is kept after the stats are cleared
+        if table_page_mut.capacity() > SLOT_OF_STATS {
+            table_page_mut.insert_at(SLOT_OF_STATS, &(), &bytes)?;
+        } else {
+            table_page_mut.append(&(), &bytes)?;
+        }
+        Ok(())
+    }
+    /// new tuples never reuse the slots freed by removes, so scans see them in insertion order
+    pub fn append_only(&self) -> bool {
+        let table_page = self.table_page();
+        table_page.is_used_at(SLOT_OF_APPEND_ONLY)
+            && table_page.data_at(SLOT_OF_APPEND_ONLY)[0] != 0
+    }
+    #[allow(dead_code)]
+    pub fn set_append_only(&mut self, append_only: bool) -> Result<(), TableError> {
+        let table_page_mut = self.table_page_mut();
+        if table_page_mut.is_used_at(SLOT_OF_APPEND_ONLY) {
+            table_page_mut.remove_at(SLOT_OF_APPEND_ONLY)?;
+        }
+        // the slot of stats is left empty if there are none
+        while table_page_mut.capacity() < SLOT_OF_APPEND_ONLY {
+            let idx = table_page_mut.capacity();
+            table_page_mut.append(&(), &[0])?;
+            table_page_mut.remove_at(idx)?;
+        }
+        let bytes = [append_only as u8];
+        if table_page_mut.capacity() > SLOT_OF_APPEND_ONLY {
+            table_page_mut.insert_at(SLOT_OF_APPEND_ONLY, &(), &bytes)?;
+        } else {
+            table_page_mut.append(&(), &bytes)?;
+        }
+        Ok(())
     }
     pub fn page_id(&self) -> PageID {
         self.page.borrow().page_id.unwrap()
     }
     pub fn insert(&mut self, datums: Vec<Datum>) -> Result<RecordID, TableError> {
-        let page_id_of_first_slice = self.meta().page_id_of_first_slice;
-        let mut slice = Slice::open(
-            self.bpm.clone(),
-            self.schema.clone(),
-            page_id_of_first_slice,
-        );
+        let page_id_of_last_slice = self.meta().page_id_of_last_slice;
+        let mut slice = Slice::open(self.bpm.clone(), self.schema.clone(), page_id_of_last_slice)?;
+        slice.set_append_only(self.append_only());
         if let Ok(record_id) = slice.insert(&datums) {
             Ok(record_id)
+        } else if let Some(record_id) = match slice.compact() {
+            // the last slice may still fit once its empty slots are reclaimed
+            0 => None,
+            _ => slice.insert(&datums).ok(),
+        } {
+            Ok(record_id)
         } else {
             let mut new_slice = Slice::new(self.bpm.clone(), self.schema.clone());
-            self.meta_mut().page_id_of_first_slice = new_slice.page_id();
-            new_slice.meta_mut()?.next_page_id = Some(slice.page_id());
             let record_id = new_slice.insert(&datums)?;
+            slice.meta_mut()?.next_page_id = Some(new_slice.page_id());
+            self.meta_mut().page_id_of_last_slice = new_slice.page_id();
             Ok(record_id)
         }
     }
+    /// replace the tuple in place if possible, otherwise move it to another slot, the
+    /// ref_cnt is kept and the new record id is returned
+    pub fn update(
+        &mut self,
+        record_id: RecordID,
+        datums: Vec<Datum>,
+    ) -> Result<RecordID, TableError> {
+        let mut slice = Slice::open(self.bpm.clone(), self.schema.clone(), record_id.0)?;
+        let ref_cnt = slice.ref_cnt_at(record_id.1)?;
+        slice.remove_at(record_id.1)?;
+        if slice.insert_at(record_id.1, ref_cnt, &datums).is_ok() {
+            return Ok(record_id);
+        }
+        let new_record_id = self.insert(datums)?;
+        self.set_ref_cnt_of(new_record_id, ref_cnt)?;
+        Ok(new_record_id)
+    }
     pub fn iter(&self) -> TableIter {
         let page_id_of_first_slice = self.meta().page_id_of_first_slice;
         let slice = Slice::open(
             self.bpm.clone(),
             self.schema.clone(),
             page_id_of_first_slice,
-        );
+        )
+        .unwrap();
         TableIter {
             slice: Some(slice),
             bpm: self.bpm.clone(),
             schema: self.schema.clone(),
         }
     }
+    /// none if the record id is stale, e.g. the tuple has been removed
     pub fn tuple_at(&self, record_id: RecordID) -> Option<Vec<Datum>> {
-        let slice = Slice::open(self.bpm.clone(), self.schema.clone(), record_id.0);
-        Some(slice.tuple_at(record_id.1).unwrap())
+        let slice = Slice::open(self.bpm.clone(), self.schema.clone(), record_id.0).ok()?;
+        slice.tuple_at(record_id.1).ok()
     }
     pub fn set_ref_cnt_of(&mut self, record_id: RecordID, cnt: usize) -> Result<(), TableError> {
-        let mut slice = Slice::open(self.bpm.clone(), self.schema.clone(), record_id.0);
+        let mut slice = Slice::open(self.bpm.clone(), self.schema.clone(), record_id.0)?;
         slice.set_ref_cnt_at(record_id.1, cnt)
     }
     pub fn ref_cnt_of(&self, record_id: RecordID) -> Result<usize, TableError> {
-        let slice = Slice::open(self.bpm.clone(), self.schema.clone(), record_id.0);
+        let slice = Slice::open(self.bpm.clone(), self.schema.clone(), record_id.0)?;
         slice.ref_cnt_at(record_id.1)
     }
     pub fn from_slice(slices: Vec<Slice>, schema: SchemaRef, bpm: BufferPoolManagerRef) -> Self {
@@ -191,7 +296,8 @@ impl Table {
         let mut slices = vec![];
         let mut page_id = Some(self.meta().page_id_of_first_slice);
         while page_id.is_some() {
-            let slice = Slice::open(self.bpm.clone(), self.schema.clone(), page_id.unwrap());
+            let slice =
+                Slice::open(self.bpm.clone(), self.schema.clone(), page_id.unwrap()).unwrap();
             let next_page_id = slice.meta().unwrap().next_page_id;
             slices.push(slice);
             page_id = next_page_id;
@@ -199,12 +305,51 @@ impl Table {
         slices
     }
     pub fn remove(&mut self, record_id: RecordID) -> Result<(), TableError> {
-        let mut slice = Slice::open(self.bpm.clone(), self.schema.clone(), record_id.0);
+        let mut slice = Slice::open(self.bpm.clone(), self.schema.clone(), record_id.0)?;
         if slice.ref_cnt_at(record_id.1)? > 0 {
             return Err(TableError::RemovingReferedTuple);
         }
         slice.remove_at(record_id.1)
     }
+    /// remove the tuple even if it is still referenced
+    pub fn remove_unchecked(&mut self, record_id: RecordID) -> Result<(), TableError> {
+        let mut slice = Slice::open(self.bpm.clone(), self.schema.clone(), record_id.0)?;
+        slice.remove_at(record_id.1)
+    }
+    /// bytes used by the table page and all the slice pages
+    pub fn disk_footprint(&self) -> Result<usize, TableError> {
+        let mut bytes = self.table_page().store_stat().0;
+        let mut page_id = Some(self.meta().page_id_of_first_slice);
+        while let Some(page_id_of_slice) = page_id {
+            let slice = Slice::open(self.bpm.clone(), self.schema.clone(), page_id_of_slice)?;
+            bytes += slice.store_stat().0;
+            page_id = slice.meta()?.next_page_id;
+        }
+        Ok(bytes)
+    }
+    /// page ids of the slices from the first one
+    pub fn slice_page_ids(&self) -> Result<Vec<PageID>, TableError> {
+        let mut slice_page_ids = vec![];
+        let mut page_id = Some(self.meta().page_id_of_first_slice);
+        while let Some(page_id_of_slice) = page_id {
+            let slice = Slice::open(self.bpm.clone(), self.schema.clone(), page_id_of_slice)?;
+            page_id = slice.meta()?.next_page_id;
+            slice_page_ids.push(page_id_of_slice);
+        }
+        Ok(slice_page_ids)
+    }
+    /// remove all the tuples, the table keeps its page id
+    pub fn truncate(&mut self) -> Result<(), TableError> {
+        let slice_page_ids = self.slice_page_ids()?;
+        let slice = Slice::new(self.bpm.clone(), self.schema.clone());
+        self.meta_mut().page_id_of_first_slice = slice.page_id();
+        self.meta_mut().page_id_of_last_slice = slice.page_id();
+        drop(slice);
+        for page_id in slice_page_ids {
+            self.bpm.borrow_mut().free(page_id)?;
+        }
+        Ok(())
+    }
     pub fn erase(self) {
         let bpm = self.bpm.clone();
         let table_page_id = self.page.borrow().page_id.unwrap();
@@ -220,6 +365,54 @@ impl Table {
             bpm.borrow_mut().free(page_id).unwrap();
         }
     }
+    const DUMP_MAGIC: &'static [u8] = b"NDBB";
+    ///
+    /// write the raw bytes of the tuples after the schema, so that the dump is loaded back
+    /// without reparsing text:
+    ///
+    ///     | magic | len_of_schema | schema | len_of_tuple[0] | tuple[0] | ... |
+    ///
+    pub fn dump_binary(&self, path: &str) -> Result<(), TableError> {
+        let mut bytes = Self::DUMP_MAGIC.to_vec();
+        let schema = self.schema.to_bytes();
+        bytes.extend_from_slice(&(schema.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&schema);
+        for tuple in self.iter().flat_map(|s| s.tuple_iter().collect_vec()) {
+            let tuple = Datum::bytes_from_tuple(&tuple);
+            bytes.extend_from_slice(&(tuple.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&tuple);
+        }
+        std::fs::write(path, bytes).map_err(StorageError::from)?;
+        Ok(())
+    }
+    /// read back the schema and tuples written by dump_binary
+    pub fn load_binary(path: &str) -> Result<(Schema, Vec<Vec<Datum>>), TableError> {
+        let bytes = std::fs::read(path).map_err(StorageError::from)?;
+        if !bytes.starts_with(Self::DUMP_MAGIC) {
+            return Err(TableError::InvalidDump(path.to_owned()));
+        }
+        let mut offset = Self::DUMP_MAGIC.len();
+        let schema = Schema::from_bytes(
+            next_chunk(&bytes, &mut offset)
+                .ok_or_else(|| TableError::InvalidDump(path.to_owned()))?,
+        );
+        let mut tuples = vec![];
+        while offset < bytes.len() {
+            let chunk = next_chunk(&bytes, &mut offset)
+                .ok_or_else(|| TableError::InvalidDump(path.to_owned()))?;
+            tuples.push(Datum::tuple_from_bytes_with_schema(chunk, &schema)?);
+        }
+        Ok((schema, tuples))
+    }
+}
+
+/// the length prefixed chunk at offset, none if the bytes are truncated
+fn next_chunk<'a>(bytes: &'a [u8], offset: &mut usize) -> Option<&'a [u8]> {
+    let len = bytes.get(*offset..*offset + 4)?;
+    let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+    let chunk = bytes.get(*offset + 4..*offset + 4 + len)?;
+    *offset += 4 + len;
+    Some(chunk)
 }
 
 #[derive(Error, Debug)]
@@ -240,12 +433,34 @@ pub enum TableError {
     SlicePage(#[from] SlottedPageError),
     #[error("Removing Refered Tuple")]
     RemovingReferedTuple,
+    #[error("Slice {0} Opened With Mismatched Schema")]
+    SchemaMismatch(PageID),
+    #[error("Invalid Binary Dump: {0}")]
+    InvalidDump(String),
+    #[error("Datum: {0}")]
+    Datum(#[from] DatumError),
+    #[error("Schema Incompatible With Stored Tuples")]
+    IncompatibleSchema,
+}
+
+impl TableError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::DatumSchemaNotMatch => ErrorCode::TypeMismatch,
+            Self::Storage(err) => err.code(),
+            Self::SlicePage(err) => err.code(),
+            Self::RemovingReferedTuple => ErrorCode::ConstraintViolation,
+            Self::InvalidDump(_) => ErrorCode::InvalidArgument,
+            Self::Datum(err) => err.code(),
+            _ => ErrorCode::Internal,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::storage::BufferPoolManager;
+    use crate::storage::{BufferPoolManager, PAGE_SIZE};
     use itertools::Itertools;
     use std::cell::RefCell;
     use std::fs::remove_file;
@@ -276,6 +491,89 @@ mod tests {
         remove_file(filename).unwrap();
     }
 
+    #[test]
+    fn test_append_to_last_slice() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(5);
+            let filename = bpm.borrow().filename();
+            let schema = Rc::new(Schema::from_type_and_names(&[(
+                DataType::new_as_int(false),
+                "v1".to_string(),
+            )]));
+            let mut slice = Slice::new(bpm.clone(), schema.clone());
+            let mut per_slice = 0;
+            while slice.insert(&[Datum::Int(Some(per_slice))]).is_ok() {
+                per_slice += 1;
+            }
+            drop(slice);
+            let mut table = Table::new(schema, bpm);
+            let num_tuples = per_slice * 3 + 1;
+            for idx in 0..num_tuples {
+                table.insert(vec![Datum::Int(Some(idx))]).unwrap();
+            }
+            // every slice but the last is full
+            let counts = table.iter().map(|s| s.count()).collect_vec();
+            assert_eq!(
+                counts,
+                vec![
+                    per_slice as usize,
+                    per_slice as usize,
+                    per_slice as usize,
+                    1
+                ]
+            );
+            // tuples come back in the order they are inserted
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(
+                tuples,
+                (0..num_tuples)
+                    .map(|idx| vec![Datum::Int(Some(idx))])
+                    .collect_vec()
+            );
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_append_only() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(5);
+            let filename = bpm.borrow().filename();
+            let schema = Rc::new(Schema::from_type_and_names(&[(
+                DataType::new_as_int(false),
+                "v1".to_string(),
+            )]));
+            let mut table = Table::new(schema, bpm.clone());
+            assert!(!table.append_only());
+            table.set_append_only(true).unwrap();
+            let record_ids = (0..3)
+                .map(|v| table.insert(vec![v.into()]).unwrap())
+                .collect_vec();
+            table.remove(record_ids[1]).unwrap();
+            // the flag is kept by the table page
+            let page_id = table.page_id();
+            drop(table);
+            let mut table = Table::open(page_id, bpm);
+            assert!(table.append_only());
+            let record_id = table.insert(vec![3.into()]).unwrap();
+            assert_eq!(record_id, (record_ids[2].0, 3));
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![0.into()], vec![2.into()], vec![3.into()]]);
+            table.set_append_only(false).unwrap();
+            assert!(!table.append_only());
+            assert!(table.stats().is_none());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
     #[test]
     fn test_create_open() {
         let (filename, page_id) = {
@@ -297,4 +595,131 @@ mod tests {
         };
         remove_file(filename).unwrap();
     }
+
+    #[test]
+    fn test_tuple_at_stale_record() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(5);
+            let filename = bpm.borrow().filename();
+            let schema =
+                Schema::from_type_and_names(&[(DataType::new_as_int(false), "v1".to_string())]);
+            let mut table = Table::new(Rc::new(schema), bpm);
+            let record_id1 = table.insert(vec![1.into()]).unwrap();
+            let record_id2 = table.insert(vec![2.into()]).unwrap();
+            table.remove(record_id1).unwrap();
+            assert_eq!(table.tuple_at(record_id1), None);
+            assert_eq!(table.tuple_at(record_id2), Some(vec![2.into()]));
+            assert_eq!(table.tuple_at((record_id2.0, record_id2.1 + 100)), None);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_insert_after_compact() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(5);
+            let filename = bpm.borrow().filename();
+            let schema =
+                Schema::from_type_and_names(&[(DataType::new_as_varchar(false), "v1".to_string())]);
+            let mut table = Table::new(Rc::new(schema), bpm.clone());
+            let record_ids = (0..64)
+                .map(|_| table.insert(vec!["a".into()]).unwrap())
+                .collect_vec();
+            let page_id = record_ids[0].0;
+            assert!(record_ids.iter().all(|record_id| record_id.0 == page_id));
+            // the slots left empty at the tail take too much space for the long tuple
+            for record_id in record_ids.iter().skip(1) {
+                table.remove(*record_id).unwrap();
+            }
+            let long = "b".repeat(PAGE_SIZE * 3 / 4);
+            let record_id = table.insert(vec![long.as_str().into()]).unwrap();
+            assert_eq!(record_id, (page_id, 1));
+            assert_eq!(table.meta().page_id_of_last_slice, page_id);
+            assert_eq!(table.tuple_at(record_id), Some(vec![long.as_str().into()]));
+            assert_eq!(table.tuple_at(record_ids[0]), Some(vec!["a".into()]));
+            // the slots of an append only table are not reused, the long tuple goes elsewhere
+            let mut table = Table::new(table.schema.clone(), bpm);
+            table.set_append_only(true).unwrap();
+            let record_ids = (0..64)
+                .map(|_| table.insert(vec!["a".into()]).unwrap())
+                .collect_vec();
+            for record_id in record_ids.iter().skip(1) {
+                table.remove(*record_id).unwrap();
+            }
+            let record_id = table.insert(vec![long.as_str().into()]).unwrap();
+            assert_ne!(record_id.0, record_ids[0].0);
+            assert_eq!(table.tuple_at(record_ids[1]), None);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_disk_footprint() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(10);
+            let filename = bpm.borrow().filename();
+            let schema =
+                Schema::from_type_and_names(&[(DataType::new_as_int(false), "v1".to_string())]);
+            let mut table = Table::new(Rc::new(schema), bpm.clone());
+            let mut footprint = table.disk_footprint().unwrap();
+            for idx in 0..1000 {
+                table.insert(vec![Datum::Int(Some(idx))]).unwrap();
+                let grown = table.disk_footprint().unwrap();
+                assert!(grown > footprint);
+                footprint = grown;
+            }
+            let slices = Table::open(table.page_id(), bpm).into_slice();
+            assert!(slices.len() > 1);
+            let used = table.table_page().store_stat().0
+                + slices.iter().map(|s| s.store_stat().0).sum::<usize>();
+            assert_eq!(footprint, used);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_set_schema() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(5);
+            let filename = bpm.borrow().filename();
+            let schema = Schema::from_type_and_names(&[
+                (DataType::new_as_int(false), "v1".to_string()),
+                (DataType::new_as_varchar(true), "v2".to_string()),
+            ]);
+            let mut table = Table::new(Rc::new(schema.clone()), bpm.clone());
+            table.insert(vec![1.into(), "a".into()]).unwrap();
+            // constraints and names leave the tuples as they are
+            let mut compatible = schema.clone();
+            compatible.unique.push(vec![0]);
+            compatible.rename_column("v2", "v3").unwrap();
+            table.set_schema(Rc::new(compatible.clone())).unwrap();
+            let mut table = Table::open(table.page_id(), bpm);
+            assert_eq!(table.schema.as_ref(), &compatible);
+            let tuples = table
+                .iter()
+                .flat_map(|s| s.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, vec![vec![1.into(), "a".into()]]);
+            let retyped = Schema::from_type_and_names(&[
+                (DataType::new_as_varchar(false), "v1".to_string()),
+                (DataType::new_as_varchar(true), "v2".to_string()),
+            ]);
+            assert!(matches!(
+                table.set_schema(Rc::new(retyped)),
+                Err(TableError::IncompatibleSchema)
+            ));
+            let appended = Schema::from_type_and_names(&[
+                (DataType::new_as_int(false), "v1".to_string()),
+                (DataType::new_as_varchar(true), "v2".to_string()),
+                (DataType::new_as_int(true), "v3".to_string()),
+            ]);
+            assert!(table.set_schema(Rc::new(appended)).is_err());
+            assert_eq!(table.schema.as_ref(), &compatible);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
 }
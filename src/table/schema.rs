@@ -1,3 +1,4 @@
+use crate::error::ErrorCode;
 use crate::expr::{ColumnRefExpr, ExprImpl};
 use crate::table::DataType;
 use itertools::Itertools;
@@ -10,6 +11,8 @@ use thiserror::Error;
 ///
 ///     | num_column | Column[0] | Column[1] | ... |
 ///     | len_unique | unique_payload |
+///     | len_primary | primary_payload | len_foreign | foreign_payload |
+///     | num_check | len_check[0] | check_content[0] | ... |
 ///
 /// Column Format:
 ///
@@ -74,6 +77,8 @@ pub struct Schema {
     pub unique: Vec<Vec<usize>>,
     pub primary: Vec<usize>,
     pub foreign: Vec<(usize, Vec<(usize, usize)>)>,
+    /// source text of the check exprs
+    pub checks: Vec<String>,
 }
 
 impl Schema {
@@ -84,8 +89,32 @@ impl Schema {
             unique: vec![],
             primary: vec![],
             foreign: vec![],
+            checks: vec![],
         }
     }
+    /// FNV-1a hash of the column types, names, nullability and constraints are left out
+    /// as they don't change how the tuples are laid out
+    pub fn fingerprint(&self) -> u64 {
+        self.columns
+            .iter()
+            .map(|c| c.data_type.to_bytes()[0] & 127)
+            .fold(0xcbf29ce484222325, |hash, type_id| {
+                (hash ^ type_id as u64).wrapping_mul(0x100000001b3)
+            })
+    }
+    /// whether the tuples stored with this schema are read the same with the other one,
+    /// an appended column is rejected too, as the slices are checked by the fingerprint
+    pub fn same_layout(&self, other: &Schema) -> bool {
+        self.columns.len() == other.columns.len()
+            && self
+                .columns
+                .iter()
+                .zip(other.columns.iter())
+                .all(|(lhs, rhs)| {
+                    lhs.offset == rhs.offset
+                        && lhs.data_type.to_bytes()[0] & 127 == rhs.data_type.to_bytes()[0] & 127
+                })
+    }
     pub fn to_bytes(&self) -> Vec<u8> {
         vec![
             self.columns.len().to_le_bytes().to_vec(),
@@ -141,6 +170,17 @@ impl Schema {
                 })
                 .flatten()
                 .collect_vec(),
+            self.checks.len().to_le_bytes().to_vec(),
+            self.checks
+                .iter()
+                .map(|c| {
+                    vec![c.len().to_le_bytes().to_vec(), c.as_bytes().to_vec()]
+                        .into_iter()
+                        .flatten()
+                        .collect_vec()
+                })
+                .flatten()
+                .collect_vec(),
         ]
         .into_iter()
         .flatten()
@@ -196,11 +236,24 @@ impl Schema {
             }
             foreign.push((page_id, vec));
         }
+        // schemas written before check constraints end here
+        let mut checks = vec![];
+        if offset + 8 <= bytes.len() {
+            let checks_len = usize::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+            offset += 8;
+            for _ in 0..checks_len {
+                let len = usize::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+                offset += 8;
+                checks.push(String::from_utf8(bytes[offset..offset + len].to_vec()).unwrap());
+                offset += len;
+            }
+        }
         Self {
             columns,
             unique,
             primary,
             foreign,
+            checks,
         }
     }
     pub fn project_by(&self, idxes: &[usize]) -> Vec<ExprImpl> {
@@ -231,6 +284,20 @@ impl Schema {
             .map(|c| (c.data_type, c.desc.clone()))
             .collect_vec()
     }
+    pub fn rename_column(
+        &mut self,
+        column_name: &str,
+        new_column_name: &str,
+    ) -> Result<(), SchemaError> {
+        if self.index_by_column_name(new_column_name).is_some() {
+            return Err(SchemaError::DuplicatedColumn(new_column_name.to_owned()));
+        }
+        let idx = self
+            .index_by_column_name(column_name)
+            .ok_or(SchemaError::ColumnNotFound)?;
+        self.columns[idx].desc = new_column_name.to_owned();
+        Ok(())
+    }
     pub fn index_by_column_name(&self, column_name: &str) -> Option<usize> {
         self.columns
             .iter()
@@ -250,6 +317,19 @@ pub enum SchemaError {
     PrimaryNotFound,
     #[error("Not Match")]
     NotMatch,
+    #[error("Duplicated Column: {0}")]
+    DuplicatedColumn(String),
+}
+
+impl SchemaError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::ColumnNotFound | Self::PrimaryNotFound => ErrorCode::ColumnNotFound,
+            Self::DuplicatedPrimary => ErrorCode::InvalidArgument,
+            Self::NotMatch => ErrorCode::TypeMismatch,
+            Self::DuplicatedColumn(_) => ErrorCode::AlreadyExists,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -265,6 +345,7 @@ mod tests {
         schema.primary = vec![0, 1];
         schema.unique.push(vec![1]);
         schema.foreign.push((1, vec![(1, 0), (0, 2)]));
+        schema.checks.push("v_int > 0".into());
         let bytes = schema.to_bytes();
         assert_eq!(Schema::from_bytes(&bytes), schema,);
     }
@@ -1,4 +1,5 @@
 use crate::datum::{DataType, Datum};
+use crate::index::RecordID;
 use crate::storage::{
     BufferPoolManagerRef, KeyDataIter, PageID, PageRef, SlotIndexIter, SlottedPage,
 };
@@ -6,6 +7,7 @@ use crate::table::{Schema, SchemaRef, TableError};
 use itertools::Itertools;
 use prettytable::{Cell, Row, Table};
 use std::fmt;
+use std::mem::size_of;
 use std::rc::Rc;
 
 ///
@@ -16,27 +18,49 @@ use std::rc::Rc;
 ///
 /// Meta Format:
 ///
-///     | next_page_id | num_tuple | head | tail |
+///     | next_page_id | fingerprint | num_tuple | head | tail |
 ///
 /// Note that:
 ///     
 ///     - next_page_id is None if the value is zero,
+///     - fingerprint is the one of the schema the slice created with,
+///     - each slot keeps a SlotKey beside its offset,
+///     - inserts reuse the slots freed by removes, unless the slice is append only,
 ///
 pub struct Slice {
     bpm: BufferPoolManagerRef,
     page: PageRef,
     pub schema: SchemaRef,
+    /// new tuples always go after the existing ones, so scans see them in insertion order
+    append_only: bool,
 }
 
 #[derive(Clone, Copy)]
 pub struct SliceMeta {
     pub next_page_id: Option<PageID>,
+    pub fingerprint: u64,
 }
 
-type SlicePage = SlottedPage<SliceMeta, usize>;
+/// references from foreign keys, and the versions between which the tuple is visible, where
+/// a zero deleted version means the tuple is not deleted
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct SlotKey {
+    pub ref_cnt: u32,
+    pub created: u32,
+    pub deleted: u32,
+}
+
+impl SlotKey {
+    /// whether a read at the version sees the tuple
+    pub fn visible_at(&self, version: u32) -> bool {
+        self.created <= version && (self.deleted == 0 || self.deleted > version)
+    }
+}
+
+type SlicePage = SlottedPage<SliceMeta, SlotKey>;
 
 pub struct TupleIter<'page> {
-    key_data_iter: KeyDataIter<'page, usize>,
+    key_data_iter: KeyDataIter<'page, SlotKey>,
     pub next_page_id: Option<PageID>,
     schema: SchemaRef,
 }
@@ -47,7 +71,7 @@ pub struct SlotIter<'page> {
 
 impl<'page> TupleIter<'page> {
     pub fn new(
-        key_data_iter: KeyDataIter<'page, usize>,
+        key_data_iter: KeyDataIter<'page, SlotKey>,
         next_page_id: Option<PageID>,
         schema: SchemaRef,
     ) -> Self {
@@ -76,10 +100,7 @@ impl<'page> Iterator for TupleIter<'page> {
     type Item = Vec<Datum>;
     fn next(&mut self) -> Option<Self::Item> {
         if let Some((_, data)) = self.key_data_iter.next() {
-            Some(Datum::tuple_from_bytes_with_schema(
-                data,
-                self.schema.as_ref(),
-            ))
+            Some(Datum::tuple_from_bytes_with_schema(data, self.schema.as_ref()).unwrap())
         } else {
             None
         }
@@ -94,16 +115,25 @@ impl Drop for Slice {
 }
 
 impl Slice {
+    /// a single value under the header
+    fn new_as_value(
+        bpm: BufferPoolManagerRef,
+        header: &str,
+        data_type: DataType,
+        value: Datum,
+    ) -> Result<Self, TableError> {
+        let schema = Schema::from_type_and_names(&[(data_type, header.to_owned())]);
+        let mut slices = ResultBuilder::from_rows(bpm, Rc::new(schema), vec![vec![value]])?;
+        Ok(slices.remove(0))
+    }
+
     pub fn new_as_message(
         bpm: BufferPoolManagerRef,
         header: &str,
         message: &str,
     ) -> Result<Self, TableError> {
-        let schema =
-            Schema::from_type_and_names(&[(DataType::new_as_varchar(false), header.to_owned())]);
-        let mut slice = Self::new(bpm, Rc::new(schema));
-        slice.insert(&[Datum::VarChar(Some(message.to_owned()))])?;
-        Ok(slice)
+        let value = Datum::VarChar(Some(message.to_owned()));
+        Self::new_as_value(bpm, header, DataType::new_as_varchar(false), value)
     }
 
     pub fn new_as_count(
@@ -111,33 +141,62 @@ impl Slice {
         header: &str,
         cnt: usize,
     ) -> Result<Self, TableError> {
-        let schema =
-            Schema::from_type_and_names(&[(DataType::new_as_int(false), header.to_owned())]);
-        let mut slice = Self::new(bpm, Rc::new(schema));
-        slice.insert(&[Datum::Int(Some(cnt as i32))])?;
-        Ok(slice)
+        let value = Datum::Int(Some(cnt as i32));
+        Self::new_as_value(bpm, header, DataType::new_as_int(false), value)
     }
 
     pub fn new(bpm: BufferPoolManagerRef, schema: SchemaRef) -> Self {
         let page = bpm.borrow_mut().alloc().unwrap();
         unsafe {
             let slotted = &mut *(page.borrow_mut().buffer.as_mut_ptr() as *mut SlicePage);
-            slotted.reset(&SliceMeta { next_page_id: None });
+            slotted.reset(&SliceMeta {
+                next_page_id: None,
+                fingerprint: schema.fingerprint(),
+            });
         }
         // mark dirty
         page.borrow_mut().is_dirty = true;
-        Self { page, bpm, schema }
+        Self {
+            page,
+            bpm,
+            schema,
+            append_only: false,
+        }
     }
 
-    pub fn open(bpm: BufferPoolManagerRef, schema: SchemaRef, page_id: PageID) -> Self {
-        let page = bpm.borrow_mut().fetch(page_id).unwrap();
-        Self { page, bpm, schema }
+    /// open the slice, the schema must have the same layout as the one it created with
+    pub fn open(
+        bpm: BufferPoolManagerRef,
+        schema: SchemaRef,
+        page_id: PageID,
+    ) -> Result<Self, TableError> {
+        let page = bpm.borrow_mut().fetch(page_id)?;
+        let slice = Self {
+            page,
+            bpm,
+            schema,
+            append_only: false,
+        };
+        if slice.slice_page().meta().fingerprint != slice.schema.fingerprint() {
+            return Err(TableError::SchemaMismatch(page_id));
+        }
+        Ok(slice)
     }
 
     pub fn page_id(&self) -> PageID {
         self.page.borrow().page_id.unwrap()
     }
 
+    /// never reuse the slots freed by removes, the table keeps the mode for its slices
+    pub fn set_append_only(&mut self, append_only: bool) {
+        self.append_only = append_only;
+    }
+
+    /// bytes used and free of the page
+    pub fn store_stat(&self) -> (usize, usize) {
+        self.slice_page().store_stat()
+    }
+
     fn slice_page(&self) -> &SlicePage {
         unsafe { &*(self.page.borrow().buffer.as_ptr() as *const SlicePage) }
     }
@@ -158,35 +217,124 @@ impl Slice {
     }
 
     pub fn insert(&mut self, tuple: &[Datum]) -> Result<(usize, usize), TableError> {
+        self.insert_with_version(tuple, 0)
+    }
+
+    /// insert the tuple created at the version, reads at earlier versions do not see it
+    pub fn insert_with_version(
+        &mut self,
+        tuple: &[Datum],
+        version: u32,
+    ) -> Result<(usize, usize), TableError> {
         let page_id = self.page_id();
+        // fail before serializing the tuple if it can not fit
+        let slice_page = self.slice_page();
+        let len_of_slot = match !self.append_only && slice_page.count() < slice_page.capacity() {
+            true => 0,
+            false => size_of::<SlotKey>() + 16,
+        };
+        if Datum::tuple_bytes_len(tuple) + len_of_slot > slice_page.store_stat().1 {
+            return Err(TableError::SliceOutOfSpace);
+        }
+        let append_only = self.append_only;
         let slice_page = self.slice_page_mut();
-        let slot_id = slice_page.insert(&0, &Datum::bytes_from_tuple(tuple))?;
+        let key = SlotKey {
+            created: version,
+            ..SlotKey::default()
+        };
+        let bytes = Datum::bytes_from_tuple(tuple);
+        let slot_id = match append_only {
+            true => {
+                let slot_id = slice_page.capacity();
+                slice_page.append(&key, &bytes)?;
+                slot_id
+            }
+            false => slice_page.insert(&key, &bytes)?,
+        };
         Ok((page_id, slot_id))
     }
 
+    /// put the tuple back into the removed slot, the versions of the slot are kept
+    pub fn insert_at(
+        &mut self,
+        idx: usize,
+        ref_cnt: usize,
+        tuple: &[Datum],
+    ) -> Result<(), TableError> {
+        let slice_page = self.slice_page_mut();
+        let key = SlotKey {
+            ref_cnt: ref_cnt as u32,
+            ..*slice_page.key_at(idx)
+        };
+        slice_page.insert_at(idx, &key, &Datum::bytes_from_tuple(tuple))?;
+        Ok(())
+    }
+
+    /// drop all the tuples, so a scratch slice is reused instead of allocating a page again
+    pub fn clear(&mut self) {
+        let meta = SliceMeta {
+            next_page_id: None,
+            fingerprint: self.schema.fingerprint(),
+        };
+        self.slice_page_mut().reset(&meta);
+    }
+
     pub fn remove_at(&mut self, idx: usize) -> Result<(), TableError> {
         let slice_page = self.slice_page_mut();
         slice_page.remove_at(idx)?;
         Ok(())
     }
 
+    /// reclaim the space of the trailing empty slots, returns the bytes reclaimed, an append
+    /// only slice keeps them so the slot ids are never handed out again
+    pub fn compact(&mut self) -> usize {
+        if self.append_only {
+            return 0;
+        }
+        self.slice_page_mut().compact()
+    }
+
     pub fn set_ref_cnt_at(&mut self, idx: usize, cnt: usize) -> Result<(), TableError> {
         let slice_page = self.slice_page_mut();
-        *slice_page.key_mut_at(idx) = cnt;
+        slice_page.key_mut_at(idx).ref_cnt = cnt as u32;
         Ok(())
     }
 
     pub fn ref_cnt_at(&self, idx: usize) -> Result<usize, TableError> {
         let slice_page = self.slice_page();
-        Ok(*slice_page.key_at(idx))
+        Ok(slice_page.key_at(idx).ref_cnt as usize)
+    }
+
+    /// mark the tuple deleted at the version, reads at earlier versions still see it
+    #[allow(dead_code)]
+    pub fn delete_at_version(&mut self, idx: usize, version: u32) -> Result<(), TableError> {
+        let slice_page = self.slice_page_mut();
+        slice_page.key_mut_at(idx).deleted = version;
+        Ok(())
+    }
+
+    /// tuples visible to a read at the version
+    #[allow(dead_code)]
+    pub fn tuple_iter_at(&self, version: u32) -> impl Iterator<Item = Vec<Datum>> + '_ {
+        let slice_page = self.slice_page();
+        slice_page
+            .key_data_iter()
+            .filter(move |(key, _)| key.visible_at(version))
+            .map(move |(_, data)| {
+                Datum::tuple_from_bytes_with_schema(data, self.schema.as_ref()).unwrap()
+            })
     }
 
+    /// an empty or out of range slot is an error instead of garbage
     pub fn tuple_at(&self, idx: usize) -> Result<Vec<Datum>, TableError> {
         let slice_page = self.slice_page();
+        if !slice_page.is_used_at(idx) {
+            return Err(TableError::SliceIndexOutOfBound);
+        }
         Ok(Datum::tuple_from_bytes_with_schema(
             slice_page.data_at(idx),
             self.schema.as_ref(),
-        ))
+        )?)
     }
 
     pub fn tuple_iter(&self) -> TupleIter {
@@ -203,6 +351,14 @@ impl Slice {
         SlotIter::new(slice_page.idx_iter())
     }
 
+    /// tuples with the record ids of them
+    pub fn record_iter(&self) -> impl Iterator<Item = (RecordID, Vec<Datum>)> + '_ {
+        let page_id = self.page_id();
+        self.slot_iter()
+            .zip(self.tuple_iter())
+            .map(move |(idx, tuple)| ((page_id, idx), tuple))
+    }
+
     pub fn count(&self) -> usize {
         let slice_page = self.slice_page();
         slice_page.count()
@@ -211,6 +367,13 @@ impl Slice {
 
 impl fmt::Display for Slice {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render("NULL", false))
+    }
+}
+
+impl Slice {
+    /// the tuples as a text table, with nulls shown as null_display
+    pub fn render(&self, null_display: &str, bool_as_int: bool) -> String {
         let mut table = Table::new();
         let header = self
             .schema
@@ -222,11 +385,57 @@ impl fmt::Display for Slice {
         for tuple in self.tuple_iter() {
             let tuple = tuple
                 .iter()
-                .map(|d| Cell::new(d.to_string().as_str()))
+                .map(|d| Cell::new(&d.render(null_display, bool_as_int)))
                 .collect_vec();
             table.add_row(Row::new(tuple));
         }
-        write!(f, "{}", table)
+        table.to_string()
+    }
+}
+
+/// the slices of a result built from its rows, a row over the space left in the last slice
+/// starts a new one
+pub struct ResultBuilder {
+    bpm: BufferPoolManagerRef,
+    schema: SchemaRef,
+    slices: Vec<Slice>,
+}
+
+impl ResultBuilder {
+    pub fn new(bpm: BufferPoolManagerRef, schema: SchemaRef) -> Self {
+        Self {
+            bpm,
+            schema,
+            slices: vec![],
+        }
+    }
+    /// fails only if the row does not fit even into an empty slice
+    pub fn push(&mut self, tuple: &[Datum]) -> Result<(), TableError> {
+        if let Some(slice) = self.slices.last_mut() {
+            match slice.insert(tuple) {
+                Err(TableError::SliceOutOfSpace) => {}
+                result => return result.map(|_| ()),
+            }
+        }
+        let mut slice = Slice::new(self.bpm.clone(), self.schema.clone());
+        slice.insert(tuple)?;
+        self.slices.push(slice);
+        Ok(())
+    }
+    /// the slices in the order of the rows, none if no row is pushed
+    pub fn finish(self) -> Vec<Slice> {
+        self.slices
+    }
+    pub fn from_rows(
+        bpm: BufferPoolManagerRef,
+        schema: SchemaRef,
+        rows: impl IntoIterator<Item = Vec<Datum>>,
+    ) -> Result<Vec<Slice>, TableError> {
+        let mut builder = Self::new(bpm, schema);
+        for row in rows {
+            builder.push(&row)?;
+        }
+        Ok(builder.finish())
     }
 }
 
@@ -263,7 +472,7 @@ mod tests {
                 (DataType::new_as_int(false), "v1".to_string()),
                 (DataType::new_as_varchar(false), "v2".to_string()),
             ]);
-            let mut slice = Slice::open(bpm, Rc::new(schema), page_id);
+            let mut slice = Slice::open(bpm, Rc::new(schema), page_id).unwrap();
             slice.insert(tuple3.as_slice()).unwrap();
             assert_eq!(slice.tuple_at(0).unwrap(), tuple1);
             assert_eq!(slice.tuple_at(1).unwrap(), tuple2);
@@ -330,4 +539,185 @@ mod tests {
         };
         remove_file(filename).unwrap();
     }
+
+    #[test]
+    fn test_open_with_mismatched_schema() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(5);
+            let filename = bpm.borrow().filename();
+            let schema = Schema::from_type_and_names(&[
+                (DataType::new_as_int(false), "v1".to_string()),
+                (DataType::new_as_varchar(false), "v2".to_string()),
+            ]);
+            let page_id = {
+                let mut slice = Slice::new(bpm.clone(), Rc::new(schema));
+                slice.insert(&[20.into(), "hello".into()]).unwrap();
+                slice.page_id()
+            };
+            // names and nullability don't change the layout
+            let schema = Schema::from_type_and_names(&[
+                (DataType::new_as_int(true), "v3".to_string()),
+                (DataType::new_as_varchar(true), "v4".to_string()),
+            ]);
+            let slice = Slice::open(bpm.clone(), Rc::new(schema), page_id).unwrap();
+            assert_eq!(slice.tuple_at(0).unwrap(), vec![20.into(), "hello".into()]);
+            drop(slice);
+            let schema = Schema::from_type_and_names(&[
+                (DataType::new_as_varchar(false), "v1".to_string()),
+                (DataType::new_as_int(false), "v2".to_string()),
+            ]);
+            assert!(matches!(
+                Slice::open(bpm.clone(), Rc::new(schema), page_id),
+                Err(TableError::SchemaMismatch(_))
+            ));
+            // the page is released on mismatch
+            assert!(bpm.borrow().page_pin_summary().is_empty());
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_tuple_iter_at_version() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(5);
+            let filename = bpm.borrow().filename();
+            let schema = Rc::new(Schema::from_type_and_names(&[(
+                DataType::new_as_int(false),
+                "v1".to_string(),
+            )]));
+            let mut slice = Slice::new(bpm, schema);
+            for version in 1..=3 {
+                slice
+                    .insert_with_version(&[(version as i32).into()], version)
+                    .unwrap();
+            }
+            let tuples_at = |slice: &Slice, version| slice.tuple_iter_at(version).collect_vec();
+            assert_eq!(tuples_at(&slice, 0), Vec::<Vec<Datum>>::new());
+            assert_eq!(tuples_at(&slice, 2), vec![vec![1.into()], vec![2.into()]]);
+            slice.delete_at_version(0, 3).unwrap();
+            assert_eq!(tuples_at(&slice, 2), vec![vec![1.into()], vec![2.into()]]);
+            assert_eq!(tuples_at(&slice, 3), vec![vec![2.into()], vec![3.into()]]);
+            // the ref count lives beside the versions
+            slice.set_ref_cnt_at(1, 2).unwrap();
+            assert_eq!(slice.ref_cnt_at(1).unwrap(), 2);
+            assert_eq!(tuples_at(&slice, 3), vec![vec![2.into()], vec![3.into()]]);
+            // replacing the tuple keeps the version it is created at
+            slice.remove_at(1).unwrap();
+            slice.insert_at(1, 2, &[20.into()]).unwrap();
+            assert_eq!(tuples_at(&slice, 1), vec![vec![1.into()]]);
+            assert_eq!(tuples_at(&slice, 3), vec![vec![20.into()], vec![3.into()]]);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_insert_into_full_slice() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(5);
+            let filename = bpm.borrow().filename();
+            let schema = Rc::new(Schema::from_type_and_names(&[(
+                DataType::new_as_varchar(false),
+                "v1".to_string(),
+            )]));
+            let mut slice = Slice::new(bpm, schema);
+            let tuple = vec![Datum::VarChar(Some("x".repeat(256)))];
+            while slice.insert(&tuple).is_ok() {}
+            let count = slice.count();
+            let stat = slice.slice_page().store_stat();
+            assert!(matches!(
+                slice.insert(&tuple),
+                Err(TableError::SliceOutOfSpace)
+            ));
+            assert_eq!(slice.count(), count);
+            assert_eq!(slice.slice_page().store_stat(), stat);
+            assert!(slice.tuple_iter().all(|t| t == tuple));
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_record_iter() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(5);
+            let filename = bpm.borrow().filename();
+            let schema = Rc::new(Schema::from_type_and_names(&[(
+                DataType::new_as_int(false),
+                "v1".to_string(),
+            )]));
+            let mut slice = Slice::new(bpm, schema);
+            for v in 0..5 {
+                slice.insert(&[v.into()]).unwrap();
+            }
+            slice.remove_at(1).unwrap();
+            slice.remove_at(3).unwrap();
+            let page_id = slice.page_id();
+            let (record_ids, tuples): (Vec<_>, Vec<_>) = slice.record_iter().unzip();
+            assert_eq!(
+                record_ids,
+                slice.slot_iter().map(|idx| (page_id, idx)).collect_vec()
+            );
+            assert_eq!(tuples, slice.tuple_iter().collect_vec());
+            assert_eq!(record_ids, vec![(page_id, 0), (page_id, 2), (page_id, 4)]);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_append_only() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(5);
+            let filename = bpm.borrow().filename();
+            let schema =
+                Schema::from_type_and_names(&[(DataType::new_as_int(false), "v1".to_string())]);
+            let schema = Rc::new(schema);
+            let mut slice = Slice::new(bpm.clone(), schema.clone());
+            for v in 0..3 {
+                slice.insert(&[v.into()]).unwrap();
+            }
+            slice.remove_at(1).unwrap();
+            // the freed slot is reused by default
+            assert_eq!(slice.insert(&[3.into()]).unwrap().1, 1);
+            slice.remove_at(0).unwrap();
+            slice.set_append_only(true);
+            assert_eq!(slice.insert(&[4.into()]).unwrap().1, 3);
+            let tuples = slice.tuple_iter().collect_vec();
+            assert_eq!(tuples, vec![vec![3.into()], vec![2.into()], vec![4.into()]]);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_result_builder() {
+        let filename = {
+            let bpm = BufferPoolManager::new_random_shared(10);
+            let filename = bpm.borrow().filename();
+            let schema = Rc::new(Schema::from_type_and_names(&[
+                (DataType::new_as_int(false), "v1".to_string()),
+                (DataType::new_as_varchar(false), "v2".to_string()),
+            ]));
+            let rows = (0..2000)
+                .map(|v| vec![v.into(), format!("row{}", v).as_str().into()])
+                .collect_vec();
+            let slices =
+                ResultBuilder::from_rows(bpm.clone(), schema.clone(), rows.clone()).unwrap();
+            assert!(slices.len() > 1);
+            let tuples = slices
+                .iter()
+                .flat_map(|slice| slice.tuple_iter().collect_vec())
+                .collect_vec();
+            assert_eq!(tuples, rows);
+            assert!(ResultBuilder::from_rows(bpm.clone(), schema, vec![])
+                .unwrap()
+                .is_empty());
+            let slice = Slice::new_as_count(bpm, "count", 3).unwrap();
+            assert_eq!(slice.tuple_iter().collect_vec(), vec![vec![3.into()]]);
+            filename
+        };
+        remove_file(filename).unwrap();
+    }
 }
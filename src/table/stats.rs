@@ -0,0 +1,103 @@
+use crate::datum::{DataType, Datum};
+use crate::table::Schema;
+use itertools::Itertools;
+use std::collections::HashSet;
+
+/// statistics of a column collected by analyze
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats {
+    pub min: Datum,
+    pub max: Datum,
+    pub num_nulls: usize,
+    pub num_distinct: usize,
+    /// rows of the table, nulls included
+    pub num_rows: usize,
+}
+
+fn nullable_of(data_type: DataType) -> DataType {
+    match data_type {
+        DataType::Int(_) => DataType::new_as_int(true),
+        DataType::VarChar(_) => DataType::new_as_varchar(true),
+        DataType::Bool(_) => DataType::new_as_bool(true),
+        DataType::Date(_) => DataType::new_as_date(true),
+        DataType::Float(_) => DataType::new_as_float(true),
+    }
+}
+
+impl ColumnStats {
+    /// collect the stats of a column from all of its values
+    pub fn collect(data_type: DataType, values: impl Iterator<Item = Datum>) -> Self {
+        let mut num_nulls = 0;
+        let mut num_rows = 0;
+        let mut distinct = HashSet::new();
+        for value in values {
+            num_rows += 1;
+            if value.is_null() {
+                num_nulls += 1;
+            } else {
+                distinct.insert(value);
+            }
+        }
+        let null = Datum::null_of(&data_type);
+        Self {
+            min: distinct
+                .iter()
+                .min()
+                .cloned()
+                .unwrap_or_else(|| null.clone()),
+            max: distinct.iter().max().cloned().unwrap_or(null),
+            num_nulls,
+            num_distinct: distinct.len(),
+            num_rows,
+        }
+    }
+    /// stats of all the columns are stored as a single tuple with this schema
+    pub fn schema_of(schema: &Schema) -> Schema {
+        Schema::from_type_and_names(
+            &schema
+                .columns
+                .iter()
+                .flat_map(|c| {
+                    vec![
+                        (nullable_of(c.data_type), format!("min({})", c.desc)),
+                        (nullable_of(c.data_type), format!("max({})", c.desc)),
+                        (DataType::new_as_int(false), format!("nulls({})", c.desc)),
+                        (DataType::new_as_int(false), format!("distinct({})", c.desc)),
+                        (DataType::new_as_int(false), format!("rows({})", c.desc)),
+                    ]
+                })
+                .collect_vec(),
+        )
+    }
+    pub fn to_tuple(stats: &[ColumnStats]) -> Vec<Datum> {
+        stats
+            .iter()
+            .flat_map(|s| {
+                vec![
+                    s.min.clone(),
+                    s.max.clone(),
+                    (s.num_nulls as i32).into(),
+                    (s.num_distinct as i32).into(),
+                    (s.num_rows as i32).into(),
+                ]
+            })
+            .collect_vec()
+    }
+    pub fn from_tuple(tuple: Vec<Datum>) -> Vec<ColumnStats> {
+        tuple
+            .into_iter()
+            .chunks(5)
+            .into_iter()
+            .map(|chunk| {
+                let (min, max, num_nulls, num_distinct, num_rows) = chunk.collect_tuple().unwrap();
+                Self {
+                    min,
+                    max,
+                    num_nulls: i32::from(num_nulls) as usize,
+                    num_distinct: i32::from(num_distinct) as usize,
+                    num_rows: i32::from(num_rows) as usize,
+                }
+            })
+            .collect_vec()
+    }
+}